@@ -0,0 +1,108 @@
+use std::sync::Arc;
+use criterion::{black_box, criterion_group, criterion_main, BatchSize, BenchmarkId, Criterion};
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+use rayon::ThreadPoolBuilder;
+use ga_synth::signal_processing::components::oscillator::{saw_wave, sine_wave};
+use ga_synth::signal_processing::signal_analysis::SpectrumConfig;
+use ga_synth::signal_processing::{LENGTH, SAMPLE_RATE};
+use ga_synth::simulation::algorithms::genetic::{GASimulation, GASimulationBuilder, Individual, IndividualGenerator};
+use ga_synth::simulation::synthesis_methods::subtractive::SubtractiveIndividual;
+use ga_synth::utils::{blackman_window, convolve, fft_convolve};
+
+/// Cost of a single frequency-domain MSE evaluation, as performed for every individual of a
+/// simulation, when the FFT is planned on every call compared to reusing the analyser of the
+/// generator.
+fn spectrum_mse(c: &mut Criterion) {
+    let target = saw_wave(440.0, LENGTH, SAMPLE_RATE as f32, 1.0, 0.0);
+    let candidate = sine_wave(445.0, LENGTH, SAMPLE_RATE as f32, 1.0, 0.0);
+
+    let mut group = c.benchmark_group("freq_spectrum_mse");
+    group.bench_function("throwaway analyser", |b| {
+        b.iter(|| black_box(&candidate).freq_spectrum_mse(black_box(&target)).unwrap())
+    });
+
+    let config = SpectrumConfig::default();
+    group.bench_function("reused analyser", |b| {
+        b.iter(|| black_box(&candidate).freq_spectrum_mse_with(black_box(&target), &config).unwrap())
+    });
+    group.finish();
+}
+
+/// Cost of a single generation of a large population, dominated by the crossovers, which are
+/// collected in parallel without synchronising the threads.
+fn ga_generation(c: &mut Criterion) {
+    let generator = SubtractiveIndividual::new_generator()
+        .target(Arc::new(saw_wave(440.0, 0.1, SAMPLE_RATE as f32, 1.0, 0.0)))
+        .oscillator();
+    let builder = GASimulationBuilder::new()
+        .initial_population(1_000)
+        .max_generations(1)
+        .seed(1)
+        .quiet()
+        .generator(generator);
+
+    let mut group = c.benchmark_group("ga_generation");
+    group.sample_size(10);
+    group.bench_function("1000 individuals", |b| {
+        b.iter_batched(
+            || -> GASimulation<SubtractiveIndividual> { builder.clone().build().unwrap() },
+            |mut simulation| simulation.run().unwrap(),
+            BatchSize::LargeInput,
+        )
+    });
+    group.finish();
+}
+
+/// Cost of evaluating a batch of random additions, which should decrease with the number of
+/// threads the evaluation is spread across.
+fn evaluate_population(c: &mut Criterion) {
+    let generator = SubtractiveIndividual::new_generator()
+        .target(Arc::new(saw_wave(440.0, 0.1, SAMPLE_RATE as f32, 1.0, 0.0)))
+        .oscillator();
+    let simulation: GASimulation<SubtractiveIndividual> = GASimulationBuilder::new()
+        .initial_population(2)
+        .quiet()
+        .generator(generator.clone())
+        .build()
+        .unwrap();
+
+    let mut group = c.benchmark_group("evaluate_population");
+    group.sample_size(10);
+    for threads in [1, 2, 4, 8] {
+        let pool = ThreadPoolBuilder::new().num_threads(threads).build().unwrap();
+        group.bench_with_input(BenchmarkId::new("1000 additions", threads), &pool, |b, pool| {
+            b.iter_batched(
+                || {
+                    let mut simulation = simulation.clone();
+                    let mut rng = StdRng::seed_from_u64(1);
+                    simulation.population.extend((0..1_000).map(|_| generator.generate_unevaluated(&mut rng)));
+                    simulation
+                },
+                |mut simulation| pool.install(|| simulation.evaluate_population()),
+                BatchSize::LargeInput,
+            )
+        });
+    }
+    group.finish();
+}
+
+/// Cost of filtering a whole signal with the 400 taps designed for a band of 0.01, directly and
+/// with the FFT.
+fn filter_convolution(c: &mut Criterion) {
+    let signal = saw_wave(440.0, LENGTH, SAMPLE_RATE as f32, 1.0, 0.0);
+    let filter = blackman_window(400);
+
+    let mut group = c.benchmark_group("filter_convolution");
+    group.sample_size(10);
+    group.bench_function("direct", |b| {
+        b.iter(|| convolve(black_box(&filter), black_box(signal.samples())))
+    });
+    group.bench_function("fft", |b| {
+        b.iter(|| fft_convolve(black_box(&filter), black_box(signal.samples())))
+    });
+    group.finish();
+}
+
+criterion_group!(benches, spectrum_mse, ga_generation, evaluate_population, filter_convolution);
+criterion_main!(benches);
@@ -11,6 +11,7 @@ const GENERATIONS: u32 = 500;
 const N_SIMS: u8 = 10;
 
 fn main() {
+    env_logger::Builder::from_env(env_logger::Env::default().default_filter_or("info")).init();
     // subtractive_multiple();
     // additive_multiple();
     subtractive()
@@ -19,7 +20,7 @@ fn main() {
 fn subtractive_multiple() {
     let generator = SubtractiveIndividual::new_generator()
         .target_file(TARGET)
-        .fitness_type(FitnessType::FreqDomainMSE)
+        .fitness_type(FitnessType::freq_domain_mse())
         .oscillator();
 
     (0..N_SIMS).into_par_iter().for_each(|i| {
@@ -34,7 +35,7 @@ fn subtractive_multiple() {
             .max_generations(GENERATIONS)
             .signal_export(&format!("test_1/a/{}.wav", i))
             .csv_export(&format!("test_1/a/{}.csv", i))
-            .build();
+            .build().expect("Simulation should have been valid.");
 
         simulation.run().expect("Simulation should have completed.");
     });
@@ -45,7 +46,7 @@ fn subtractive_multiple() {
 fn additive_multiple() {
     let generator = AdditiveIndividual::new_generator()
         .target_file(TARGET)
-        .fitness_type(FitnessType::FreqDomainMSE)
+        .fitness_type(FitnessType::freq_domain_mse())
         .harmonics();
 
     (0..N_SIMS).into_par_iter().for_each(|i| {
@@ -60,7 +61,7 @@ fn additive_multiple() {
             .max_generations(GENERATIONS)
             .signal_export(&format!("test_1/b/{}.wav", i))
             .csv_export(&format!("test_1/b/{}.csv", i))
-            .build();
+            .build().expect("Simulation should have been valid.");
 
         simulation.run().expect("Simulation should have completed.");
     });
@@ -71,7 +72,7 @@ fn additive_multiple() {
 fn subtractive() {
     let generator = SubtractiveIndividual::new_generator()
         .target_file("audio_samples/440hz_sine.wav")
-        .fitness_type(FitnessType::FreqDomainMSE)
+        .fitness_type(FitnessType::freq_domain_mse())
         .oscillator();
 
     let mut simulation: GASimulation<SubtractiveIndividual> = GASimulationBuilder::new()
@@ -83,7 +84,7 @@ fn subtractive() {
         .max_generations(GENERATIONS)
         .signal_export("test_1_a.wav")
         .csv_export("test_1_a.csv")
-        .build();
+        .build().expect("Simulation should have been valid.");
 
     simulation.run().expect("Simulation should have completed.");
 }
@@ -91,7 +92,7 @@ fn subtractive() {
 fn additive() {
     let generator = AdditiveIndividual::new_generator()
         .target_file("audio_samples/440hz_sine.wav")
-        .fitness_type(FitnessType::FreqDomainMSE)
+        .fitness_type(FitnessType::freq_domain_mse())
         .harmonics();
     
     let mut simulation: GASimulation<AdditiveIndividual> = GASimulationBuilder::new()
@@ -103,7 +104,7 @@ fn additive() {
         .max_generations(GENERATIONS)
         .signal_export("test_1_b.wav")
         .csv_export("test_1_b.csv")
-        .build();
+        .build().expect("Simulation should have been valid.");
     
     simulation.run().expect("Simulation should have completed.");
 }
\ No newline at end of file
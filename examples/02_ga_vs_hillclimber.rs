@@ -1,6 +1,6 @@
-use rayon::prelude::*;
 use ga_synth::FitnessType;
-use ga_synth::simulation::algorithms::genetic::{GASimulation, GASimulationBuilder, Individual, IndividualGenerator, PopulationEvolution};
+use ga_synth::simulation::algorithms::batch::BatchReport;
+use ga_synth::simulation::algorithms::genetic::{GASimulationBuilder, Individual, IndividualGenerator, PopulationEvolution};
 use ga_synth::simulation::algorithms::hillclimbing::{HillClimberBuilder, HillClimbingSimulation};
 use ga_synth::simulation::synthesis_methods::subtractive::SubtractiveIndividual;
 
@@ -11,6 +11,7 @@ const GENERATIONS: u32 = 500;
 const N_SIMS: u8 = 10;
 
 fn main() {
+    env_logger::Builder::from_env(env_logger::Env::default().default_filter_or("info")).init();
     
     ga_multiple();
     hillclimber_multiple()
@@ -19,44 +20,42 @@ fn main() {
 fn ga_multiple() {
     let generator = SubtractiveIndividual::new_generator()
         .target_file(TARGET)
-        .fitness_type(FitnessType::FreqDomainMSE)
+        .fitness_type(FitnessType::freq_domain_mse())
         .oscillator();
 
-    (0..N_SIMS).into_par_iter().for_each(|i| {
-        println!("Running GA simulation {i}/{N_SIMS}");
-
-        let mut simulation: GASimulation<SubtractiveIndividual> = GASimulationBuilder::new()
-            .generator(generator.clone())
-            .population_evolution(PopulationEvolution::Constant)
-            .initial_population(POPULATION)
-            .n_random_additions(4)
-            .mutation_rate(0.05)
-            .max_generations(GENERATIONS)
-            .signal_export(&format!("test_2/a/{}.wav", i))
-            .csv_export(&format!("test_2/a/{}.csv", i))
-            .build();
-
-        simulation.run().expect("Simulation should have completed.");
-    });
+    println!("Running {N_SIMS} GA simulations");
+
+    let batch: BatchReport<SubtractiveIndividual> = GASimulationBuilder::new()
+        .generator(generator)
+        .population_evolution(PopulationEvolution::Constant)
+        .initial_population(POPULATION)
+        .n_random_additions(4)
+        .mutation_rate(0.05)
+        .max_generations(GENERATIONS)
+        .signal_export("test_2/a/fittest.wav")
+        .csv_export("test_2/a/generations.csv")
+        .run_repeated(N_SIMS as u32)
+        .expect("Simulations should have completed.");
+
+    println!("Best fitness: {} (std {})", batch.mean_best_fitness(), batch.std_best_fitness());
 }
 
 fn hillclimber_multiple() {
     let generator = SubtractiveIndividual::new_generator()
         .target_file(TARGET)
-        .fitness_type(FitnessType::FreqDomainMSE)
+        .fitness_type(FitnessType::freq_domain_mse())
         .oscillator();
 
-    (0..N_SIMS).into_par_iter().for_each(|i| {
-        println!("Running hill climbing simulation {i}/{N_SIMS}");
-        
-        let mut simulation: HillClimbingSimulation<SubtractiveIndividual> = HillClimberBuilder::new()
-            .generator(generator.clone())
-            .max_iterations((POPULATION / 2) * GENERATIONS)
-            .max_unsuccessful_iters(10000)
-            .signal_export(&format!("test_2/b/{}.wav", i))
-            .csv_export(&format!("test_2/b/{}.csv", i))
-            .build();
-
-        simulation.run().expect("Simulation should have completed.");
-    });
+    println!("Running {N_SIMS} hill climbing simulations");
+
+    let builder = HillClimberBuilder::new()
+        .generator(generator)
+        .max_iterations((POPULATION / 2) * GENERATIONS)
+        .max_unsuccessful_iters(10000)
+        .signal_export("test_2/b/fittest.wav")
+        .csv_export("test_2/b/iterations.csv");
+    let report = HillClimbingSimulation::<SubtractiveIndividual>::run_multi(builder, N_SIMS as u32)
+        .expect("Simulations should have completed.");
+
+    println!("Best fitness: {}", report.fittest.fitness());
 }
\ No newline at end of file
@@ -10,6 +10,7 @@ const GENERATIONS: u32 = 500;
 const N_SIMS: u8 = 10;
 
 fn main() {
+    env_logger::Builder::from_env(env_logger::Env::default().default_filter_or("info")).init();
     fitness_mse();
     fitness_time_domain();
 }
@@ -17,7 +18,7 @@ fn main() {
 fn fitness_mse() {
     let generator = SubtractiveIndividual::new_generator()
         .target_file(TARGET)
-        .fitness_type(FitnessType::FreqDomainMSE)
+        .fitness_type(FitnessType::freq_domain_mse())
         .oscillator();
 
     (0..N_SIMS).into_par_iter().for_each(|i| {
@@ -32,7 +33,7 @@ fn fitness_mse() {
             .max_generations(GENERATIONS)
             .signal_export(&format!("test_3/a/{}.wav", i))
             .csv_export(&format!("test_3/a/{}.csv", i))
-            .build();
+            .build().expect("Simulation should have been valid.");
 
         simulation.run().expect("Simulation should have completed.");
     });
@@ -43,7 +44,7 @@ fn fitness_mse() {
 fn fitness_time_domain() {
     let generator = SubtractiveIndividual::new_generator()
         .target_file(TARGET)
-        .fitness_type(FitnessType::TimeDomainEuclidean)
+        .fitness_type(FitnessType::time_domain_euclidean())
         .oscillator();
 
     (0..N_SIMS).into_par_iter().for_each(|i| {
@@ -58,7 +59,7 @@ fn fitness_time_domain() {
             .max_generations(GENERATIONS)
             .signal_export(&format!("test_3/b/{}.wav", i))
             .csv_export(&format!("test_3/b/{}.csv", i))
-            .build();
+            .build().expect("Simulation should have been valid.");
 
         simulation.run().expect("Simulation should have completed.");
     });
@@ -10,6 +10,7 @@ const GENERATIONS: u32 = 200;
 const N_SIMS: u8 = 10;
 
 fn main() {
+    env_logger::Builder::from_env(env_logger::Env::default().default_filter_or("info")).init();
     constant_population();
     evolving_population();
 }
@@ -17,7 +18,7 @@ fn main() {
 fn constant_population() {
     let generator = SubtractiveIndividual::new_generator()
         .target_file(TARGET)
-        .fitness_type(FitnessType::FreqDomainMSE)
+        .fitness_type(FitnessType::freq_domain_mse())
         .oscillator();
     
     (0..N_SIMS).into_par_iter().for_each(|i| {
@@ -32,7 +33,7 @@ fn constant_population() {
             .max_generations(GENERATIONS)
             .signal_export(&format!("test_4/a/{}.wav", i))
             .csv_export(&format!("test_4/a/{}.csv", i))
-            .build();
+            .build().expect("Simulation should have been valid.");
 
         simulation.run().expect("Simulation should have completed.");
     });
@@ -43,7 +44,7 @@ fn constant_population() {
 fn evolving_population() {
     let generator = SubtractiveIndividual::new_generator()
         .target_file(TARGET)
-        .fitness_type(FitnessType::FreqDomainMSE)
+        .fitness_type(FitnessType::freq_domain_mse())
         .oscillator();
 
     (0..N_SIMS).into_par_iter().for_each(|i| {
@@ -58,7 +59,7 @@ fn evolving_population() {
             .max_generations(GENERATIONS)
             .signal_export(&format!("test_4/b/{}.wav", i))
             .csv_export(&format!("test_4/b/{}.csv", i))
-            .build();
+            .build().expect("Simulation should have been valid.");
 
         simulation.run().expect("Simulation should have completed.");
     });
@@ -8,6 +8,7 @@ const TARGET: &str = "audio_samples/440hz_sine.wav";
 const GENERATIONS: u32 = 500;
 
 fn main() {
+    env_logger::Builder::from_env(env_logger::Env::default().default_filter_or("info")).init();
     (3..10).into_par_iter().for_each(|i: u32| run(2u32.pow(i)));
     println!("All simulations completed.");
 }
@@ -15,7 +16,7 @@ fn main() {
 fn run(population: u32) {
     let generator = SubtractiveIndividual::new_generator()
         .target_file(TARGET)
-        .fitness_type(FitnessType::FreqDomainMSE)
+        .fitness_type(FitnessType::freq_domain_mse())
         .oscillator();
 
     println!("Running simulation with population {}", population);
@@ -29,7 +30,7 @@ fn run(population: u32) {
         .max_generations(GENERATIONS)
         .signal_export(&format!("test_5/{}.wav", population))
         .csv_export(&format!("test_5/{}.csv", population))
-        .build();
+        .build().expect("Simulation should have been valid.");
 
     simulation.run().expect("Simulation should have completed.");
 }
\ No newline at end of file
@@ -9,6 +9,7 @@ const GENERATIONS: u32 = 1000;
 const N_SIMS: u8 = 10;
 
 fn main() {
+    env_logger::Builder::from_env(env_logger::Env::default().default_filter_or("info")).init();
     subtractive("audio_samples/sawtooth440.wav");
     additive("audio_samples/sawtooth440.wav");
     
@@ -19,7 +20,7 @@ fn main() {
 fn subtractive(target: &str) {
     let generator = SubtractiveIndividual::new_generator()
         .target_file(target)
-        .fitness_type(FitnessType::FreqDomainMSE)
+        .fitness_type(FitnessType::freq_domain_mse())
         .oscillator();
 
     (0..N_SIMS).into_par_iter().for_each(|i| {
@@ -34,7 +35,7 @@ fn subtractive(target: &str) {
             .max_generations(GENERATIONS)
             .signal_export(&format!("test_7/{}/a/{}.wav", target, i))
             .csv_export(&format!("test_7/{}/a/{}.csv", target, i))
-            .build();
+            .build().expect("Simulation should have been valid.");
 
         simulation.run().expect("Simulation should have completed.");
     });
@@ -45,7 +46,7 @@ fn subtractive(target: &str) {
 fn additive(target: &str) {
     let generator = AdditiveIndividual::new_generator()
         .target_file(target)
-        .fitness_type(FitnessType::FreqDomainMSE)
+        .fitness_type(FitnessType::freq_domain_mse())
         .harmonics();
 
     (0..N_SIMS).into_par_iter().for_each(|i| {
@@ -60,7 +61,7 @@ fn additive(target: &str) {
             .max_generations(GENERATIONS)
             .signal_export(&format!("test_7/{}/b/{}.wav", target, i))
             .csv_export(&format!("test_7/{}/b/{}.csv", target, i))
-            .build();
+            .build().expect("Simulation should have been valid.");
 
         simulation.run().expect("Simulation should have completed.");
     });
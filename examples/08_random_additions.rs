@@ -10,6 +10,7 @@ const GENERATIONS: u32 = 500;
 const POPULATION: u32 = 100;
 
 fn main() {
+    env_logger::Builder::from_env(env_logger::Env::default().default_filter_or("info")).init();
     (0..8).into_par_iter().for_each(|i| run(5 * i));
     println!("All simulations completed.");
 }
@@ -17,7 +18,7 @@ fn main() {
 fn run(n: u32) {
     let generator = SubtractiveIndividual::new_generator()
         .target_file(TARGET)
-        .fitness_type(FitnessType::FreqDomainMSE)
+        .fitness_type(FitnessType::freq_domain_mse())
         .oscillator();
 
     let mut simulation: GASimulation<SubtractiveIndividual> = GASimulationBuilder::new()
@@ -29,7 +30,7 @@ fn run(n: u32) {
         .max_generations(GENERATIONS)
         .signal_export(&format!("test_8/{}.wav", n))
         .csv_export(&format!("test_8/{}.csv", n))
-        .build();
+        .build().expect("Simulation should have been valid.");
 
     simulation.run().expect("Simulation should have completed.");
 }
\ No newline at end of file
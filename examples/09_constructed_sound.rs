@@ -3,7 +3,7 @@ use rayon::prelude::*;
 use ga_synth::FitnessType;
 use ga_synth::signal_processing::Signal;
 use ga_synth::simulation::algorithms::genetic::{GASimulation, GASimulationBuilder, Individual, IndividualGenerator, PopulationEvolution};
-use ga_synth::simulation::components::oscillator::OscillatorComponent;
+use ga_synth::simulation::components::oscillator::{OscillatorComponent, OscillatorSettings};
 use ga_synth::simulation::synthesis_methods::additive::AdditiveIndividual;
 use ga_synth::simulation::synthesis_methods::subtractive::SubtractiveIndividual;
 
@@ -14,6 +14,7 @@ const GENERATIONS: u32 = 500;
 const N_SIMS: u8 = 10;
 
 fn main() {
+    env_logger::Builder::from_env(env_logger::Env::default().default_filter_or("info")).init();
     // construct_sound()
     // subtractive_multiple();
     // additive_multiple();
@@ -31,6 +32,17 @@ fn construct_sound() {
         square_phase: 0.1,
         saw_amp: 0.4,
         saw_phase: 0.0,
+        tri_amp: 0.0,
+        tri_phase: 0.0,
+        pulse_amp: 0.0,
+        pulse_phase: 0.0,
+        duty: 0.5,
+        noise_amp: 0.0,
+        unison_voices: 1,
+        detune_cents: 0.0,
+        slave_ratio: 1.0,
+        noise_seed: 0,
+        settings: OscillatorSettings::default(),
     };
 
     signal.apply_oscillator(oscillator);
@@ -40,7 +52,7 @@ fn construct_sound() {
 fn subtractive() {
     let generator = SubtractiveIndividual::new_generator()
         .target_file(TARGET)
-        .fitness_type(FitnessType::FreqDomainMSE)
+        .fitness_type(FitnessType::freq_domain_mse())
         .oscillator();
 
     let mut simulation: GASimulation<SubtractiveIndividual> = GASimulationBuilder::new()
@@ -52,7 +64,7 @@ fn subtractive() {
         .max_generations(GENERATIONS)
         .signal_export(&format!("test_9/out.wav"))
         .csv_export(&format!("test_9/out.csv"))
-        .build();
+        .build().expect("Simulation should have been valid.");
 
     simulation.run().expect("Simulation should have completed.");
 }
@@ -60,7 +72,7 @@ fn subtractive() {
 fn subtractive_multiple() {
     let generator = SubtractiveIndividual::new_generator()
         .target_file(TARGET)
-        .fitness_type(FitnessType::FreqDomainMSE)
+        .fitness_type(FitnessType::freq_domain_mse())
         .oscillator();
 
     (0..N_SIMS).into_par_iter().for_each(|i| {
@@ -75,7 +87,7 @@ fn subtractive_multiple() {
             .max_generations(GENERATIONS)
             .signal_export(&format!("test_9/a/{}.wav", i))
             .csv_export(&format!("test_9/a/{}.csv", i))
-            .build();
+            .build().expect("Simulation should have been valid.");
 
         simulation.run().expect("Simulation should have completed.");
     });
@@ -86,7 +98,7 @@ fn subtractive_multiple() {
 fn additive_multiple() {
     let generator = AdditiveIndividual::new_generator()
         .target_file(TARGET)
-        .fitness_type(FitnessType::FreqDomainMSE)
+        .fitness_type(FitnessType::freq_domain_mse())
         .harmonics();
 
     (0..N_SIMS).into_par_iter().for_each(|i| {
@@ -101,7 +113,7 @@ fn additive_multiple() {
             .max_generations(GENERATIONS)
             .signal_export(&format!("test_9/b/{}.wav", i))
             .csv_export(&format!("test_9/b/{}.csv", i))
-            .build();
+            .build().expect("Simulation should have been valid.");
 
         simulation.run().expect("Simulation should have completed.");
     });
@@ -0,0 +1,45 @@
+use std::ops::ControlFlow;
+use ga_synth::FitnessType;
+use ga_synth::simulation::algorithms::genetic::{Individual, IndividualGenerator};
+use ga_synth::simulation::algorithms::hillclimbing::{HillClimberBuilder, HillClimbingSimulation, IterationSummary};
+use ga_synth::simulation::synthesis_methods::subtractive::SubtractiveIndividual;
+
+const TARGET: &str = "audio_samples/440hz_sine.wav";
+
+const ITERATIONS: u32 = 5000;
+const PROGRESS_INTERVAL: u32 = 250;
+
+fn main() {
+    env_logger::Builder::from_env(env_logger::Env::default().default_filter_or("warn")).init();
+
+    let generator = SubtractiveIndividual::new_generator()
+        .target_file(TARGET)
+        .fitness_type(FitnessType::freq_domain_mse())
+        .oscillator();
+
+    let mut simulation: HillClimbingSimulation<SubtractiveIndividual> = HillClimberBuilder::new()
+        .generator(generator)
+        .max_iterations(ITERATIONS)
+        .csv_export("test_10/iterations.csv")
+        .signal_export("test_10/fittest.wav")
+        .on_iteration(progress)
+        .build()
+        .expect("Simulation should have been built.");
+    let report = simulation.run_with_report().expect("Simulation should have completed.");
+
+    println!("Stopped after {} iterations ({:?}) with fitness {}",
+             report.iterations, report.termination_reason, report.fittest.fitness());
+}
+
+/// Prints the state of the climb every few iterations and whenever it finds a fitter neighbour.
+fn progress(summary: &IterationSummary) -> ControlFlow<()> {
+    if summary.accepted || summary.iteration.is_multiple_of(PROGRESS_INTERVAL) {
+        println!("[{:>5}/{ITERATIONS}] fitness {:.6} | step size {:.5} | fundamental {}{}",
+                 summary.iteration,
+                 summary.fitness,
+                 summary.step_size,
+                 summary.fundamental.map_or("-".to_string(), |f| format!("{f:.1}Hz")),
+                 if summary.accepted { " *" } else { "" });
+    }
+    ControlFlow::Continue(())
+}
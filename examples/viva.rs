@@ -7,9 +7,10 @@ const POPULATION: u32 = 150;
 const GENERATIONS: u32 = 300;
 
 fn main() {
+    env_logger::Builder::from_env(env_logger::Env::default().default_filter_or("info")).init();
     let generator = SubtractiveIndividual::new_generator()
         .target_file("audio_samples/440hz_sine.wav")
-        .fitness_type(FitnessType::FreqDomainMSE)
+        .fitness_type(FitnessType::freq_domain_mse())
         .oscillator();
 
     let mut simulation: GASimulation<SubtractiveIndividual> = GASimulationBuilder::new()
@@ -21,7 +22,7 @@ fn main() {
         .max_generations(GENERATIONS)
         .signal_export("viva/test.wav")
         .csv_export("viva/test.csv")
-        .build();
+        .build().expect("Simulation should have been valid.");
 
     simulation.run().expect("Simulation should have completed.");
 }
\ No newline at end of file
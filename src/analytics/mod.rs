@@ -1,12 +1,18 @@
 use std::fs;
+use std::io::Write;
 use std::path::Path;
-use csv::Writer;
+use csv::{Writer, WriterBuilder};
 use itertools::Itertools;
+use log::info;
 use serde::{Serialize, Deserialize};
 use crate::simulation::algorithms::genetic::{GASimulation, Individual};
 use crate::simulation::algorithms::hillclimbing::HillClimbingSimulation;
+use crate::simulation::algorithms::multi_objective::MultiObjectiveGASimulation;
 use crate::utils::{mean, std};
 
+/// Start of the metadata lines written after the rows of a CSV export.
+const METADATA_PREFIX: &str = "# ";
+
 #[derive(Default)]
 pub struct Recorder<R: Record> {
     rows: Vec<R>,
@@ -14,23 +20,48 @@ pub struct Recorder<R: Record> {
 
 #[derive(Serialize, Deserialize, PartialEq, Debug, Clone, Default)]
 pub struct GenerationRow {
-    generation: u32,
-    offspring: u32,
-    fundamental: f32,
-    max_fitness: f32,
-    average_fitness: f32,
-    std: f32,
+    pub generation: u32,
+    pub island: u32,
+    pub offspring: u32,
+    pub invalid: u32,
+    pub local_search_evaluations: u32,
+    pub fundamental: f32,
+    pub target_fundamental: f32,
+    pub max_fitness: f32,
+    pub best_ever_fitness: f32,
+    pub average_fitness: f32,
+    pub std: f32,
+    pub diversity: f32,
+    pub mutation_rate: f32,
+    pub seed: u64,
 }
 
-#[derive(serde::Serialize, Clone, Default)]
+/// Statistics of a generation of a multi-objective simulation.
+#[derive(Serialize, Deserialize, PartialEq, Debug, Clone, Default)]
+pub struct ParetoRow {
+    pub generation: u32,
+    pub front_size: u32,
+    pub best_first_objective: f32,
+    pub best_second_objective: f32,
+    pub seed: u64,
+}
+
+#[derive(serde::Serialize, PartialEq, Debug, Clone, Default)]
 pub struct IterationRow {
-    iteration: u32,
-    fitness: f32,
-    fundamental: f32
+    pub iteration: u32,
+    pub fitness: f32,
+    pub fundamental: f32,
+    pub seed: u64,
+    pub step_size: f32,
+    pub restart: u32,
+    pub unsuccessful_iters: u32,
+    pub accepted: bool,
 }
 
 impl<R: Record> Recorder<R> {
-    pub(crate) fn new() -> Self {
+    /// Creates an empty recorder. Custom rows only need to implement `Record` to be collected and
+    /// exported alongside the built-in ones.
+    pub fn new() -> Self {
         Self {
             rows: vec![]
         }
@@ -40,6 +71,11 @@ impl<R: Record> Recorder<R> {
         self.rows.push(record);
     }
 
+    /// Removes all the recorded rows, e.g. once they have been exported.
+    pub fn clear(&mut self) {
+        self.rows.clear();
+    }
+
     pub fn to_csv(&self, file_path: &str) -> Result<(), ()> {
         // fs::create_dir("exports/csv").map_err(|_| ())?;
         let path = Path::new("exports/csv").join(file_path);
@@ -52,7 +88,43 @@ impl<R: Record> Recorder<R> {
         }
         
         wtr.flush().expect("Writer should have been flushed.");
-        println!("Data successfully written to file {file_path}");
+        info!("Data successfully written to file {file_path}");
+        Ok(())
+    }
+
+    /// Appends the rows to an existing CSV file without repeating the header, or creates it as
+    /// `to_csv` does if it does not exist. The metadata lines at the end of the file are replaced
+    /// by the rows, so that they stay at the end.
+    pub fn append_to_csv(&self, file_path: &str) -> anyhow::Result<()> {
+        let path = Path::new("exports/csv").join(file_path);
+        if !path.exists() {
+            return self.to_csv(file_path).map_err(|_| anyhow::anyhow!("Could not export to {file_path}"));
+        }
+
+        let contents = fs::read_to_string(&path)?;
+        let metadata_len: usize = contents.split_inclusive('\n').rev()
+            .take_while(|line| line.starts_with(METADATA_PREFIX))
+            .map(str::len)
+            .sum();
+
+        let file = fs::OpenOptions::new().append(true).open(path)?;
+        file.set_len((contents.len() - metadata_len) as u64)?;
+        let mut wtr = WriterBuilder::new().has_headers(false).from_writer(file);
+        for row in &self.rows {
+            wtr.serialize(row)?;
+        }
+
+        wtr.flush()?;
+        info!("Data successfully appended to file {file_path}");
+        Ok(())
+    }
+
+    /// Writes a `key: value` metadata line at the end of an exported CSV file, starting with `#`
+    /// so that CSV readers can skip it as a comment.
+    pub fn write_metadata(file_path: &str, key: &str, value: &str) -> anyhow::Result<()> {
+        let path = Path::new("exports/csv").join(file_path);
+        let mut file = fs::OpenOptions::new().append(true).open(path)?;
+        writeln!(file, "{METADATA_PREFIX}{key}: {value}")?;
         Ok(())
     }
 }
@@ -61,27 +133,44 @@ pub trait Record: Serialize {}
 
 impl Record for GenerationRow {}
 impl Record for IterationRow {}
+impl Record for ParetoRow {}
 
 impl<T: Individual> From<&mut GASimulation<T>> for GenerationRow {
     fn from(simulation: &mut GASimulation<T>) -> Self {
         let generation = simulation.generation;
+        let island = simulation.island;
         let offspring = simulation.offspring;
+        let invalid = simulation.invalid;
+        let local_search_evaluations = simulation.local_search_evaluations;
         let fundamental = simulation.fundamental.unwrap_or(0.0);
             // .expect("There should be a fundamental frequency");
+        let target_fundamental = simulation.target_fundamental.unwrap_or(0.0);
         let max_fitness = simulation.population
             .first()
             .expect("There should be at least one individual")
             .fitness();
+        let best_ever_fitness = simulation.best_ever.as_ref().map_or(max_fitness, |i| i.fitness());
         let average_fitness = mean(&simulation.population.iter().map(|i| i.fitness()).collect_vec());
         let std = std(&simulation.population.iter().map(|i| i.fitness()).collect_vec());
+        let diversity = simulation.diversity;
+        let mutation_rate = simulation.mutation_rate;
+        let seed = simulation.seed;
 
         Self {
             generation,
+            island,
             offspring,
+            invalid,
+            local_search_evaluations,
             fundamental,
+            target_fundamental,
             max_fitness,
+            best_ever_fitness,
             average_fitness,
-            std
+            std,
+            diversity,
+            mutation_rate,
+            seed,
         }
     }
 }
@@ -91,11 +180,37 @@ impl<T: Individual> From<&mut HillClimbingSimulation<T>> for IterationRow {
         let iteration = simulation.iteration;
         let fitness = simulation.current_individual.fitness();
         let fundamental = simulation.current_individual.get_fundamental().unwrap_or(0.0);
+        let seed = simulation.seed;
+        let step_size = simulation.step_size;
+        let restart = simulation.restart;
+        let unsuccessful_iters = simulation.unsuccessful_iters;
+        let accepted = simulation.accepted;
         
         Self {
             iteration,
             fitness,
             fundamental,
+            seed,
+            step_size,
+            restart,
+            unsuccessful_iters,
+            accepted,
+        }
+    }
+}
+
+impl<T: Individual> From<&MultiObjectiveGASimulation<T>> for ParetoRow {
+    fn from(simulation: &MultiObjectiveGASimulation<T>) -> Self {
+        let best = |objective: fn(&(f32, f32)) -> f32| {
+            simulation.scores.iter().map(objective).fold(0.0, f32::max)
+        };
+
+        Self {
+            generation: simulation.generation,
+            front_size: simulation.front_size() as u32,
+            best_first_objective: best(|s| s.0),
+            best_second_objective: best(|s| s.1),
+            seed: simulation.seed,
         }
     }
 }
@@ -111,7 +226,7 @@ mod tests {
 
         // Write
         let mut recorder = Recorder::new();
-        let record = GenerationRow { generation: 10, max_fitness: 0.3, average_fitness: 0.3, std: 0.3, offspring: 50, fundamental: 0.0 };
+        let record = GenerationRow { generation: 10, island: 0, max_fitness: 0.3, best_ever_fitness: 0.4, average_fitness: 0.3, std: 0.3, diversity: 0.2, mutation_rate: 0.05, offspring: 50, invalid: 0, local_search_evaluations: 0, fundamental: 0.0, target_fundamental: 440.0, seed: 42 };
         recorder.add_record(record.clone());
         recorder.add_record(record.clone());
         recorder.add_record(record.clone());
@@ -127,4 +242,31 @@ mod tests {
         let rd_record: GenerationRow = iter.next().unwrap().unwrap();
         assert_eq!(rd_record, record);
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_csv_metadata() {
+        let path = "tests/metadata.csv";
+        let record = IterationRow { iteration: 1, fitness: 0.5, fundamental: 440.0, seed: 42, step_size: 0.1, restart: 0,
+            unsuccessful_iters: 0, accepted: false };
+        let mut recorder = Recorder::new();
+        recorder.add_record(record.clone());
+        recorder.to_csv(path).unwrap();
+        Recorder::<IterationRow>::write_metadata(path, "termination_reason", "MaxIterations").unwrap();
+
+        // appended rows replace the metadata, which is written again after them
+        recorder.append_to_csv(path).unwrap();
+        Recorder::<IterationRow>::write_metadata(path, "termination_reason", "Cancelled").unwrap();
+
+        let csv = fs::read_to_string(Path::new("exports/csv").join(path)).unwrap();
+        let lines = csv.lines().collect_vec();
+        assert_eq!(lines.len(), 4);
+        assert_eq!(lines[3], "# termination_reason: Cancelled");
+
+        // the metadata is skipped by readers treating it as a comment
+        let mut rdr = csv::ReaderBuilder::new()
+            .comment(Some(b'#'))
+            .from_path(Path::new("exports/csv").join(path))
+            .unwrap();
+        assert_eq!(rdr.records().count(), 2);
+    }
+}
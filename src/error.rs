@@ -2,37 +2,242 @@ use std::error::Error;
 use std::fmt;
 use std::fmt::{Debug, Formatter};
 use spectrum_analyzer::error::SpectrumAnalyzerError;
+use crate::signal_processing::signal_analysis::MAX_FFT_LENGTH;
+use crate::simulation::algorithms::crossover::CrossoverOperator;
+use crate::simulation::algorithms::hillclimbing::StepSchedule;
+use crate::simulation::algorithms::mutation::MutationOperator;
+use crate::simulation::algorithms::selection::SelectionStrategy;
 
-/// Errors that can be encountered during the execution of the genetic algorithm.
+/// Errors that can be encountered when building or running the genetic algorithm.
 // TODO make them more specific, explaining the reason why something went wrong.
-#[derive(Debug)]
+#[derive(Debug, PartialEq)]
 pub enum GeneticSimulationError {
     OffspringNotProduced,
-    RandomIndividualNotGenerated
+    RandomIndividualNotGenerated,
+    MissingGenerator,
+    MissingTarget,
+    /// The initial population should have at least two individuals.
+    InvalidPopulationSize(u32),
+    /// Every mutation rate of the schedule should be a probability.
+    InvalidMutationRate,
+    /// The simulation should run for at least one generation.
+    InvalidMaxGenerations,
+    /// The elites should not exceed the individuals selected on each generation.
+    InvalidElitism { elitism: u32, selected: u32 },
+    /// A decreasing population should not end larger than it started.
+    InvalidFinalPopulation { final_size: u32, initial: u32 },
+    /// The fraction of the population selected on each generation should be in (0, 1].
+    InvalidSurvivorFraction(f32),
+    /// Every fitness value should be finite.
+    InvalidFitness { details: String },
+    /// The parameters of the selection strategy should be within their valid ranges.
+    InvalidSelection(SelectionStrategy),
+    /// An island model should have at least one island.
+    InvalidIslandCount,
+    /// A batch should run the simulation at least once.
+    InvalidRunCount,
+    /// The islands should exchange migrants at least every generation.
+    InvalidMigrationInterval,
+    /// The migrants should not exceed the individuals selected on each generation.
+    InvalidMigrationCount { migration_count: u32, selected: u32 },
+    /// The checkpoint could not be written while the simulation was running.
+    CheckpointNotSaved { details: String },
+    /// The results of the simulation could not be exported once it finished.
+    ExportFailed { details: String },
+    /// Each pair of parents should perform at least one crossover.
+    InvalidOffspringPerPair,
+    /// The analysis band should be non-empty and below the Nyquist frequency.
+    InvalidAnalysisBand { min_hz: f32, max_hz: f32 },
+    /// The analysis length should be a power of two no larger than `MAX_FFT_LENGTH`.
+    InvalidAnalysisLength(usize),
+    /// The fitness sharing distance should be positive.
+    InvalidSharingDistance(f32),
+    /// Each steady-state step should produce at least one offspring.
+    InvalidOffspringPerStep,
+    /// The step size of the local search should be in (0, 1].
+    InvalidLocalSearchStepSize(f32),
+    /// The parameters of the crossover operator should be within their valid ranges.
+    InvalidCrossover(CrossoverOperator),
+    /// The parameters of the mutation operator should be within their valid ranges.
+    InvalidMutation(MutationOperator),
+    /// The hall of fame should keep at least one individual.
+    InvalidHallOfFameSize,
+    /// The stagnation limit should be at least one generation.
+    InvalidStagnationLimit,
+    /// Checkpoints should be saved at least every generation.
+    InvalidCheckpointInterval,
+    /// The generations should be logged at least every generation.
+    InvalidLogInterval,
 }
 
 impl Error for GeneticSimulationError {}
 
 impl fmt::Display for GeneticSimulationError {
     fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
-        // TODO include self in output
-        write!(f, "Something went wrong")
+        match self {
+            GeneticSimulationError::MissingGenerator => write!(f, "No individual generator was given"),
+            GeneticSimulationError::MissingTarget => write!(f, "No target signal was given"),
+            GeneticSimulationError::InvalidPopulationSize(size) => {
+                write!(f, "The initial population of {size} should have at least two individuals")
+            }
+            GeneticSimulationError::InvalidMutationRate => {
+                write!(f, "The mutation rates should be between 0 and 1")
+            }
+            GeneticSimulationError::InvalidMaxGenerations => {
+                write!(f, "The simulation should run for at least one generation")
+            }
+            GeneticSimulationError::InvalidElitism { elitism, selected } => {
+                write!(f, "The {elitism} elites exceed the {selected} individuals selected on each generation")
+            }
+            GeneticSimulationError::InvalidFinalPopulation { final_size, initial } => {
+                write!(f, "The final population of {final_size} exceeds the initial population of {initial}")
+            }
+            GeneticSimulationError::InvalidSurvivorFraction(fraction) => {
+                write!(f, "The survivor fraction of {fraction} should be greater than 0 and at most 1")
+            }
+            GeneticSimulationError::InvalidFitness { details } => {
+                write!(f, "An individual has a fitness that is not finite: {details}")
+            }
+            GeneticSimulationError::InvalidSelection(strategy) => {
+                write!(f, "The parameters of the selection strategy {strategy:?} are out of range")
+            }
+            GeneticSimulationError::InvalidIslandCount => {
+                write!(f, "There should be at least one island")
+            }
+            GeneticSimulationError::InvalidRunCount => {
+                write!(f, "There should be at least one run")
+            }
+            GeneticSimulationError::InvalidMigrationInterval => {
+                write!(f, "The migration interval should be at least one generation")
+            }
+            GeneticSimulationError::InvalidMigrationCount { migration_count, selected } => {
+                write!(f, "The {migration_count} migrants exceed the {selected} individuals selected on each generation")
+            }
+            GeneticSimulationError::CheckpointNotSaved { details } => {
+                write!(f, "The checkpoint could not be saved: {details}")
+            }
+            GeneticSimulationError::ExportFailed { details } => {
+                write!(f, "The results could not be exported: {details}")
+            }
+            GeneticSimulationError::InvalidOffspringPerPair => {
+                write!(f, "Each pair should perform at least one crossover")
+            }
+            GeneticSimulationError::InvalidAnalysisBand { min_hz, max_hz } => {
+                write!(f, "The analysis band from {min_hz} Hz to {max_hz} Hz should be non-empty and below the Nyquist frequency")
+            }
+            GeneticSimulationError::InvalidAnalysisLength(length) => {
+                write!(f, "The analysis length of {length} should be a power of two no larger than {MAX_FFT_LENGTH}")
+            }
+            GeneticSimulationError::InvalidSharingDistance(sigma) => {
+                write!(f, "The sharing distance of {sigma} should be positive")
+            }
+            GeneticSimulationError::InvalidOffspringPerStep => {
+                write!(f, "Each step should produce at least one offspring")
+            }
+            GeneticSimulationError::InvalidLocalSearchStepSize(step_size) => {
+                write!(f, "The local search step size of {step_size} should be greater than 0 and at most 1")
+            }
+            GeneticSimulationError::InvalidCrossover(crossover) => {
+                write!(f, "The parameters of the crossover operator {crossover:?} are out of range")
+            }
+            GeneticSimulationError::InvalidMutation(mutation) => {
+                write!(f, "The parameters of the mutation operator {mutation:?} are out of range")
+            }
+            GeneticSimulationError::InvalidHallOfFameSize => {
+                write!(f, "The hall of fame should keep at least one individual")
+            }
+            GeneticSimulationError::InvalidStagnationLimit => {
+                write!(f, "The stagnation limit should be at least one generation")
+            }
+            GeneticSimulationError::InvalidCheckpointInterval => {
+                write!(f, "The checkpoint interval should be at least one generation")
+            }
+            GeneticSimulationError::InvalidLogInterval => {
+                write!(f, "The logging interval should be at least one generation")
+            }
+            // TODO include self in output
+            _ => write!(f, "Something went wrong"),
+        }
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, PartialEq)]
 pub enum HillClimbingSimulationError {
     NoFitterNeighbourFound,
     GeneratorMissing,
     TargetMissing,
+    /// The initial step size should be positive and finite.
+    InvalidStepSize(f32),
+    /// The minimum step size should be below the initial one, or the climb would stop straight away.
+    InvalidMinStepSize { min_step_size: f32, init_step_size: f32 },
+    /// The simulation should run for at least one iteration.
+    InvalidMaxIterations,
+    /// The factors of the step schedule should be within their valid ranges.
+    InvalidStepSchedule(StepSchedule),
+    /// The fitness history of late acceptance should hold at least one iteration.
+    InvalidLateAcceptance,
+    /// Each climb should run for at least one iteration.
+    InvalidIterationsPerRestart,
+    /// Each step should evaluate at least one neighbour.
+    InvalidNeighboursPerIter,
+    /// Checkpoints should be saved at least every iteration.
+    InvalidCheckpointInterval,
+    /// A multi-start run should have at least one climber.
+    InvalidClimberCount,
+    /// The analysis band should be non-empty and below the Nyquist frequency.
+    InvalidAnalysisBand { min_hz: f32, max_hz: f32 },
+    /// The analysis length should be a power of two no larger than `MAX_FFT_LENGTH`.
+    InvalidAnalysisLength(usize),
+    /// The checkpoint could not be written while the simulation was running.
+    CheckpointNotSaved { details: String },
 }
 
 impl Error for HillClimbingSimulationError {}
 
 impl fmt::Display for HillClimbingSimulationError {
     fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
-        // TODO include self in output
-        write!(f, "Something went wrong")
+        match self {
+            HillClimbingSimulationError::GeneratorMissing => write!(f, "No individual generator was given"),
+            HillClimbingSimulationError::TargetMissing => write!(f, "No target signal was given"),
+            HillClimbingSimulationError::InvalidStepSize(step_size) => {
+                write!(f, "The initial step size of {step_size} should be positive and finite")
+            }
+            HillClimbingSimulationError::InvalidMinStepSize { min_step_size, init_step_size } => {
+                write!(f, "The minimum step size of {min_step_size} should be below the initial step size of {init_step_size}")
+            }
+            HillClimbingSimulationError::InvalidMaxIterations => {
+                write!(f, "The simulation should run for at least one iteration")
+            }
+            HillClimbingSimulationError::InvalidStepSchedule(schedule) => {
+                write!(f, "The factors of the step schedule {schedule:?} are out of range")
+            }
+            HillClimbingSimulationError::InvalidLateAcceptance => {
+                write!(f, "The fitness history should hold at least one iteration")
+            }
+            HillClimbingSimulationError::InvalidIterationsPerRestart => {
+                write!(f, "Each climb should run for at least one iteration")
+            }
+            HillClimbingSimulationError::InvalidNeighboursPerIter => {
+                write!(f, "Each step should evaluate at least one neighbour")
+            }
+            HillClimbingSimulationError::InvalidCheckpointInterval => {
+                write!(f, "The checkpoint interval should be at least one iteration")
+            }
+            HillClimbingSimulationError::InvalidClimberCount => {
+                write!(f, "There should be at least one climber")
+            }
+            HillClimbingSimulationError::InvalidAnalysisBand { min_hz, max_hz } => {
+                write!(f, "The analysis band from {min_hz} Hz to {max_hz} Hz should be non-empty and below the Nyquist frequency")
+            }
+            HillClimbingSimulationError::InvalidAnalysisLength(length) => {
+                write!(f, "The analysis length of {length} should be a power of two no larger than {MAX_FFT_LENGTH}")
+            }
+            HillClimbingSimulationError::CheckpointNotSaved { details } => {
+                write!(f, "The checkpoint could not be saved: {details}")
+            }
+            // TODO include self in output
+            _ => write!(f, "Something went wrong"),
+        }
     }
 }
 
@@ -41,6 +246,13 @@ impl fmt::Display for HillClimbingSimulationError {
 pub enum SignalProcessingError {
     InvalidSpectrum(SpectrumAnalyzerError),
     CouldNotReadFromFile(&'static str),
+    CouldNotWriteToFile(String),
+    InvalidFftLength(usize),
+    /// The hop between the frames of a spectrogram should be positive.
+    InvalidHop,
+    ZeroEnergy,
+    SpectrumLengthMismatch { a: usize, b: usize },
+    SignalLengthMismatch { a: usize, b: usize },
 }
 
 impl Error for SignalProcessingError {}
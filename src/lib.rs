@@ -16,16 +16,88 @@ pub mod simulation;
 
 mod error;
 
-#[derive(Copy, Clone, Debug, PartialEq)]
+use crate::signal_processing::signal_analysis::{SpectrumConfig, WindowFunction};
+use serde::{Serialize, Deserialize};
+
+/// Metric used to calculate the fitness of an individual with respect to the target. The variants
+/// carry their own configuration, and the constructor functions, e.g.
+/// `FitnessType::freq_domain_mse()`, provide the default one.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 pub enum FitnessType {
-    FreqDomainMSE,
-    TimeDomainEuclidean,
+    /// Mean squared error between the frequency spectra. The band and window function, if
+    /// specified, override the analysis settings of the generator for this metric only.
+    FreqDomainMSE {
+        limit: Option<(f32, f32)>,
+        window: Option<WindowFunction>,
+    },
+    /// Euclidean distance between the samples. If `max_lag_ms` is specified, the individual's
+    /// signal is first aligned with the target at the lag within `±max_lag_ms` milliseconds that
+    /// maximises their cross-correlation, so that otherwise perfect but slightly shifted candidates
+    /// are not punished.
+    TimeDomainEuclidean {
+        max_lag_ms: Option<f32>,
+    },
+    /// Mean absolute error between the samples, which unlike the squared error is not dominated by a
+    /// few large mismatches, e.g. in the onsets of percussive sounds.
+    TimeDomainMAE,
+    /// Root-mean-square error between the samples normalised by the RMS of the target. Every
+    /// individual has a fitness of 0 against a silent target.
+    TimeDomainNRMSE,
+    /// Signal-to-noise ratio between the frequency spectrum of the target and the error of the
+    /// individual, which unlike the MSE is comparable across loud and quiet targets. Every
+    /// individual has a fitness of 0 against a silent target.
+    SpectralSNR,
+    /// Itakura–Saito divergence between the power spectrum of the target and that of the
+    /// individual, which tracks the perceived match of resonant sounds better than the MSE.
+    ItakuraSaito,
+    /// Cosine similarity between the magnitude spectra of the individual and the target, which
+    /// ignores any difference in their overall level.
+    SpectralCosine,
+    /// Mean squared error between the amplitude envelopes, i.e. the RMS over consecutive windows of
+    /// `window_ms` milliseconds, which rewards matching the dynamics of the target regardless of its
+    /// spectrum. It is best combined with a spectral metric in `Weighted`.
+    EnvelopeMSE {
+        window_ms: f32,
+    },
+    /// Weighted sum of several fitness types, e.g. `Weighted(vec![(freq_domain_mse(), 0.7),
+    /// (time_domain_euclidean(), 0.3)])`. The weights are normalised to add up to 1, and those of
+    /// zero are not evaluated at all.
+    Weighted(Vec<(FitnessType, f32)>),
     // TimeDomainCrossCorr,
 }
 
+impl FitnessType {
+    /// Frequency-domain MSE using the analysis settings of the generator.
+    pub fn freq_domain_mse() -> Self {
+        FitnessType::FreqDomainMSE { limit: None, window: None }
+    }
+
+    /// Time-domain Euclidean distance without any alignment.
+    pub fn time_domain_euclidean() -> Self {
+        FitnessType::TimeDomainEuclidean { max_lag_ms: None }
+    }
+
+    /// Time-domain Euclidean distance after aligning the signals within `±max_lag_ms` milliseconds.
+    pub fn time_domain_euclidean_aligned(max_lag_ms: f32) -> Self {
+        FitnessType::TimeDomainEuclidean { max_lag_ms: Some(max_lag_ms) }
+    }
+
+    /// Applies the analysis settings carried by the fitness type on top of the given ones.
+    pub fn spectrum_config(&self, base: SpectrumConfig) -> SpectrumConfig {
+        match self {
+            FitnessType::FreqDomainMSE { limit, window } => SpectrumConfig {
+                band: limit.or(base.band),
+                window: window.unwrap_or(base.window),
+                ..base
+            },
+            _ => base,
+        }
+    }
+}
+
 impl Default for FitnessType {
     fn default() -> Self {
-        FitnessType::FreqDomainMSE
+        FitnessType::freq_domain_mse()
     }
 }
 
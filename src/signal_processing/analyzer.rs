@@ -0,0 +1,141 @@
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::fmt;
+use std::fmt::{Debug, Formatter};
+use std::sync::{Arc, Mutex};
+use itertools::Itertools;
+use rustfft::num_complex::Complex;
+use rustfft::{Fft, FftPlanner};
+use spectrum_analyzer::error::SpectrumAnalyzerError;
+use spectrum_analyzer::scaling::SpectrumScalingFunction;
+use spectrum_analyzer::{Frequency, FrequencyLimit, FrequencySpectrum, FrequencyValue};
+
+type FftBuffer = Vec<Complex<f32>>;
+type FftPlans = HashMap<usize, Arc<dyn Fft<f32>>>;
+
+thread_local! {
+    /// Input and scratch buffers of the FFT, reused by every transform on the same thread.
+    static BUFFERS: RefCell<(FftBuffer, FftBuffer)> = const { RefCell::new((vec![], vec![])) };
+}
+
+/// Calculates frequency spectra while keeping the FFT plan of every length it has transformed, so
+/// that repeated analyses of blocks of the same length, e.g. the fitness evaluations of a
+/// simulation, do not plan the FFT again. Clones share the same plans and can be used from several
+/// threads at once.
+#[derive(Clone, Default)]
+pub struct SpectrumAnalyzer {
+    plans: Arc<Mutex<FftPlans>>,
+}
+
+impl SpectrumAnalyzer {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Calculates the magnitude spectrum of the samples, whose length must be a power of two, in the
+    /// same way as `spectrum_analyzer::samples_fft_to_spectrum`.
+    pub fn spectrum(
+        &self,
+        samples: &[f32],
+        sampling_rate: u32,
+        limit: FrequencyLimit,
+        scaling: Option<&SpectrumScalingFunction>,
+    ) -> Result<FrequencySpectrum, SpectrumAnalyzerError> {
+        if samples.len() < 2 {
+            return Err(SpectrumAnalyzerError::TooFewSamples);
+        }
+        if samples.iter().any(|x| x.is_nan()) {
+            return Err(SpectrumAnalyzerError::NaNValuesNotSupported);
+        }
+        if samples.iter().any(|x| x.is_infinite()) {
+            return Err(SpectrumAnalyzerError::InfinityValuesNotSupported);
+        }
+        if !samples.len().is_power_of_two() {
+            return Err(SpectrumAnalyzerError::SamplesLengthNotAPowerOfTwo);
+        }
+        limit.verify(sampling_rate as f32 / 2.0).map_err(SpectrumAnalyzerError::InvalidFrequencyLimit)?;
+
+        let fft = self.plan(samples.len());
+        let resolution = sampling_rate as f32 / samples.len() as f32;
+
+        let data = BUFFERS.with(|buffers| {
+            let (buffer, scratch) = &mut *buffers.borrow_mut();
+            buffer.clear();
+            buffer.extend(samples.iter().map(|&s| Complex::new(s, 0.0)));
+            scratch.resize(fft.get_inplace_scratch_len(), Complex::default());
+            fft.process_with_scratch(buffer, scratch);
+
+            buffer.iter()
+                .take(samples.len() / 2 + 1)
+                .enumerate()
+                .map(|(i, c)| (i as f32 * resolution, c.norm()))
+                .filter(|(fr, _)| limit.maybe_min().is_none_or(|min| *fr >= min))
+                .filter(|(fr, _)| limit.maybe_max().is_none_or(|max| *fr <= max))
+                .map(|(fr, val)| (Frequency::from(fr), FrequencyValue::from(val)))
+                .collect::<Vec<_>>()
+        });
+
+        let mut working_buffer = data.clone();
+        let mut spectrum = FrequencySpectrum::new(data, resolution, samples.len() as u32, &mut working_buffer);
+        if let Some(scaling) = scaling {
+            spectrum.apply_scaling_fn(scaling, &mut working_buffer)?;
+        }
+
+        Ok(spectrum)
+    }
+
+    /// Forward FFT plan for the given length, which is only planned the first time it is needed.
+    fn plan(&self, length: usize) -> Arc<dyn Fft<f32>> {
+        let mut plans = self.plans.lock().expect("The FFT plans should not be poisoned.");
+        Arc::clone(plans.entry(length).or_insert_with(|| FftPlanner::new().plan_fft_forward(length)))
+    }
+}
+
+impl Debug for SpectrumAnalyzer {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let lengths = self.plans.lock().map(|plans| plans.keys().copied().sorted().collect::<Vec<_>>()).unwrap_or_default();
+        f.debug_struct("SpectrumAnalyzer").field("planned_lengths", &lengths).finish()
+    }
+}
+
+/// All analysers calculate the same spectra, regardless of the plans they have cached.
+impl PartialEq for SpectrumAnalyzer {
+    fn eq(&self, _other: &Self) -> bool {
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use spectrum_analyzer::samples_fft_to_spectrum;
+    use crate::signal_processing::components::oscillator::sine_wave;
+    use crate::signal_processing::SAMPLE_RATE;
+
+    #[test]
+    fn test_matches_spectrum_analyzer() {
+        let samples = sine_wave(440.0, 0.5, SAMPLE_RATE as f32, 1.0, 0.0).samples()[..8_192].to_vec();
+        let limit = FrequencyLimit::Range(20.0, 10_000.0);
+        let scaling: &SpectrumScalingFunction = &|val, info| val - info.min;
+
+        let expected = samples_fft_to_spectrum(&samples, SAMPLE_RATE, limit, Some(scaling)).unwrap();
+        let analyzer = SpectrumAnalyzer::new();
+        for _ in 0..2 {
+            let spectrum = analyzer.spectrum(&samples, SAMPLE_RATE, limit, Some(scaling)).unwrap();
+            assert_eq!(spectrum.data().len(), expected.data().len());
+            for ((fr, fv), (efr, efv)) in spectrum.data().iter().zip(expected.data()) {
+                assert_eq!(fr.val(), efr.val());
+                assert!((fv.val() - efv.val()).abs() <= 1e-3 * expected.max().1.val());
+            }
+        }
+    }
+
+    #[test]
+    fn test_invalid_samples() {
+        let analyzer = SpectrumAnalyzer::new();
+        assert!(matches!(analyzer.spectrum(&[0.0; 1_000], SAMPLE_RATE, FrequencyLimit::All, None),
+                         Err(SpectrumAnalyzerError::SamplesLengthNotAPowerOfTwo)));
+        assert!(matches!(analyzer.spectrum(&[f32::NAN; 1_024], SAMPLE_RATE, FrequencyLimit::All, None),
+                         Err(SpectrumAnalyzerError::NaNValuesNotSupported)));
+    }
+}
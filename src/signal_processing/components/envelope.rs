@@ -1,8 +1,183 @@
-use crate::simulation::components::envelope::EnvelopeComponent;
-use crate::signal_processing::Signal;
+use crate::simulation::components::envelope::{CurveShape, EnvelopeComponent};
+use crate::signal_processing::{Signal, SAMPLE_RATE};
+
+/// Number of time constants spanned by an exponential segment, which is then scaled to end exactly
+/// on its target rather than within 1% of it.
+const EXPONENTIAL_STEEPNESS: f32 = 5.0;
 
 impl Signal {
+    /// Shapes the amplitude of the signal with the stages of the envelope: a rise from silence to
+    /// full level over the attack, a fall to the sustain level over the decay, and a fall to silence
+    /// over the release, which ends with the signal. The sustain level is held in between. If the
+    /// attack, decay and release are longer than the signal altogether, they are shortened in
+    /// proportion until they fit in it exactly, leaving no time for the sustain.
     pub fn apply_envelope(&mut self, envelope: EnvelopeComponent) {
-        todo!()
+        let gains = envelope_gains(&envelope, self.n_samples(), SAMPLE_RATE as f32);
+
+        for (sample, gain) in self.0.iter_mut().zip(gains) {
+            *sample *= gain;
+        }
+    }
+}
+
+/// Gain of the envelope at each of the `n` samples of a signal with the given sample rate.
+pub fn envelope_gains(envelope: &EnvelopeComponent, n: usize, sample_rate: f32) -> Vec<f32> {
+    let duration = n as f32 / sample_rate;
+    let total = envelope.attack + envelope.decay + envelope.release;
+    let scale = if total > duration { duration / total } else { 1.0 };
+
+    let samples = |seconds: f32| (seconds * scale * sample_rate) as usize;
+    let (attack, decay, release) = (samples(envelope.attack), samples(envelope.decay), samples(envelope.release).min(n));
+    let sustain = envelope.sustain_level;
+    // level of a segment from `from` to `to` at the fraction `progress` of its length
+    let segment = |from: f32, to: f32, progress: f32| from + (to - from) * curve(envelope.curve, progress);
+
+    // level of the attack, decay and sustain stages, which the release starts from
+    let level = |i: usize| {
+        if i < attack {
+            segment(0.0, 1.0, i as f32 / attack as f32)
+        } else if i < attack + decay {
+            segment(1.0, sustain, (i - attack) as f32 / decay as f32)
+        } else {
+            sustain
+        }
+    };
+
+    let release_start = n - release;
+    let release_level = level(release_start);
+
+    (0..n)
+        .map(|i| if i < release_start {
+            level(i)
+        } else {
+            segment(release_level, 0.0, (i - release_start) as f32 / release as f32)
+        })
+        .collect()
+}
+
+/// Fraction of the way between the levels of a segment covered at the fraction `progress` of its
+/// length, which goes from 0 at its start to 1 at its end.
+fn curve(shape: CurveShape, progress: f32) -> f32 {
+    match shape {
+        CurveShape::Linear => progress,
+        CurveShape::Exponential => {
+            (1.0 - (-EXPONENTIAL_STEEPNESS * progress).exp()) / (1.0 - (-EXPONENTIAL_STEEPNESS).exp())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::simulation::components::envelope::{CurveShape, EnvelopeComponent};
+    use crate::signal_processing::{Signal, LENGTH, SAMPLE_RATE};
+
+    fn constant() -> Signal {
+        Signal::from_samples(&vec![0.5; (LENGTH * SAMPLE_RATE as f32) as usize])
+    }
+
+    fn envelope(attack: f32, decay: f32, sustain_level: f32, release: f32, curve: CurveShape) -> EnvelopeComponent {
+        EnvelopeComponent { attack, decay, sustain_level, release, curve }
+    }
+
+    #[test]
+    fn test_adsr_stages() {
+        let mut signal = constant();
+        signal.apply_envelope(envelope(0.1, 0.2, 0.2, 0.5, CurveShape::Linear));
+        let samples = signal.samples();
+        let ms = |ms: usize| ms * SAMPLE_RATE as usize / 1000;
+        let n = samples.len();
+        let close = |a: f32, b: f32| (a - b).abs() < 1e-3;
+
+        // the attack rises from silence to the level of the input
+        assert_eq!(samples[0], 0.0);
+        assert!(close(samples[ms(50)], 0.25));
+        assert!(close(samples[ms(100)], 0.5));
+        // the decay falls to the sustain level, of a fifth of the input
+        assert!(close(samples[ms(200)], 0.5 * 0.6));
+        assert!(close(samples[ms(300)], 0.1));
+        // which is held until the release
+        assert!(samples[ms(300)..n - ms(500)].iter().all(|&s| close(s, 0.1)));
+        // and the release falls to silence at the end of the signal
+        assert!(close(samples[n - ms(250)], 0.05));
+        assert!(samples[n - 1] < 1e-5);
+        assert!(samples.windows(2).skip(ms(100)).all(|w| w[1] <= w[0]));
+    }
+
+    #[test]
+    fn test_stages_exceeding_signal() {
+        let n = (LENGTH * SAMPLE_RATE as f32) as usize;
+
+        // an attack longer than the signal is shortened to rise over the whole of it
+        let mut signal = constant();
+        signal.apply_envelope(envelope(6.0, 0.0, 1.0, 0.0, CurveShape::Linear));
+        assert_eq!(signal.n_samples(), n);
+        assert!((signal.samples()[n / 2] - 0.25).abs() < 1e-3);
+        assert!((signal.samples()[n - 1] - 0.5).abs() < 1e-3);
+
+        // and a release longer than the signal falls over the whole of it
+        let mut signal = constant();
+        signal.apply_envelope(envelope(0.0, 0.0, 1.0, 5.0, CurveShape::Linear));
+        assert_eq!(signal.samples()[0], 0.5);
+        assert!((signal.samples()[n / 2] - 0.25).abs() < 1e-3);
+
+        // without any stages the signal is left as it is
+        let mut signal = constant();
+        signal.apply_envelope(envelope(0.0, 0.0, 1.0, 0.0, CurveShape::Exponential));
+        assert_eq!(signal, constant());
+    }
+
+    #[test]
+    fn test_curve_shapes() {
+        let attack = |curve| {
+            let mut signal = constant();
+            signal.apply_envelope(envelope(1.0, 0.0, 1.0, 0.0, curve));
+            signal.samples()[..SAMPLE_RATE as usize + 1].to_vec()
+        };
+        let (linear, exponential) = (attack(CurveShape::Linear), attack(CurveShape::Exponential));
+        let (quarter, half, end) = (SAMPLE_RATE as usize / 4, SAMPLE_RATE as usize / 2, SAMPLE_RATE as usize);
+
+        // both start from silence and reach the level of the input at the end of the attack
+        assert_eq!((linear[0], exponential[0]), (0.0, 0.0));
+        assert!((linear[end] - 0.5).abs() < 1e-6 && (exponential[end] - 0.5).abs() < 1e-6);
+        // the linear attack rises steadily, while the exponential one rises quickly and then slows
+        assert!((linear[half] - 0.25).abs() < 1e-3);
+        assert!(exponential[quarter] > 0.35 && exponential[half] > 0.45);
+        assert!(exponential[1..end].iter().zip(&linear[1..end]).all(|(e, l)| e > l));
+        assert!(exponential.windows(2).all(|w| w[1] >= w[0]));
+    }
+
+    #[test]
+    fn test_longest_stages() {
+        // the longest stages add up to 10 s, so they are shortened to 30% to fit in the 3 s signal
+        let mut signal = constant();
+        signal.apply_envelope(envelope(2.0, 3.0, 0.4, 5.0, CurveShape::Linear));
+        let samples = signal.samples();
+        let at = |seconds: f32| samples[(seconds * SAMPLE_RATE as f32) as usize];
+        let close = |a: f32, b: f32| (a - b).abs() < 1e-3;
+
+        assert!(close(at(0.3), 0.25));
+        assert!(close(at(0.6), 0.5));
+        assert!(close(at(1.05), 0.5 * 0.7));
+        assert!(close(at(1.5), 0.2));
+        assert!(close(at(2.25), 0.1));
+        assert!(samples[samples.len() - 1] < 1e-5);
+    }
+
+    #[test]
+    fn test_short_signal() {
+        // a 0.2 s signal fits half of stages that would last 0.4 s
+        let n = (0.2 * SAMPLE_RATE as f32) as usize;
+        let mut signal = Signal::from_samples(&vec![0.5; n]);
+        signal.apply_envelope(envelope(0.1, 0.2, 0.5, 0.1, CurveShape::Exponential));
+        let samples = signal.samples();
+        let at = |seconds: f32| samples[(seconds * SAMPLE_RATE as f32) as usize];
+
+        assert_eq!(signal.n_samples(), n);
+        assert_eq!(samples[0], 0.0);
+        assert!((at(0.05) - 0.5).abs() < 1e-3);
+        assert!((at(0.15) - 0.25).abs() < 1e-3);
+        assert!(samples[n - 1] < 1e-3);
+        let peak = samples.iter().copied().fold(0.0, f32::max);
+        assert!((peak - 0.5).abs() < 1e-3);
     }
 }
@@ -1,31 +1,95 @@
 use std::f32::consts::PI;
 use crate::signal_processing::{SAMPLE_RATE, Signal};
-use crate::simulation::components::filters::FilterComponent;
+use crate::simulation::components::filters::{max_freq, BiquadKind, FilterComponent, FilterEnvelope};
 use crate::utils;
 
+/// Number of samples filtered with the same frequencies when they follow an envelope, i.e. about
+/// 12 ms.
+const MODULATION_BLOCK: usize = 512;
+
 impl Signal {
 
     pub(crate) fn apply_filter(&mut self, filter_comp: FilterComponent) {
-        let filter = match filter_comp {
+        if let FilterComponent::Biquad { kind, cutoff, q } = filter_comp {
+            let mut biquad = Biquad::default();
+            biquad.set_coefficients(kind, cutoff, q, SAMPLE_RATE as f32);
+            self.0.iter_mut().for_each(|sample| *sample = biquad.process(*sample));
+            return;
+        }
+
+        let filter = Self::filter_kernel(filter_comp);
+
+        *self = Signal::from_samples(&utils::fast_convolve(&filter, self.samples()))
+    }
+
+    /// Applies a filter whose frequencies follow an envelope, i.e. are offset by its amount times
+    /// its level. The signal is split into blocks of `MODULATION_BLOCK` samples, each of them
+    /// filtered with the frequencies at its centre and overlap-added, so that the result has the
+    /// same length as that of `apply_filter`. A biquad keeps its state from one block to the next,
+    /// and only its coefficients change.
+    pub(crate) fn apply_filter_modulated(&mut self, filter_comp: FilterComponent, envelope: FilterEnvelope) {
+        let offset = |start: usize, len: usize| {
+            envelope.amount * envelope.level((start + len / 2) as f32 / SAMPLE_RATE as f32)
+        };
+
+        if let FilterComponent::Biquad { .. } = filter_comp {
+            let mut biquad = Biquad::default();
+            for (i, block) in self.0.chunks_mut(MODULATION_BLOCK).enumerate() {
+                if let FilterComponent::Biquad { kind, cutoff, q } = filter_comp.shifted(offset(i * MODULATION_BLOCK, block.len())) {
+                    biquad.set_coefficients(kind, cutoff, q, SAMPLE_RATE as f32);
+                }
+                block.iter_mut().for_each(|sample| *sample = biquad.process(*sample));
+            }
+            return;
+        }
+
+        // the length of the kernel depends on the band only, which is not modulated
+        let half_len = Self::filter_kernel(filter_comp).len() / 2;
+        let samples = self.samples();
+        let mut output = vec![0.0; (samples.len() + half_len).saturating_sub(1)];
+
+        for (i, block) in samples.chunks(MODULATION_BLOCK).enumerate() {
+            let start = i * MODULATION_BLOCK;
+            let filter = Self::filter_kernel(filter_comp.shifted(offset(start, block.len())));
+
+            // the kernel is centred on each sample, in the same way as in `utils::convolve`
+            for (j, &sample) in block.iter().enumerate() {
+                for (k, &tap) in filter.iter().enumerate() {
+                    let output_idx = (start + j + half_len).checked_sub(k);
+                    if let Some(out) = output_idx.and_then(|idx| output.get_mut(idx)) {
+                        *out += sample * tap;
+                    }
+                }
+            }
+        }
+
+        *self = Signal::from_samples(&output)
+    }
+
+    fn filter_kernel(filter_comp: FilterComponent) -> Vec<f32> {
+        let sample_rate = SAMPLE_RATE as f32;
+        match filter_comp {
             FilterComponent::LowPass { cutoff_freq, band } => {
-                Self::low_pass_filter(cutoff_freq, band)
+                Self::low_pass_filter(cutoff_freq, band, sample_rate)
             }
             FilterComponent::HighPass { cutoff_freq, band } => {
-                Self::high_pass_filter(cutoff_freq, band)
+                Self::high_pass_filter(cutoff_freq, band, sample_rate)
             }
             FilterComponent::BandPass { low_freq: low_frequency, high_freq: high_frequency, band } => {
-                Self::band_pass_filter(low_frequency, high_frequency, band)
+                Self::band_pass_filter(low_frequency, high_frequency, band, sample_rate)
             }
             FilterComponent::BandReject { low_freq: low_frequency, high_freq: high_frequency, band } => {
-                Self::band_reject_filter(low_frequency, high_frequency, band)
+                Self::band_reject_filter(low_frequency, high_frequency, band, sample_rate)
             }
-        };
-
-        *self = Signal::from_samples(&utils::convolve(&filter, self.samples()))
+            FilterComponent::Biquad { .. } => unreachable!("Biquad filters are recursive and have no kernel"),
+        }
     }
 
-    fn low_pass_filter(cutoff_freq: f32, band: f32) -> Vec<f32> {
-        let cutoff = Self::cutoff_from_frequency(cutoff_freq);
+    /// Designs a windowed-sinc low-pass filter for a signal with the given sample rate. Like the
+    /// rest of the filters, frequencies above the highest one allowed at that rate are clamped to
+    /// it, since the main lobe of the sinc would no longer fit below the Nyquist frequency.
+    fn low_pass_filter(cutoff_freq: f32, band: f32, sample_rate: f32) -> Vec<f32> {
+        let cutoff = Self::cutoff_from_frequency(cutoff_freq, sample_rate);
 
         // Filter length, i.e. the number of points in the filter. Inversely proportional to the
         // bandwidth.
@@ -54,25 +118,213 @@ impl Signal {
         filter.iter().map(|&el| el / sum).collect()
     }
 
-    fn high_pass_filter(cutoff: f32, band: f32) -> Vec<f32> {
-        utils::spectral_invert(&Self::low_pass_filter(cutoff, band))
+    fn high_pass_filter(cutoff: f32, band: f32, sample_rate: f32) -> Vec<f32> {
+        utils::spectral_invert(&Self::low_pass_filter(cutoff, band, sample_rate))
     }
 
-    fn band_pass_filter(low_freq: f32, high_freq: f32, band: f32) -> Vec<f32> {
+    fn band_pass_filter(low_freq: f32, high_freq: f32, band: f32, sample_rate: f32) -> Vec<f32> {
         assert!(low_freq <= high_freq);
-        let low_pass = Self::low_pass_filter(high_freq, band);
-        let high_pass = Self::high_pass_filter(low_freq, band);
+        let low_pass = Self::low_pass_filter(high_freq, band, sample_rate);
+        let high_pass = Self::high_pass_filter(low_freq, band, sample_rate);
         utils::add(&high_pass, &low_pass)
     }
 
-    fn band_reject_filter(low_freq: f32, high_freq: f32, band: f32) -> Vec<f32> {
+    fn band_reject_filter(low_freq: f32, high_freq: f32, band: f32, sample_rate: f32) -> Vec<f32> {
         assert!(low_freq <= high_freq);
-        let low_pass = Self::low_pass_filter(low_freq, band);
-        let high_pass = Self::high_pass_filter(high_freq, band);
+        let low_pass = Self::low_pass_filter(low_freq, band, sample_rate);
+        let high_pass = Self::high_pass_filter(high_freq, band, sample_rate);
         utils::convolve(&high_pass, &low_pass)
     }
 
-    fn cutoff_from_frequency(freq: f32) -> f32 {
-        freq / SAMPLE_RATE as f32
+    /// The frequency as a fraction of the sample rate, clamped to the highest one allowed at it.
+    fn cutoff_from_frequency(freq: f32, sample_rate: f32) -> f32 {
+        freq.min(max_freq(sample_rate)) / sample_rate
+    }
+}
+
+/// Second-order recursive filter in direct form II, i.e. with the coefficients normalised by `a0`
+/// and the two previous values of its internal state.
+#[derive(Default)]
+struct Biquad {
+    b0: f32,
+    b1: f32,
+    b2: f32,
+    a1: f32,
+    a2: f32,
+    z1: f32,
+    z2: f32,
+}
+
+impl Biquad {
+    /// Sets the coefficients from the formulae of the RBJ audio EQ cookbook, where the band-pass
+    /// has a gain of 1 at its cutoff. The cutoff is clamped to the highest one allowed at the sample
+    /// rate, above which it would alias.
+    fn set_coefficients(&mut self, kind: BiquadKind, cutoff: f32, q: f32, sample_rate: f32) {
+        let w0 = 2.0 * PI * cutoff.min(max_freq(sample_rate)) / sample_rate;
+        let (sin, cos) = w0.sin_cos();
+        let alpha = sin / (2.0 * q);
+
+        let (b0, b1, b2) = match kind {
+            BiquadKind::LowPass => ((1.0 - cos) / 2.0, 1.0 - cos, (1.0 - cos) / 2.0),
+            BiquadKind::HighPass => ((1.0 + cos) / 2.0, -(1.0 + cos), (1.0 + cos) / 2.0),
+            BiquadKind::BandPass => (alpha, 0.0, -alpha),
+            BiquadKind::Notch => (1.0, -2.0 * cos, 1.0),
+        };
+        let (a0, a1, a2) = (1.0 + alpha, -2.0 * cos, 1.0 - alpha);
+
+        self.b0 = b0 / a0;
+        self.b1 = b1 / a0;
+        self.b2 = b2 / a0;
+        self.a1 = a1 / a0;
+        self.a2 = a2 / a0;
+    }
+
+    fn process(&mut self, sample: f32) -> f32 {
+        let w = sample - self.a1 * self.z1 - self.a2 * self.z2;
+        let output = self.b0 * w + self.b1 * self.z1 + self.b2 * self.z2;
+        self.z2 = self.z1;
+        self.z1 = w;
+        output
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::signal_processing::LENGTH;
+    use crate::signal_processing::components::oscillator::{saw_wave, sine_wave};
+    use rand::{Rng, SeedableRng};
+    use rand::rngs::StdRng;
+
+    /// Spectral centroid of each frame of the spectrogram of the signal.
+    fn centroid_trajectory(signal: &Signal) -> Vec<f32> {
+        let spectrogram = signal.spectrogram(2_048, 2_048).unwrap();
+        spectrogram.magnitudes.iter()
+            .map(|frame| {
+                let weighted: f32 = frame.iter().zip(&spectrogram.frequencies).map(|(m, f)| m * f).sum();
+                weighted / frame.iter().sum::<f32>()
+            })
+            .collect()
+    }
+
+    fn filtered(envelope: FilterEnvelope) -> Signal {
+        let mut signal = saw_wave(110.0, LENGTH, SAMPLE_RATE as f32, 0.5, 0.0);
+        signal.apply_filter_modulated(FilterComponent::LowPass { cutoff_freq: 500.0, band: 0.05 }, envelope);
+        signal
+    }
+
+    #[test]
+    fn test_unmodulated_filter() {
+        let filter = FilterComponent::HighPass { cutoff_freq: 2_000.0, band: 0.1 };
+        let saw = saw_wave(110.0, 0.5, SAMPLE_RATE as f32, 0.5, 0.0);
+        let (mut a, mut b) = (saw.clone(), saw.clone());
+        a.apply_filter(filter);
+        b.apply_filter_modulated(filter, FilterEnvelope { amount: 0.0, attack: 0.1, decay: 0.1 });
+
+        // without an amount the blocks add up to the filtered signal
+        assert_eq!(a.n_samples(), b.n_samples());
+        assert!(a.samples().iter().zip(b.samples()).all(|(a, b)| (a - b).abs() < 1e-4));
+
+        // and a biquad keeps its state across them
+        let filter = FilterComponent::Biquad { kind: BiquadKind::LowPass, cutoff: 500.0, q: 4.0 };
+        let (mut a, mut b) = (saw.clone(), saw);
+        a.apply_filter(filter);
+        b.apply_filter_modulated(filter, FilterEnvelope { amount: 0.0, attack: 0.1, decay: 0.1 });
+        assert_eq!(a, b);
+    }
+
+    #[test]
+    fn test_cutoff_trajectory() {
+        // the cutoff sweeps down from 8.5 kHz over the first 2 s, and then stays at 500 Hz
+        let trajectory = centroid_trajectory(&filtered(FilterEnvelope { amount: 8_000.0, attack: 0.0, decay: 2.0 }));
+        let (start, middle, end) = (trajectory[1], trajectory[trajectory.len() / 3], trajectory[trajectory.len() - 2]);
+        assert!(start > middle && middle > end && start > 4.0 * end, "{trajectory:?}");
+        assert!(trajectory.windows(2).take(trajectory.len() * 2 / 3).all(|w| w[1] < w[0] * 1.05), "{trajectory:?}");
+
+        // and doesn't move without an amount
+        let trajectory = centroid_trajectory(&filtered(FilterEnvelope { amount: 0.0, attack: 0.0, decay: 2.0 }));
+        let (min, max) = trajectory.iter().fold((f32::MAX, 0.0f32), |(min, max), &c| (min.min(c), max.max(c)));
+        assert!(max < 1.1 * min, "{trajectory:?}");
+    }
+
+    #[test]
+    fn test_sweep_below_base_cutoff() {
+        // the envelope pulls the cutoff far below 0 Hz, where it stops at a positive floor
+        let envelope = FilterEnvelope { amount: -10_000.0, attack: 0.2, decay: 0.5 };
+        let saw = saw_wave(110.0, 1.0, SAMPLE_RATE as f32, 0.5, 0.0);
+        for filter in [
+            FilterComponent::LowPass { cutoff_freq: 500.0, band: 0.05 },
+            FilterComponent::BandPass { low_freq: 300.0, high_freq: 2_000.0, band: 0.1 },
+            FilterComponent::Biquad { kind: BiquadKind::LowPass, cutoff: 500.0, q: 4.0 },
+            FilterComponent::Biquad { kind: BiquadKind::BandPass, cutoff: 1_000.0, q: 10.0 },
+        ] {
+            let mut signal = saw.clone();
+            signal.apply_filter_modulated(filter, envelope);
+            assert!(signal.samples().iter().all(|s| s.is_finite()), "{filter:?}");
+        }
+    }
+
+    #[test]
+    fn test_biquad_band_pass() {
+        let mut rng = StdRng::seed_from_u64(11);
+        let noise: Vec<f32> = (0..(LENGTH * SAMPLE_RATE as f32) as usize).map(|_| rng.gen_range(-0.5..0.5)).collect();
+        let mut signal = Signal::from_samples(&noise);
+        signal.apply_filter(FilterComponent::Biquad { kind: BiquadKind::BandPass, cutoff: 1_000.0, q: 10.0 });
+        assert_eq!(signal.n_samples(), noise.len());
+
+        let spectrum = signal.freq_spectrum().unwrap();
+        let mean = |min_hz: f32, max_hz: f32| {
+            let band: Vec<f32> = spectrum.data().iter()
+                .filter(|(fr, _)| (min_hz..max_hz).contains(&fr.val()))
+                .map(|(_, fv)| fv.val())
+                .collect();
+            band.iter().sum::<f32>() / band.len() as f32
+        };
+
+        // the peak of the white noise is at the cutoff, and an octave away it is far quieter
+        let (peak, _) = spectrum.max();
+        assert!((peak.val() - 1_000.0).abs() < 50.0, "peak at {peak}");
+        let (centre, below, above) = (mean(975.0, 1_025.0), mean(450.0, 550.0), mean(1_900.0, 2_100.0));
+        assert!(centre > 5.0 * below && centre > 5.0 * above, "{centre} against {below} and {above}");
+    }
+
+    #[test]
+    fn test_biquad_kinds() {
+        let rms = |kind, freq| {
+            let mut signal = sine_wave(freq, 0.5, SAMPLE_RATE as f32, 1.0, 0.0);
+            signal.apply_filter(FilterComponent::Biquad { kind, cutoff: 1_000.0, q: 0.707 });
+            let tail = &signal.samples()[signal.n_samples() / 2..];
+            (tail.iter().map(|s| s * s).sum::<f32>() / tail.len() as f32).sqrt()
+        };
+
+        // a sine at the cutoff of a notch is removed, while the pass filters let through those on
+        // their side of it
+        assert!(rms(BiquadKind::Notch, 1_000.0) < 0.01);
+        assert!(rms(BiquadKind::Notch, 5_000.0) > 0.6);
+        assert!(rms(BiquadKind::LowPass, 100.0) > 0.6 && rms(BiquadKind::LowPass, 10_000.0) < 0.01);
+        assert!(rms(BiquadKind::HighPass, 10_000.0) > 0.6 && rms(BiquadKind::HighPass, 100.0) < 0.01);
+    }
+
+    #[test]
+    fn test_frequencies_above_nyquist() {
+        // at 22.05 kHz the highest cutoff is 9.92 kHz, below the Nyquist frequency of 11.025 kHz
+        let sample_rate = 22_050.0;
+        let highest = max_freq(sample_rate);
+        assert!(highest < sample_rate / 2.0);
+
+        // a cutoff above it designs the same filter as the highest one, rather than a degenerate sinc
+        let low_pass = Signal::low_pass_filter(15_000.0, 0.1, sample_rate);
+        assert_eq!(low_pass, Signal::low_pass_filter(highest, 0.1, sample_rate));
+        assert!(low_pass.iter().all(|tap| tap.is_finite()));
+        assert!((low_pass.iter().sum::<f32>() - 1.0).abs() < 1e-4);
+        assert_eq!(Signal::band_pass_filter(1_000.0, 20_000.0, 0.1, sample_rate), Signal::band_pass_filter(1_000.0, highest, 0.1, sample_rate));
+
+        let filtered = |cutoff| {
+            let mut biquad = Biquad::default();
+            biquad.set_coefficients(BiquadKind::LowPass, cutoff, 2.0, sample_rate);
+            saw_wave(110.0, 0.1, sample_rate, 0.5, 0.0).into_iter().map(|sample| biquad.process(sample)).collect::<Vec<f32>>()
+        };
+        assert_eq!(filtered(20_000.0), filtered(highest));
+        assert!(filtered(20_000.0).iter().all(|sample| sample.abs() < 2.0));
     }
-}
\ No newline at end of file
+}
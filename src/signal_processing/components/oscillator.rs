@@ -1,32 +1,100 @@
-use crate::simulation::components::oscillator::OscillatorComponent;
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+use crate::simulation::components::oscillator::{MixNormalisation, OscillatorComponent, Waveform, WaveformQuality};
 use crate::signal_processing::{Signal, LENGTH, SAMPLE_RATE};
 
+/// Renders a waveform from its frequency, length, sample rate, amplitude and phase offset.
+type WaveFn = fn(f32, f32, f32, f32, f32) -> Signal;
+/// Renders a pulse waveform, which also takes its duty cycle.
+type PulseFn = fn(f32, f32, f32, f32, f32, f32) -> Signal;
+
 impl Signal {
     pub fn apply_oscillator(&mut self, oscillator: OscillatorComponent) {
-        let sine = sine_wave(
-            oscillator.freq,
-            LENGTH,
-            SAMPLE_RATE as f32,
-            oscillator.sine_amp,
-            oscillator.sine_phase,
-        );
-        let square = square_wave(
-            oscillator.freq,
-            LENGTH,
-            SAMPLE_RATE as f32,
-            oscillator.square_amp,
-            oscillator.square_phase,
-        );
-        let saw = saw_wave(
-            oscillator.freq,
-            LENGTH,
-            SAMPLE_RATE as f32,
-            oscillator.saw_amp,
-            oscillator.saw_phase,
-        );
+        let waveforms = oscillator.settings.waveforms;
+        // the disabled waveforms are silent, so they are not rendered at all
+        let mut mix = Signal::init(LENGTH, SAMPLE_RATE as f32);
+
+        let (square, saw, pulse) = match oscillator.settings.quality {
+            WaveformQuality::Naive => (square_wave as WaveFn, saw_wave as WaveFn, pulse_wave as PulseFn),
+            WaveformQuality::BandLimited => (square_wave_band_limited as WaveFn, saw_wave_band_limited as WaveFn, pulse_wave_band_limited as PulseFn),
+        };
+
+        // the hard-synced waves are slaves of every voice, which resets their phase on each cycle
+        let sync = oscillator.settings.sync;
+
+        // every unison voice is a copy of the waveforms, which share their amplitude
+        let voice_freqs = oscillator.voice_freqs();
+        let voice_amp = 1.0 / voice_freqs.len() as f32;
+
+        for freq in voice_freqs {
+            if waveforms.contains(Waveform::Sine) {
+                mix = mix.add_amp(&sine_wave(
+                    freq,
+                    LENGTH,
+                    SAMPLE_RATE as f32,
+                    oscillator.sine_amp * voice_amp,
+                    oscillator.sine_phase,
+                ));
+            }
+            if waveforms.contains(Waveform::Square) {
+                let (amp, phase) = (oscillator.square_amp * voice_amp, oscillator.square_phase);
+                mix = mix.add_amp(&if sync {
+                    square_wave_synced(freq, oscillator.slave_ratio, LENGTH, SAMPLE_RATE as f32, amp, phase)
+                } else {
+                    square(freq, LENGTH, SAMPLE_RATE as f32, amp, phase)
+                });
+            }
+            if waveforms.contains(Waveform::Saw) {
+                let (amp, phase) = (oscillator.saw_amp * voice_amp, oscillator.saw_phase);
+                mix = mix.add_amp(&if sync {
+                    saw_wave_synced(freq, oscillator.slave_ratio, LENGTH, SAMPLE_RATE as f32, amp, phase)
+                } else {
+                    saw(freq, LENGTH, SAMPLE_RATE as f32, amp, phase)
+                });
+            }
+            if waveforms.contains(Waveform::Triangle) {
+                mix = mix.add_amp(&triangle_wave(
+                    freq,
+                    LENGTH,
+                    SAMPLE_RATE as f32,
+                    oscillator.tri_amp * voice_amp,
+                    oscillator.tri_phase,
+                ));
+            }
+            if waveforms.contains(Waveform::Pulse) {
+                mix = mix.add_amp(&pulse(
+                    freq,
+                    LENGTH,
+                    SAMPLE_RATE as f32,
+                    oscillator.pulse_amp * voice_amp,
+                    oscillator.pulse_phase,
+                    oscillator.duty,
+                ));
+            }
+        }
+        // drawing the noise is skipped when it would be silent anyway
+        if waveforms.contains(Waveform::Noise) && oscillator.noise_amp > 0.0 {
+            mix = mix.add_amp(&white_noise(LENGTH, SAMPLE_RATE as f32, oscillator.noise_amp, oscillator.noise_seed));
+        }
 
         // *self = sine.add_amp(&square).add_amp(&saw).scale_amp(1.0 / 3.0);
-        *self = sine.add_amp(&square).add_amp(&saw);
+        *self = mix;
+
+        match oscillator.settings.mix {
+            MixNormalisation::None => {}
+            MixNormalisation::SumToOne => {
+                let total = oscillator.total_amp();
+                if total > 0.0 {
+                    *self = self.scale_amp(1.0 / total);
+                }
+            }
+            MixNormalisation::PeakLimit => {
+                let peak = self.peak();
+                if peak > 1.0 {
+                    *self = self.scale_amp(1.0 / peak);
+                }
+            }
+        }
     }
 }
 
@@ -61,6 +129,19 @@ pub fn square_wave(
     sample_rate: f32,
     amplitude: f32,
     phase_offset: f32
+) -> Signal {
+    pulse_wave(freq, length, sample_rate, amplitude, phase_offset, 0.5)
+}
+
+/// Produces a pulse waveform with the specified parameters, which is high for the fraction `duty`
+/// of every cycle and low for the rest of it.
+pub fn pulse_wave(
+    freq: f32,
+    length: f32,
+    sample_rate: f32,
+    amplitude: f32,
+    phase_offset: f32,
+    duty: f32
 ) -> Signal {
     const PI_2: f32 = core::f32::consts::PI * 2.0;
 
@@ -72,7 +153,7 @@ pub fn square_wave(
 
     for i in 0..n as u32 {
         let value =
-            if ((i as f32 + (phase_factor * phase_offset)) % samples_cycle) < (samples_cycle / 2.0) {
+            if ((i as f32 + (phase_factor * phase_offset)) % samples_cycle) < (samples_cycle * duty) {
                 1
             } else {
                 -1
@@ -111,9 +192,183 @@ pub fn saw_wave(
     Signal(samples)
 }
 
+/// Produces a saw waveform like `saw_wave` whose discontinuities are smoothed with PolyBLEP, so that
+/// the harmonics above the Nyquist frequency do not alias into the audible range.
+pub fn saw_wave_band_limited(
+    freq: f32,
+    length: f32,
+    sample_rate: f32,
+    amplitude: f32,
+    phase_offset: f32
+) -> Signal {
+    const PI_2: f32 = core::f32::consts::PI * 2.0;
+
+    let dt = freq / sample_rate;
+    let n = sample_rate * length;
+
+    let samples = (0..n as u32)
+        .map(|i| {
+            let position = (dt * i as f32 + phase_offset / PI_2).rem_euclid(1.0);
+            amplitude * (2.0 * position - 1.0 - poly_blep(position, dt))
+        })
+        .collect();
+
+    Signal(samples)
+}
+
+/// Produces a square waveform like `square_wave` whose discontinuities are smoothed with PolyBLEP.
+pub fn square_wave_band_limited(
+    freq: f32,
+    length: f32,
+    sample_rate: f32,
+    amplitude: f32,
+    phase_offset: f32
+) -> Signal {
+    pulse_wave_band_limited(freq, length, sample_rate, amplitude, phase_offset, 0.5)
+}
+
+/// Produces a pulse waveform like `pulse_wave` whose discontinuities are smoothed with PolyBLEP.
+pub fn pulse_wave_band_limited(
+    freq: f32,
+    length: f32,
+    sample_rate: f32,
+    amplitude: f32,
+    phase_offset: f32,
+    duty: f32
+) -> Signal {
+    const PI_2: f32 = core::f32::consts::PI * 2.0;
+
+    let dt = freq / sample_rate;
+    let n = sample_rate * length;
+
+    let samples = (0..n as u32)
+        .map(|i| {
+            let position = (dt * i as f32 + phase_offset / PI_2).rem_euclid(1.0);
+            let value = if position < duty { 1.0 } else { -1.0 };
+            // the rising edge at the start of the cycle and the falling one at the duty cycle
+            let falling = (position - duty).rem_euclid(1.0);
+            amplitude * (value + poly_blep(position, dt) - poly_blep(falling, dt))
+        })
+        .collect();
+
+    Signal(samples)
+}
+
+/// Produces a saw waveform at `ratio` times `freq` that is hard-synced to `freq`, i.e. whose phase
+/// is reset at the start of every cycle of `freq`. The phase offset shifts the cycles of `freq`.
+pub fn saw_wave_synced(
+    freq: f32,
+    ratio: f32,
+    length: f32,
+    sample_rate: f32,
+    amplitude: f32,
+    phase_offset: f32
+) -> Signal {
+    let samples = synced_positions(freq, ratio, length, sample_rate, phase_offset)
+        .map(|position| amplitude * (2.0 * position - 1.0))
+        .collect();
+
+    Signal(samples)
+}
+
+/// Produces a square waveform at `ratio` times `freq` that is hard-synced to `freq`, like
+/// `saw_wave_synced`.
+pub fn square_wave_synced(
+    freq: f32,
+    ratio: f32,
+    length: f32,
+    sample_rate: f32,
+    amplitude: f32,
+    phase_offset: f32
+) -> Signal {
+    let samples = synced_positions(freq, ratio, length, sample_rate, phase_offset)
+        .map(|position| if position < 0.5 { amplitude } else { -amplitude })
+        .collect();
+
+    Signal(samples)
+}
+
+/// Positions within the cycles of a slave oscillator at `ratio` times `freq`, between 0 and 1, whose
+/// phase is reset at the start of every cycle of `freq`.
+fn synced_positions(freq: f32, ratio: f32, length: f32, sample_rate: f32, phase_offset: f32) -> impl Iterator<Item = f32> {
+    const PI_2: f32 = core::f32::consts::PI * 2.0;
+
+    let dt = freq / sample_rate;
+    let n = sample_rate * length;
+
+    (0..n as u32).map(move |i| {
+        let master = (dt * i as f32 + phase_offset / PI_2).rem_euclid(1.0);
+        (master * ratio).fract()
+    })
+}
+
+/// PolyBLEP residual of a discontinuity of height 2 at the start of every cycle, given the position
+/// within the cycle, between 0 and 1, and its increment per sample. It is only non-zero in the
+/// samples right before and after the discontinuity.
+fn poly_blep(position: f32, dt: f32) -> f32 {
+    if position < dt {
+        let t = position / dt;
+        2.0 * t - t * t - 1.0
+    } else if position > 1.0 - dt {
+        let t = (position - 1.0) / dt;
+        t * t + 2.0 * t + 1.0
+    } else {
+        0.0
+    }
+}
+
+/// Produces a triangle waveform at the specified parameters, which rises from 0 at the start of
+/// every cycle like the sine wave.
+pub fn triangle_wave(
+    freq: f32,
+    length: f32,
+    sample_rate: f32,
+    amplitude: f32,
+    phase_offset: f32
+) -> Signal {
+    const PI_2: f32 = core::f32::consts::PI * 2.0;
+
+    let sample_period = 1.0 / sample_rate;
+    let n = sample_rate * length;
+
+    let mut samples: Vec<f32> = vec![];
+
+    for i in 0..n as u32 {
+        // fraction of the cycle, shifted by a quarter so that it starts at 0 rather than -1
+        let position = (freq * i as f32 * sample_period + phase_offset / PI_2 + 0.25).rem_euclid(1.0);
+        let value = 1.0 - 4.0 * (position - 0.5).abs();
+
+        samples.push(amplitude * value);
+    }
+
+    Signal(samples)
+}
+
+/// Produces white noise with the specified parameters, uniformly distributed between `-amplitude`
+/// and `amplitude`. The samples are drawn from a generator seeded with `seed`, so that the same
+/// parameters always produce the same noise.
+pub fn white_noise(
+    length: f32,
+    sample_rate: f32,
+    amplitude: f32,
+    seed: u64
+) -> Signal {
+    let mut rng = ChaCha8Rng::seed_from_u64(seed);
+    let n = sample_rate * length;
+
+    let samples = (0..n as u32)
+        .map(|_| amplitude * rng.gen_range(-1.0..=1.0))
+        .collect();
+
+    Signal(samples)
+}
+
 #[cfg(test)]
 mod tests {
-    use crate::signal_processing::components::oscillator::{saw_wave, sine_wave, square_wave};
+    use crate::signal_processing::components::oscillator::{pulse_wave, pulse_wave_band_limited, saw_wave, saw_wave_band_limited, saw_wave_synced, sine_wave, square_wave, square_wave_band_limited, square_wave_synced, triangle_wave, white_noise};
+    use crate::signal_processing::signal_analysis::{SpectrumConfig, WindowFunction};
+    use crate::simulation::components::oscillator::{MixNormalisation, OscillatorComponent, OscillatorSettings, Waveform, WaveformQuality, Waveforms};
+    use crate::signal_processing::{Signal, LENGTH, SAMPLE_RATE};
 
     #[test]
     fn test_sine() {
@@ -141,4 +396,198 @@ mod tests {
         assert_eq!(signal.next(), Some(0.0));
         assert_eq!(signal.next(), Some(0.5));
     }
+
+    #[test]
+    fn test_triangle() {
+        let signal = triangle_wave(1.0, 1.0, 8.0, 1.0, 0.0).into_iter().collect::<Vec<f32>>();
+        assert_eq!(signal, vec![0.0, 0.5, 1.0, 0.5, 0.0, -0.5, -1.0, -0.5]);
+
+        // a phase of half a cycle inverts the wave
+        let mut signal = triangle_wave(1.0, 1.0, 8.0, 0.5, core::f32::consts::PI).into_iter();
+        assert_eq!(signal.next(), Some(0.0));
+        assert_eq!(signal.next(), Some(-0.25));
+        assert_eq!(signal.next(), Some(-0.5));
+    }
+
+    #[test]
+    fn test_pulse() {
+        // each cycle of 250 samples is high for the fraction given by the duty, give or take one
+        for duty in [0.1, 0.25, 0.5, 0.8] {
+            let signal = pulse_wave(4.0, 1.0, 1000.0, 1.0, 0.0, duty);
+            let high = signal.samples().iter().filter(|&&s| s == 1.0).count();
+            let low = signal.samples().iter().filter(|&&s| s == -1.0).count();
+            assert_eq!(high + low, 1000);
+            assert!((high as f32 / 1000.0 - duty).abs() <= 4.0 / 1000.0);
+        }
+
+        // a duty cycle of a half is the square wave
+        assert_eq!(pulse_wave(3.0, 1.0, 50.0, 0.5, 1.0, 0.5), square_wave(3.0, 1.0, 50.0, 0.5, 1.0));
+    }
+
+    #[test]
+    fn test_white_noise() {
+        // the same seed always renders the same noise, within the amplitude
+        let noise = white_noise(1.0, SAMPLE_RATE as f32, 0.5, 7);
+        assert_eq!(noise, white_noise(1.0, SAMPLE_RATE as f32, 0.5, 7));
+        assert_ne!(noise, white_noise(1.0, SAMPLE_RATE as f32, 0.5, 8));
+        assert!(noise.samples().iter().all(|s| s.abs() <= 0.5));
+
+        // the averaged spectrum has about the same energy in every band, unlike a sine wave's
+        let band_spread = |signal: Signal| {
+            let config = SpectrumConfig { averaged: true, ..Default::default() };
+            let spectrum = signal.spectrum(&config).unwrap();
+            let magnitudes = spectrum.data().iter().map(|(_, fv)| fv.val()).collect::<Vec<f32>>();
+            let bands = magnitudes.chunks(magnitudes.len() / 8)
+                .map(|band| band.iter().sum::<f32>() / band.len() as f32)
+                .collect::<Vec<f32>>();
+            bands.iter().copied().fold(f32::MIN, f32::max) / bands.iter().copied().fold(f32::MAX, f32::min)
+        };
+        assert!(band_spread(noise) < 1.5);
+        assert!(band_spread(sine_wave(440.0, 1.0, SAMPLE_RATE as f32, 0.5, 0.0)) > 10.0);
+    }
+
+    #[test]
+    fn test_mix_normalisation() {
+        let loud = OscillatorComponent::from_genes(&[440.0, 0.8, 0.0, 0.8, 0.0, 0.8, 0.0, 0.8, 0.0, 0.8, 0.0, 0.3, 0.2, 1.0, 0.0, 1.0]);
+        let quiet = OscillatorComponent::from_genes(&[440.0, 0.3, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.5, 0.0, 1.0, 0.0, 1.0]);
+        let peak = |oscillator: OscillatorComponent, mix| {
+            let mut signal = Signal::default();
+            signal.apply_oscillator(OscillatorComponent { settings: OscillatorSettings { mix, ..Default::default() }, ..oscillator });
+            signal.peak()
+        };
+
+        // the raw sum of the waveforms clips
+        assert!(peak(loud, MixNormalisation::None) > 2.0);
+        assert!((peak(quiet, MixNormalisation::None) - 0.3).abs() < 1e-3);
+
+        // the amplitudes only set the relative levels of the waveforms, so quiet mixes are raised
+        assert!(peak(loud, MixNormalisation::SumToOne) <= 1.0);
+        assert!((peak(quiet, MixNormalisation::SumToOne) - 1.0).abs() < 1e-3);
+
+        // only mixes that would clip are scaled, right up to the limit
+        assert!((peak(loud, MixNormalisation::PeakLimit) - 1.0).abs() < 1e-6);
+        assert!((peak(quiet, MixNormalisation::PeakLimit) - 0.3).abs() < 1e-3);
+
+        // a silent oscillator stays silent
+        let silent = OscillatorComponent { sine_amp: 0.0, ..quiet };
+        for mix in [MixNormalisation::None, MixNormalisation::SumToOne, MixNormalisation::PeakLimit] {
+            assert_eq!(peak(silent, mix), 0.0);
+        }
+    }
+
+    #[test]
+    fn test_unison() {
+        let sine = |unison_voices, detune_cents| {
+            let mut signal = Signal::default();
+            signal.apply_oscillator(OscillatorComponent {
+                unison_voices,
+                detune_cents,
+                settings: OscillatorSettings { waveforms: Waveforms::of(&[Waveform::Sine]), ..Default::default() },
+                ..OscillatorComponent::from_genes(&[1000.0, 1.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.5, 0.0, 1.0, 0.0, 1.0])
+            });
+            signal
+        };
+
+        // detuned voices are peaks of about the same magnitude around the fundamental
+        let mut peaks = sine(3, 50.0).spectral_peaks(3, 10.0).unwrap();
+        let loudest = peaks[0].1;
+        peaks.sort_by(|(a, _), (b, _)| a.total_cmp(b));
+        for ((freq, magnitude), expected_freq) in peaks.into_iter().zip([971.5, 1000.0, 1029.3]) {
+            assert!((freq - expected_freq).abs() < 2.0, "{freq} Hz should be close to {expected_freq} Hz");
+            assert!(magnitude > 0.5 * loudest);
+        }
+
+        // without detune, the voices collapse into the single peak of one voice
+        let unison = sine(3, 0.0);
+        let peaks = unison.spectral_peaks(3, 10.0).unwrap();
+        assert!((peaks[0].0 - 1000.0).abs() < 1.0);
+        assert!(peaks[1..].iter().all(|(_, magnitude)| *magnitude < 0.01 * peaks[0].1));
+        assert!(unison.samples().iter().zip(sine(1, 0.0).samples()).all(|(a, b)| (a - b).abs() < 1e-5));
+    }
+
+    #[test]
+    fn test_band_limited() {
+        // energy away from the harmonics of 2 kHz, where the harmonics above the Nyquist frequency
+        // fold back to
+        let alias_energy = |signal: Signal| {
+            let config = SpectrumConfig { window: WindowFunction::Hann, ..Default::default() };
+            let spectrum = signal.spectrum(&config).unwrap();
+            let energy = |harmonic: bool| spectrum.data().iter()
+                .filter(|(f, _)| {
+                    let distance = (f.val() / 2_000.0 - (f.val() / 2_000.0).round()).abs() * 2_000.0;
+                    (distance < 50.0) == harmonic
+                })
+                .map(|(_, fv)| fv.val().powi(2))
+                .sum::<f32>();
+            energy(false) / energy(true)
+        };
+
+        let saws = (saw_wave(2_000.0, 1.0, SAMPLE_RATE as f32, 0.5, 0.0), saw_wave_band_limited(2_000.0, 1.0, SAMPLE_RATE as f32, 0.5, 0.0));
+        let squares = (square_wave(2_000.0, 1.0, SAMPLE_RATE as f32, 0.5, 0.0), square_wave_band_limited(2_000.0, 1.0, SAMPLE_RATE as f32, 0.5, 0.0));
+        let pulses = (pulse_wave(2_000.0, 1.0, SAMPLE_RATE as f32, 0.5, 0.0, 0.2), pulse_wave_band_limited(2_000.0, 1.0, SAMPLE_RATE as f32, 0.5, 0.0, 0.2));
+        for (naive, band_limited) in [saws, squares, pulses] {
+            assert!(alias_energy(band_limited) < 0.1 * alias_energy(naive));
+        }
+
+        // away from the discontinuities, the waveforms are the same as the naive ones
+        let matching = |naive: Signal, band_limited: Signal| {
+            let close = naive.samples().iter().zip(band_limited.samples()).filter(|(a, b)| (*a - *b).abs() < 1e-3).count();
+            close as f32 / naive.n_samples() as f32
+        };
+        assert!(matching(saw_wave(100.0, 1.0, SAMPLE_RATE as f32, 0.5, 1.0), saw_wave_band_limited(100.0, 1.0, SAMPLE_RATE as f32, 0.5, 1.0)) > 0.99);
+        assert!(matching(pulse_wave(100.0, 1.0, SAMPLE_RATE as f32, 0.5, 1.0, 0.3), pulse_wave_band_limited(100.0, 1.0, SAMPLE_RATE as f32, 0.5, 1.0, 0.3)) > 0.99);
+
+        // the oscillator renders them when they are selected
+        let saw = |quality| {
+            let mut signal = Signal::default();
+            signal.apply_oscillator(OscillatorComponent {
+                settings: OscillatorSettings { waveforms: Waveforms::of(&[Waveform::Saw]), quality, ..Default::default() },
+                ..OscillatorComponent::from_genes(&[2_000.0, 0.0, 0.0, 0.0, 0.0, 0.5, 0.0, 0.0, 0.0, 0.0, 0.0, 0.5, 0.0, 1.0, 0.0, 1.0])
+            });
+            signal
+        };
+        assert_eq!(saw(WaveformQuality::Naive), saw_wave(2_000.0, LENGTH, SAMPLE_RATE as f32, 0.5, 0.0));
+        assert_eq!(saw(WaveformQuality::BandLimited), saw_wave_band_limited(2_000.0, LENGTH, SAMPLE_RATE as f32, 0.5, 0.0));
+    }
+
+    #[test]
+    fn test_hard_sync() {
+        // the harmonic of 200 Hz with the largest magnitude
+        let strongest_harmonic = |signal: Signal| {
+            let spectrum = signal.freq_spectrum().unwrap();
+            (1..=12).max_by(|&a, &b| {
+                let magnitude = |k: u32| spectrum.freq_val_closest(200.0 * k as f32).1.val();
+                magnitude(a).total_cmp(&magnitude(b))
+            }).unwrap()
+        };
+
+        // the slave is reset on every cycle of the master, so the spectrum is harmonic to the master,
+        // with a peak that follows the frequency of the slave
+        for ratio in [2.5, 4.5, 6.5] {
+            for wave in [saw_wave_synced, square_wave_synced] {
+                let harmonic = strongest_harmonic(wave(200.0, ratio, LENGTH, SAMPLE_RATE as f32, 1.0, 0.0));
+                assert!((harmonic as f32 - ratio).abs() <= 1.0, "harmonic {harmonic} for a ratio of {ratio}");
+            }
+        }
+        assert_eq!(strongest_harmonic(saw_wave(200.0, LENGTH, SAMPLE_RATE as f32, 1.0, 0.0)), 1);
+
+        // a slave at the frequency of the master is the plain waveform
+        let synced = saw_wave_synced(200.0, 1.0, LENGTH, SAMPLE_RATE as f32, 1.0, 1.0);
+        let plain = saw_wave(200.0, LENGTH, SAMPLE_RATE as f32, 1.0, 1.0);
+        let mismatched = synced.samples().iter().zip(plain.samples()).filter(|(a, b)| (*a - *b).abs() > 1e-2).count();
+        assert!(mismatched < synced.n_samples() / 100, "{mismatched} samples differ");
+
+        // the oscillator only syncs its square and saw waves when enabled
+        let render = |sync: bool| {
+            let mut signal = Signal::default();
+            signal.apply_oscillator(OscillatorComponent {
+                slave_ratio: 4.5,
+                settings: OscillatorSettings { waveforms: Waveforms::of(&[Waveform::Saw]), sync, ..Default::default() },
+                ..OscillatorComponent::from_genes(&[200.0, 0.0, 0.0, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.5, 0.0, 1.0, 0.0, 1.0])
+            });
+            signal
+        };
+        assert_eq!(render(true), saw_wave_synced(200.0, 4.5, LENGTH, SAMPLE_RATE as f32, 1.0, 0.0));
+        assert_eq!(render(false), saw_wave(200.0, LENGTH, SAMPLE_RATE as f32, 1.0, 0.0));
+    }
 }
@@ -1,4 +1,6 @@
+pub mod analyzer;
 pub mod signal_analysis;
+pub mod spectrogram;
 pub mod components;
 
 use std::fs;
@@ -8,12 +10,17 @@ use std::path::Path;
 use crate::error::SignalProcessingError;
 use crate::error::SignalProcessingError::CouldNotReadFromFile;
 use anyhow::Result;
+use serde::{Serialize, Deserialize};
+use log::info;
 
 // const FREQ: f32 = 440.0;
 pub const LENGTH: f32 = 3.0;
 pub const SAMPLE_RATE: u32 = 44_100;
+/// Range in which fundamental frequencies are searched for when analysing a signal.
+pub const MIN_FUNDAMENTAL: f32 = 20.0;
+pub const MAX_FUNDAMENTAL: f32 = 10_000.0;
 
-#[derive(Clone, PartialEq, Default, Debug)]
+#[derive(Clone, PartialEq, Default, Debug, Serialize, Deserialize)]
 pub struct Signal(Vec<f32>);
 
 impl IntoIterator for Signal {
@@ -58,7 +65,7 @@ impl Signal {
         let mut file_out = File::create(path)
             .expect("The creation of a new file should be successful");
         wav_io::write_to_file(&mut file_out, &head, &self.0).map_err(|_| ())?;
-        println!("Signal successfully written to file {}", file_path);
+        info!("Signal successfully written to file {}", file_path);
         Ok(())
     }
 
@@ -69,6 +76,11 @@ impl Signal {
     pub fn samples(&self) -> &[f32] {
         &self.0
     }
+
+    /// The largest absolute value of the samples.
+    pub fn peak(&self) -> f32 {
+        self.0.iter().fold(0.0, |peak, s| peak.max(s.abs()))
+    }
 }
 
 #[cfg(test)]
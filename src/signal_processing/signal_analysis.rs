@@ -1,48 +1,674 @@
-use crate::signal_processing::{Signal, SAMPLE_RATE};
-use spectrum_analyzer::{samples_fft_to_spectrum, FrequencyLimit, FrequencySpectrum};
+use crate::signal_processing::{Signal, MAX_FUNDAMENTAL, MIN_FUNDAMENTAL, SAMPLE_RATE};
+use crate::signal_processing::analyzer::SpectrumAnalyzer;
+use spectrum_analyzer::{FrequencyLimit, FrequencySpectrum, FrequencyValue};
+use spectrum_analyzer::windows::{hamming_window, hann_window};
 use std::ops::Sub;
 use spectrum_analyzer::error::SpectrumAnalyzerError;
 use crate::error::SignalProcessingError;
+use crate::utils;
+use itertools::Itertools;
+use serde::{Serialize, Deserialize};
+
+/// Number of samples analysed by default when calculating the frequency spectrum of a signal.
+pub const FFT_LENGTH: usize = 16_384;
+/// Largest number of samples the spectrum analyser can transform at once.
+pub const MAX_FFT_LENGTH: usize = 16_384;
+/// Fraction of the fundamental frequency around each harmonic whose energy counts as harmonic when
+/// calculating the harmonicity of a signal.
+pub const HARMONIC_TOLERANCE: f32 = 0.03;
+/// Power floor used by the Itakura–Saito divergence for silent frequency bins.
+pub const IS_EPSILON: f32 = 1e-10;
+
+/// Window function applied to the samples before calculating their frequency spectrum. Tapering
+/// the edges of the analysed block reduces the spectral leakage of partials that are not aligned
+/// with a frequency bin.
+#[derive(Copy, Clone, Debug, PartialEq, Default, Serialize, Deserialize)]
+pub enum WindowFunction {
+    /// No windowing, i.e. the samples are used as they are.
+    #[default]
+    Rect,
+    Hann,
+    Hamming,
+    Blackman,
+}
+
+impl WindowFunction {
+    /// Returns a copy of the samples with the window applied.
+    pub fn apply(&self, samples: &[f32]) -> Vec<f32> {
+        match self {
+            WindowFunction::Rect => samples.to_vec(),
+            WindowFunction::Hann => hann_window(samples),
+            WindowFunction::Hamming => hamming_window(samples),
+            WindowFunction::Blackman => samples.iter()
+                .zip(utils::blackman_window(samples.len()))
+                .map(|(s, w)| s * w)
+                .collect(),
+        }
+    }
+}
+
+/// How to compare the samples of two signals with a different number of samples.
+#[derive(Copy, Clone, Debug, PartialEq, Default, Serialize, Deserialize)]
+pub enum LengthHandling {
+    /// Return a `SignalLengthMismatch` error.
+    Error,
+    /// Pad the shorter signal with silence.
+    #[default]
+    ZeroPad,
+}
+
+/// Frequency weighting applied to the magnitude of each frequency bin, so that the error is
+/// measured closer to how it is perceived.
+#[derive(Copy, Clone, Debug, PartialEq, Default, Serialize, Deserialize)]
+pub enum Weighting {
+    /// All frequencies contribute equally.
+    #[default]
+    None,
+    /// Standard A-weighting curve (IEC 61672), which attenuates low and very high frequencies the
+    /// ear is less sensitive to.
+    AWeight,
+}
+
+impl Weighting {
+    /// Linear gain applied to the magnitude of a frequency bin.
+    pub fn gain(&self, freq: f32) -> f32 {
+        match self {
+            Weighting::None => 1.0,
+            Weighting::AWeight => 10f32.powf(a_weighting_db(freq) / 20.0),
+        }
+    }
+}
+
+/// A-weighting in dB of the given frequency, normalised to 0 dB at 1 kHz.
+pub fn a_weighting_db(freq: f32) -> f32 {
+    let f2 = (freq as f64).powi(2);
+    let r_a = 12_194f64.powi(2) * f2.powi(2) / (
+        (f2 + 20.6f64.powi(2))
+            * ((f2 + 107.7f64.powi(2)) * (f2 + 737.9f64.powi(2))).sqrt()
+            * (f2 + 12_194f64.powi(2))
+    );
+
+    (20.0 * r_a.log10() + 2.0) as f32
+}
+
+/// Specifies how the frequency spectrum of a signal is obtained. Both the candidate and the target
+/// signals need to be analysed in the same way for their spectra to be comparable.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct SpectrumConfig {
+    /// Window function applied before the FFT.
+    pub window: WindowFunction,
+    /// Frequency band (min, max) in Hz the spectrum is restricted to. The whole spectrum is used if
+    /// not specified.
+    pub band: Option<(f32, f32)>,
+    /// Number of samples per analysed block. Must be a power of two no larger than
+    /// `MAX_FFT_LENGTH`.
+    pub length: usize,
+    /// Whether to average the spectra of consecutive blocks covering the whole signal, rather than
+    /// only analysing its first block.
+    pub averaged: bool,
+    /// Weighting applied to the spectra before calculating the MSE.
+    pub weighting: Weighting,
+    /// Analyser performing the FFT, which keeps its plans across every spectrum calculated with
+    /// this configuration or its clones. The plans are not serialised.
+    #[serde(skip)]
+    pub analyzer: SpectrumAnalyzer,
+}
+
+impl Default for SpectrumConfig {
+    fn default() -> Self {
+        Self {
+            window: WindowFunction::default(),
+            band: None,
+            length: FFT_LENGTH,
+            averaged: false,
+            weighting: Weighting::default(),
+            analyzer: SpectrumAnalyzer::default(),
+        }
+    }
+}
+
+impl SpectrumConfig {
+    /// Whether the number of samples per block can be transformed by the spectrum analyser.
+    pub fn is_valid_length(length: usize) -> bool {
+        length.is_power_of_two() && length <= MAX_FFT_LENGTH
+    }
+
+    /// Whether the spectrum analyser can restrict the spectrum to the band, i.e. it is non-empty
+    /// and within `[0, SAMPLE_RATE / 2]`.
+    pub fn is_valid_band((min_hz, max_hz): (f32, f32)) -> bool {
+        min_hz >= 0.0 && min_hz < max_hz && max_hz <= SAMPLE_RATE as f32 / 2.0
+    }
+
+    /// Frequency limit passed to the spectrum analyser.
+    fn frequency_limit(&self) -> FrequencyLimit {
+        match self.band {
+            Some((min, max)) => FrequencyLimit::Range(min, max),
+            None => FrequencyLimit::All,
+        }
+    }
+}
 
 impl Signal {
 
     /// Calculates the mean-squared error (MSE) between the frequency spectrum of two signals.
     pub fn freq_spectrum_mse(&self, other: &Self) -> Result<f32, SignalProcessingError> {
-        let self_spectrum = self.freq_spectrum()?;
-        let other_spectrum = other.freq_spectrum()?;
+        self.freq_spectrum_mse_with(other, &SpectrumConfig::default())
+    }
+
+    /// Calculates the mean-squared error (MSE) between the frequency spectrum of two signals, both
+    /// analysed with the given configuration.
+    pub fn freq_spectrum_mse_with(&self, other: &Self, config: &SpectrumConfig) -> Result<f32, SignalProcessingError> {
+        spectrum_mse(&self.spectrum(config)?, &other.spectrum(config)?, config.weighting)
+    }
+
+    /// Calculates the MSE between the frequency spectrum of two signals after weighting the
+    /// magnitude of each frequency bin.
+    pub fn freq_spectrum_mse_weighted(&self, other: &Self, weighting: Weighting) -> Result<f32, SignalProcessingError> {
+        self.freq_spectrum_mse_with(other, &SpectrumConfig { weighting, ..Default::default() })
+    }
+
+    /// Calculates the signal-to-noise ratio in dB between the frequency spectrum of a reference
+    /// signal `other` and the error of `self` with respect to it, i.e.
+    /// `10 * log10(sum(other²) / sum((other - self)²))` over the frequency bins. Unlike the MSE,
+    /// it does not depend on the overall level of the signals. Identical spectra yield an infinite
+    /// SNR, and a silent reference results in an error.
+    pub fn spectral_snr(&self, other: &Self) -> Result<f32, SignalProcessingError> {
+        self.spectral_snr_with(other, &SpectrumConfig::default())
+    }
+
+    /// Calculates the spectral SNR between two signals, both analysed with the given configuration.
+    pub fn spectral_snr_with(&self, other: &Self, config: &SpectrumConfig) -> Result<f32, SignalProcessingError> {
+        let self_magnitudes = self.magnitudes(config)?;
+        let other_magnitudes = other.magnitudes(config)?;
+
+        let signal_energy = other_magnitudes.iter().map(|o| o.powi(2)).sum::<f32>();
+        if signal_energy <= 0.0 {
+            return Err(SignalProcessingError::ZeroEnergy);
+        }
+
+        let noise_energy = self_magnitudes.iter().zip(&other_magnitudes)
+            .map(|(s, o)| (o - s).powi(2))
+            .sum::<f32>();
 
-        // self_spectrum.data().iter().zip(other_spectrum.data().iter()).for_each(|(s, o)| {
-        //     println!("self: {:?}, other: {:?}", s, o);
-        // });
+        Ok(10.0 * (signal_energy / noise_energy).log10())
+    }
 
-        // number of discrete frequency points
-        let n = self_spectrum.data().len() as f32;
+    /// Calculates the Itakura–Saito divergence between the power spectrum of the target `other` and
+    /// that of `self`, the candidate, i.e. the mean of `P/P̂ - ln(P/P̂) - 1` over the frequency bins,
+    /// where `P` is the target's power and `P̂` the candidate's. It is asymmetric: missing energy
+    /// where the target has some is penalised much more heavily than excess energy, so the order
+    /// of the call matters. Both powers are floored at `IS_EPSILON` to avoid dividing by zero.
+    pub fn itakura_saito(&self, other: &Self) -> Result<f32, SignalProcessingError> {
+        self.itakura_saito_with(other, &SpectrumConfig::default())
+    }
 
-        let self_freq_vals = self_spectrum.data().iter().map(|(f, fv)| fv);
-        let other_freq_vals = other_spectrum.data().iter().map(|(f, fv)| fv);
+    /// Calculates the Itakura–Saito divergence between two signals, both analysed with the given
+    /// configuration.
+    pub fn itakura_saito_with(&self, other: &Self, config: &SpectrumConfig) -> Result<f32, SignalProcessingError> {
+        let self_power = self.power_spectrum(config)?;
+        let other_power = other.power_spectrum(config)?;
+        let n = self_power.len() as f32;
 
         Ok(
-            // perform the mean squared error of the frequency spectrum
-            self_freq_vals.zip(other_freq_vals)
-                .map(|(s, o)| (s.val() - o.val()).powi(2))
+            self_power.iter().zip(&other_power)
+                .map(|(s, o)| {
+                    let ratio = o.max(IS_EPSILON) / s.max(IS_EPSILON);
+                    ratio - ratio.ln() - 1.0
+                })
                 .sum::<f32>() / n
         )
     }
 
+    /// Calculates the cosine similarity between the magnitude spectra of two signals, i.e. the dot
+    /// product of their magnitudes over the product of their norms. It is invariant to the overall
+    /// level of either signal. If either spectrum has a zero norm, the similarity is 0.
+    pub fn spectral_cosine_similarity(&self, other: &Self) -> Result<f32, SignalProcessingError> {
+        self.spectral_cosine_similarity_with(other, &SpectrumConfig::default())
+    }
+
+    /// Calculates the cosine similarity between the magnitude spectra of two signals, both analysed
+    /// with the given configuration.
+    pub fn spectral_cosine_similarity_with(&self, other: &Self, config: &SpectrumConfig) -> Result<f32, SignalProcessingError> {
+        let self_magnitudes = self.magnitudes(config)?;
+        let other_magnitudes = other.magnitudes(config)?;
+
+        let dot = self_magnitudes.iter().zip(&other_magnitudes).map(|(s, o)| s * o).sum::<f32>();
+        let self_norm = self_magnitudes.iter().map(|s| s.powi(2)).sum::<f32>().sqrt();
+        let other_norm = other_magnitudes.iter().map(|o| o.powi(2)).sum::<f32>().sqrt();
+
+        if self_norm == 0.0 || other_norm == 0.0 {
+            Ok(0.0)
+        } else {
+            Ok(dot / (self_norm * other_norm))
+        }
+    }
+
+    /// Calculates the frequency spectrum as specified by the configuration, without subtracting the
+    /// minimum magnitude from the frequency bins.
+    pub(crate) fn unscaled_spectrum(&self, config: &SpectrumConfig) -> Result<FrequencySpectrum, SignalProcessingError> {
+        self.spectrum_with_limit(config, config.frequency_limit(), false)
+    }
+
+    /// Power of the frequency bins of the unscaled spectrum, as specified by the configuration.
+    fn power_spectrum(&self, config: &SpectrumConfig) -> Result<Vec<f32>, SignalProcessingError> {
+        Ok(
+            self.unscaled_spectrum(config)?
+                .data().iter()
+                .map(|(_, fv)| fv.val().powi(2))
+                .collect()
+        )
+    }
+
+    /// Magnitudes of the frequency bins of the spectrum, as specified by the configuration.
+    fn magnitudes(&self, config: &SpectrumConfig) -> Result<Vec<f32>, SignalProcessingError> {
+        Ok(self.spectrum(config)?.data().iter().map(|(_, fv)| fv.val()).collect())
+    }
+
     pub fn freq_spectrum(&self) -> Result<FrequencySpectrum, SignalProcessingError> {
-        samples_fft_to_spectrum(
-            self.normalise().samples(),
+        self.spectrum(&SpectrumConfig::default())
+    }
+
+    /// Calculates the frequency spectrum after applying the given window function.
+    pub fn freq_spectrum_with(&self, window: WindowFunction) -> Result<FrequencySpectrum, SignalProcessingError> {
+        self.spectrum(&SpectrumConfig { window, ..Default::default() })
+    }
+
+    /// Calculates the frequency spectrum restricted to the given frequency limit.
+    pub fn freq_spectrum_limited(&self, limit: FrequencyLimit) -> Result<FrequencySpectrum, SignalProcessingError> {
+        self.spectrum_with_limit(&SpectrumConfig::default(), limit, true)
+    }
+
+    /// Calculates the frequency spectrum as specified by the configuration.
+    pub fn spectrum(&self, config: &SpectrumConfig) -> Result<FrequencySpectrum, SignalProcessingError> {
+        self.spectrum_with_limit(config, config.frequency_limit(), true)
+    }
+
+    /// Calculates the frequency spectrum as specified by the configuration, but restricted to the given
+    /// limit. If `scaled`, the minimum magnitude is subtracted from every frequency bin.
+    fn spectrum_with_limit(&self, config: &SpectrumConfig, limit: FrequencyLimit, scaled: bool) -> Result<FrequencySpectrum, SignalProcessingError> {
+        if !SpectrumConfig::is_valid_length(config.length) {
+            return Err(SignalProcessingError::InvalidFftLength(config.length));
+        }
+
+        if !config.averaged || self.n_samples() <= config.length {
+            return self.normalise_to(config.length).block_spectrum(config, limit, scaled);
+        }
+
+        let spectra = self.samples()
+            .chunks(config.length)
+            .map(|block| {
+                Signal::from_samples(block).normalise_to(config.length).block_spectrum(config, limit, scaled)
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        let n = spectra.len() as f32;
+
+        // average the magnitude of each frequency bin across all the blocks
+        let data = spectra[0].data().iter().enumerate()
+            .map(|(i, (fr, _))| {
+                let total = spectra.iter().map(|s| s.data()[i].1.val()).sum::<f32>();
+                (*fr, FrequencyValue::from(total / n))
+            })
+            .collect::<Vec<_>>();
+        let mut working_buffer = data.clone();
+
+        Ok(FrequencySpectrum::new(
+            data,
+            spectra[0].frequency_resolution(),
+            config.length as u32,
+            &mut working_buffer,
+        ))
+    }
+
+    /// Calculates the spectrum of a single block, whose length is assumed to be valid.
+    fn block_spectrum(&self, config: &SpectrumConfig, limit: FrequencyLimit, scaled: bool) -> Result<FrequencySpectrum, SignalProcessingError> {
+        config.analyzer.spectrum(
+            &config.window.apply(self.samples()),
             SAMPLE_RATE,
-            FrequencyLimit::All,
-            Some(&|val, info| val - info.min),
+            limit,
+            if scaled { Some(&|val, info| val - info.min) } else { None },
         ).map_err(SignalProcessingError::InvalidSpectrum)
     }
 
+    /// Calculates the spectral centroid, i.e. the magnitude-weighted mean frequency of the spectrum.
+    /// It is commonly used as a measure of the "brightness" of a sound. A silent signal has a
+    /// centroid of 0 Hz.
+    pub fn spectral_centroid(&self) -> Result<f32, SignalProcessingError> {
+        let spectrum = self.freq_spectrum()?;
+
+        let total = spectrum.data().iter().map(|(_, fv)| fv.val()).sum::<f32>();
+        if total <= 0.0 {
+            return Ok(0.0);
+        }
+
+        let weighted = spectrum.data().iter().map(|(f, fv)| f.val() * fv.val()).sum::<f32>();
+
+        Ok(weighted / total)
+    }
+
+    /// Calculates the spectral rolloff, i.e. the frequency below which the given percentage
+    /// (0 - 100) of the total spectral magnitude is contained. A silent signal has a rolloff of
+    /// 0 Hz.
+    pub fn spectral_rolloff(&self, percent: f32) -> Result<f32, SignalProcessingError> {
+        let spectrum = self.freq_spectrum()?;
+
+        let total = spectrum.data().iter().map(|(_, fv)| fv.val()).sum::<f32>();
+        if total <= 0.0 {
+            return Ok(0.0);
+        }
+
+        let threshold = total * percent.clamp(0.0, 100.0) / 100.0;
+        let mut cumulative = 0.0;
+
+        for (f, fv) in spectrum.data() {
+            cumulative += fv.val();
+            if cumulative >= threshold {
+                return Ok(f.val());
+            }
+        }
+
+        Ok(spectrum.max_fr().val())
+    }
+
+    /// Finds the `n` most prominent peaks of the frequency spectrum as (frequency, magnitude) pairs
+    /// sorted by decreasing magnitude. Peaks are local maxima whose frequency and magnitude are
+    /// refined with parabolic interpolation between the neighbouring bins, and any peak closer than
+    /// `min_separation_hz` to a more prominent one is discarded, so that a single wide peak does not
+    /// take up several slots. The spectrum is Hann-windowed, whose main lobe is close to a parabola
+    /// in a logarithmic scale, so that the interpolated magnitudes of partials between bins remain
+    /// comparable.
+    pub fn spectral_peaks(&self, n: usize, min_separation_hz: f32) -> Result<Vec<(f32, f32)>, SignalProcessingError> {
+        let spectrum = self.freq_spectrum_with(WindowFunction::Hann)?;
+        let resolution = spectrum.frequency_resolution();
+        let data = spectrum.data();
+        let log_magnitude = |i: usize| data[i].1.val().max(f32::MIN_POSITIVE).ln();
+
+        let mut candidates = (1..data.len().saturating_sub(1))
+            .filter_map(|i| {
+                let (a, b, c) = (data[i - 1].1.val(), data[i].1.val(), data[i + 1].1.val());
+                if b <= a || b < c {
+                    return None;
+                }
+
+                let (a, b, c) = (log_magnitude(i - 1), log_magnitude(i), log_magnitude(i + 1));
+                let denominator = a - 2.0 * b + c;
+                let offset = if denominator == 0.0 { 0.0 } else { 0.5 * (a - c) / denominator };
+                Some((data[i].0.val() + offset * resolution, (b - 0.25 * (a - c) * offset).exp()))
+            })
+            .collect::<Vec<_>>();
+        candidates.sort_by(|(_, a), (_, b)| b.total_cmp(a));
+
+        let mut peaks: Vec<(f32, f32)> = vec![];
+        for (freq, magnitude) in candidates {
+            if peaks.len() == n {
+                break;
+            }
+            if peaks.iter().all(|(f, _)| (f - freq).abs() >= min_separation_hz) {
+                peaks.push((freq, magnitude));
+            }
+        }
+
+        Ok(peaks)
+    }
+
+    /// Calculates the ratio between the energy near integer multiples of the fundamental frequency
+    /// and the total energy of the spectrum, ignoring the DC component. A purely harmonic signal has
+    /// a harmonicity close to 1, while broadband noise lowers it. If no fundamental is provided, it
+    /// is detected from the signal, and a harmonicity of 0 is returned if none can be found.
+    pub fn harmonicity(&self, fundamental_hint: Option<f32>) -> f32 {
+        let Some(fundamental) = fundamental_hint
+            .or_else(|| self.detect_fundamental(MIN_FUNDAMENTAL, MAX_FUNDAMENTAL))
+            .filter(|f| *f > 0.0) else {
+            return 0.0;
+        };
+
+        let config = SpectrumConfig { window: WindowFunction::Hann, ..Default::default() };
+        let Ok(spectrum) = self.unscaled_spectrum(&config) else {
+            return 0.0;
+        };
+        // width around each harmonic considered part of it, covering at least the main lobe
+        let tolerance = (HARMONIC_TOLERANCE * fundamental).max(3.0 * spectrum.frequency_resolution());
+
+        let (harmonic, total) = spectrum.data().iter()
+            .skip(1)
+            .fold((0.0, 0.0), |(harmonic, total), (fr, fv)| {
+                let energy = fv.val().powi(2);
+                let ratio = fr.val() / fundamental;
+                let distance = (ratio - ratio.round()).abs() * fundamental;
+                if ratio.round() >= 1.0 && distance <= tolerance {
+                    (harmonic + energy, total + energy)
+                } else {
+                    (harmonic, total + energy)
+                }
+            });
+
+        if total > 0.0 { harmonic / total } else { 0.0 }
+    }
+
+    /// Calculates the number of zero crossings per second. Runs of zero-valued samples are skipped,
+    /// so a crossing is only counted when the sign differs from the last non-zero sample.
+    pub fn zero_crossing_rate(&self) -> f32 {
+        if self.n_samples() < 2 {
+            return 0.0;
+        }
+
+        let mut crossings = 0;
+        let mut last_sign = 0.0;
+
+        for s in self.samples().iter().filter(|&&s| s != 0.0) {
+            let sign = s.signum();
+            if last_sign != 0.0 && sign != last_sign {
+                crossings += 1;
+            }
+            last_sign = sign;
+        }
+
+        let duration = self.n_samples() as f32 / SAMPLE_RATE as f32;
+        crossings as f32 / duration
+    }
+
+    /// Estimates the fundamental frequency of the signal within `[min_hz, max_hz]` using the YIN
+    /// algorithm. Unlike picking the highest peak of the spectrum, it works from the periodicity of
+    /// the waveform, so it is robust to signals whose strongest partial is not the fundamental.
+    /// Returns `None` if the range is not positive, or the signal is too short, silent or not
+    /// periodic enough.
+    pub fn detect_fundamental(&self, min_hz: f32, max_hz: f32) -> Option<f32> {
+        const THRESHOLD: f32 = 0.1;
+        // Above this normalised difference the signal is not considered periodic.
+        const MAX_APERIODICITY: f32 = 0.5;
+
+        if !(min_hz > 0.0 && max_hz > min_hz) {
+            return None;
+        }
+
+        let samples = self.samples();
+        let sample_rate = SAMPLE_RATE as f32;
+
+        let tau_min = ((sample_rate / max_hz).floor() as usize).max(2);
+        let tau_max = (sample_rate / min_hz).ceil() as usize;
+        // integration window
+        let window = tau_max.max(1024);
+
+        if tau_min >= tau_max || samples.len() < window + tau_max + 1 {
+            return None;
+        }
+
+        // difference function
+        let diff: Vec<f32> = (0..=tau_max + 1)
+            .map(|tau| {
+                (0..window).map(|j| (samples[j] - samples[j + tau]).powi(2)).sum::<f32>()
+            })
+            .collect();
+
+        if diff.iter().all(|&d| d == 0.0) {
+            return None;
+        }
+
+        // cumulative mean normalised difference function
+        let mut cmnd = vec![1.0; diff.len()];
+        let mut running_sum = 0.0;
+        for tau in 1..diff.len() {
+            running_sum += diff[tau];
+            cmnd[tau] = if running_sum > 0.0 { diff[tau] * tau as f32 / running_sum } else { 1.0 };
+        }
+
+        // first dip below the threshold, followed down to its local minimum, or the global minimum
+        // if the threshold is never reached
+        let tau = match (tau_min..=tau_max).find(|&tau| cmnd[tau] < THRESHOLD) {
+            Some(mut tau) => {
+                while tau < tau_max && cmnd[tau + 1] < cmnd[tau] {
+                    tau += 1;
+                }
+                tau
+            }
+            None => {
+                let tau = (tau_min..=tau_max)
+                    .min_by(|&a, &b| cmnd[a].total_cmp(&cmnd[b]))
+                    .expect("The lag range should not be empty");
+                if cmnd[tau] > MAX_APERIODICITY {
+                    return None;
+                }
+                tau
+            }
+        };
+
+        // parabolic interpolation for sub-sample accuracy
+        let (prev, curr, next) = (cmnd[tau - 1], cmnd[tau], cmnd[tau + 1]);
+        let denominator = prev - 2.0 * curr + next;
+        let offset = if denominator.abs() > f32::EPSILON {
+            (0.5 * (prev - next) / denominator).clamp(-1.0, 1.0)
+        } else {
+            0.0
+        };
+
+        Some(sample_rate / (tau as f32 + offset))
+    }
+
+    /// Calculates the Euclidean distance between the samples of both signals. If their lengths
+    /// differ, the shorter one is padded with silence so that every sample counts.
     pub fn euclidean_distance(&self, other: &Self) -> f32 {
-        self.samples().iter().zip(other.samples()).map(|(s, o)| (s - o).powi(2))
+        self.padded_samples(other).map(|(s, o)| (s - o).powi(2))
             .sum::<f32>().sqrt()
     }
 
+    /// Calculates the Euclidean distance between the samples of both signals, handling a length
+    /// mismatch as specified.
+    pub fn euclidean_distance_with(&self, other: &Self, handling: LengthHandling) -> Result<f32, SignalProcessingError> {
+        self.check_length(other, handling)?;
+        Ok(self.euclidean_distance(other))
+    }
+
+    /// Calculates the mean absolute error between the samples of both signals, padding the shorter
+    /// one with silence. It is far less sensitive than the squared error to a few large mismatches.
+    pub fn mean_absolute_error(&self, other: &Self) -> f32 {
+        let n = self.n_samples().max(other.n_samples());
+        if n == 0 {
+            return 0.0;
+        }
+
+        self.padded_samples(other).map(|(s, o)| (s - o).abs()).sum::<f32>() / n as f32
+    }
+
+    /// Calculates the root-mean-square error between the samples of both signals divided by the
+    /// RMS of `other`, the reference, padding the shorter one with silence. A silent reference
+    /// results in an error.
+    pub fn nrmse(&self, other: &Self) -> Result<f32, SignalProcessingError> {
+        let n = self.n_samples().max(other.n_samples()) as f32;
+        let reference_rms = (other.samples().iter().map(|o| o.powi(2)).sum::<f32>() / n).sqrt();
+        if n == 0.0 || reference_rms == 0.0 {
+            return Err(SignalProcessingError::ZeroEnergy);
+        }
+
+        Ok(self.euclidean_distance(other) / n.sqrt() / reference_rms)
+    }
+
+    /// Calculates the RMS of consecutive windows of `window_len` samples, the last of which may be
+    /// shorter, which traces the loudness of the signal over time.
+    pub fn amplitude_envelope(&self, window_len: usize) -> Vec<f32> {
+        assert!(window_len > 0, "The window should contain at least one sample");
+
+        self.samples()
+            .chunks(window_len)
+            .map(|window| (window.iter().map(|s| s.powi(2)).sum::<f32>() / window.len() as f32).sqrt())
+            .collect()
+    }
+
+    /// Calculates the mean squared error between the amplitude envelopes of both signals over
+    /// windows of `window_len` samples, padding the shorter one with silence. Unlike the errors
+    /// between the samples, it does not depend on the phase or the spectrum of the signals, only on
+    /// how their loudness changes over time.
+    pub fn envelope_mse(&self, other: &Self, window_len: usize) -> f32 {
+        let (a, b) = (self.amplitude_envelope(window_len), other.amplitude_envelope(window_len));
+        let n = a.len().max(b.len());
+        if n == 0 {
+            return 0.0;
+        }
+
+        a.iter()
+            .zip_longest(&b)
+            .map(|pair| {
+                let (a, b) = pair.map_any(|a| *a, |b| *b).or_default();
+                (a - b).powi(2)
+            })
+            .sum::<f32>() / n as f32
+    }
+
+    /// Returns an error if the lengths of the signals differ and mismatches are not tolerated.
+    fn check_length(&self, other: &Self, handling: LengthHandling) -> Result<(), SignalProcessingError> {
+        match handling {
+            LengthHandling::Error if self.n_samples() != other.n_samples() => {
+                Err(SignalProcessingError::SignalLengthMismatch { a: self.n_samples(), b: other.n_samples() })
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Pairs of samples of both signals, with the shorter one padded with silence.
+    fn padded_samples<'a>(&'a self, other: &'a Self) -> impl Iterator<Item = (f32, f32)> + 'a {
+        self.samples().iter()
+            .zip_longest(other.samples())
+            .map(|pair| pair.map_any(|s| *s, |o| *o).or_default())
+    }
+
+    /// Finds the lag within `±max_lag` samples that maximises the cross-correlation between the two
+    /// signals, i.e. such that `self[i + lag]` best matches `other[i]`. The correlation is averaged
+    /// over the overlap so that shorter overlaps are not penalised.
+    pub fn best_lag(&self, other: &Self, max_lag: usize) -> isize {
+        let max_lag = max_lag as isize;
+
+        (-max_lag..=max_lag)
+            .map(|lag| {
+                let (s, o) = self.overlap(other, lag);
+                let corr = if s.is_empty() {
+                    f32::MIN
+                } else {
+                    s.iter().zip(o).map(|(s, o)| s * o).sum::<f32>() / s.len() as f32
+                };
+                (lag, corr)
+            })
+            // prefer the smallest shift if several lags correlate equally
+            .max_by(|(lag_a, a), (lag_b, b)| a.total_cmp(b).then(lag_b.abs().cmp(&lag_a.abs())))
+            .map_or(0, |(lag, _)| lag)
+    }
+
+    /// Calculates the Euclidean distance between the two signals over their overlap, after shifting
+    /// `self` by the lag within `±max_lag` samples that best aligns it with `other`.
+    pub fn aligned_euclidean_distance(&self, other: &Self, max_lag: usize) -> f32 {
+        let (s, o) = self.overlap(other, self.best_lag(other, max_lag));
+        Signal::from_samples(s).euclidean_distance(&Signal::from_samples(o))
+    }
+
+    /// Overlapping samples of both signals, with `self[i + lag]` paired with `other[i]`.
+    fn overlap<'a>(&'a self, other: &'a Self, lag: isize) -> (&'a [f32], &'a [f32]) {
+        let (s, o) = if lag >= 0 {
+            (&self.samples()[(lag as usize).min(self.n_samples())..], other.samples())
+        } else {
+            (self.samples(), &other.samples()[(-lag as usize).min(other.n_samples())..])
+        };
+        let n = s.len().min(o.len());
+
+        (&s[..n], &o[..n])
+    }
+
     /// Creates a copy of the signal whose number of samples is a power of two in order to analyse its frequency spectrum.
     /// Currently not in use
     pub fn extend_pow_two(&self) -> Self {
@@ -57,7 +683,12 @@ impl Signal {
     }
 
     pub fn normalise(&self) -> Self {
-        let n = 16_384;
+        self.normalise_to(FFT_LENGTH)
+    }
+
+    /// Creates a copy of the signal with exactly `n` samples, either truncating it or padding it
+    /// with silence.
+    pub fn normalise_to(&self, n: usize) -> Self {
         let mut new_samples = self.0.clone();
 
         if self.n_samples() >= n {
@@ -69,15 +700,38 @@ impl Signal {
     }
 }
 
-// Function that can calculate the error between two Fourier transforms
-// pub fn mse()
+/// Calculates the mean-squared error (MSE) between two frequency spectra after weighting the
+/// magnitude of each frequency bin. The spectra must have the same number of bins, i.e. be obtained
+/// with the same analysis length and band, otherwise a `SpectrumLengthMismatch` error is returned
+/// rather than comparing bins of different frequencies.
+pub fn spectrum_mse(a: &FrequencySpectrum, b: &FrequencySpectrum, weighting: Weighting) -> Result<f32, SignalProcessingError> {
+    if a.data().len() != b.data().len() {
+        return Err(SignalProcessingError::SpectrumLengthMismatch { a: a.data().len(), b: b.data().len() });
+    }
+
+    // number of discrete frequency points
+    let n = a.data().len() as f32;
+
+    let a_freq_vals = a.data().iter();
+    let b_freq_vals = b.data().iter().map(|(_, fv)| fv);
+
+    Ok(
+        // perform the mean squared error of the (weighted) frequency spectrum
+        a_freq_vals.zip(b_freq_vals)
+            .map(|((fr, a), b)| (weighting.gain(fr.val()) * (a.val() - b.val())).powi(2))
+            .sum::<f32>() / n
+    )
+}
 
 #[cfg(test)]
 mod tests {
     use super::*;
     use std::fs::File;
     use itertools::Itertools;
+    use rand::rngs::StdRng;
+    use rand::{Rng, SeedableRng};
     use wav_io::splitter::normalize_f;
+    use crate::signal_processing::components::oscillator::{saw_wave, sine_wave, square_wave};
 
     #[test]
     fn test_freq_spectrum() {
@@ -120,10 +774,442 @@ mod tests {
         assert_eq!(signal_3.n_samples(), signal_3.normalise().n_samples());
     }
 
+    #[test]
+    fn test_spectrum_length() {
+        let signal = sine_wave(440.0, 1.0, SAMPLE_RATE as f32, 1.0, 0.0);
+        let config = SpectrumConfig { length: 4_096, ..Default::default() };
+        assert_eq!(signal.spectrum(&config).unwrap().data().len(), 4_096 / 2 + 1);
+
+        for length in [3_000, 2 * MAX_FFT_LENGTH] {
+            let config = SpectrumConfig { length, ..Default::default() };
+            assert!(matches!(signal.spectrum(&config), Err(SignalProcessingError::InvalidFftLength(_))));
+        }
+    }
+
+    #[test]
+    fn test_averaged_spectrum() {
+        // identical heads, but wildly different tails
+        let head = sine_wave(440.0, 0.5, SAMPLE_RATE as f32, 1.0, 0.0);
+        let signal_1 = Signal::from_samples(&[head.samples(), head.samples()].concat());
+        let signal_2 = Signal::from_samples(&[
+            head.samples(),
+            sine_wave(3_000.0, 0.5, SAMPLE_RATE as f32, 1.0, 0.0).samples(),
+        ].concat());
+
+        let head_only = SpectrumConfig::default();
+        let averaged = SpectrumConfig { averaged: true, ..Default::default() };
+
+        assert_eq!(signal_1.freq_spectrum_mse_with(&signal_2, &head_only).unwrap(), 0.0);
+        assert!(signal_1.freq_spectrum_mse_with(&signal_2, &averaged).unwrap() > 1.0);
+        assert_eq!(signal_1.freq_spectrum_mse_with(&signal_1, &averaged).unwrap(), 0.0);
+
+        // short signals are analysed as a single block regardless
+        let short = sine_wave(440.0, 0.1, SAMPLE_RATE as f32, 1.0, 0.0);
+        assert_eq!(short.freq_spectrum_mse_with(&short.normalise(), &averaged).unwrap(), 0.0);
+    }
+
+    #[test]
+    fn test_spectral_snr() {
+        let target = sine_wave(440.0, 1.0, SAMPLE_RATE as f32, 1.0, 0.0)
+            .add_amp(&sine_wave(1_320.0, 1.0, SAMPLE_RATE as f32, 0.5, 0.0));
+        let candidate = sine_wave(440.0, 1.0, SAMPLE_RATE as f32, 1.0, 0.0);
+
+        assert_eq!(target.spectral_snr(&target).unwrap(), f32::INFINITY);
+
+        // the SNR only depends on the relative error, not the overall level
+        let loud = candidate.spectral_snr(&target).unwrap();
+        let quiet = candidate.scale_amp(0.1).spectral_snr(&target.scale_amp(0.1)).unwrap();
+        assert!(loud.is_finite() && loud > 0.0);
+        assert!((loud - quiet).abs() < 0.01, "loud: {loud}, quiet: {quiet}");
+
+        assert!(matches!(
+            candidate.spectral_snr(&Signal::default()),
+            Err(SignalProcessingError::ZeroEnergy)
+        ));
+    }
+
+    /// Unit impulse, whose spectrum is flat, with the frequency bins in the range removed.
+    fn band_rejected_impulse(n: usize, bins: std::ops::Range<usize>) -> Signal {
+        Signal::from_samples(&(0..n)
+            .map(|i| {
+                let impulse = if i == 0 { 1.0 } else { 0.0 };
+                let band = bins.clone()
+                    .map(|k| (2.0 * std::f64::consts::PI * (k * i) as f64 / n as f64).cos())
+                    .sum::<f64>() * 2.0 / n as f64;
+                (impulse - band) as f32
+            })
+            .collect_vec())
+    }
+
+    #[test]
+    fn test_itakura_saito() {
+        let n = FFT_LENGTH;
+        let target = band_rejected_impulse(n, 0..0);
+        let candidate = band_rejected_impulse(n, 1_000..1_500);
+
+        assert!(target.itakura_saito(&target).unwrap().abs() < 1e-4);
+
+        // missing energy where the target has some is heavily penalised
+        let candidate_vs_target = candidate.itakura_saito(&target).unwrap();
+        let target_vs_candidate = target.itakura_saito(&candidate).unwrap();
+        assert!(candidate_vs_target > 10.0 * target_vs_candidate,
+                "candidate vs target: {candidate_vs_target}, target vs candidate: {target_vs_candidate}");
+
+        // the MSE barely notices the rejected band
+        let is_fitness = 1.0 / (1.0 + candidate_vs_target);
+        let mse_fitness = 2.0 * utils::sigmoid(-(candidate.freq_spectrum_mse(&target).unwrap() / 1000.0).log10().exp());
+        assert!(is_fitness < mse_fitness, "IS: {is_fitness}, MSE: {mse_fitness}");
+    }
+
+    #[test]
+    fn test_spectral_cosine_similarity() {
+        let signal_1 = sine_wave(440.0, 1.0, SAMPLE_RATE as f32, 1.0, 0.0);
+        let signal_2 = sine_wave(3_000.0, 1.0, SAMPLE_RATE as f32, 1.0, 0.0);
+
+        assert!((signal_1.spectral_cosine_similarity(&signal_1).unwrap() - 1.0).abs() < 1e-4);
+        assert!((signal_1.scale_amp(0.1).spectral_cosine_similarity(&signal_1).unwrap() - 1.0).abs() < 1e-4);
+        assert!(signal_1.spectral_cosine_similarity(&signal_2).unwrap() < 0.1);
+        assert_eq!(signal_1.spectral_cosine_similarity(&Signal::default()).unwrap(), 0.0);
+    }
+
+    #[test]
+    fn test_a_weighting() {
+        assert!(a_weighting_db(1_000.0).abs() < 0.1);
+        assert!((a_weighting_db(100.0) + 19.1).abs() < 0.2);
+        assert!((a_weighting_db(10_000.0) + 2.5).abs() < 0.2);
+        assert!(a_weighting_db(0.0).is_infinite());
+        assert_eq!(Weighting::None.gain(100.0), 1.0);
+        assert!((Weighting::AWeight.gain(1_000.0) - 1.0).abs() < 0.01);
+    }
+
+    #[test]
+    fn test_freq_spectrum_mse_weighted() {
+        // the same error at 100 Hz barely counts after A-weighting, while at 2 kHz it remains
+        let base = sine_wave(1_000.0, 1.0, SAMPLE_RATE as f32, 1.0, 0.0);
+        let low = base.add_amp(&sine_wave(100.0, 1.0, SAMPLE_RATE as f32, 0.5, 0.0));
+        let mid = base.add_amp(&sine_wave(2_000.0, 1.0, SAMPLE_RATE as f32, 0.5, 0.0));
+
+        let low_ratio = low.freq_spectrum_mse_weighted(&base, Weighting::AWeight).unwrap()
+            / low.freq_spectrum_mse(&base).unwrap();
+        let mid_ratio = mid.freq_spectrum_mse_weighted(&base, Weighting::AWeight).unwrap()
+            / mid.freq_spectrum_mse(&base).unwrap();
+
+        assert!(low_ratio < 0.05, "low: {low_ratio}");
+        assert!(mid_ratio > 0.5, "mid: {mid_ratio}");
+    }
+
+    #[test]
+    fn test_spectrum_mse_length_mismatch() {
+        let signal = sine_wave(440.0, 1.0, SAMPLE_RATE as f32, 1.0, 0.0);
+        let short = signal.spectrum(&SpectrumConfig { length: 4_096, ..Default::default() }).unwrap();
+        let long = signal.freq_spectrum().unwrap();
+        let limited = signal.freq_spectrum_limited(FrequencyLimit::Max(8_000.0)).unwrap();
+
+        assert!(matches!(
+            spectrum_mse(&short, &long, Weighting::None),
+            Err(SignalProcessingError::SpectrumLengthMismatch { a: 2_049, b: 8_193 })
+        ));
+        assert!(matches!(
+            spectrum_mse(&long, &limited, Weighting::None),
+            Err(SignalProcessingError::SpectrumLengthMismatch { .. })
+        ));
+        assert_eq!(spectrum_mse(&long, &long, Weighting::None).unwrap(), 0.0);
+    }
+
+    #[test]
+    fn test_window_function_leakage() {
+        // 441 Hz falls between two frequency bins
+        let sine = sine_wave(441.0, 1.0, SAMPLE_RATE as f32, 1.0, 0.0);
+        let rect = sine.freq_spectrum_with(WindowFunction::Rect).unwrap();
+        let hann = sine.freq_spectrum_with(WindowFunction::Hann).unwrap();
+
+        // number of bins within 40 dB of the peak
+        let main_lobe_width = |spectrum: &FrequencySpectrum| {
+            let peak = spectrum.max().1.val();
+            spectrum.data().iter().filter(|(_, fv)| fv.val() > 0.01 * peak).count()
+        };
+
+        // magnitude far away from the partial relative to the peak
+        let far_leakage = |spectrum: &FrequencySpectrum| {
+            let peak = spectrum.max().1.val();
+            spectrum.data().iter()
+                .filter(|(f, _)| (f.val() - 441.0).abs() > 100.0)
+                .map(|(_, fv)| fv.val() / peak)
+                .sum::<f32>()
+        };
+
+        assert!(main_lobe_width(&hann) < main_lobe_width(&rect));
+        assert!(far_leakage(&hann) < 0.1 * far_leakage(&rect));
+    }
+
+    #[test]
+    fn test_window_function_default() {
+        let sine = sine_wave(441.0, 1.0, SAMPLE_RATE as f32, 1.0, 0.0);
+        assert_eq!(
+            sine.freq_spectrum().unwrap().data(),
+            sine.freq_spectrum_with(WindowFunction::Rect).unwrap().data()
+        );
+    }
+
+    #[test]
+    fn test_analysis_band() {
+        // bin-aligned partial above 8 kHz, so that it does not leak into the lower bins
+        let high_freq = 4_458.0 * SAMPLE_RATE as f32 / 16_384.0;
+        let signal_1 = sine_wave(440.0, 1.0, SAMPLE_RATE as f32, 1.0, 0.0);
+        let signal_2 = signal_1.add_amp(&sine_wave(high_freq, 1.0, SAMPLE_RATE as f32, 0.8, 0.0));
+
+        let full = SpectrumConfig::default();
+        let limited = SpectrumConfig { band: Some((20.0, 8_000.0)), ..Default::default() };
+
+        let full_mse = signal_1.freq_spectrum_mse_with(&signal_2, &full).unwrap();
+        let limited_mse = signal_1.freq_spectrum_mse_with(&signal_2, &limited).unwrap();
+        assert!(full_mse > 1.0);
+        assert!(limited_mse < 1e-4 * full_mse, "limited: {limited_mse}, full: {full_mse}");
+
+        let spectrum = signal_2.freq_spectrum_limited(FrequencyLimit::Range(20.0, 8_000.0)).unwrap();
+        assert!(spectrum.min_fr().val() >= 20.0 && spectrum.max_fr().val() <= 8_000.0);
+    }
+
+    #[test]
+    fn test_spectral_centroid() {
+        let sine = sine_wave(440.0, 1.0, SAMPLE_RATE as f32, 1.0, 0.0);
+        let saw = saw_wave(440.0, 1.0, SAMPLE_RATE as f32, 1.0, 0.0);
+
+        let sine_centroid = sine.spectral_centroid().unwrap();
+        let saw_centroid = saw.spectral_centroid().unwrap();
+        assert!(saw_centroid > sine_centroid, "saw: {saw_centroid}, sine: {sine_centroid}");
+    }
+
+    #[test]
+    fn test_spectral_rolloff() {
+        let sine = sine_wave(440.0, 1.0, SAMPLE_RATE as f32, 1.0, 0.0);
+        let saw = saw_wave(440.0, 1.0, SAMPLE_RATE as f32, 1.0, 0.0);
+
+        assert!(saw.spectral_rolloff(85.0).unwrap() > sine.spectral_rolloff(85.0).unwrap());
+        assert!(sine.spectral_rolloff(10.0).unwrap() <= sine.spectral_rolloff(90.0).unwrap());
+    }
+
+    #[test]
+    fn test_spectral_descriptors_silent() {
+        let empty = Signal::default();
+        let silent = Signal::from_samples(&[0.0; 1_000]);
+
+        for signal in [empty, silent] {
+            assert_eq!(signal.spectral_centroid().unwrap(), 0.0);
+            assert_eq!(signal.spectral_rolloff(85.0).unwrap(), 0.0);
+        }
+    }
+
+    #[test]
+    fn test_spectral_peaks() {
+        let signal = sine_wave(440.0, 1.0, SAMPLE_RATE as f32, 1.0, 0.0)
+            .add_amp(&sine_wave(880.0, 1.0, SAMPLE_RATE as f32, 0.5, 0.0))
+            .add_amp(&sine_wave(1_320.0, 1.0, SAMPLE_RATE as f32, 0.25, 0.0));
+        let peaks = signal.spectral_peaks(3, 50.0).unwrap();
+
+        assert_eq!(peaks.len(), 3);
+        for ((freq, magnitude), (expected_freq, expected_amp)) in peaks.iter().zip([(440.0, 1.0), (880.0, 0.5), (1_320.0, 0.25)]) {
+            assert!((freq - expected_freq).abs() < 1.0, "{freq} Hz should be close to {expected_freq} Hz");
+            let relative = magnitude / peaks[0].1;
+            assert!((relative - expected_amp).abs() < 0.05 * expected_amp, "{relative} should be close to {expected_amp}");
+        }
+
+        // the sidelobes of the main peaks are not reported as peaks
+        let peaks = signal.spectral_peaks(10, 50.0).unwrap();
+        assert!(peaks.windows(2).all(|p| p[0].1 >= p[1].1));
+        assert!(peaks.iter().enumerate().all(|(i, (f, _))| peaks[..i].iter().all(|(g, _)| (f - g).abs() >= 50.0)));
+        assert!(Signal::default().spectral_peaks(3, 50.0).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_harmonicity() {
+        let saw = saw_wave(440.0, 1.0, SAMPLE_RATE as f32, 0.5, 0.0);
+        let mut rng = StdRng::seed_from_u64(1);
+        let noise = Signal::from_samples(&(0..saw.n_samples()).map(|_| rng.gen_range(-0.3..0.3)).collect_vec());
+        let noisy_saw = saw.add_amp(&noise);
+
+        let clean = saw.harmonicity(None);
+        let noisy = noisy_saw.harmonicity(Some(440.0));
+        assert!(clean > 0.95, "clean: {clean}");
+        assert!(noisy < clean - 0.1, "noisy: {noisy}, clean: {clean}");
+        assert!((saw.harmonicity(Some(440.0)) - clean).abs() < 0.01);
+        assert_eq!(Signal::default().harmonicity(None), 0.0);
+    }
+
+    #[test]
+    fn test_zero_crossing_rate() {
+        // 4 crossings over 8 samples, i.e. over 8 / SAMPLE_RATE seconds
+        let signal = Signal::from_samples(&[1.0, -1.0, 1.0, -1.0, 1.0, 1.0, 1.0, 1.0]);
+        assert_eq!(signal.zero_crossing_rate(), 4.0 * SAMPLE_RATE as f32 / 8.0);
+
+        // consecutive zeros between samples of different sign count as a single crossing
+        let signal = Signal::from_samples(&[1.0, 0.0, 0.0, 0.0, -1.0, 0.0, -1.0, -1.0]);
+        assert_eq!(signal.zero_crossing_rate(), SAMPLE_RATE as f32 / 8.0);
+
+        // touching zero without changing sign is not a crossing
+        let signal = Signal::from_samples(&[1.0, 0.0, 1.0, 0.0]);
+        assert_eq!(signal.zero_crossing_rate(), 0.0);
+
+        let sine = sine_wave(440.0, 1.0, SAMPLE_RATE as f32, 1.0, 0.1);
+        assert!((sine.zero_crossing_rate() - 880.0).abs() <= 2.0);
+    }
+
+    #[test]
+    fn test_zero_crossing_rate_short() {
+        assert_eq!(Signal::default().zero_crossing_rate(), 0.0);
+        assert_eq!(Signal::from_samples(&[1.0]).zero_crossing_rate(), 0.0);
+    }
+
+    #[test]
+    fn test_detect_fundamental() {
+        let sine = sine_wave(440.0, 1.0, SAMPLE_RATE as f32, 1.0, 0.0);
+        let saw = saw_wave(440.0, 1.0, SAMPLE_RATE as f32, 1.0, 0.0);
+        let square = square_wave(110.0, 1.0, SAMPLE_RATE as f32, 1.0, 0.0);
+
+        for (signal, expected) in [(sine, 440.0), (saw, 440.0), (square, 110.0)] {
+            let detected = signal.detect_fundamental(50.0, 2_000.0).unwrap();
+            assert!((detected - expected).abs() <= 2.0, "expected {expected}, detected {detected}");
+        }
+    }
+
+    #[test]
+    fn test_detect_fundamental_weak_first_partial() {
+        // the second and third harmonics are much louder than the fundamental
+        let signal = sine_wave(220.0, 1.0, SAMPLE_RATE as f32, 0.2, 0.0)
+            .add_amp(&sine_wave(440.0, 1.0, SAMPLE_RATE as f32, 1.0, 0.0))
+            .add_amp(&sine_wave(660.0, 1.0, SAMPLE_RATE as f32, 0.8, 0.0));
+
+        let detected = signal.detect_fundamental(50.0, 2_000.0).unwrap();
+        assert!((detected - 220.0).abs() <= 2.0, "detected {detected}");
+    }
+
+    #[test]
+    fn test_detect_fundamental_silent() {
+        assert_eq!(Signal::default().detect_fundamental(50.0, 2_000.0), None);
+        assert_eq!(Signal::from_samples(&[0.0; 10_000]).detect_fundamental(50.0, 2_000.0), None);
+    }
+
+    #[test]
+    fn test_detect_fundamental_invalid_range() {
+        let sine = sine_wave(440.0, 1.0, SAMPLE_RATE as f32, 1.0, 0.0);
+        for (min_hz, max_hz) in [(0.0, 2_000.0), (-50.0, 2_000.0), (2_000.0, 50.0), (440.0, 440.0), (f32::NAN, 2_000.0)] {
+            assert_eq!(sine.detect_fundamental(min_hz, max_hz), None, "{min_hz}..{max_hz}");
+        }
+    }
+
+    #[test]
+    fn test_aligned_euclidean_distance() {
+        let target = sine_wave(440.0, 1.0, SAMPLE_RATE as f32, 1.0, 0.0)
+            .add_amp(&sine_wave(1_234.5, 1.0, SAMPLE_RATE as f32, 0.5, 0.0));
+        // delayed copy of the target
+        let shift = 200;
+        let delayed = Signal::from_samples(&[vec![0.0; shift], target.samples()[..target.n_samples() - shift].to_vec()].concat());
+
+        assert_eq!(delayed.best_lag(&target, 300), shift as isize);
+        assert_eq!(target.best_lag(&delayed, 300), -(shift as isize));
+        assert_eq!(target.best_lag(&target, 300), 0);
+
+        assert!(delayed.euclidean_distance(&target) > 10.0);
+        assert_eq!(delayed.aligned_euclidean_distance(&target, 300), 0.0);
+        assert_eq!(target.aligned_euclidean_distance(&delayed, 300), 0.0);
+        // the shift is out of reach
+        assert!(delayed.aligned_euclidean_distance(&target, 10) > 1.0);
+    }
+
     #[test]
     fn test_euclidean_distance() {
         let signal_1 = Signal::from_samples(&[0.0, 0.5, 0.5, 1.0]);
         let signal_2 = Signal::from_samples(&[1.0, 0.0, 1.0, 0.0]);
         assert_eq!(signal_1.euclidean_distance(&signal_2), 2.5f32.sqrt())
     }
+
+    #[test]
+    fn test_mean_absolute_error() {
+        let signal_1 = Signal::from_samples(&[0.0, 0.5, 0.5, 1.0]);
+        let signal_2 = Signal::from_samples(&[1.0, 0.0, 1.0, 0.0]);
+        assert_eq!(signal_1.mean_absolute_error(&signal_2), 0.75);
+        assert_eq!(signal_1.mean_absolute_error(&Signal::from_samples(&[0.0, 0.5])), 0.375);
+        assert_eq!(Signal::default().mean_absolute_error(&Signal::default()), 0.0);
+    }
+
+    #[test]
+    fn test_nrmse() {
+        let target = sine_wave(440.0, 1.0, SAMPLE_RATE as f32, 1.0, 0.0);
+        assert_eq!(target.nrmse(&target).unwrap(), 0.0);
+        // silence is as far from the target as the target itself
+        assert!((Signal::default().nrmse(&target).unwrap() - 1.0).abs() < 1e-4);
+        // the error is relative to the level of the target
+        let quiet = target.scale_amp(0.1);
+        assert!((target.scale_amp(0.5).nrmse(&target).unwrap() - quiet.scale_amp(0.5).nrmse(&quiet).unwrap()).abs() < 1e-4);
+        assert!(matches!(target.nrmse(&Signal::default()), Err(SignalProcessingError::ZeroEnergy)));
+    }
+
+    #[test]
+    fn test_amplitude_envelope() {
+        let signal = Signal::from_samples(&[1.0, -1.0, 0.5, -0.5, 0.0]);
+        assert_eq!(signal.amplitude_envelope(2), vec![1.0, 0.5, 0.0]);
+        assert_eq!(Signal::default().amplitude_envelope(2), Vec::<f32>::new());
+
+        // a sine has an RMS of 1/√2 of its amplitude in every window of whole cycles
+        let sine = sine_wave(441.0, 1.0, SAMPLE_RATE as f32, 0.8, 0.0);
+        let envelope = sine.amplitude_envelope(SAMPLE_RATE as usize / 441 * 10);
+        assert!(envelope.iter().all(|rms| (rms - 0.8 / 2f32.sqrt()).abs() < 1e-3));
+    }
+
+    #[test]
+    fn test_envelope_mse() {
+        let signal = Signal::from_samples(&[1.0, -1.0, 0.5, -0.5]);
+        assert_eq!(signal.envelope_mse(&signal.scale_amp(-1.0), 2), 0.0);
+        assert_eq!(signal.envelope_mse(&Signal::from_samples(&[0.5, 0.5]), 2), (0.25 + 0.25) / 2.0);
+
+        // the phase of a sine does not change its envelope
+        let sine = sine_wave(441.0, 1.0, SAMPLE_RATE as f32, 1.0, 0.0);
+        let shifted = sine_wave(441.0, 1.0, SAMPLE_RATE as f32, 1.0, 1.0);
+        assert!(sine.envelope_mse(&shifted, 1000) < 1e-6);
+        assert!(sine.envelope_mse(&sine.scale_amp(0.5), 1000) > 0.01);
+    }
+
+    #[test]
+    fn test_single_spike_ranking() {
+        let target = sine_wave(440.0, 0.1, SAMPLE_RATE as f32, 0.5, 0.0);
+        // small error everywhere vs a perfect copy with a single large spike
+        let offset = Signal::from_samples(&target.samples().iter().map(|s| s + 0.01).collect_vec());
+        let mut samples = target.samples().to_vec();
+        samples[100] += 1.0;
+        let spiked = Signal::from_samples(&samples);
+
+        assert!(spiked.euclidean_distance(&target) > offset.euclidean_distance(&target));
+        assert!(spiked.nrmse(&target).unwrap() > offset.nrmse(&target).unwrap());
+        assert!(spiked.mean_absolute_error(&target) < offset.mean_absolute_error(&target));
+    }
+
+    #[test]
+    fn test_euclidean_distance_shorter_candidate() {
+        let target = Signal::from_samples(&[0.5, 0.5, 0.5, 0.5]);
+        let candidate = Signal::from_samples(&[0.5, 0.5]);
+
+        // the missing samples count as silence
+        assert_eq!(candidate.euclidean_distance(&target), 0.5f32.sqrt());
+        assert_eq!(candidate.euclidean_distance_with(&target, LengthHandling::ZeroPad).unwrap(), 0.5f32.sqrt());
+        assert!(matches!(
+            candidate.euclidean_distance_with(&target, LengthHandling::Error),
+            Err(SignalProcessingError::SignalLengthMismatch { a: 2, b: 4 })
+        ));
+    }
+
+    #[test]
+    fn test_euclidean_distance_shorter_target() {
+        let target = Signal::from_samples(&[0.5, 0.5]);
+        let clean = Signal::from_samples(&[0.5, 0.5, 0.0, 0.0]);
+        let garbage = Signal::from_samples(&[0.5, 0.5, 1.0, -1.0]);
+
+        // the samples after the end of the target are no longer ignored
+        assert_eq!(clean.euclidean_distance(&target), 0.0);
+        assert_eq!(garbage.euclidean_distance(&target), 2f32.sqrt());
+        assert!(matches!(
+            garbage.euclidean_distance_with(&target, LengthHandling::Error),
+            Err(SignalProcessingError::SignalLengthMismatch { a: 4, b: 2 })
+        ));
+        assert_eq!(target.euclidean_distance_with(&target, LengthHandling::Error).unwrap(), 0.0);
+    }
 }
@@ -0,0 +1,169 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use csv::Writer;
+use log::info;
+use crate::error::SignalProcessingError;
+use crate::error::SignalProcessingError::CouldNotWriteToFile;
+use crate::signal_processing::signal_analysis::{SpectrumConfig, WindowFunction};
+use crate::signal_processing::{Signal, SAMPLE_RATE};
+
+/// Number of samples per frame used when exporting spectrograms of a simulation.
+pub const SPECTROGRAM_FRAME_LENGTH: usize = 2_048;
+/// Number of samples between the start of consecutive frames when exporting spectrograms.
+pub const SPECTROGRAM_HOP: usize = 512;
+
+/// Magnitude spectrum of consecutive frames of a signal, i.e. how its frequency content evolves
+/// over time.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Spectrogram {
+    /// Time in seconds at the centre of each frame.
+    pub times: Vec<f32>,
+    /// Frequency in Hz of each bin.
+    pub frequencies: Vec<f32>,
+    /// Magnitude of each frequency bin, indexed by frame first and bin second.
+    pub magnitudes: Vec<Vec<f32>>,
+}
+
+impl Signal {
+    /// Calculates the spectrogram of the signal from Hann-windowed frames of `frame_len` samples,
+    /// which must be a valid analysis length, starting every `hop` samples, which must be positive.
+    /// A signal shorter than a frame is padded with silence.
+    pub fn spectrogram(&self, frame_len: usize, hop: usize) -> Result<Spectrogram, SignalProcessingError> {
+        if hop == 0 {
+            return Err(SignalProcessingError::InvalidHop);
+        }
+        let config = SpectrumConfig { window: WindowFunction::Hann, length: frame_len, ..Default::default() };
+
+        let n_frames = if self.n_samples() <= frame_len {
+            1
+        } else {
+            (self.n_samples() - frame_len) / hop + 1
+        };
+
+        let mut times = vec![];
+        let mut frequencies = vec![];
+        let mut magnitudes = vec![];
+
+        for i in 0..n_frames {
+            let start = i * hop;
+            let end = (start + frame_len).min(self.n_samples());
+            let spectrum = Signal::from_samples(&self.samples()[start..end]).unscaled_spectrum(&config)?;
+
+            if frequencies.is_empty() {
+                frequencies = spectrum.data().iter().map(|(fr, _)| fr.val()).collect();
+            }
+            times.push((start + frame_len / 2) as f32 / SAMPLE_RATE as f32);
+            magnitudes.push(spectrum.data().iter().map(|(_, fv)| fv.val()).collect());
+        }
+
+        Ok(Spectrogram { times, frequencies, magnitudes })
+    }
+}
+
+impl Spectrogram {
+    /// Exports the spectrogram to a CSV file with a `time,frequency,magnitude` row per frame and
+    /// frequency bin.
+    pub fn to_csv(&self, file_path: &str) -> Result<(), SignalProcessingError> {
+        let error = || CouldNotWriteToFile(file_path.to_string());
+        let path = Self::export_path(file_path)?;
+        let mut wtr = Writer::from_path(path).map_err(|_| error())?;
+
+        wtr.write_record(["time", "frequency", "magnitude"]).map_err(|_| error())?;
+        for (time, frame) in self.times.iter().zip(&self.magnitudes) {
+            for (freq, magnitude) in self.frequencies.iter().zip(frame) {
+                wtr.write_record(&[time.to_string(), freq.to_string(), magnitude.to_string()])
+                    .map_err(|_| error())?;
+            }
+        }
+
+        wtr.flush().map_err(|_| error())?;
+        info!("Spectrogram successfully written to file {file_path}");
+        Ok(())
+    }
+
+    /// Renders the spectrogram to a PNG image, with time on the horizontal axis, frequency on the
+    /// vertical one and the magnitudes in dB relative to the loudest frequency bin. The axes are not
+    /// labelled, as no fonts are bundled.
+    #[cfg(feature = "plotting")]
+    pub fn to_png(&self, file_path: &str) -> Result<(), SignalProcessingError> {
+        use plotters::prelude::*;
+
+        let error = || CouldNotWriteToFile(file_path.to_string());
+
+        let path = Self::export_path(file_path)?;
+        let root = BitMapBackend::new(&path, (1_024, 768)).into_drawing_area();
+        root.fill(&WHITE).map_err(|_| error())?;
+
+        let max_time = self.times.last().copied().unwrap_or(0.0).max(f32::EPSILON);
+        let max_freq = self.frequencies.last().copied().unwrap_or(0.0).max(f32::EPSILON);
+        let mut chart = ChartBuilder::on(&root)
+            .build_cartesian_2d(0.0..max_time, 0.0..max_freq)
+            .map_err(|_| error())?;
+
+        let max_magnitude = self.magnitudes.iter().flatten().copied().fold(f32::EPSILON, f32::max);
+        let frame_width = self.times.get(1).map_or(max_time, |t| t - self.times[0]);
+        let bin_height = self.frequencies.get(1).copied().unwrap_or(max_freq);
+
+        chart.draw_series(self.times.iter().zip(&self.magnitudes).flat_map(|(time, frame)| {
+            self.frequencies.iter().zip(frame).map(move |(freq, magnitude)| {
+                // map -80..0 dB to the colour scale
+                let db = 20.0 * (magnitude.max(f32::EPSILON) / max_magnitude).log10();
+                let intensity = ((db + 80.0) / 80.0).clamp(0.0, 1.0) as f64;
+                Rectangle::new(
+                    [(time - frame_width / 2.0, *freq), (time + frame_width / 2.0, freq + bin_height)],
+                    HSLColor(0.7 - 0.7 * intensity, 1.0, 0.5 * intensity).filled(),
+                )
+            })
+        })).map_err(|_| error())?;
+
+        root.present().map_err(|_| error())?;
+        info!("Spectrogram successfully written to file {file_path}");
+        Ok(())
+    }
+
+    /// Path inside the exports directory, creating its parent directories if needed.
+    fn export_path(file_path: &str) -> Result<PathBuf, SignalProcessingError> {
+        let error = || CouldNotWriteToFile(file_path.to_string());
+        let path = Path::new("exports/spectrogram").join(file_path);
+        fs::create_dir_all(path.parent().expect("File should have parent."))
+            .map_err(|_| error())?;
+        Ok(path)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::signal_processing::components::oscillator::sine_wave;
+
+    #[test]
+    fn test_spectrogram() {
+        // 440 Hz for the first half second, then 3 kHz
+        let signal = Signal::from_samples(&[
+            sine_wave(440.0, 0.5, SAMPLE_RATE as f32, 1.0, 0.0).samples(),
+            sine_wave(3_000.0, 0.5, SAMPLE_RATE as f32, 1.0, 0.0).samples(),
+        ].concat());
+        let spectrogram = signal.spectrogram(2_048, 512).unwrap();
+
+        assert_eq!(spectrogram.times.len(), (signal.n_samples() - 2_048) / 512 + 1);
+        assert_eq!(spectrogram.frequencies.len(), 2_048 / 2 + 1);
+        assert!(spectrogram.magnitudes.iter().all(|frame| frame.len() == spectrogram.frequencies.len()));
+        assert!(spectrogram.times.windows(2).all(|t| t[0] < t[1]));
+
+        let peak_freq = |frame: &[f32]| {
+            let (i, _) = frame.iter().enumerate().max_by(|a, b| a.1.total_cmp(b.1)).unwrap();
+            spectrogram.frequencies[i]
+        };
+        assert!((peak_freq(&spectrogram.magnitudes[0]) - 440.0).abs() < 25.0);
+        assert!((peak_freq(spectrogram.magnitudes.last().unwrap()) - 3_000.0).abs() < 25.0);
+    }
+
+    #[test]
+    fn test_spectrogram_short_signal() {
+        let signal = sine_wave(440.0, 0.01, SAMPLE_RATE as f32, 1.0, 0.0);
+        let spectrogram = signal.spectrogram(2_048, 512).unwrap();
+        assert_eq!(spectrogram.times.len(), 1);
+        assert!(matches!(signal.spectrogram(1_000, 512), Err(SignalProcessingError::InvalidFftLength(1_000))));
+        assert!(matches!(signal.spectrogram(2_048, 0), Err(SignalProcessingError::InvalidHop)));
+    }
+}
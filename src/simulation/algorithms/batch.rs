@@ -0,0 +1,141 @@
+use std::path::Path;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng, thread_rng};
+use rayon::prelude::*;
+use crate::error::GeneticSimulationError;
+use crate::simulation::algorithms::genetic::{GASimulationBuilder, Individual, SimulationReport};
+use crate::utils::{mean, std};
+
+/// Outcome of running the same genetic algorithm simulation several times with different seeds.
+#[derive(Clone, Debug)]
+pub struct BatchReport<T: Individual> {
+    /// The report of every run, in the order of their numbered export directories.
+    pub reports: Vec<SimulationReport<T>>,
+    /// Seed from which the seeds of all the runs are drawn.
+    pub seed: u64,
+}
+
+impl<T: Individual> BatchReport<T> {
+    /// The fittest individual of each run's whole history.
+    fn best_fitness(&self) -> Vec<f32> {
+        self.reports.iter().map(|report| report.fittest.fitness()).collect()
+    }
+
+    /// Average over the runs of the fitness of their fittest individual.
+    pub fn mean_best_fitness(&self) -> f32 {
+        mean(&self.best_fitness())
+    }
+
+    /// Spread over the runs of the fitness of their fittest individual, as given by `utils::std`.
+    pub fn std_best_fitness(&self) -> f32 {
+        std(&self.best_fitness())
+    }
+
+    /// Average number of generations the runs took for their best fitness to reach the threshold,
+    /// among those that reached it, or `None` if none did.
+    pub fn mean_generations_to(&self, threshold: f32) -> Option<f32> {
+        let generations: Vec<f32> = self.reports.iter()
+            .filter_map(|report| report.history.iter().position(|row| row.best_ever_fitness >= threshold))
+            .map(|generation| generation as f32)
+            .collect();
+
+        (!generations.is_empty()).then(|| mean(&generations))
+    }
+}
+
+/// Inserts a directory named after the run before the file name of an export path.
+pub(crate) fn numbered(path: &str, run: u32) -> String {
+    let path = Path::new(path);
+    let file_name = path.file_name().expect("The export path should have a file name.");
+    path.parent().unwrap_or(Path::new("")).join(run.to_string()).join(file_name)
+        .to_str().expect("The path should be valid unicode.")
+        .to_string()
+}
+
+/// Appends a directory named after the run to an export directory.
+pub(crate) fn numbered_dir(dir: &str, run: u32) -> String {
+    Path::new(dir).join(run.to_string()).to_str().expect("The path should be valid unicode.").to_string()
+}
+
+impl<T: Individual> GASimulationBuilder<T>
+where
+    T::Generator: Clone + Send,
+{
+    /// Runs `n` independent simulations with the parameters of the builder in parallel, returning
+    /// all their reports. Each run draws its own seed from the seed of the builder, so that the
+    /// whole batch is reproducible, and its exports are written to a subdirectory named after its
+    /// number, e.g. `runs/3/stats.csv` for a CSV export to `runs/stats.csv`.
+    pub fn run_repeated(self, n: u32) -> Result<BatchReport<T>, GeneticSimulationError> {
+        if n == 0 {
+            return Err(GeneticSimulationError::InvalidRunCount);
+        }
+        let seed = self.seed.unwrap_or_else(|| thread_rng().gen());
+        let mut rng = StdRng::seed_from_u64(seed);
+        let builders: Vec<GASimulationBuilder<T>> = (0..n).map(|run| GASimulationBuilder {
+            csv_export: self.csv_export.as_deref().map(|path| numbered(path, run)),
+            signal_export: self.signal_export.as_deref().map(|path| numbered(path, run)),
+            spectrogram_export: self.spectrogram_export.as_deref().map(|dir| numbered_dir(dir, run)),
+            hall_of_fame_export: self.hall_of_fame_export.as_deref().map(|dir| numbered_dir(dir, run)),
+            checkpoint: self.checkpoint.as_ref().map(|(interval, path)| (*interval, numbered(path, run))),
+            seed: Some(rng.gen()),
+            ..self.clone()
+        }).collect();
+
+        let reports = builders.into_par_iter()
+            .map(|builder| builder.build()?.run_with_report())
+            .collect::<Result<_, _>>()?;
+
+        Ok(BatchReport { reports, seed })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+    use super::*;
+    use crate::signal_processing::components::oscillator::sine_wave;
+    use crate::signal_processing::SAMPLE_RATE;
+    use crate::simulation::algorithms::genetic::IndividualGenerator;
+    use crate::simulation::synthesis_methods::subtractive::SubtractiveIndividual;
+
+    fn builder() -> GASimulationBuilder<SubtractiveIndividual> {
+        let generator = SubtractiveIndividual::new_generator()
+            .target(Arc::new(sine_wave(440.0, 1.0, SAMPLE_RATE as f32, 1.0, 0.0)))
+            .oscillator();
+
+        GASimulationBuilder::new()
+            .initial_population(10)
+            .max_generations(3)
+            .seed(12)
+            .quiet()
+            .generator(generator)
+    }
+
+    #[test]
+    fn test_run_repeated() {
+        let batch = builder().run_repeated(4).unwrap();
+        let again = builder().run_repeated(4).unwrap();
+
+        // the runs differ from each other, but the batch is reproducible from its seed
+        assert_eq!(batch.reports.len(), 4);
+        assert!(batch.reports.iter().all(|report| report.generations == 3));
+        assert!(batch.best_fitness().windows(2).any(|w| w[0] != w[1]));
+        assert_eq!(batch.best_fitness(), again.best_fitness());
+
+        let best = batch.best_fitness().into_iter().fold(0.0, f32::max);
+        assert!(batch.mean_best_fitness() <= best);
+        assert!(batch.mean_generations_to(0.0) == Some(0.0));
+        assert!(batch.mean_generations_to(best + 1.0).is_none());
+    }
+
+    #[test]
+    fn test_no_runs() {
+        assert!(matches!(builder().run_repeated(0), Err(GeneticSimulationError::InvalidRunCount)));
+    }
+
+    #[test]
+    fn test_numbered_export_paths() {
+        assert_eq!(numbered("runs/stats.csv", 3), "runs/3/stats.csv");
+        assert_eq!(numbered("stats.csv", 0), "0/stats.csv");
+    }
+}
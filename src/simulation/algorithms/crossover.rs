@@ -0,0 +1,175 @@
+use rand::Rng;
+use serde::{Serialize, Deserialize};
+use crate::simulation::algorithms::mutation::MutationOperator;
+use crate::utils::random_weighted_average;
+
+/// Specifies how the genes of two parents are recombined into the genes of their two children.
+/// Every operator produces complementary children, so that each parent's value of a gene ends up
+/// in one of them, except for mutations.
+#[derive(Copy, Clone, Debug, PartialEq, Default, Serialize, Deserialize)]
+pub enum CrossoverOperator {
+    /// Each pair of genes is blended with a random weight, giving values within the range spanned
+    /// by both parents.
+    #[default]
+    Arithmetic,
+    /// Each gene is copied from a randomly chosen parent, and its counterpart from the other one.
+    Uniform,
+    /// The genes are split at a random index of the ordering in which the components expose them,
+    /// with each child taking the genes before it from one parent and the rest from the other.
+    SinglePoint,
+    /// Simulated binary crossover, which spreads each pair of genes symmetrically around their
+    /// midpoint by a random factor whose distribution is controlled by the index `eta`: the larger
+    /// it is, the closer the children land to their parents. Children falling outside the bounds of
+    /// a gene are clamped to them. The index must not be negative.
+    SBX { eta: f32 },
+}
+
+impl CrossoverOperator {
+    /// Whether the parameters of the operator are within their valid ranges.
+    pub fn is_valid(&self) -> bool {
+        match self {
+            CrossoverOperator::SBX { eta } => *eta >= 0.0,
+            _ => true,
+        }
+    }
+
+    /// Recombines two sequences of genes, which are truncated to the shortest of them, into two
+    /// complementary children. Each gene of the children then mutates with probability `r` by the
+    /// `mutation` operator, replacing it with a value drawn by `random_gene` from the index of the
+    /// gene if needed. `bounds` returns the minimum and maximum values of the gene at each index,
+    /// which the operators spreading the genes beyond their parents must respect.
+    #[allow(clippy::too_many_arguments)]
+    pub fn combine<R: Rng + ?Sized>(
+        &self,
+        genes_self: &[f32],
+        genes_other: &[f32],
+        r: f32,
+        mutation: MutationOperator,
+        mut random_gene: impl FnMut(usize, &mut R) -> f32,
+        bounds: impl Fn(usize) -> (f32, f32),
+        rng: &mut R,
+    ) -> (Vec<f32>, Vec<f32>) {
+        let pairs = genes_self.iter().copied().zip(genes_other.iter().copied());
+        let mut mutate_gene = |i: usize, gene: f32, rng: &mut R| {
+            mutation.mutate(gene, bounds(i), |rng| random_gene(i, rng), rng)
+        };
+
+        let (children_self, children_other): (Vec<f32>, Vec<f32>) = match self {
+            CrossoverOperator::Arithmetic => {
+                return pairs.enumerate()
+                    .map(|(i, (s, o))| random_weighted_average(s, o, r, |gene, rng| mutate_gene(i, gene, rng), rng))
+                    .unzip();
+            }
+            CrossoverOperator::Uniform => {
+                pairs.map(|(s, o)| if rng.gen_bool(0.5) { (o, s) } else { (s, o) }).unzip()
+            }
+            CrossoverOperator::SinglePoint => {
+                let len = genes_self.len().min(genes_other.len());
+                let point = if len > 1 { rng.gen_range(1..len) } else { 0 };
+                pairs.enumerate().map(|(i, (s, o))| if i < point { (s, o) } else { (o, s) }).unzip()
+            }
+            CrossoverOperator::SBX { eta } => {
+                pairs.enumerate().map(|(i, (s, o))| {
+                    let (min, max) = bounds(i);
+                    let (a, b) = sbx(s, o, *eta, rng);
+                    (a.clamp(min, max), b.clamp(min, max))
+                }).unzip()
+            }
+        };
+
+        let mut mutate = |genes: Vec<f32>, rng: &mut R| genes.into_iter().enumerate()
+            .map(|(i, gene)| if rng.gen::<f32>() < r { mutate_gene(i, gene, rng) } else { gene })
+            .collect();
+
+        (mutate(children_self, rng), mutate(children_other, rng))
+    }
+}
+
+/// Spreads two values around their midpoint by a spread factor drawn from the polynomial
+/// distribution of simulated binary crossover with index `eta`.
+fn sbx<R: Rng + ?Sized>(v_self: f32, v_other: f32, eta: f32, rng: &mut R) -> (f32, f32) {
+    let u: f32 = rng.gen();
+    let beta = if u <= 0.5 {
+        (2.0 * u).powf(1.0 / (eta + 1.0))
+    } else {
+        (1.0 / (2.0 * (1.0 - u))).powf(1.0 / (eta + 1.0))
+    };
+
+    (
+        0.5 * ((1.0 + beta) * v_self + (1.0 - beta) * v_other),
+        0.5 * ((1.0 - beta) * v_self + (1.0 + beta) * v_other),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+    use crate::simulation::components::oscillator::{OscillatorComponent, OscillatorSettings};
+    use crate::utils::mean;
+    use itertools::Itertools;
+
+    #[test]
+    fn test_uniform_crossover() {
+        let mut rng = StdRng::seed_from_u64(1);
+        let (a, b) = (OscillatorComponent::create(OscillatorSettings::default(), &mut rng), OscillatorComponent::create(OscillatorSettings::default(), &mut rng));
+
+        // without mutations, every gene is inherited from one parent and its counterpart from the
+        // other one
+        for _ in 0..10 {
+            let (child_a, child_b) = a.combine(&b, 0.0, CrossoverOperator::Uniform, MutationOperator::Replace, &mut rng).unwrap();
+            let genes = a.genes().into_iter().zip(b.genes()).zip(child_a.genes().into_iter().zip(child_b.genes()));
+            for ((ga, gb), (gca, gcb)) in genes {
+                assert!((gca == ga && gcb == gb) || (gca == gb && gcb == ga));
+            }
+        }
+    }
+
+    #[test]
+    fn test_single_point_crossover() {
+        let (a, b) = ([1.0, 2.0, 3.0, 4.0, 5.0], [-1.0, -2.0, -3.0, -4.0, -5.0]);
+        let mut rng = StdRng::seed_from_u64(1);
+
+        for _ in 0..10 {
+            let (child_a, child_b) = CrossoverOperator::SinglePoint.combine(&a, &b, 0.0, MutationOperator::Replace, |_, _| 0.0, |_| (-5.0, 5.0), &mut rng);
+            let point = child_a.iter().position(|&g| g < 0.0).expect("The split should be within the genes");
+
+            assert!(point > 0);
+            assert!(child_a[point..].iter().all(|&g| g < 0.0));
+            assert!(child_a.iter().zip(&child_b).all(|(ga, gb)| ga + gb == 0.0));
+        }
+    }
+
+    #[test]
+    fn test_sbx_crossover() {
+        let mut rng = StdRng::seed_from_u64(1);
+        let children = |eta: f32, rng: &mut StdRng| (0..10_000).flat_map(|_| {
+            let (a, b) = CrossoverOperator::SBX { eta }.combine(&[0.4], &[0.6], 0.0, MutationOperator::Replace, |_, _| 0.0, |_| (0.0, 1.0), rng);
+            [a[0], b[0]]
+        }).collect_vec();
+
+        // the children are spread symmetrically around the midpoint of their parents
+        let wide = children(2.0, &mut rng);
+        let above = wide.iter().filter(|&&c| c > 0.5).count() as f32 / wide.len() as f32;
+        assert!((mean(&wide) - 0.5).abs() < 0.005, "{}", mean(&wide));
+        assert!((0.48..0.52).contains(&above), "{above}");
+        assert!(wide.iter().all(|c| (0.0..=1.0).contains(c)));
+
+        // and land closer to their parents as the distribution index grows
+        let spread = |children: &[f32]| mean(&children.iter().map(|c| ((c - 0.5).abs() - 0.1).abs()).collect_vec());
+        let narrow = children(20.0, &mut rng);
+        assert!(spread(&narrow) < spread(&wide) / 2.0, "{} {}", spread(&narrow), spread(&wide));
+        assert!(!CrossoverOperator::SBX { eta: -1.0 }.is_valid());
+    }
+
+    #[test]
+    fn test_sbx_crossover_bounds() {
+        let mut rng = StdRng::seed_from_u64(1);
+
+        for _ in 0..1000 {
+            let (a, b) = CrossoverOperator::SBX { eta: 0.0 }.combine(&[0.01, 9.9], &[0.02, 9.95], 0.0, MutationOperator::Replace, |_, _| 0.0, |i| [(0.0, 1.0), (0.0, 10.0)][i], &mut rng);
+            assert!(a.iter().chain(&b).zip([1.0, 10.0, 1.0, 10.0]).all(|(&g, max)| (0.0..=max).contains(&g)));
+        }
+    }
+}
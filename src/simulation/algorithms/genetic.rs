@@ -1,60 +1,302 @@
 use std::borrow::Borrow;
-use crate::signal_processing::Signal;
-use crate::utils::sigmoid;
-use rand::seq::SliceRandom;
-use rand::{Rng, thread_rng};
+use crate::signal_processing::{Signal, MAX_FUNDAMENTAL, MIN_FUNDAMENTAL, SAMPLE_RATE};
+use crate::signal_processing::signal_analysis::{LengthHandling, SpectrumConfig, Weighting, WindowFunction};
+use crate::signal_processing::spectrogram::{SPECTROGRAM_FRAME_LENGTH, SPECTROGRAM_HOP};
+use crate::utils::{mean, sigmoid, std};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng, thread_rng};
+use rand_chacha::ChaCha8Rng;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
 use std::fmt::{Binary, Debug};
+use std::fs;
 use std::fs::File;
-use std::ops::Deref;
+use std::io::{BufReader, BufWriter};
+use std::path::Path;
+use std::time::{Duration, Instant};
+use std::ops::{ControlFlow, Deref};
 use std::sync::{Arc, Mutex};
+use std::sync::atomic::{AtomicBool, Ordering};
 use itertools::{Itertools};
-use crate::error::{GeneticSimulationError};
+use crate::error::{GeneticSimulationError, SignalProcessingError};
 use rayon::prelude::*;
 use crate::{FitnessType};
 use crate::analytics::{GenerationRow, Recorder};
+use crate::simulation::algorithms::crossover::CrossoverOperator;
+use crate::simulation::algorithms::mutation::MutationOperator;
+use crate::simulation::algorithms::hall_of_fame::HallOfFame;
+use crate::simulation::algorithms::selection::{shared_fitness, PairingStrategy, Selection, SelectionStrategy};
+use crate::simulation::algorithms::TerminationReason;
 use anyhow::Result;
+use log::{debug, info, warn};
+
+/// Distance below which two individuals are considered duplicates of each other.
+const DUPLICATE_DISTANCE: f32 = 1e-6;
+
+/// Maximum number of individuals among which the genotype diversity of a population is measured.
+const DIVERSITY_SAMPLE: usize = 50;
 
 /// Represents a simulation of the genetic algorithm for a generic sound signal_processing method.
-#[derive(Clone, Debug)]
+/// The whole state of the simulation can be serialised, so that it can be checkpointed and resumed.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(bound = "")]
 pub struct GASimulation<T: Individual> {
     /// Current generation number.
     pub generation: u32,
-    /// The probability of seeing a mutation in a specific gene.
+    /// Index of the island the population evolves on in an island model simulation, or 0 when
+    /// running on its own.
+    pub island: u32,
+    /// The probability of seeing a mutation in a specific gene in the current generation, as given
+    /// by the schedule.
     pub mutation_rate: f32,
+    /// How the mutation rate changes over the generations.
+    pub mutation_schedule: MutationSchedule,
     /// The number of generations the simulation will run for.
     pub max_generations: u32,
     /// The population of the current generation sorted by fitness.
     pub population: Vec<T>,
-    /// The signal we are using as target and upon which the fitness function is defined.
-    pub target: Signal,
+    /// The fittest individual of all the generations so far, which selection may have dropped
+    /// from the current population.
+    pub best_ever: Option<T>,
+    /// The fittest distinct individuals of all the generations so far, if kept.
+    pub hall_of_fame: Option<HallOfFame<T>>,
+    /// The signal we are using as target and upon which the fitness function is defined, shared
+    /// with the generator and every individual.
+    pub target: Arc<Signal>,
     /// Number of randomly added individuals on each generation.
     pub n_random_additions: u32,
+    /// When the randomly added individuals join the population on each generation.
+    pub immigrant_policy: ImmigrantPolicy,
     /// The size of the population at the beginning of the simulation.
     pub initial_population: u32,
-    /// How the population evolves as new individuals are considered.
+    /// Fraction of the population selected on each generation of a constant or increasing
+    /// population.
+    pub survivor_fraction: f32,
+    /// How the population evolves as new individuals are considered. A scheduled evolution is not
+    /// stored in checkpoints and is resumed as a constant one, so it has to be set again.
+    #[serde(serialize_with = "serialize_population_evolution")]
     pub population_evolution: PopulationEvolution,
     /// Number of individuals produced in a generation.
     pub offspring: u32,
+    /// Number of times an invalid individual is generated or recombined again before it is kept
+    /// with a fitness of 0.
+    pub invalid_retries: u32,
+    /// Number of individuals created in the current generation that were still invalid after
+    /// exhausting the retries.
+    pub invalid: u32,
+    /// Number of the fittest individuals of each generation copied unchanged into the next one.
+    pub elitism: u32,
+    /// Which individuals survive each generation and how the parents of the offspring are drawn
+    /// from them. Only the built-in strategies are stored in checkpoints, and a custom selection
+    /// is resumed as the default strategy.
+    #[serde(serialize_with = "serialize_selection", deserialize_with = "deserialize_selection")]
+    pub selection: Arc<dyn Selection<T>>,
+    /// How the parents drawn by the selection are paired up for crossover.
+    pub pairing: PairingStrategy,
+    /// Number of crossovers performed by each pair of parents on every generation.
+    pub offspring_per_pair: u32,
+    /// Distance within which individuals share their fitness during selection, if specified.
+    pub fitness_sharing: Option<f32>,
+    /// Whether duplicated individuals are replaced by random ones on each generation.
+    pub deduplicate: bool,
+    /// Number of offspring produced on each step of a steady-state simulation, which replace the
+    /// least fit individuals instead of a whole generation, if specified.
+    pub steady_state: Option<u32>,
+    /// Number of hill-climbing iterations, step size and number of the fittest individuals refined
+    /// after each generation, if specified.
+    pub local_search: Option<(u32, f32, u32)>,
+    /// Number of fitness evaluations spent refining the fittest individuals in the current
+    /// generation.
+    pub local_search_evaluations: u32,
+    /// How the genes of each pair of parents are recombined into their offspring.
+    pub crossover: CrossoverOperator,
+    /// How the genes of the offspring change when they mutate.
+    pub mutation: MutationOperator,
     /// Fundamental frequency of the fittest individual.
     pub fundamental: Option<f32>,
+    /// Mean genotype distance between pairs of individuals of the current population.
+    pub diversity: f32,
+    /// Fundamental frequency detected in the target signal.
+    pub target_fundamental: Option<f32>,
     /// Generator used to bring new randomised individuals.
     pub generator: T::Generator,
     /// Whether the simulation should be exported to a CSV file and what file name.
     pub csv_export: Option<String>,
     /// Whether the fittest individual should be exported to a WAV file and what file name.
     pub signal_export: Option<String>,
+    /// Whether the spectrograms of the fittest individual and the target should be exported and to
+    /// what directory.
+    pub spectrogram_export: Option<String>,
+    /// Whether the members of the hall of fame should be exported to WAV files, named after their
+    /// rank as `best_0.wav`, `best_1.wav`..., and to what directory.
+    pub hall_of_fame_export: Option<String>,
+    /// Number of consecutive generations without the best fitness improving by at least the given
+    /// delta after which the simulation stops early, if specified.
+    pub stagnation_limit: Option<(u32, f32)>,
+    /// Genotype diversity below which the population is considered converged and the simulation
+    /// stops early, if specified.
+    pub convergence_threshold: Option<f32>,
+    /// Wall-clock time after which the simulation stops at the end of the current generation, if
+    /// specified.
+    pub time_limit: Option<Duration>,
+    /// Why the simulation stopped, once it has run.
+    pub termination_reason: Option<TerminationReason>,
+    /// Wall-clock time spent running the simulation.
+    pub elapsed: Duration,
+    /// Every how many generations the whole simulation is saved and to what file, if specified.
+    pub checkpoint: Option<(u32, String)>,
+    /// Every how many generations the fittest individual is logged at the info level, if at all.
+    /// The other generations are logged at the debug level.
+    pub log_interval: Option<u32>,
+    /// Called with a summary of every generation while the simulation runs, if specified. It is
+    /// not stored in checkpoints, so it has to be set again on resumed simulations.
+    #[serde(skip)]
+    pub on_generation: Option<GenerationCallback<T>>,
+    /// Flag checked before every generation, which stops the simulation gracefully once it is set,
+    /// if specified. It is not stored in checkpoints.
+    #[serde(skip)]
+    pub cancel_token: Option<Arc<AtomicBool>>,
+    /// Seed of the random number generator, which determines the whole run of the simulation.
+    pub seed: u64,
+    /// Random number generator from which every random decision of the simulation is drawn. Its
+    /// state is stored in checkpoints, so that resumed runs stay reproducible.
+    rng: ChaCha8Rng,
+}
+
+fn serialize_selection<T: Individual, S: Serializer>(selection: &Arc<dyn Selection<T>>, serializer: S) -> Result<S::Ok, S::Error> {
+    selection.strategy().serialize(serializer)
+}
+
+fn deserialize_selection<'de, T: Individual, D: Deserializer<'de>>(deserializer: D) -> Result<Arc<dyn Selection<T>>, D::Error> {
+    let strategy = Option::<SelectionStrategy>::deserialize(deserializer)?;
+    Ok(Arc::new(strategy.unwrap_or_default()))
+}
+
+fn serialize_population_evolution<S: Serializer>(evolution: &PopulationEvolution, serializer: S) -> Result<S::Ok, S::Error> {
+    match evolution {
+        PopulationEvolution::Scheduled(_) => PopulationEvolution::Constant.serialize(serializer),
+        evolution => evolution.serialize(serializer),
+    }
+}
+
+/// Snapshot of a generation passed to the callback of `GASimulationBuilder::on_generation`. It is
+/// built from the same state as the rows of the CSV export.
+#[derive(Debug)]
+pub struct GenerationSummary<'a, T: Individual> {
+    /// Number of the generation that has just been calculated.
+    pub generation: u32,
+    /// Fitness of the fittest individual.
+    pub best_fitness: f32,
+    /// Average fitness of the population.
+    pub average_fitness: f32,
+    /// Spread of the fitness values of the population, as exported to the CSV file.
+    pub std: f32,
+    /// Fundamental frequency of the fittest individual.
+    pub fundamental: Option<f32>,
+    /// Number of individuals produced in the generation.
+    pub offspring: u32,
+    /// The fittest individual, whose `dbg()` describes its parameters.
+    pub fittest: &'a T,
+}
+
+impl<'a, T: Individual> From<&'a GASimulation<T>> for GenerationSummary<'a, T> {
+    fn from(simulation: &'a GASimulation<T>) -> Self {
+        let fitness = simulation.population.iter().map(|i| i.fitness()).collect_vec();
+
+        Self {
+            generation: simulation.generation,
+            best_fitness: fitness.first().copied().unwrap_or(0.0),
+            average_fitness: mean(&fitness),
+            std: std(&fitness),
+            fundamental: simulation.fundamental,
+            offspring: simulation.offspring,
+            fittest: simulation.population.first()
+                .expect("There should be a fittest individual in the population"),
+        }
+    }
+}
+
+/// Outcome of running a genetic algorithm simulation.
+#[derive(Clone, Debug)]
+pub struct SimulationReport<T: Individual> {
+    /// The fittest individual of the whole run, which may not be in the final population.
+    pub fittest: T,
+    /// The statistics of every generation run, starting with the population the simulation
+    /// started from, whether or not they are exported to a CSV file.
+    pub history: Vec<GenerationRow>,
+    /// Number of generations run, which may be fewer than the maximum if it stopped early.
+    pub generations: u32,
+    /// Wall-clock time spent running the simulation, including before it was resumed.
+    pub elapsed: Duration,
+    /// Why the simulation stopped.
+    pub termination_reason: TerminationReason,
+    /// Size of the final population.
+    pub final_population: usize,
+    /// The fittest distinct individuals of the whole run, if kept.
+    pub hall_of_fame: Option<HallOfFame<T>>,
+}
+
+/// Callback run after every generation of a simulation, which stops it early by returning
+/// `ControlFlow::Break`. Clones of a simulation share the same callback.
+pub struct GenerationCallback<T: Individual>(Arc<Mutex<GenerationFn<T>>>);
+
+type GenerationFn<T> = dyn FnMut(&GenerationSummary<T>) -> ControlFlow<()> + Send;
+
+impl<T: Individual> GenerationCallback<T> {
+    /// Calls the callback with the summary of a generation.
+    pub fn call(&self, summary: &GenerationSummary<T>) -> ControlFlow<()> {
+        let mut callback = self.0.lock().expect("The generation callback should not have panicked");
+        callback(summary)
+    }
+}
+
+impl<T: Individual> Clone for GenerationCallback<T> {
+    fn clone(&self) -> Self {
+        Self(Arc::clone(&self.0))
+    }
+}
+
+impl<T: Individual> Debug for GenerationCallback<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("GenerationCallback")
+    }
 }
 
+#[derive(Clone)]
 pub struct GASimulationBuilder<T: Individual> {
     pub generator: Option<T::Generator>,
     pub target: Option<Arc<Signal>>,
     pub initial_population: u32,
     pub n_random_additions: u32,
-    pub mutation_rate: f32,
+    pub immigrant_policy: ImmigrantPolicy,
+    pub mutation_schedule: MutationSchedule,
     pub max_generations: u32,
     pub population_evolution: PopulationEvolution,
+    pub survivor_fraction: f32,
+    pub elitism: u32,
+    pub selection: Arc<dyn Selection<T>>,
+    pub pairing: PairingStrategy,
+    pub offspring_per_pair: u32,
+    pub invalid_retries: u32,
+    pub fitness_sharing: Option<f32>,
+    pub deduplicate: bool,
+    pub steady_state: Option<u32>,
+    pub local_search: Option<(u32, f32, u32)>,
+    pub crossover: CrossoverOperator,
+    pub mutation: MutationOperator,
     pub csv_export: Option<String>,
-    pub signal_export: Option<String>
+    pub signal_export: Option<String>,
+    pub spectrogram_export: Option<String>,
+    pub hall_of_fame: Option<(u32, f32)>,
+    pub hall_of_fame_export: Option<String>,
+    pub stagnation_limit: Option<(u32, f32)>,
+    pub convergence_threshold: Option<f32>,
+    pub time_limit: Option<Duration>,
+    pub checkpoint: Option<(u32, String)>,
+    pub log_interval: Option<u32>,
+    pub on_generation: Option<GenerationCallback<T>>,
+    pub cancel_token: Option<Arc<AtomicBool>>,
+    pub seed: Option<u64>,
 }
 
 impl<T: Individual> Default for GASimulationBuilder<T> {
@@ -64,11 +306,35 @@ impl<T: Individual> Default for GASimulationBuilder<T> {
             target: None,
             initial_population: 100,
             n_random_additions: 5,
-            mutation_rate: 0.05,
+            immigrant_policy: ImmigrantPolicy::default(),
+            mutation_schedule: MutationSchedule::default(),
             max_generations: 1_000,
             population_evolution: PopulationEvolution::default(),
+            survivor_fraction: 0.5,
+            elitism: 0,
+            selection: Arc::new(SelectionStrategy::default()),
+            pairing: PairingStrategy::default(),
+            offspring_per_pair: 1,
+            invalid_retries: 0,
+            fitness_sharing: None,
+            deduplicate: false,
+            steady_state: None,
+            local_search: None,
+            crossover: CrossoverOperator::default(),
+            mutation: MutationOperator::default(),
             csv_export: None,
             signal_export: None,
+            spectrogram_export: None,
+            hall_of_fame: None,
+            hall_of_fame_export: None,
+            stagnation_limit: None,
+            convergence_threshold: None,
+            time_limit: None,
+            checkpoint: None,
+            log_interval: Some(10),
+            on_generation: None,
+            cancel_token: None,
+            seed: None,
         }
     }
 }
@@ -80,40 +346,167 @@ impl<T: Individual> GASimulationBuilder<T> {
         Self::default()
     }
 
-    /// Builds the GA simulation builder.
-    pub fn build(self) -> GASimulation<T> {
-        let generator = self.generator.expect("Expected a generator.");
-        let population = GASimulation::init_population(self.initial_population, &generator);
-        let target_arc = self.target
-            .expect("Expected a reference counter to the target signal.");
-        let target = Signal::clone(&*target_arc);
+    /// Builds the GA simulation, checking that its parameters are consistent with each other.
+    pub fn build(self) -> Result<GASimulation<T>, GeneticSimulationError> {
+        self.validate()?;
+        let mut generator = self.generator.ok_or(GeneticSimulationError::MissingGenerator)?;
+        let target = self.target.ok_or(GeneticSimulationError::MissingTarget)?;
+        if !Arc::ptr_eq(&target, &generator.get_target()) {
+            generator = generator.target(Arc::clone(&target));
+        }
+
+        let seed = self.seed.unwrap_or_else(|| thread_rng().gen());
+        let mut rng = ChaCha8Rng::seed_from_u64(seed);
+        let population = GASimulation::init_population(self.initial_population, &generator, self.invalid_retries, &mut rng);
+        let target_fundamental = target.detect_fundamental(MIN_FUNDAMENTAL, MAX_FUNDAMENTAL);
+
+        let mut hall_of_fame = self.hall_of_fame
+            .map(|(size, min_distance)| HallOfFame::new(size as usize, min_distance));
+        if let Some(hall_of_fame) = &mut hall_of_fame {
+            hall_of_fame.update(&population);
+        }
 
-        GASimulation {
+        let invalid = count_invalid(&population);
+        let mut simulation = GASimulation {
+            best_ever: population.first().cloned(),
+            hall_of_fame,
             population,
             target,
             generator,
             offspring: 0,
+            invalid_retries: self.invalid_retries,
+            invalid,
             generation: 0,
+            island: 0,
             fundamental: None,
-            mutation_rate: self.mutation_rate,
+            diversity: 0.0,
+            target_fundamental,
+            mutation_rate: self.mutation_schedule.initial_rate(),
+            mutation_schedule: self.mutation_schedule,
             max_generations: self.max_generations,
             n_random_additions: self.n_random_additions,
+            immigrant_policy: self.immigrant_policy,
             initial_population: self.initial_population,
             population_evolution: self.population_evolution,
+            survivor_fraction: self.survivor_fraction,
+            elitism: self.elitism,
+            selection: self.selection,
+            pairing: self.pairing,
+            offspring_per_pair: self.offspring_per_pair,
+            fitness_sharing: self.fitness_sharing,
+            deduplicate: self.deduplicate,
+            steady_state: self.steady_state,
+            local_search: self.local_search,
+            local_search_evaluations: 0,
+            crossover: self.crossover,
+            mutation: self.mutation,
             csv_export: self.csv_export,
             signal_export: self.signal_export,
+            spectrogram_export: self.spectrogram_export,
+            hall_of_fame_export: self.hall_of_fame_export,
+            stagnation_limit: self.stagnation_limit,
+            convergence_threshold: self.convergence_threshold,
+            time_limit: self.time_limit,
+            termination_reason: None,
+            elapsed: Duration::ZERO,
+            checkpoint: self.checkpoint,
+            log_interval: self.log_interval,
+            on_generation: self.on_generation,
+            cancel_token: self.cancel_token,
+            seed,
+            rng,
+        };
+        simulation.diversity = simulation.genotype_diversity();
+
+        Ok(simulation)
+    }
+
+    /// Checks the parameters that would otherwise make the simulation fail once it runs.
+    pub(crate) fn validate(&self) -> Result<(), GeneticSimulationError> {
+        if self.initial_population < 2 {
+            return Err(GeneticSimulationError::InvalidPopulationSize(self.initial_population));
+        }
+        if !self.mutation_schedule.is_valid() {
+            return Err(GeneticSimulationError::InvalidMutationRate);
+        }
+        if self.max_generations == 0 {
+            return Err(GeneticSimulationError::InvalidMaxGenerations);
+        }
+        if let Some(strategy) = self.selection.strategy().filter(|strategy| !strategy.is_valid()) {
+            return Err(GeneticSimulationError::InvalidSelection(strategy));
+        }
+
+        if !(self.survivor_fraction > 0.0 && self.survivor_fraction <= 1.0) {
+            return Err(GeneticSimulationError::InvalidSurvivorFraction(self.survivor_fraction));
+        }
+
+        let mut selected = PopulationEvolution::fraction_selected(self.initial_population as usize, self.survivor_fraction) as u32;
+        if let PopulationEvolution::Decreasing { final_size } = self.population_evolution {
+            if final_size > self.initial_population {
+                return Err(GeneticSimulationError::InvalidFinalPopulation {
+                    final_size,
+                    initial: self.initial_population,
+                });
+            }
+            selected = selected.min(PopulationEvolution::decreasing_selected(final_size as usize) as u32);
+        }
+        if self.elitism > selected {
+            return Err(GeneticSimulationError::InvalidElitism { elitism: self.elitism, selected });
+        }
+
+        if self.offspring_per_pair == 0 {
+            return Err(GeneticSimulationError::InvalidOffspringPerPair);
+        }
+        if let Some(sigma) = self.fitness_sharing.filter(|&sigma| sigma <= 0.0 || sigma.is_nan()) {
+            return Err(GeneticSimulationError::InvalidSharingDistance(sigma));
+        }
+        if self.steady_state == Some(0) {
+            return Err(GeneticSimulationError::InvalidOffspringPerStep);
+        }
+        if let Some((_, step_size, _)) = self.local_search.filter(|&(_, step_size, _)| !(step_size > 0.0 && step_size <= 1.0)) {
+            return Err(GeneticSimulationError::InvalidLocalSearchStepSize(step_size));
+        }
+        if !self.crossover.is_valid() {
+            return Err(GeneticSimulationError::InvalidCrossover(self.crossover));
+        }
+        if !self.mutation.is_valid() {
+            return Err(GeneticSimulationError::InvalidMutation(self.mutation));
+        }
+        if self.hall_of_fame.is_some_and(|(size, _)| size == 0) {
+            return Err(GeneticSimulationError::InvalidHallOfFameSize);
         }
+        if self.stagnation_limit.is_some_and(|(n, _)| n == 0) {
+            return Err(GeneticSimulationError::InvalidStagnationLimit);
+        }
+        if self.checkpoint.as_ref().is_some_and(|(n, _)| *n == 0) {
+            return Err(GeneticSimulationError::InvalidCheckpointInterval);
+        }
+        if self.log_interval == Some(0) {
+            return Err(GeneticSimulationError::InvalidLogInterval);
+        }
+
+        if let Some(config) = self.generator.as_ref().map(|generator| generator.get_spectrum_config()) {
+            if let Some((min_hz, max_hz)) = config.band.filter(|&band| !SpectrumConfig::is_valid_band(band)) {
+                return Err(GeneticSimulationError::InvalidAnalysisBand { min_hz, max_hz });
+            }
+            if !SpectrumConfig::is_valid_length(config.length) {
+                return Err(GeneticSimulationError::InvalidAnalysisLength(config.length));
+            }
+        }
+
+        Ok(())
     }
 
-    /// Specifies target signal.
+    /// Specifies target signal, which replaces the target of the generator.
     pub fn target(mut self, target: Signal) -> Self {
         self.target = Some(target.into());
         self
     }
 
-    /// Takes an individual generator.
+    /// Takes an individual generator, whose target is used unless one has been specified with
+    /// `target`.
     pub fn generator(mut self, generator: T::Generator) -> Self {
-        self.target = Some(generator.get_target());
+        self.target = self.target.or_else(|| Some(generator.get_target()));
         self.generator = Some(generator);
         self
     }
@@ -130,9 +523,23 @@ impl<T: Individual> GASimulationBuilder<T> {
         self
     }
 
-    /// Specifies the mutation rate of the simulation.
-    pub fn mutation_rate(mut self, mutation_rate: f32) -> Self {
-        self.mutation_rate = mutation_rate;
+    /// Specifies when the randomly generated individuals join the population on each generation.
+    /// Defaults to `ImmigrantPolicy::AppendBeforeSelection`.
+    pub fn immigrant_policy(mut self, immigrant_policy: ImmigrantPolicy) -> Self {
+        self.immigrant_policy = immigrant_policy;
+        self
+    }
+
+    /// Specifies a mutation rate used in every generation of the simulation, which is the same as a
+    /// `MutationSchedule::Constant` schedule.
+    pub fn mutation_rate(self, mutation_rate: f32) -> Self {
+        self.mutation_schedule(MutationSchedule::Constant(mutation_rate))
+    }
+
+    /// Specifies how the mutation rate changes over the generations. Defaults to a constant rate of
+    /// 0.05.
+    pub fn mutation_schedule(mut self, mutation_schedule: MutationSchedule) -> Self {
+        self.mutation_schedule = mutation_schedule;
         self
     }
 
@@ -148,6 +555,106 @@ impl<T: Individual> GASimulationBuilder<T> {
         self
     }
 
+    /// Specifies the fraction of the population selected on each generation of a constant or
+    /// increasing population, rounded down. It must be in (0, 1] and defaults to 0.5. Together
+    /// with `offspring_per_pair`, it determines how many offspring replace the discarded
+    /// individuals, trading the exploitation of the fittest ones against exploration.
+    pub fn survivor_fraction(mut self, fraction: f32) -> Self {
+        self.survivor_fraction = fraction;
+        self
+    }
+
+    /// Specifies the number of the fittest individuals of each generation copied unchanged into the
+    /// next one, which guarantees that the best fitness never decreases. It cannot exceed the
+    /// individuals selected from the initial population.
+    pub fn elitism(mut self, k: u32) -> Self {
+        self.elitism = k;
+        self
+    }
+
+    /// Specifies which individuals survive each generation and how the parents of the offspring
+    /// are drawn from them, either with one of the built-in `SelectionStrategy` variants or a
+    /// custom implementation. Defaults to `SelectionStrategy::Uniform`.
+    pub fn selection(mut self, selection: Box<dyn Selection<T>>) -> Self {
+        self.selection = Arc::from(selection);
+        self
+    }
+
+    /// Specifies how the parents drawn by the selection are paired up for crossover. Defaults to
+    /// `PairingStrategy::Random`, which keeps the pairs formed by the selection.
+    pub fn pairing(mut self, pairing: PairingStrategy) -> Self {
+        self.pairing = pairing;
+        self
+    }
+
+    /// Specifies how many crossovers each pair of parents performs on every generation, each of
+    /// them usually producing two children. Defaults to 1, so that the survivors are replaced by
+    /// as many offspring, plus one when they are odd.
+    pub fn offspring_per_pair(mut self, n: u32) -> Self {
+        self.offspring_per_pair = n;
+        self
+    }
+
+    /// Generates or recombines invalid individuals, as reported by `Individual::is_valid`, again up
+    /// to `retries` times, so that they do not waste the population on individuals with a fitness
+    /// of 0. The ones still invalid afterwards are kept with a fitness of 0 and counted in the
+    /// statistics of each generation. Disabled by default.
+    pub fn retry_invalid(mut self, retries: u32) -> Self {
+        self.invalid_retries = retries;
+        self
+    }
+
+    /// Enables fitness sharing, where the fitness of each individual is divided by the number of
+    /// individuals within a distance of `sigma` during selection, so that different solutions with
+    /// similar fitness can coexist instead of the population collapsing onto whichever is found
+    /// first. Distances are measured with `Individual::distance`.
+    pub fn fitness_sharing(mut self, sigma: f32) -> Self {
+        self.fitness_sharing = Some(sigma);
+        self
+    }
+
+    /// Specifies whether the individuals of each new generation that are duplicates of a fitter one
+    /// are replaced by randomly generated individuals, which avoids wasting the population on
+    /// clones once it converges.
+    pub fn deduplicate(mut self, deduplicate: bool) -> Self {
+        self.deduplicate = deduplicate;
+        self
+    }
+
+    /// Switches the simulation to steady-state replacement, where each step produces
+    /// `offspring_per_step` children from parents drawn from the whole population, and each child
+    /// replaces the least fit individual if it is fitter. Generations then count steps, the
+    /// population size stays constant, and the population evolution, elitism, random additions and
+    /// deduplication settings do not apply.
+    pub fn steady_state(mut self, offspring_per_step: u32) -> Self {
+        self.steady_state = Some(offspring_per_step);
+        self
+    }
+
+    /// Refines the `top_k` fittest individuals after each generation with `iters` iterations of
+    /// hill climbing, where each iteration evolves the individual with the given step size and
+    /// keeps the result if it is fitter, turning the simulation into a memetic algorithm. The
+    /// individuals are refined in parallel, and the extra fitness evaluations are reported with
+    /// the statistics of each generation. Disabled by default.
+    pub fn local_search(mut self, iters: u32, step_size: f32, top_k: u32) -> Self {
+        self.local_search = Some((iters, step_size, top_k));
+        self
+    }
+
+    /// Specifies how the genes of each pair of parents are recombined into their offspring. Defaults
+    /// to `CrossoverOperator::Arithmetic`.
+    pub fn crossover(mut self, crossover: CrossoverOperator) -> Self {
+        self.crossover = crossover;
+        self
+    }
+
+    /// Specifies how the genes of the offspring change when they mutate, with the probability
+    /// given by the mutation schedule. Defaults to `MutationOperator::Replace`.
+    pub fn mutation(mut self, mutation: MutationOperator) -> Self {
+        self.mutation = mutation;
+        self
+    }
+
     /// Takes a CSV file name where the simulation will be exported.
     pub fn csv_export(mut self, file_name: &str) -> Self {
         self.csv_export = Some(file_name.to_string());
@@ -159,12 +666,134 @@ impl<T: Individual> GASimulationBuilder<T> {
         self.signal_export = Some(file_name.to_string());
         self
     }
+
+    /// Takes a directory where the spectrograms of the returned signal and the target will be
+    /// exported, as CSV files and, with the `plotting` feature, PNG images.
+    pub fn spectrogram_export(mut self, dir: &str) -> Self {
+        self.spectrogram_export = Some(dir.to_string());
+        self
+    }
+
+    /// Keeps the `size` fittest distinct individuals found during the whole run, where individuals
+    /// closer than `min_distance` to a fitter one are considered near-duplicates of it.
+    pub fn hall_of_fame(mut self, size: u32, min_distance: f32) -> Self {
+        self.hall_of_fame = Some((size, min_distance));
+        self
+    }
+
+    /// Used to specify the directory the members of the hall of fame will be exported to, as
+    /// `best_0.wav`, `best_1.wav`... by decreasing fitness. Only applies if a hall of fame is kept.
+    pub fn hall_of_fame_export(mut self, dir: &str) -> Self {
+        self.hall_of_fame_export = Some(dir.to_string());
+        self
+    }
+
+    /// Stops the simulation early once the best fitness found so far has not improved by at least
+    /// `min_delta` for `n` consecutive generations.
+    pub fn stagnation_limit(mut self, n: u32, min_delta: f32) -> Self {
+        self.stagnation_limit = Some((n, min_delta));
+        self
+    }
+
+    /// Stops the simulation early once the genotype diversity of the population falls below the
+    /// threshold, i.e. once its individuals have converged onto the same region of the search
+    /// space, regardless of whether the best fitness is still improving.
+    pub fn converge_when_diversity_below(mut self, threshold: f32) -> Self {
+        self.convergence_threshold = Some(threshold);
+        self
+    }
+
+    /// Stops the simulation once it has been running for the given time, after finishing the
+    /// current generation and the exports.
+    pub fn time_limit(mut self, time_limit: Duration) -> Self {
+        self.time_limit = Some(time_limit);
+        self
+    }
+
+    /// Saves the whole simulation to the given file every `n_generations` generations while it
+    /// runs, from which it can be continued with `GASimulation::resume` if it is interrupted.
+    pub fn checkpoint_every(mut self, n_generations: u32, path: &str) -> Self {
+        self.checkpoint = Some((n_generations, path.to_string()));
+        self
+    }
+
+    /// Logs the fittest individual at the info level every `n_generations` generations instead of
+    /// every 10.
+    pub fn log_every(mut self, n_generations: u32) -> Self {
+        self.log_interval = Some(n_generations);
+        self
+    }
+
+    /// Only logs the generations at the debug level, e.g. when running many simulations in
+    /// parallel.
+    pub fn quiet(mut self) -> Self {
+        self.log_interval = None;
+        self
+    }
+
+    /// Calls the given function with a summary of every generation while the simulation runs,
+    /// after it has been recorded for the CSV export. Returning `ControlFlow::Break` stops the
+    /// simulation at that generation, which is then exported as usual.
+    pub fn on_generation(
+        mut self,
+        callback: impl FnMut(&GenerationSummary<T>) -> ControlFlow<()> + Send + 'static,
+    ) -> Self {
+        self.on_generation = Some(GenerationCallback(Arc::new(Mutex::new(callback))));
+        self
+    }
+
+    /// Stops the simulation before the next generation once the flag is set, e.g. from another
+    /// thread or by `cancel_on_ctrl_c`, exporting the generations run so far and the fittest
+    /// individual as usual. The same flag can be shared by many simulations.
+    pub fn cancel_token(mut self, token: Arc<AtomicBool>) -> Self {
+        self.cancel_token = Some(token);
+        self
+    }
+
+    /// Seeds the random number generator of the simulation, so that runs with the same seed,
+    /// generator and parameters are reproducible, regardless of the number of threads they run
+    /// on. A random seed is used if not specified.
+    pub fn seed(mut self, seed: u64) -> Self {
+        self.seed = Some(seed);
+        self
+    }
 }
 
-#[derive(Clone, Debug)]
+/// Specifies how the size of the population changes over the generations. On every generation, the
+/// `s` selected individuals are paired up, the odd one out with a random partner, and each of the
+/// `ceil(s / 2)` pairs performs `offspring_per_pair` crossovers of two children each, so that the
+/// next population has `s + 2 * offspring_per_pair * ceil(s / 2)` individuals.
+#[derive(Clone, Serialize, Deserialize)]
 pub enum PopulationEvolution {
+    /// The survivor fraction of the initial population, half of it by default, is selected on
+    /// every generation.
     Constant,
-    Increasing
+    /// The survivor fraction of the current population, including the random additions, is
+    /// selected on every generation, so that by default it grows by about the random additions
+    /// each time.
+    Increasing,
+    /// The population shrinks linearly from the initial population to `final_size` over the
+    /// maximum number of generations, never selecting fewer than two individuals.
+    Decreasing { final_size: u32 },
+    /// The number of individuals selected on each generation is given by a function of the number
+    /// of the generation being calculated, e.g. to warm up with a large population or restart it
+    /// periodically. It is clamped between two individuals and the current population, which then
+    /// grows from the selected individuals as with the other evolutions.
+    #[serde(skip)]
+    Scheduled(Arc<dyn Fn(u32) -> u32 + Send + Sync>),
+}
+
+impl Debug for PopulationEvolution {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PopulationEvolution::Constant => f.write_str("Constant"),
+            PopulationEvolution::Increasing => f.write_str("Increasing"),
+            PopulationEvolution::Decreasing { final_size } => {
+                f.debug_struct("Decreasing").field("final_size", final_size).finish()
+            }
+            PopulationEvolution::Scheduled(_) => f.write_str("Scheduled"),
+        }
+    }
 }
 
 impl Default for PopulationEvolution {
@@ -173,182 +802,945 @@ impl Default for PopulationEvolution {
     }
 }
 
-impl<T: Individual> GASimulation<T> {
-    fn init_population(n: u32, generator: &T::Generator) -> Vec<T> {
-        let mut vec: Vec<T> = (0..n).into_par_iter().map(|_| generator.generate()).collect();
-        vec.par_sort_by(|a, b| b.cmp(a));
-        vec
+impl PopulationEvolution {
+    /// Number of individuals selected from a population of the given size with a survivor
+    /// fraction.
+    fn fraction_selected(size: usize, fraction: f32) -> usize {
+        (size as f32 * fraction) as usize
     }
 
-    /// A step in the iteration of the algorithm. Given the current state of the simulation, calculates the next
-    /// generation.
-    fn next(&mut self) -> Result<(), GeneticSimulationError> {
-        // Add n randomly generated individuals to the current population and sort it.
-        let mut current_population = self.population.clone();
-        let mut random_additions = vec![];
-        for _ in 0..self.n_random_additions {
-            random_additions.push(self.generator.generate());
-        }
-        current_population.extend(random_additions);
-        current_population.sort_by(|a, b| b.cmp(a));
-
-        // number of selected individuals for the next generation
-        let n_selected = match self.population_evolution {
-            PopulationEvolution::Constant =>  { self.initial_population as usize / 2 }
-            PopulationEvolution::Increasing => { current_population.len() / 2 }
-        };
-
-        // construct a new population vec from the n selected individuals
-        let mut new_population: Vec<T> = Vec::from(&current_population[0..n_selected]);
-        let offspring_mutex: Arc<Mutex<Vec<T>>> = Arc::new(Mutex::new(vec![]));
-
-        let mut rng = thread_rng();
+    /// Number of individuals selected on a generation of a decreasing population of the given size.
+    fn decreasing_selected(size: usize) -> usize {
+        (size / 2).max(2)
+    }
+}
 
-        for _ in 0..2 {
-            new_population.shuffle(&mut rng);
-            new_population.par_iter().chunks(2).for_each(|p| {
-                if p.len() == 2 {
-                    if let Some(c) = p[0].crossover(p[1], self.mutation_rate) {
-                        let mut guard = offspring_mutex.lock().unwrap();
-                        guard.push(c);
-                    }
-                }
-            });
-        }
+/// Specifies when the randomly generated individuals of each generation, the immigrants, join the
+/// population.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum ImmigrantPolicy {
+    /// The immigrants are added to the population before selection, competing with the rest of it
+    /// to survive. Once the population has improved, they are usually discarded straight away, as
+    /// random individuals are far less fit than the ones that survived the previous generations.
+    #[default]
+    AppendBeforeSelection,
+    /// The immigrants overwrite the least fit individuals after selection, so that they always
+    /// survive into the next generation and reproduce. They are not counted by an increasing
+    /// population when computing how many individuals are selected.
+    ReplaceWorstAfterSelection,
+}
 
-        let offspring = offspring_mutex.lock().unwrap();
-        
-        // update offspring for stats purposes
-        self.offspring = offspring.len() as u32;
+/// Specifies how the mutation rate of a simulation changes over the generations.
+#[derive(Copy, Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum MutationSchedule {
+    /// The same mutation rate is used in every generation.
+    Constant(f32),
+    /// The mutation rate changes linearly from `start` in the first generation to `end` in the
+    /// last one.
+    Linear { start: f32, end: f32 },
+    /// The mutation rate changes exponentially from `start` in the first generation to `end` in the
+    /// last one, so that an annealing schedule spends longer at the lower rates. Both rates must be
+    /// positive.
+    Exponential { start: f32, end: f32 },
+    /// The mutation rate starts at `low` and moves towards `high` in every generation whose
+    /// population has a fitness spread below `diversity_threshold`, so that a converging
+    /// population explores more, and back towards `low` once the diversity recovers. The spread is
+    /// the one exported as `std` in the CSV.
+    AdaptiveDiversity { low: f32, high: f32, diversity_threshold: f32 },
+}
 
-        // join the new population and offspring vecs, then sort it
-        new_population.extend(offspring.to_vec());
-        new_population.sort_by(|a, b| b.cmp(a));
-        
-        // update generation population with the new one
-        self.population = new_population;
+impl Default for MutationSchedule {
+    fn default() -> Self {
+        Self::Constant(0.05)
+    }
+}
 
-        // update fundamental frequency and print current population
-        let fittest: &T = self.population.first().expect("There should be a fittest individual in the population");
-        self.fundamental = fittest.get_fundamental();
-        
-        if self.generation % 10 == 0 {
-            println!("Gen: {}, - {:?}", self.generation, fittest.dbg());
+impl MutationSchedule {
+    /// Fraction of the distance to the target rate covered in each generation of an adaptive
+    /// schedule.
+    const ADAPTATION: f32 = 0.5;
+
+    /// Whether the mutation rates of the schedule are valid probabilities, in increasing order for
+    /// an adaptive schedule.
+    pub fn is_valid(&self) -> bool {
+        let probability = |rate: &f32| (0.0..=1.0).contains(rate);
+        match self {
+            MutationSchedule::Constant(rate) => probability(rate),
+            MutationSchedule::Linear { start, end } => probability(start) && probability(end),
+            MutationSchedule::Exponential { start, end } => {
+                probability(start) && probability(end) && *start > 0.0 && *end > 0.0
+            }
+            MutationSchedule::AdaptiveDiversity { low, high, .. } => probability(low) && probability(high) && low <= high,
         }
-        
-        // increase generation count
-        self.generation += 1;
-        
-        Ok(())
     }
 
-
-    /// Runs a genetic algorithm simulation.
-    pub fn run(&mut self) -> Result<T, GeneticSimulationError> {
-        // let mut generation = 0;
-        let mut recorder: Recorder<GenerationRow> = Recorder::new();
-
-        if self.csv_export.is_some() {
-            recorder.add_record(self.into());
+    /// The mutation rate of the first generation.
+    pub fn initial_rate(&self) -> f32 {
+        match *self {
+            MutationSchedule::Constant(rate) => rate,
+            MutationSchedule::Linear { start, .. } | MutationSchedule::Exponential { start, .. } => start,
+            MutationSchedule::AdaptiveDiversity { low, .. } => low,
         }
+    }
 
-        while self.generation < self.max_generations {
-            // calculate the next generation and update state
-            self.next()?;
-            
-            // update the record
-            if self.csv_export.is_some() {
-                recorder.add_record(self.into());
+    /// The mutation rate of the given generation, out of `max_generations`. Adaptive schedules
+    /// depend instead on the rate of the previous generation and the diversity of the current
+    /// population.
+    pub fn rate(&self, generation: u32, max_generations: u32, previous: f32, diversity: f32) -> f32 {
+        let progress = generation as f32 / max_generations.max(1) as f32;
+        match *self {
+            MutationSchedule::Constant(rate) => rate,
+            MutationSchedule::Linear { start, end } => start + (end - start) * progress,
+            MutationSchedule::Exponential { start, end } => start * (end / start).powf(progress),
+            MutationSchedule::AdaptiveDiversity { low, high, diversity_threshold } => {
+                let target = if diversity < diversity_threshold { high } else { low };
+                (previous + Self::ADAPTATION * (target - previous)).clamp(low, high)
             }
         }
+    }
+}
 
-        if let Some(file_name) = &self.csv_export {
-            recorder.to_csv(file_name).expect("Exporting to CSV should have been successful");
-        }
+/// Calculates in parallel the fitness of the individuals that do not have it cached yet.
+pub(crate) fn evaluate_fitness<T: Individual>(population: &mut [T]) {
+    population.par_iter_mut()
+        .filter(|individual| individual.get_fitness().is_none())
+        .for_each(|individual| *individual = individual.clone().include_fitness());
+}
 
-        // Once the iteration is finished, we select the fittest in the final population
-        let fittest: T = self.population.first()
-            .expect("There should be a fittest individual in the population.").to_owned();
-        println!("{:?}", fittest.dbg());
+/// Number of individuals that are not valid.
+fn count_invalid<T: Individual>(individuals: &[T]) -> u32 {
+    individuals.iter().filter(|i| !i.is_valid()).count() as u32
+}
 
-        if let Some(file_name) = &self.signal_export {
-           fittest.to_signal().to_wav(file_name)
-               .expect("Exporting to a WAV file should have been successful.")
+/// Recombines two parents, recombining them again up to `retries` times to replace the children
+/// that are not valid.
+fn crossover_valid<T: Individual, R: Rng + ?Sized>(
+    a: &T,
+    b: &T,
+    rate: f32,
+    operator: CrossoverOperator,
+    mutation: MutationOperator,
+    retries: u32,
+    rng: &mut R,
+) -> Vec<T> {
+    let mut offspring = a.crossover(b, rate, operator, mutation, rng);
+    for _ in 0..retries {
+        if offspring.iter().all(|child| child.is_valid()) {
+            break;
+        }
+        let retried = a.crossover(b, rate, operator, mutation, rng);
+        for (child, retried) in offspring.iter_mut().zip(retried) {
+            if !child.is_valid() {
+                *child = retried;
+            }
         }
-
-        Ok(fittest)
     }
+    offspring
 }
 
-/// Template for generating an individual with a certain configuration. The implementations for
-/// each generator provide a way to specify the components present in a synthesis method.
-pub trait IndividualGenerator<T: Individual>: Sized {
-    /// Creates a new individual generator.
-    fn new() -> Self;
-
-    /// Generates an Individual having specified the components present.
-    fn generate(&self) -> T;
-
-    /// Specifies a target signal.
-    fn target(self, target: Arc<Signal>) -> Self;
-    
-    /// Specifies the target sound by taking the URI of the file containing it.
-    fn target_file(self, file_path: &str) -> Self {
-        let file_in = File::open(file_path)
-            .expect("Expected a target file in the specified directory.");
-        let target = Signal::from_wav_file(file_in)
-            .expect("Target file should have been converted into signal.");
-        self.target(Arc::new(target))
+/// Pairs up the parents drawn by the selection from the population with the pairing strategy.
+fn pair_parents<T: Individual>(pairing: PairingStrategy, parents: Vec<usize>, population: &[T], rng: &mut ChaCha8Rng) -> Vec<usize> {
+    if pairing == PairingStrategy::Random {
+        return parents;
     }
-
-    /// Specifies the fitness evaluation method to be used.
-    fn fitness_type(self, fitness_type: FitnessType) -> Self;
-    
-    /// Retrieves the target signal from the generator.
-    fn get_target(&self) -> Arc<Signal>;
+    let fitness = population.iter().map(|i| i.fitness()).collect_vec();
+    pairing.pair(parents, &fitness, rng)
 }
 
-pub trait Individual: Clone + Ord + Debug + Send + Sync {
-    type Generator: IndividualGenerator<Self> + Sync;
+/// Sorts a population by decreasing fitness, which every individual must have already cached so
+/// that it is not recalculated by the comparisons. The sort is stable, so individuals of equal
+/// fitness, e.g. clones, keep the order they were produced in, which unlike the order in which the
+/// threads finish is the same on every run.
+pub(crate) fn sort_by_fitness<T: Individual>(population: &mut [T]) {
+    debug_assert!(population.iter().all(|i| i.get_fitness().is_some()),
+                  "Every individual should have its fitness included before sorting");
+    population.par_sort_by(|a, b| b.cmp(a));
+}
 
-    fn new_generator() -> Self::Generator;
+impl<T: Individual> GASimulation<T> {
+    fn init_population(n: u32, generator: &T::Generator, retries: u32, rng: &mut ChaCha8Rng) -> Vec<T> {
+        // each individual draws from its own generator, so that the result does not depend on how
+        // the work is split between threads
+        let seeds = (0..n).map(|_| rng.gen()).collect_vec();
+        let mut vec: Vec<T> = seeds.into_par_iter()
+            .map(|seed| generator.generate_valid(retries, &mut StdRng::seed_from_u64(seed)).include_fitness())
+            .collect();
+        sort_by_fitness(&mut vec);
+        vec
+    }
 
-    /// Returns a clone of the `Rc<Signal>` object holding the target signal.
-    fn get_target(&self) -> Arc<Signal>;
+    /// Mean distance between pairs of individuals of the population, measured on a sample of them
+    /// evenly spaced by rank, so that it does not draw from the random number generator.
+    pub fn genotype_diversity(&self) -> f32 {
+        let step = self.population.len().div_ceil(DIVERSITY_SAMPLE).max(1);
+        let distances = self.population.iter().step_by(step)
+            .tuple_combinations()
+            .map(|(a, b)| a.distance(b))
+            .collect_vec();
 
-    /// Getter method used to return the `fitness` field from the implementations.
-    // fn get_fitness(&self) -> Option<f32>;
+        if distances.is_empty() { 0.0 } else { mean(&distances) }
+    }
 
-    /// Defines how 'fit' the individual is, i.e. how close is the individual to the target
-    /// sound wave, by comparing it to the frequency spectrum.
-    fn fitness(&self) -> f32;
+    /// Calculates in parallel the fitness of the individuals of the population that do not have it
+    /// cached yet, e.g. the random additions of a generation.
+    pub fn evaluate_population(&mut self) {
+        evaluate_fitness(&mut self.population);
+    }
 
-    fn get_fitness_type(&self) -> FitnessType;
+    /// Replaces the individuals of a sorted population that are duplicates of a fitter one with
+    /// randomly generated individuals, returning the population sorted again.
+    fn replace_duplicates(&mut self, population: Vec<T>) -> Vec<T> {
+        let len = population.len();
+        let mut kept: Vec<T> = Vec::with_capacity(len);
+        for individual in population {
+            if kept.iter().all(|k| k.distance(&individual) >= DUPLICATE_DISTANCE) {
+                kept.push(individual);
+            }
+        }
 
-    fn calculate_fitness(&self) -> f32 {
-        match self.get_fitness_type() {
-            FitnessType::FreqDomainMSE => self.freq_domain_mse_fitness(),
-            FitnessType::TimeDomainEuclidean => self.time_domain_euclidean_fitness(),
-            // FitnessType::TimeDomainCrossCorr => self.time_domain_cross_corr_fitness()
+        for _ in kept.len()..len {
+            let replacement = self.generator.generate_valid(self.invalid_retries, &mut self.rng);
+            self.invalid += !replacement.is_valid() as u32;
+            kept.push(replacement);
         }
+        evaluate_fitness(&mut kept);
+        sort_by_fitness(&mut kept);
+        kept
     }
 
-    fn freq_domain_mse_fitness(&self) -> f32 {
-        let mse = self.to_signal().freq_spectrum_mse(&self.get_target()).expect("MSE should be valid");
-        let cost = (mse / 1000.0).log10().exp();
+    /// Sorts a population by decreasing fitness before selecting from it, shared between the
+    /// neighbours of each individual if fitness sharing is enabled.
+    fn sort_for_selection(&self, population: &mut Vec<T>) {
+        let Some(sigma) = self.fitness_sharing else {
+            sort_by_fitness(population);
+            return;
+        };
 
-        // the higher the total cost, the lower the fitness
-        2.0 * sigmoid(-cost)
+        let fitness = population.iter().map(|i| i.fitness()).collect_vec();
+        let shared = shared_fitness(&fitness, |i, j| population[i].distance(&population[j]), sigma);
+        let mut keyed = shared.into_iter().zip(population.drain(..)).collect_vec();
+        keyed.sort_by(|(a, _), (b, _)| b.total_cmp(a));
+        population.extend(keyed.into_iter().map(|(_, individual)| individual));
     }
 
-    fn time_domain_euclidean_fitness(&self) -> f32 {
-        let distance= self.to_signal().euclidean_distance(&self.get_target());
+    /// Advances the simulation by one generation, returning its summary. Unlike `run`, stepping
+    /// through a simulation leaves the stopping criteria and the exports to the caller, so that
+    /// custom logic can run between the generations:
+    ///
+    /// ```no_run
+    /// use std::sync::Arc;
+    /// use ga_synth::signal_processing::components::oscillator::sine_wave;
+    /// use ga_synth::signal_processing::SAMPLE_RATE;
+    /// use ga_synth::simulation::algorithms::genetic::{GASimulation, GASimulationBuilder, Individual, IndividualGenerator};
+    /// use ga_synth::simulation::synthesis_methods::subtractive::SubtractiveIndividual;
+    ///
+    /// let generator = SubtractiveIndividual::new_generator()
+    ///     .target(Arc::new(sine_wave(440.0, 1.0, SAMPLE_RATE as f32, 1.0, 0.0)))
+    ///     .oscillator();
+    /// let mut simulation: GASimulation<SubtractiveIndividual> = GASimulationBuilder::new()
+    ///     .generator(generator)
+    ///     .build()?;
+    ///
+    /// // stop as soon as the fittest individual is within a hertz of the target's fundamental
+    /// while simulation.generation < simulation.max_generations {
+    ///     let summary = simulation.step()?;
+    ///     if summary.fundamental.is_some_and(|f| (f - 440.0).abs() < 1.0) {
+    ///         break;
+    ///     }
+    /// }
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn step(&mut self) -> Result<GenerationSummary<'_, T>, GeneticSimulationError> {
+        self.next()?;
+        Ok(GenerationSummary::from(&*self))
+    }
+
+    /// A step in the iteration of the algorithm. Given the current state of the simulation, calculates the next
+    /// generation.
+    pub(crate) fn next(&mut self) -> Result<(), GeneticSimulationError> {
+        // update the mutation rate before the population reproduces, which adaptive schedules base
+        // on its diversity
+        let diversity = std(&self.population.iter().map(|i| i.fitness()).collect_vec());
+        self.mutation_rate = self.mutation_schedule
+            .rate(self.generation, self.max_generations, self.mutation_rate, diversity);
+
+        match self.steady_state {
+            Some(offspring_per_step) => self.replace_worst(offspring_per_step as usize),
+            None => self.replace_generation(),
+        }
+        if let Some((iters, step_size, top_k)) = self.local_search {
+            self.refine_fittest(iters, step_size, top_k as usize);
+        }
+
+        // custom individuals may compute their fitness without sanitising it
+        if let Some(individual) = self.population.iter().find(|i| !i.fitness().is_finite()) {
+            return Err(GeneticSimulationError::InvalidFitness {
+                details: format!("{} for {}", individual.fitness(), individual.dbg()),
+            });
+        }
+
+        // update fundamental frequency and the best individual so far, and log the fittest one
+        let fittest: &T = self.population.first().expect("There should be a fittest individual in the population");
+        self.fundamental = fittest.get_fundamental();
+        if self.best_ever.as_ref().is_none_or(|best| fittest > best) {
+            self.best_ever = Some(fittest.clone());
+        }
+        if let Some(hall_of_fame) = &mut self.hall_of_fame {
+            hall_of_fame.update(&self.population);
+        }
+        self.diversity = self.genotype_diversity();
+        
+        if self.log_interval.is_some_and(|n| self.generation.is_multiple_of(n)) {
+            info!("Gen: {}, - {:?}", self.generation, fittest.dbg());
+        } else {
+            debug!("Gen: {}, - {:?}", self.generation, fittest.dbg());
+        }
+        
+        // increase generation count
+        self.generation += 1;
+        
+        Ok(())
+    }
+
+    /// Replaces each of the `top_k` fittest individuals with the result of a short hill climb from
+    /// it, re-sorting the population afterwards. Each climb draws from its own generator, seeded in
+    /// order from the simulation's one, so that seeded runs remain reproducible.
+    fn refine_fittest(&mut self, iters: u32, step_size: f32, top_k: usize) {
+        let k = top_k.min(self.population.len());
+        let seeds = (0..k).map(|_| self.rng.gen()).collect_vec();
+        self.population[..k].par_iter_mut().zip(seeds).for_each(|(individual, seed)| {
+            let rng = &mut StdRng::seed_from_u64(seed);
+            for _ in 0..iters {
+                let neighbour = individual.evolve(step_size, rng);
+                if neighbour > *individual {
+                    *individual = neighbour;
+                }
+            }
+        });
+        self.local_search_evaluations = k as u32 * iters;
+        sort_by_fitness(&mut self.population);
+    }
+
+    /// Generates the random additions of a generation, which are only created here and then
+    /// evaluated in parallel.
+    fn generate_immigrants(&mut self) -> Vec<T> {
+        let mut immigrants = (0..self.n_random_additions)
+            .map(|_| self.generator.generate_valid(self.invalid_retries, &mut self.rng))
+            .collect_vec();
+        self.invalid += count_invalid(&immigrants);
+        evaluate_fitness(&mut immigrants);
+        immigrants
+    }
+
+    /// Replaces the whole population with the survivors of selection and their offspring. The
+    /// individuals are moved between the generations rather than cloned.
+    fn replace_generation(&mut self) {
+        let n_elites = (self.elitism as usize).min(self.population.len());
+        self.invalid = 0;
+        if self.immigrant_policy == ImmigrantPolicy::AppendBeforeSelection {
+            let mut immigrants = self.generate_immigrants();
+            self.population.append(&mut immigrants);
+        }
+
+        // The elites of the previous generation go through unchanged, so they are not considered
+        // for selection.
+        let mut population = std::mem::take(&mut self.population);
+        let mut candidates = population.split_off(n_elites);
+        self.sort_for_selection(&mut candidates);
+
+        // number of selected individuals for the next generation
+        let n_selected = match &self.population_evolution {
+            PopulationEvolution::Constant => {
+                PopulationEvolution::fraction_selected(self.initial_population as usize, self.survivor_fraction)
+            }
+            PopulationEvolution::Increasing => {
+                PopulationEvolution::fraction_selected(candidates.len() + n_elites, self.survivor_fraction)
+            }
+            PopulationEvolution::Decreasing { final_size } => {
+                let progress = ((self.generation + 1) as f32 / self.max_generations.max(1) as f32).min(1.0);
+                let (initial, last) = (self.initial_population as f32, *final_size as f32);
+                let size = (initial + (last - initial) * progress).round() as usize;
+                PopulationEvolution::decreasing_selected(size.max(*final_size as usize))
+            }
+            PopulationEvolution::Scheduled(schedule) => {
+                // the elites are always selected, even if the schedule asks for fewer individuals
+                let size = candidates.len() + n_elites;
+                (schedule(self.generation + 1) as usize).clamp(2.max(n_elites), size.max(2))
+            }
+        };
+
+        // join the elites and the rest of the n selected individuals, where the default selection
+        // of the fittest candidates only needs to drop the others
+        let survivors = self.selection.select_survivors(&candidates, n_selected - n_elites, &mut self.rng);
+        if survivors.iter().enumerate().all(|(position, &i)| position == i) {
+            candidates.truncate(survivors.len());
+            population.append(&mut candidates);
+        } else {
+            population.extend(survivors.into_iter().map(|i| candidates[i].clone()));
+        }
+        self.sort_for_selection(&mut population);
+
+        // the immigrants overwrite the least fit survivors, but never the elites
+        if self.immigrant_policy == ImmigrantPolicy::ReplaceWorstAfterSelection {
+            let immigrants = self.generate_immigrants();
+            let n_replaced = immigrants.len().min(population.len() - n_elites);
+            population.truncate(population.len() - n_replaced);
+            population.extend(immigrants.into_iter().take(n_replaced));
+            self.sort_for_selection(&mut population);
+        }
+
+        // pair up the selected individuals in a single pass, including the odd one out, each pair
+        // producing its offspring
+        let parents = self.selection.select_parents(&population, population.len().div_ceil(2) * 2, &mut self.rng);
+        let parents = pair_parents(self.pairing, parents, &population, &mut self.rng);
+        // each crossover draws from its own generator, seeded in order from the simulation's one,
+        // and the offspring are collected in the order of the crossovers
+        let crossovers = parents.chunks_exact(2)
+            .flat_map(|p| std::iter::repeat_n(p, self.offspring_per_pair as usize))
+            .map(|p| (p[0], p[1], self.rng.gen()))
+            .collect_vec();
+        let offspring: Vec<T> = crossovers.into_par_iter().flat_map_iter(|(a, b, seed)| {
+            crossover_valid(&population[a], &population[b], self.mutation_rate, self.crossover,
+                            self.mutation, self.invalid_retries, &mut StdRng::seed_from_u64(seed))
+        }).collect();
+        
+        // update offspring for stats purposes
+        self.offspring = offspring.len() as u32;
+        self.invalid += count_invalid(&offspring);
+
+        // join the selected individuals and their offspring, then sort them
+        population.extend(offspring);
+        sort_by_fitness(&mut population);
+
+        if self.deduplicate {
+            population = self.replace_duplicates(population);
+        }
+        
+        // update generation population with the new one
+        self.population = population;
+    }
+
+    /// Produces the given number of offspring from parents drawn from the whole population, each
+    /// of them replacing the least fit individual if it is fitter, so that the size of the
+    /// population stays the same.
+    fn replace_worst(&mut self, n: usize) {
+        let parents = self.selection.select_parents(&self.population, n.div_ceil(2) * 2, &mut self.rng);
+        let parents = pair_parents(self.pairing, parents, &self.population, &mut self.rng);
+        let seeds = (0..parents.len() / 2).map(|_| self.rng.gen()).collect_vec();
+        let offspring: Vec<T> = parents.par_chunks_exact(2).zip(seeds).flat_map_iter(|(p, seed)| {
+            crossover_valid(&self.population[p[0]], &self.population[p[1]], self.mutation_rate, self.crossover,
+                            self.mutation, self.invalid_retries, &mut StdRng::seed_from_u64(seed))
+        }).collect();
+        self.offspring = offspring.len().min(n) as u32;
+        self.invalid = count_invalid(&offspring[..self.offspring as usize]);
+
+        for child in offspring.into_iter().take(n) {
+            if self.population.last().is_some_and(|worst| child.fitness() > worst.fitness()) {
+                self.population.pop();
+                let position = self.population.partition_point(|i| i.fitness() >= child.fitness());
+                self.population.insert(position, child);
+            }
+        }
+    }
+
+
+    /// Runs a genetic algorithm simulation, returning the fittest individual of the whole run.
+    pub fn run(&mut self) -> Result<T, GeneticSimulationError> {
+        self.run_with_report().map(|report| report.fittest)
+    }
+
+    /// Runs a genetic algorithm simulation, returning the fittest individual along with the
+    /// history of the run.
+    pub fn run_with_report(&mut self) -> Result<SimulationReport<T>, GeneticSimulationError> {
+        let mut recorder: Recorder<GenerationRow> = Recorder::new();
+        let first_generation = self.generation;
+        let mut history: Vec<GenerationRow> = vec![self.into()];
+
+        // a resumed simulation appends its rows to the ones exported up to its checkpoint
+        let mut csv_started = self.generation > 0;
+        if self.csv_export.is_some() && !csv_started {
+            recorder.add_record(history[0].clone());
+        }
+
+        let start = Instant::now();
+        let previously_elapsed = self.elapsed;
+        let mut best_fitness = self.population.first().map_or(0.0, |i| i.fitness());
+        let mut stagnant_generations = 0;
+        self.termination_reason = Some(TerminationReason::MaxGenerations);
+
+        while self.generation < self.max_generations {
+            if self.cancel_token.as_ref().is_some_and(|token| token.load(Ordering::SeqCst)) {
+                info!("Cancelled after {} generations. Terminating", self.generation);
+                self.termination_reason = Some(TerminationReason::Cancelled);
+                break;
+            }
+
+            // calculate the next generation and update state
+            self.step()?;
+            self.elapsed = previously_elapsed + start.elapsed();
+            
+            // update the history and the record
+            let row: GenerationRow = self.into();
+            if self.csv_export.is_some() {
+                recorder.add_record(row.clone());
+            }
+            history.push(row);
+
+            // the rows recorded so far are exported along with the checkpoint, so that both match
+            if let Some((interval, path)) = &self.checkpoint {
+                if self.generation.is_multiple_of(*interval) {
+                    if let Some(file_name) = &self.csv_export {
+                        Self::export_csv(&mut recorder, file_name, csv_started);
+                        csv_started = true;
+                    }
+                    self.save_checkpoint(path).map_err(|e| GeneticSimulationError::CheckpointNotSaved {
+                        details: e.to_string(),
+                    })?;
+                }
+            }
+
+            if let Some(callback) = &self.on_generation {
+                if callback.call(&GenerationSummary::from(&*self)).is_break() {
+                    info!("Stopped by the generation callback. Terminating");
+                    self.termination_reason = Some(TerminationReason::Callback);
+                    break;
+                }
+            }
+
+            if self.time_limit.is_some_and(|limit| self.elapsed >= limit) {
+                info!("Time limit reached after {:?}. Terminating", self.elapsed);
+                self.termination_reason = Some(TerminationReason::TimeLimit);
+                break;
+            }
+
+            if self.convergence_threshold.is_some_and(|threshold| self.diversity < threshold) {
+                info!("Population converged with a diversity of {}. Terminating", self.diversity);
+                self.termination_reason = Some(TerminationReason::Converged);
+                break;
+            }
+
+            // the best fitness can regress, so improvements are measured against the best ever
+            if let Some((limit, min_delta)) = self.stagnation_limit {
+                let fitness = self.population.first().map_or(0.0, |i| i.fitness());
+                if fitness >= best_fitness + min_delta {
+                    best_fitness = fitness;
+                    stagnant_generations = 0;
+                } else {
+                    stagnant_generations += 1;
+                }
+
+                if stagnant_generations >= limit {
+                    info!("No improvement in {limit} generations. Terminating");
+                    self.termination_reason = Some(TerminationReason::Stagnation);
+                    break;
+                }
+            }
+        }
+
+        if let Some(file_name) = &self.csv_export {
+            Self::export_csv(&mut recorder, file_name, csv_started);
+        }
+
+        // Once the iteration is finished, we select the fittest of the whole run, which may not
+        // have survived into the final population
+        let fittest: T = self.best_ever.clone()
+            .or_else(|| self.population.first().cloned())
+            .expect("There should be a fittest individual in the population.");
+        info!("{:?}", fittest.dbg());
+
+        if let Some(file_name) = &self.signal_export {
+           fittest.to_signal().to_wav(file_name)
+               .expect("Exporting to a WAV file should have been successful.")
+        }
+
+        if let Some(dir) = &self.spectrogram_export {
+            Self::export_spectrograms(dir, &fittest.to_signal(), &self.target)
+                .map_err(|e| GeneticSimulationError::ExportFailed { details: format!("{e:?}") })?;
+        }
+
+        if let (Some(dir), Some(hall_of_fame)) = (&self.hall_of_fame_export, &self.hall_of_fame) {
+            for (rank, individual) in hall_of_fame.individuals().iter().enumerate() {
+                let path = Path::new(dir).join(format!("best_{rank}.wav"));
+                let path = path.to_str().expect("The path should be valid unicode.");
+                individual.to_signal().to_wav(path).map_err(|_| GeneticSimulationError::ExportFailed {
+                    details: format!("{path} could not be written"),
+                })?;
+            }
+        }
+
+        Ok(SimulationReport {
+            fittest,
+            history,
+            generations: self.generation - first_generation,
+            elapsed: self.elapsed,
+            termination_reason: self.termination_reason.unwrap_or(TerminationReason::MaxGenerations),
+            final_population: self.population.len(),
+            hall_of_fame: self.hall_of_fame.clone(),
+        })
+    }
+
+    /// Saves the whole simulation to a file in `exports/checkpoints`, from which it can be continued
+    /// with `GASimulation::resume`.
+    pub fn save_checkpoint(&self, path: &str) -> Result<()> {
+        let path = Path::new("exports/checkpoints").join(path);
+        fs::create_dir_all(path.parent().expect("File should have parent."))?;
+        bincode::serialize_into(BufWriter::new(File::create(path)?), self)?;
+        Ok(())
+    }
+
+    /// Restores a simulation saved with `save_checkpoint` to a file in `exports/checkpoints`.
+    /// Running it continues from the generation it was saved at, appending to its CSV export.
+    pub fn resume(path: &str) -> Result<Self> {
+        let file = File::open(Path::new("exports/checkpoints").join(path))?;
+        let mut simulation: Self = bincode::deserialize_from(BufReader::new(file))?;
+
+        // the target is only stored once, so the generator and the population share it again
+        let target = Arc::clone(&simulation.target);
+        simulation.generator = simulation.generator.target(Arc::clone(&target));
+        simulation.population = simulation.population.into_iter()
+            .map(|i| i.with_target(Arc::clone(&target)))
+            .collect();
+        simulation.best_ever = simulation.best_ever.map(|i| i.with_target(Arc::clone(&target)));
+        simulation.hall_of_fame = simulation.hall_of_fame.map(|h| h.with_target(Arc::clone(&target)));
+
+        Ok(simulation)
+    }
+
+    /// Exports the recorded rows to the CSV file, after the rows exported before if `append` is
+    /// set, and clears the recorder.
+    fn export_csv(recorder: &mut Recorder<GenerationRow>, file_name: &str, append: bool) {
+        if append {
+            recorder.append_to_csv(file_name).expect("Appending to CSV should have been successful");
+        } else {
+            recorder.to_csv(file_name).expect("Exporting to CSV should have been successful");
+        }
+        recorder.clear();
+    }
+
+    /// Exports the spectrograms of the fittest individual's signal and the target to the directory.
+    fn export_spectrograms(dir: &str, fittest: &Signal, target: &Signal) -> Result<(), SignalProcessingError> {
+        for (name, signal) in [("fittest", fittest), ("target", target)] {
+            let spectrogram = signal.spectrogram(SPECTROGRAM_FRAME_LENGTH, SPECTROGRAM_HOP)?;
+            let path = Path::new(dir).join(format!("{name}_spectrogram"));
+            let path = path.to_str().expect("The path should be valid unicode.");
+
+            spectrogram.to_csv(&format!("{path}.csv"))?;
+            #[cfg(feature = "plotting")]
+            spectrogram.to_png(&format!("{path}.png"))?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Template for generating an individual with a certain configuration. The implementations for
+/// each generator provide a way to specify the components present in a synthesis method.
+pub trait IndividualGenerator<T: Individual>: Sized {
+    /// Creates a new individual generator.
+    fn new() -> Self;
+
+    /// Generates an Individual having specified the components present, without calculating its
+    /// fitness yet, so that a batch of individuals can be evaluated in parallel afterwards.
+    fn generate_unevaluated<R: Rng + ?Sized>(&self, rng: &mut R) -> T;
+
+    /// Generates an Individual having specified the components present.
+    fn generate<R: Rng + ?Sized>(&self, rng: &mut R) -> T {
+        self.generate_unevaluated(rng).include_fitness()
+    }
+
+    /// Generates an Individual without calculating its fitness, generating it again up to
+    /// `retries` times while it is not valid. The last one is returned even if it is not valid.
+    fn generate_valid<R: Rng + ?Sized>(&self, retries: u32, rng: &mut R) -> T {
+        let mut individual = self.generate_unevaluated(rng);
+        for _ in 0..retries {
+            if individual.is_valid() {
+                break;
+            }
+            individual = self.generate_unevaluated(rng);
+        }
+        individual
+    }
+
+    /// Specifies a target signal.
+    fn target(self, target: Arc<Signal>) -> Self;
+    
+    /// Specifies the target sound by taking the URI of the file containing it.
+    fn target_file(self, file_path: &str) -> Self {
+        let file_in = File::open(file_path)
+            .expect("Expected a target file in the specified directory.");
+        let target = Signal::from_wav_file(file_in)
+            .expect("Target file should have been converted into signal.");
+        self.target(Arc::new(target))
+    }
+
+    /// Specifies the fitness evaluation method to be used.
+    fn fitness_type(self, fitness_type: FitnessType) -> Self;
+
+    /// Assigns a fitness of 0 to individuals whose zero-crossing rate differs from the target's by
+    /// more than the given ratio, without evaluating the fitness function.
+    fn zcr_prefilter(self, max_ratio: f32) -> Self;
+
+    /// Penalises individuals whose harmonicity deviates from the target's by subtracting `weight`
+    /// times the absolute difference from their fitness. This discourages spurious partials added
+    /// to explain the broadband noise of a target.
+    fn noise_penalty(self, weight: f32) -> Self;
+
+    /// Specifies the window function applied to both the candidate and the target signals before
+    /// their frequency spectra are compared. Defaults to `WindowFunction::Rect`.
+    fn analysis_window(self, window: WindowFunction) -> Self;
+
+    /// Restricts the comparison of frequency spectra to the band between `min_hz` and `max_hz`, so
+    /// that inaudible or noisy bins do not contribute to the fitness. Building a simulation fails
+    /// unless the band is non-empty and below the Nyquist frequency.
+    fn analysis_band(self, min_hz: f32, max_hz: f32) -> Self;
+
+    /// Weights the magnitude of each frequency bin before calculating the frequency-domain MSE,
+    /// e.g. with `Weighting::AWeight`, so that errors the ear is less sensitive to count less.
+    fn perceptual_weighting(self, weighting: Weighting) -> Self;
+
+    /// Sets the number of samples per block analysed when comparing frequency spectra. Building a
+    /// simulation fails unless it is a power of two no larger than `MAX_FFT_LENGTH`. Defaults to
+    /// `FFT_LENGTH`.
+    fn analysis_length(self, n_samples: usize) -> Self;
+
+    /// If enabled, the spectra of consecutive blocks covering the whole signal are averaged, so
+    /// that the fitness also accounts for everything after the first block. Disabled by default.
+    fn analysis_averaging(self, averaged: bool) -> Self;
+
+    /// Draws the fundamental frequency of generated individuals from a window of `spread_cents`
+    /// around the fundamental detected in the target, rather than from the whole frequency range.
+    /// The rest of the parameters remain fully random. If no fundamental can be detected, the whole
+    /// range is used.
+    fn seed_frequency_from_target(self, spread_cents: f32) -> Self;
+
+    /// If enabled, the frequency genes are combined and evolved on a logarithmic scale, so that a
+    /// step is the same interval at any pitch rather than the same number of hertz. Otherwise a
+    /// small step at low frequencies can span several semitones. Disabled by default.
+    fn log_frequency(self, enabled: bool) -> Self;
+
+    /// Retrieves the target signal from the generator.
+    fn get_target(&self) -> Arc<Signal>;
+
+    /// Retrieves the analysis settings the generated individuals compare their spectra with.
+    fn get_spectrum_config(&self) -> SpectrumConfig;
+}
+
+pub trait Individual: Clone + Ord + Debug + Send + Sync + Serialize + DeserializeOwned {
+    type Generator: IndividualGenerator<Self> + Sync + Serialize + DeserializeOwned;
+
+    fn new_generator() -> Self::Generator;
+
+    /// Returns a clone of the `Rc<Signal>` object holding the target signal.
+    fn get_target(&self) -> Arc<Signal>;
+
+    /// Replaces the target signal, which is not serialised with the individual.
+    fn with_target(self, target: Arc<Signal>) -> Self;
+
+    /// Getter method used to return the `fitness` field from the implementations, which is set by
+    /// `include_fitness` whenever an individual is generated, evolved or produced by a crossover.
+    fn get_fitness(&self) -> Option<f32>;
+
+    /// Defines how 'fit' the individual is, i.e. how close is the individual to the target
+    /// sound wave, by comparing it to the frequency spectrum. The fitness is only calculated once
+    /// when the individual is created, and this returns the cached value.
+    fn fitness(&self) -> f32 {
+        self.get_fitness().expect("The fitness should have been included when the individual was created")
+    }
+
+    fn get_fitness_type(&self) -> FitnessType;
+
+    /// Whether the individual can be synthesised as intended, e.g. without frequencies above the
+    /// Nyquist frequency. Invalid individuals are assigned a fitness of 0.
+    fn is_valid(&self) -> bool {
+        true
+    }
+
+    /// Maximum tolerated ratio between the zero-crossing rates of the individual's signal and the
+    /// target, if the zero-crossing rate pre-filter is enabled.
+    fn get_zcr_bound(&self) -> Option<f32>;
+
+    /// Weight of the noise penalty and harmonicity of the target, if the penalty is enabled.
+    fn get_noise_penalty(&self) -> Option<(f32, f32)>;
+
+    /// How the frequency spectra of the individual's signal and the target are obtained.
+    fn get_spectrum_config(&self) -> SpectrumConfig;
+
+    fn calculate_fitness(&self) -> f32 {
+        let signal = self.to_signal();
+
+        // cheaply reject candidates far from the target before running the expensive metrics
+        if let Some(bound) = self.get_zcr_bound() {
+            if !Self::passes_zcr_prefilter(&signal, &self.get_target(), bound) {
+                return 0.0;
+            }
+        }
+
+        let fitness = self.evaluate_fitness(&signal, &self.get_fitness_type());
+
+        let fitness = match self.get_noise_penalty() {
+            Some((weight, target_harmonicity)) => {
+                let harmonicity = signal.harmonicity(self.get_fundamental());
+                (fitness - weight * (harmonicity - target_harmonicity).abs()).max(0.0)
+            }
+            None => fitness,
+        };
+
+        // degenerate signals may make the metrics overflow or divide by zero, and such individuals
+        // should not take over the population
+        if fitness.is_finite() {
+            fitness
+        } else {
+            warn!("Non-finite fitness {fitness} replaced by 0 for {}", self.dbg());
+            0.0
+        }
+    }
+
+    /// Evaluates the given fitness type on the individual's signal, regardless of the one it was
+    /// created with, replacing non-finite values by 0.
+    fn evaluate(&self, fitness_type: &FitnessType) -> f32 {
+        let fitness = self.evaluate_fitness(&self.to_signal(), fitness_type);
+        if fitness.is_finite() { fitness } else { 0.0 }
+    }
+
+    /// Evaluates the given fitness type on the individual's signal.
+    fn evaluate_fitness(&self, signal: &Signal, fitness_type: &FitnessType) -> f32 {
+        match fitness_type {
+            FitnessType::FreqDomainMSE { .. } => self.freq_domain_mse_fitness_with(
+                signal,
+                &fitness_type.spectrum_config(self.get_spectrum_config()),
+            ),
+            FitnessType::TimeDomainEuclidean { max_lag_ms: None } => self.time_domain_euclidean_fitness(signal),
+            FitnessType::TimeDomainEuclidean { max_lag_ms: Some(max_lag_ms) } => {
+                self.time_domain_euclidean_aligned_fitness(signal, *max_lag_ms)
+            }
+            FitnessType::TimeDomainMAE => self.time_domain_mae_fitness(signal),
+            FitnessType::TimeDomainNRMSE => self.time_domain_nrmse_fitness(signal),
+            FitnessType::SpectralSNR => self.spectral_snr_fitness(signal),
+            FitnessType::ItakuraSaito => self.itakura_saito_fitness(signal),
+            FitnessType::SpectralCosine => self.spectral_cosine_fitness(signal),
+            FitnessType::EnvelopeMSE { window_ms } => self.envelope_mse_fitness(signal, *window_ms),
+            FitnessType::Weighted(components) => {
+                let total_weight = components.iter().map(|(_, w)| w.max(0.0)).sum::<f32>();
+                if total_weight == 0.0 {
+                    return 0.0;
+                }
+
+                components.iter()
+                    .filter(|(_, w)| *w > 0.0)
+                    .map(|(fitness_type, w)| w / total_weight * self.evaluate_fitness(signal, fitness_type))
+                    .sum()
+            }
+            // FitnessType::TimeDomainCrossCorr => self.time_domain_cross_corr_fitness()
+        }
+    }
+
+    /// Whether the ratio between the zero-crossing rates of the signal and the target is within
+    /// the given bound.
+    fn passes_zcr_prefilter(signal: &Signal, target: &Signal, bound: f32) -> bool {
+        let (a, b) = (signal.zero_crossing_rate(), target.zero_crossing_rate());
+        let (low, high) = if a < b { (a, b) } else { (b, a) };
+
+        if high == 0.0 {
+            // neither signal crosses zero
+            true
+        } else {
+            low > 0.0 && high / low <= bound
+        }
+    }
+
+    fn freq_domain_mse_fitness(&self, signal: &Signal) -> f32 {
+        self.freq_domain_mse_fitness_with(signal, &self.get_spectrum_config())
+    }
+
+    fn freq_domain_mse_fitness_with(&self, signal: &Signal, config: &SpectrumConfig) -> f32 {
+        let mse = signal.freq_spectrum_mse_with(&self.get_target(), config)
+            .expect("MSE should be valid");
+        let cost = (mse / 1000.0).log10().exp();
+
+        // the higher the total cost, the lower the fitness
+        2.0 * sigmoid(-cost)
+    }
+
+    fn time_domain_euclidean_fitness(&self, signal: &Signal) -> f32 {
+        // every sample counts, even if the lengths of the signals differ
+        let distance = signal.euclidean_distance_with(&self.get_target(), LengthHandling::ZeroPad)
+            .expect("Zero-padding should tolerate any length mismatch");
+        Self::euclidean_distance_fitness(distance)
+    }
+
+    fn time_domain_euclidean_aligned_fitness(&self, signal: &Signal, max_lag_ms: f32) -> f32 {
+        let max_lag = (max_lag_ms / 1000.0 * SAMPLE_RATE as f32).round() as usize;
+        let distance = signal.aligned_euclidean_distance(&self.get_target(), max_lag);
+        Self::euclidean_distance_fitness(distance)
+    }
+
+    fn time_domain_mae_fitness(&self, signal: &Signal) -> f32 {
+        let mae = signal.mean_absolute_error(&self.get_target());
+        let cost = (mae / 0.1).log10().exp();
+
+        // the higher the total cost, the lower the fitness
+        2.0 * sigmoid(-cost)
+    }
+
+    /// Maps the normalised RMSE into [0, 1] as `1 - nrmse`, so that silence has a fitness of 0.
+    fn time_domain_nrmse_fitness(&self, signal: &Signal) -> f32 {
+        let nrmse = match signal.nrmse(&self.get_target()) {
+            Ok(nrmse) => nrmse,
+            // the error of a silent target cannot be normalised, so nothing matches it
+            Err(SignalProcessingError::ZeroEnergy) => return 0.0,
+            Err(e) => panic!("NRMSE should be valid: {e:?}"),
+        };
+
+        (1.0 - nrmse).max(0.0)
+    }
+
+    /// Maps a Euclidean distance between signals into a fitness value.
+    fn euclidean_distance_fitness(distance: f32) -> f32 {
         let cost = (distance / 500.0).log10().exp();
 
         // the higher the total cost, the lower the fitness
         2.0 * sigmoid(-cost)
     }
 
+    /// Maps the spectral SNR into [0, 1] as `1 - 10^(-snr / 20)`, i.e. one minus the ratio between
+    /// the norms of the error and the target spectra. Identical spectra have a fitness of 1.
+    fn spectral_snr_fitness(&self, signal: &Signal) -> f32 {
+        let snr = match signal.spectral_snr_with(&self.get_target(), &self.get_spectrum_config()) {
+            Ok(snr) => snr,
+            // there is no signal to compare the noise with if the target is silent
+            Err(SignalProcessingError::ZeroEnergy) => return 0.0,
+            Err(e) => panic!("Spectral SNR should be valid: {e:?}"),
+        };
+
+        (1.0 - 10f32.powf(-snr / 20.0)).max(0.0)
+    }
+
+    /// Maps the Itakura–Saito divergence of the individual's signal (the candidate) with respect to
+    /// the target into (0, 1] as `1 / (1 + divergence)`.
+    fn itakura_saito_fitness(&self, signal: &Signal) -> f32 {
+        let divergence = signal.itakura_saito_with(&self.get_target(), &self.get_spectrum_config())
+            .expect("IS divergence should be valid");
+
+        1.0 / (1.0 + divergence)
+    }
+
+    /// The cosine similarity between the spectra of the individual's signal and the target, clamped
+    /// to [0, 1].
+    fn spectral_cosine_fitness(&self, signal: &Signal) -> f32 {
+        signal.spectral_cosine_similarity_with(&self.get_target(), &self.get_spectrum_config())
+            .expect("Cosine similarity should be valid")
+            .clamp(0.0, 1.0)
+    }
+
+    fn envelope_mse_fitness(&self, signal: &Signal, window_ms: f32) -> f32 {
+        let window_len = ((window_ms / 1000.0 * SAMPLE_RATE as f32).round() as usize).max(1);
+        let mse = signal.envelope_mse(&self.get_target(), window_len);
+        let cost = (mse / 0.01).log10().exp();
+
+        // the higher the total cost, the lower the fitness
+        2.0 * sigmoid(-cost)
+    }
+
     fn time_domain_cross_corr_fitness(&self) -> f32 {
         todo!()
     }
@@ -356,28 +1748,40 @@ pub trait Individual: Clone + Ord + Debug + Send + Sync {
     /// Replaces the fitness field with the calculated fitness value
     fn include_fitness(self) -> Self;
 
-    /// Returns an offspring from two individuals. r specifies the mutation rate represented as the likelihood
-    /// for each gene to mutate
-    fn crossover(&self, other: &Self, r: f32) -> Option<Self>
+    /// Returns the offspring of two individuals, usually two children with complementary genes
+    /// recombined by the given operator. r specifies the mutation rate represented as the
+    /// likelihood for each gene to mutate
+    fn crossover<R: Rng + ?Sized>(&self, other: &Self, r: f32, operator: CrossoverOperator, mutation: MutationOperator, rng: &mut R) -> Vec<Self>
     where
         Self: Sized;
 
     fn to_signal(&self) -> Signal;
 
-    fn evolve(&self, step_size: f32) -> Self;
+    fn evolve<R: Rng + ?Sized>(&self, step_size: f32, rng: &mut R) -> Self;
 
     // fn generate_neighbour(&self, step_size: f32) -> Self;
 
     fn dbg(&self) -> String;
     
     fn get_fundamental(&self) -> Option<f32>;
+
+    /// Distance between the genotypes of two individuals, as the sum of the distances between
+    /// their components, each of them between 0 and 1.
+    fn distance(&self, other: &Self) -> f32;
 }
 
 
 
 #[cfg(test)]
 mod tests {
-    use crate::simulation::synthesis_methods::subtractive::SubtractiveIndividual;
+    use crate::simulation::synthesis_methods::subtractive::{SubtractiveIndividual, SubtractiveIndividualGenerator};
+    use crate::utils::{mean, std};
+    use crate::signal_processing::components::oscillator::sine_wave;
+    use crate::signal_processing::LENGTH;
+    use crate::signal_processing::signal_analysis::MAX_FFT_LENGTH;
+    use rand::RngCore;
+    use std::f32::consts::PI;
+    use std::sync::atomic::{self, AtomicUsize};
     use super::*;
 
     #[test]
@@ -393,14 +1797,14 @@ mod tests {
             .population_evolution(PopulationEvolution::Increasing)
             .target(Signal::default())
             .generator(generator)
-            .build();
+            .build().unwrap();
 
         assert_eq!(simulation.population.len(), 100);
-        simulation.next().unwrap();
+        simulation.step().unwrap();
         assert_eq!(simulation.population.len(), 104);
-        simulation.next().unwrap();
+        simulation.step().unwrap();
         assert_eq!(simulation.population.len(), 108);
-        simulation.next().unwrap();
+        simulation.step().unwrap();
         assert_eq!(simulation.population.len(), 112);
     }
 
@@ -417,39 +1821,1458 @@ mod tests {
             .population_evolution(PopulationEvolution::Increasing)
             .target(Signal::default())
             .generator(generator)
-            .build();
+            .build().unwrap();
 
-        // population should grow by floor(n)
+        // half of the population is selected, and when it is odd the odd one out also mates, so
+        // that the population grows by one more than the random additions
         assert_eq!(simulation.population.len(), 100);
-        simulation.next().unwrap();
-        assert_eq!(simulation.population.len(), 101);
-        simulation.next().unwrap();
-        assert_eq!(simulation.population.len(), 104);
-        simulation.next().unwrap();
-        assert_eq!(simulation.population.len(), 105);
+        simulation.step().unwrap();
+        assert_eq!(simulation.population.len(), 103);
+        simulation.step().unwrap();
+        assert_eq!(simulation.population.len(), 107);
+        simulation.step().unwrap();
+        assert_eq!(simulation.population.len(), 111);
     }
 
     #[test]
-    fn test_constant_population() {
-        let target = Signal::default();
+    fn test_survivor_fraction() {
         let generator = SubtractiveIndividual::new_generator()
-            .target(Arc::new(target.clone()))
+            .target(Arc::new(Signal::default()))
             .oscillator();
+        let sizes = |population_evolution: PopulationEvolution, n_random_additions: u32, fraction: f32| {
+            let mut simulation: GASimulation<SubtractiveIndividual> = GASimulationBuilder::new()
+                .initial_population(100)
+                .n_random_additions(n_random_additions)
+                .population_evolution(population_evolution)
+                .survivor_fraction(fraction)
+                .generator(generator.clone())
+                .build().unwrap();
+            (0..3).map(|_| {
+                simulation.step().unwrap();
+                simulation.population.len()
+            }).collect_vec()
+        };
 
-        let mut simulation: GASimulation<SubtractiveIndividual> = GASimulationBuilder::new()
-            .initial_population(100)
-            .n_random_additions(4)
-            .population_evolution(PopulationEvolution::Constant)
-            .target(Signal::default())
-            .generator(generator)
+        // s selected individuals grow into s + 2 * ceil(s / 2), where a constant population
+        // selects 75 or 25 individuals on every generation
+        assert_eq!(sizes(PopulationEvolution::Constant, 4, 0.75), vec![151, 151, 151]);
+        assert_eq!(sizes(PopulationEvolution::Constant, 4, 0.25), vec![51, 51, 51]);
+
+        // while an increasing population selects the fraction of its size, rounded down
+        assert_eq!(sizes(PopulationEvolution::Increasing, 4, 0.75), vec![156, 240, 367]);
+        assert_eq!(sizes(PopulationEvolution::Increasing, 3, 0.25), vec![51, 27, 15]);
+    }
+
+    #[test]
+    fn test_offspring_per_pair() {
+        let generator = SubtractiveIndividual::new_generator()
+            .target(Arc::new(Signal::default()))
+            .oscillator();
+        let build = |population_evolution: PopulationEvolution| -> GASimulation<SubtractiveIndividual> {
+            GASimulationBuilder::new()
+                .initial_population(20)
+                .n_random_additions(2)
+                .offspring_per_pair(3)
+                .population_evolution(population_evolution)
+                .generator(generator.clone())
+                .build().unwrap()
+        };
+
+        // the 10 selected individuals form 5 pairs, each of them producing 3 * 2 offspring
+        let mut simulation = build(PopulationEvolution::Constant);
+        for _ in 0..3 {
+            simulation.step().unwrap();
+            assert_eq!(simulation.offspring, 30);
+            assert_eq!(simulation.population.len(), 40);
+        }
+
+        // s selected individuals grow into s + 6 * ceil(s / 2)
+        let mut simulation = build(PopulationEvolution::Increasing);
+        for size in [47, 96, 199] {
+            simulation.step().unwrap();
+            assert_eq!(simulation.population.len(), size);
+        }
+    }
+
+    #[test]
+    fn test_decreasing_population() {
+        let target = Signal::default();
+        let generator = SubtractiveIndividual::new_generator()
+            .target(Arc::new(target.clone()))
+            .oscillator();
+
+        let mut simulation: GASimulation<SubtractiveIndividual> = GASimulationBuilder::new()
+            .initial_population(100)
+            .n_random_additions(4)
+            .max_generations(5)
+            .population_evolution(PopulationEvolution::Decreasing { final_size: 20 })
+            .target(Signal::default())
+            .generator(generator)
+            .build().unwrap();
+
+        // population should shrink by (100 - 20) / 5 each generation, then stay at the final size
+        assert_eq!(simulation.population.len(), 100);
+        for size in [84, 68, 52, 36, 20, 20] {
+            simulation.step().unwrap();
+            assert_eq!(simulation.population.len(), size);
+        }
+    }
+
+    #[test]
+    fn test_decreasing_population_floor() {
+        let target = Signal::default();
+        let generator = SubtractiveIndividual::new_generator()
+            .target(Arc::new(target.clone()))
+            .oscillator();
+
+        let mut simulation: GASimulation<SubtractiveIndividual> = GASimulationBuilder::new()
+            .initial_population(10)
+            .n_random_additions(4)
+            .max_generations(4)
+            .population_evolution(PopulationEvolution::Decreasing { final_size: 0 })
+            .target(Signal::default())
+            .generator(generator)
+            .build().unwrap();
+
+        // at least two individuals are selected, producing two offspring
+        assert_eq!(simulation.population.len(), 10);
+        for size in [8, 4, 4, 4] {
+            simulation.step().unwrap();
+            assert_eq!(simulation.population.len(), size);
+        }
+    }
+
+    #[test]
+    fn test_scheduled_population() {
+        let generator = SubtractiveIndividual::new_generator()
+            .target(Arc::new(Signal::default()))
+            .oscillator();
+        let schedule = |generation: u32| if generation <= 2 { 10 } else { 25 };
+
+        let mut simulation: GASimulation<SubtractiveIndividual> = GASimulationBuilder::new()
+            .initial_population(40)
+            .n_random_additions(0)
+            .population_evolution(PopulationEvolution::Scheduled(Arc::new(schedule)))
+            .generator(generator)
+            .build().unwrap();
+
+        // the schedule is clamped to the current population, which the offspring of the selected
+        // individuals can at most double on each generation
+        for size in [20, 20, 40, 51, 51] {
+            simulation.step().unwrap();
+            assert_eq!(simulation.population.len(), size);
+        }
+
+        // the schedule cannot be stored, so it is resumed as a constant population
+        let resumed: GASimulation<SubtractiveIndividual> = bincode::deserialize(&bincode::serialize(&simulation).unwrap()).unwrap();
+        assert!(matches!(resumed.population_evolution, PopulationEvolution::Constant));
+    }
+
+    #[test]
+    fn test_decreasing_population_exceeding_initial() {
+        let generator = SubtractiveIndividual::new_generator()
+            .target(Arc::new(Signal::default()))
+            .oscillator();
+
+        let simulation: Result<GASimulation<SubtractiveIndividual>, _> = GASimulationBuilder::new()
+            .initial_population(10)
+            .population_evolution(PopulationEvolution::Decreasing { final_size: 20 })
+            .generator(generator)
+            .build();
+        assert_eq!(simulation.err().unwrap(), GeneticSimulationError::InvalidFinalPopulation { final_size: 20, initial: 10 });
+    }
+
+    #[test]
+    fn test_shared_target() {
+        let generator = SubtractiveIndividual::new_generator()
+            .target(Arc::new(sine_wave(440.0, 1.0, SAMPLE_RATE as f32, 1.0, 0.0)))
+            .oscillator();
+        let explicit = sine_wave(880.0, 1.0, SAMPLE_RATE as f32, 1.0, 0.0);
+        let build = |builder: GASimulationBuilder<SubtractiveIndividual>| -> GASimulation<SubtractiveIndividual> {
+            builder.initial_population(4).build().unwrap()
+        };
+
+        // the target of the generator is shared with the individuals rather than copied
+        let simulation = build(GASimulationBuilder::new().generator(generator.clone()));
+        assert!(Arc::ptr_eq(&simulation.target, &generator.get_target()));
+        assert!(simulation.population.iter().all(|i| Arc::ptr_eq(&i.get_target(), &simulation.target)));
+
+        // while an explicit target replaces it regardless of the order they are given in
+        for simulation in [
+            build(GASimulationBuilder::new().target(explicit.clone()).generator(generator.clone())),
+            build(GASimulationBuilder::new().generator(generator.clone()).target(explicit.clone())),
+        ] {
+            assert_eq!(*simulation.target, explicit);
+            assert!(simulation.population.iter().all(|i| Arc::ptr_eq(&i.get_target(), &simulation.target)));
+            assert!(Arc::ptr_eq(&simulation.generator.get_target(), &simulation.target));
+        }
+    }
+
+    #[test]
+    fn test_zcr_prefilter() {
+        let target = sine_wave(440.0, 1.0, SAMPLE_RATE as f32, 1.0, 0.1);
+        let close = sine_wave(500.0, 1.0, SAMPLE_RATE as f32, 1.0, 0.1);
+        let far = sine_wave(4400.0, 1.0, SAMPLE_RATE as f32, 1.0, 0.1);
+
+        assert!(SubtractiveIndividual::passes_zcr_prefilter(&close, &target, 2.0));
+        assert!(!SubtractiveIndividual::passes_zcr_prefilter(&far, &target, 2.0));
+        assert!(!SubtractiveIndividual::passes_zcr_prefilter(&Signal::default(), &target, 2.0));
+        assert!(SubtractiveIndividual::passes_zcr_prefilter(&Signal::default(), &Signal::default(), 2.0));
+
+        let generator = SubtractiveIndividual::new_generator()
+            .target(Arc::new(target))
+            .zcr_prefilter(1.5)
+            .oscillator();
+
+        // candidates outside the bound are rejected without evaluating the fitness function
+        let rng = &mut StdRng::seed_from_u64(1);
+        let rejected = (0..20)
+            .map(|_| generator.generate(rng))
+            .filter(|individual| !SubtractiveIndividual::passes_zcr_prefilter(&individual.to_signal(), &individual.get_target(), 1.5))
+            .collect_vec();
+        assert!(!rejected.is_empty());
+        assert!(rejected.iter().all(|individual| individual.fitness() == 0.0));
+    }
+
+    #[test]
+    fn test_spectral_snr_fitness() {
+        let target = sine_wave(440.0, 1.0, SAMPLE_RATE as f32, 1.0, 0.0);
+        let generator = SubtractiveIndividual::new_generator()
+            .target(Arc::new(target.clone()))
+            .fitness_type(FitnessType::SpectralSNR)
+            .oscillator();
+        let individual = generator.generate(&mut thread_rng());
+
+        assert_eq!(individual.spectral_snr_fitness(&target), 1.0);
+        assert!((individual.spectral_snr_fitness(&target.scale_amp(0.5)) - 0.5).abs() < 1e-4);
+        assert!((0.0..=1.0).contains(&individual.fitness()));
+    }
+
+    #[test]
+    fn test_itakura_saito_fitness_direction() {
+        let target = sine_wave(440.0, 1.0, SAMPLE_RATE as f32, 1.0, 0.0)
+            .add_amp(&sine_wave(880.0, 1.0, SAMPLE_RATE as f32, 0.5, 0.0));
+        let candidate = sine_wave(440.0, 1.0, SAMPLE_RATE as f32, 1.0, 0.0);
+        let individual = SubtractiveIndividual::new_generator()
+            .target(Arc::new(target.clone()))
+            .fitness_type(FitnessType::ItakuraSaito)
+            .oscillator()
+            .generate(&mut thread_rng());
+
+        // the candidate is compared against the target, not the other way around
+        let expected = 1.0 / (1.0 + candidate.itakura_saito(&target).unwrap());
+        let reversed = 1.0 / (1.0 + target.itakura_saito(&candidate).unwrap());
+        assert_eq!(individual.itakura_saito_fitness(&candidate), expected);
+        assert_ne!(expected, reversed);
+    }
+
+    #[test]
+    fn test_spectral_cosine_fitness() {
+        let target = sine_wave(440.0, 1.0, SAMPLE_RATE as f32, 1.0, 0.0);
+        let individual = SubtractiveIndividual::new_generator()
+            .target(Arc::new(target.clone()))
+            .fitness_type(FitnessType::SpectralCosine)
+            .oscillator()
+            .generate(&mut thread_rng());
+
+        // a quieter but otherwise perfect candidate is only penalised by the MSE
+        let quiet = target.scale_amp(0.1);
+        assert!((individual.spectral_cosine_fitness(&quiet) - individual.spectral_cosine_fitness(&target)).abs() < 1e-4);
+        assert!(individual.freq_domain_mse_fitness(&quiet) < individual.freq_domain_mse_fitness(&target));
+    }
+
+    #[test]
+    fn test_weighted_fitness() {
+        let target = sine_wave(440.0, 1.0, SAMPLE_RATE as f32, 1.0, 0.0);
+        let weighted = FitnessType::Weighted(vec![
+            (FitnessType::freq_domain_mse(), 0.7),
+            (FitnessType::time_domain_euclidean(), 0.3),
+            (FitnessType::SpectralSNR, 0.0),
+        ]);
+        let individual = SubtractiveIndividual::new_generator()
+            .target(Arc::new(target.clone()))
+            .fitness_type(weighted.clone())
+            .oscillator()
+            .generate(&mut thread_rng());
+
+        // same spectrum as the target but shifted in time, so each metric favours a different candidate
+        let shifted = sine_wave(440.0, 1.0, SAMPLE_RATE as f32, 1.0, 0.25);
+        let freq = individual.evaluate_fitness(&shifted, &FitnessType::freq_domain_mse());
+        let time = individual.evaluate_fitness(&shifted, &FitnessType::time_domain_euclidean());
+        let combined = individual.evaluate_fitness(&shifted, &weighted);
+
+        assert!((combined - (0.7 * freq + 0.3 * time)).abs() < 1e-5);
+        assert!(time < combined && combined < freq, "time: {time}, combined: {combined}, freq: {freq}");
+
+        // weights are normalised to add up to 1
+        let unnormalised = FitnessType::Weighted(vec![
+            (FitnessType::freq_domain_mse(), 7.0),
+            (FitnessType::time_domain_euclidean(), 3.0),
+        ]);
+        assert!((individual.evaluate_fitness(&shifted, &unnormalised) - combined).abs() < 1e-5);
+        assert_eq!(individual.evaluate_fitness(&shifted, &FitnessType::Weighted(vec![])), 0.0);
+        assert!((individual.fitness() - individual.evaluate_fitness(&individual.to_signal(), &weighted)).abs() < 1e-5);
+    }
+
+    #[test]
+    fn test_spectrogram_export() {
+        let generator = SubtractiveIndividual::new_generator()
+            .target(Arc::new(sine_wave(440.0, 1.0, SAMPLE_RATE as f32, 1.0, 0.0)))
+            .oscillator();
+        let mut simulation: GASimulation<SubtractiveIndividual> = GASimulationBuilder::new()
+            .initial_population(10)
+            .max_generations(1)
+            .spectrogram_export("test_run/")
+            .generator(generator.clone())
+            .build().unwrap();
+        simulation.run().unwrap();
+
+        for name in ["fittest", "target"] {
+            let path = Path::new("exports/spectrogram/test_run").join(format!("{name}_spectrogram.csv"));
+            assert!(path.exists(), "{path:?} should have been exported");
+        }
+
+        // a file in place of the export directory is reported once the run finishes
+        std::fs::create_dir_all("exports/spectrogram/tests").unwrap();
+        std::fs::write("exports/spectrogram/tests/not_a_directory", "").unwrap();
+        let mut simulation: GASimulation<SubtractiveIndividual> = GASimulationBuilder::new()
+            .initial_population(10)
+            .max_generations(1)
+            .seed(1)
+            .spectrogram_export("tests/not_a_directory/")
+            .generator(generator)
+            .build().unwrap();
+        assert!(matches!(simulation.run(), Err(GeneticSimulationError::ExportFailed { .. })));
+    }
+
+    #[test]
+    fn test_seeded_runs_are_reproducible() {
+        let generator = SubtractiveIndividual::new_generator()
+            .target(Arc::new(sine_wave(440.0, 1.0, SAMPLE_RATE as f32, 1.0, 0.0)))
+            .oscillator();
+        let run = |seed: u64, file_name: &str| {
+            let mut simulation: GASimulation<SubtractiveIndividual> = GASimulationBuilder::new()
+                .initial_population(10)
+                .max_generations(3)
+                .seed(seed)
+                .csv_export(file_name)
+                .generator(generator.clone())
+                .build().unwrap();
+            let fittest = simulation.run().unwrap();
+            (fittest, std::fs::read(Path::new("exports/csv").join(file_name)).unwrap())
+        };
+
+        let (fittest_a, csv_a) = run(42, "tests/seed_a.csv");
+        let (fittest_b, csv_b) = run(42, "tests/seed_b.csv");
+        let (fittest_c, _) = run(43, "tests/seed_c.csv");
+        assert_eq!(fittest_a, fittest_b);
+        assert_eq!(csv_a, csv_b);
+        assert_ne!(fittest_a, fittest_c);
+
+        // the seed is recorded with every generation
+        let csv = String::from_utf8(csv_a).unwrap();
+        let mut lines = csv.lines();
+        assert!(lines.next().unwrap().ends_with(",seed"));
+        assert!(lines.all(|line| line.ends_with(",42")));
+    }
+
+    #[test]
+    fn test_seeded_runs_are_independent_of_threads() {
+        let generator = SubtractiveIndividual::new_generator()
+            .target(Arc::new(sine_wave(440.0, 1.0, SAMPLE_RATE as f32, 1.0, 0.0)))
+            .oscillator();
+        let run = |threads: usize, file_name: &str| {
+            let pool = rayon::ThreadPoolBuilder::new().num_threads(threads).build().unwrap();
+            pool.install(|| {
+                let mut simulation: GASimulation<SubtractiveIndividual> = GASimulationBuilder::new()
+                    .initial_population(30)
+                    .max_generations(5)
+                    .n_random_additions(3)
+                    .deduplicate(true)
+                    .seed(12)
+                    .csv_export(file_name)
+                    .generator(generator.clone())
+                    .build().unwrap();
+                simulation.run().unwrap();
+            });
+            std::fs::read(Path::new("exports/csv").join(file_name)).unwrap()
+        };
+
+        assert_eq!(run(1, "tests/threads_1.csv"), run(8, "tests/threads_8.csv"));
+    }
+
+    #[test]
+    fn test_checkpoint_resume() {
+        let generator = SubtractiveIndividual::new_generator()
+            .target(Arc::new(sine_wave(440.0, 1.0, SAMPLE_RATE as f32, 1.0, 0.0)))
+            .oscillator();
+        let builder = |name: &str| GASimulationBuilder::new()
+            .initial_population(10)
+            .seed(3)
+            .csv_export(&format!("tests/{name}.csv"))
+            .generator(generator.clone());
+
+        let mut uninterrupted: GASimulation<SubtractiveIndividual> = builder("uninterrupted").max_generations(10).build().unwrap();
+        uninterrupted.run().unwrap();
+
+        let mut simulation: GASimulation<SubtractiveIndividual> = builder("resumed")
+            .max_generations(5)
+            .checkpoint_every(5, "tests/checkpoint.bin")
+            .build().unwrap();
+        simulation.run().unwrap();
+
+        let mut resumed: GASimulation<SubtractiveIndividual> = GASimulation::resume("tests/checkpoint.bin").unwrap();
+        assert_eq!(resumed.generation, 5);
+        assert_eq!(resumed.population, simulation.population);
+        assert_eq!(resumed.population[0].get_target(), simulation.population[0].get_target());
+
+        resumed.max_generations = 10;
+        resumed.run().unwrap();
+        assert_eq!(resumed.generation, 10);
+        assert_eq!(resumed.population.len(), uninterrupted.population.len());
+
+        // the rows of both halves follow each other as if the run had not been interrupted
+        let read = |name: &str| std::fs::read_to_string(format!("exports/csv/tests/{name}.csv")).unwrap();
+        assert_eq!(read("resumed"), read("uninterrupted"));
+    }
+
+    #[test]
+    fn test_checkpoint_failure() {
+        let generator = SubtractiveIndividual::new_generator()
+            .target(Arc::new(sine_wave(440.0, 1.0, SAMPLE_RATE as f32, 1.0, 0.0)))
+            .oscillator();
+
+        // a file in place of the checkpoint's directory makes saving it fail
+        std::fs::create_dir_all("exports/checkpoints/tests").unwrap();
+        std::fs::write("exports/checkpoints/tests/not_a_directory", "").unwrap();
+        let mut simulation: GASimulation<SubtractiveIndividual> = GASimulationBuilder::new()
+            .initial_population(10)
+            .max_generations(2)
+            .seed(3)
+            .checkpoint_every(1, "tests/not_a_directory/checkpoint.bin")
+            .generator(generator)
+            .build().unwrap();
+
+        assert!(matches!(simulation.run(), Err(GeneticSimulationError::CheckpointNotSaved { .. })));
+        assert_eq!(simulation.generation, 1);
+    }
+
+    #[test]
+    fn test_stagnation_limit() {
+        let generator = SubtractiveIndividual::new_generator()
+            .target(Arc::new(sine_wave(440.0, 1.0, SAMPLE_RATE as f32, 1.0, 0.0)))
+            .oscillator();
+        // without mutations or random additions, the population quickly collapses onto the same
+        // individual and stops improving
+        let stuck = || GASimulationBuilder::new()
+            .initial_population(10)
+            .n_random_additions(0)
+            .mutation_rate(0.0)
+            .seed(7)
+            .generator(generator.clone());
+
+        let mut simulation: GASimulation<SubtractiveIndividual> = stuck()
+            .max_generations(1_000)
+            .stagnation_limit(3, 0.05)
+            .build().unwrap();
+        simulation.run().unwrap();
+        assert_eq!(simulation.termination_reason, Some(TerminationReason::Stagnation));
+        assert!((3..1_000).contains(&simulation.generation));
+
+        let mut simulation: GASimulation<SubtractiveIndividual> = stuck().max_generations(2).build().unwrap();
+        simulation.run().unwrap();
+        assert_eq!(simulation.termination_reason, Some(TerminationReason::MaxGenerations));
+        assert_eq!(simulation.generation, 2);
+    }
+
+    #[test]
+    fn test_simulation_report() {
+        let generator = SubtractiveIndividual::new_generator()
+            .target(Arc::new(sine_wave(440.0, 1.0, SAMPLE_RATE as f32, 1.0, 0.0)))
+            .oscillator();
+        let mut simulation: GASimulation<SubtractiveIndividual> = GASimulationBuilder::new()
+            .initial_population(10)
+            .n_random_additions(0)
+            .mutation_rate(0.0)
+            .max_generations(1_000)
+            .stagnation_limit(3, 0.05)
+            .seed(7)
+            .generator(generator)
+            .build().unwrap();
+        let report = simulation.run_with_report().unwrap();
+
+        // the history is kept without exporting it, and stops with the simulation
+        assert_eq!(report.termination_reason, TerminationReason::Stagnation);
+        assert_eq!(report.generations, simulation.generation);
+        assert_eq!(report.history.len(), report.generations as usize + 1);
+        assert_eq!(report.history.iter().map(|row| row.generation).collect_vec(), (0..=simulation.generation).collect_vec());
+        assert_eq!(report.history.last().unwrap().best_ever_fitness, report.fittest.fitness());
+        assert_eq!(report.final_population, simulation.population.len());
+        assert_eq!(report.elapsed, simulation.elapsed);
+    }
+
+    #[test]
+    fn test_convergence() {
+        let generator = SubtractiveIndividual::new_generator()
+            .target(Arc::new(sine_wave(440.0, 1.0, SAMPLE_RATE as f32, 1.0, 0.0)))
+            .oscillator();
+        // without mutations or random additions, the population collapses onto copies of the same
+        // individual
+        let mut simulation: GASimulation<SubtractiveIndividual> = GASimulationBuilder::new()
+            .initial_population(10)
+            .n_random_additions(0)
+            .mutation_rate(0.0)
+            .max_generations(1_000)
+            .converge_when_diversity_below(0.01)
+            .seed(7)
+            .generator(generator)
+            .build()
+            .unwrap();
+        assert!(simulation.diversity > 0.01);
+
+        let report = simulation.run_with_report().unwrap();
+        assert_eq!(report.termination_reason, TerminationReason::Converged);
+        assert!(report.generations < 1_000);
+        assert!(report.history.last().unwrap().diversity < 0.01);
+        assert!(report.history[..report.history.len() - 1].iter().all(|row| row.diversity >= 0.01));
+    }
+
+    #[test]
+    fn test_mutation_schedules() {
+        let linear = MutationSchedule::Linear { start: 0.3, end: 0.02 };
+        let exponential = MutationSchedule::Exponential { start: 0.3, end: 0.02 };
+        let rates = |schedule: MutationSchedule| [0, 50, 100].map(|generation| schedule.rate(generation, 100, 0.0, 0.0));
+
+        let close = |a: [f32; 3], b: [f32; 3]| a.iter().zip(b).all(|(a, b)| (a - b).abs() < 1e-6);
+        assert!(close(rates(linear), [0.3, 0.16, 0.02]));
+        assert!(close(rates(exponential), [0.3, (0.3f32 * 0.02).sqrt(), 0.02]));
+        assert!(close(rates(MutationSchedule::Constant(0.1)), [0.1; 3]));
+
+        assert_eq!(linear.initial_rate(), 0.3);
+        assert!(!MutationSchedule::Exponential { start: 0.3, end: 0.0 }.is_valid());
+    }
+
+    #[test]
+    fn test_adaptive_mutation_rate() {
+        let generator = SubtractiveIndividual::new_generator()
+            .target(Arc::new(sine_wave(440.0, 1.0, SAMPLE_RATE as f32, 1.0, 0.0)))
+            .oscillator();
+        let schedule = |diversity_threshold| MutationSchedule::AdaptiveDiversity { low: 0.01, high: 0.5, diversity_threshold };
+        let build = |diversity_threshold| GASimulationBuilder::new()
+            .initial_population(10)
+            .n_random_additions(0)
+            .mutation_schedule(schedule(diversity_threshold))
+            .seed(5)
+            .generator(generator.clone())
+            .build().unwrap();
+
+        // a population of identical individuals has no diversity at all
+        let mut simulation: GASimulation<SubtractiveIndividual> = build(1e-3);
+        simulation.population = vec![simulation.population[0].clone(); 10];
+        assert_eq!(simulation.mutation_rate, 0.01);
+        simulation.step().unwrap();
+        assert!(simulation.mutation_rate > 0.01);
+
+        // while the rate stays at the lowest one if no population is considered converged
+        let mut simulation = build(0.0);
+        simulation.population = vec![simulation.population[0].clone(); 10];
+        simulation.step().unwrap();
+        assert_eq!(simulation.mutation_rate, 0.01);
+    }
+
+    #[test]
+    fn test_time_limit() {
+        let generator = SubtractiveIndividual::new_generator()
+            .target(Arc::new(sine_wave(440.0, 1.0, SAMPLE_RATE as f32, 1.0, 0.0)))
+            .oscillator();
+        let mut simulation: GASimulation<SubtractiveIndividual> = GASimulationBuilder::new()
+            .initial_population(10)
+            .time_limit(Duration::from_millis(1))
+            .generator(generator)
+            .build().unwrap();
+        simulation.run().unwrap();
+
+        assert_eq!(simulation.termination_reason, Some(TerminationReason::TimeLimit));
+        assert_eq!(simulation.generation, 1);
+        assert!(simulation.elapsed >= Duration::from_millis(1));
+    }
+
+    #[test]
+    fn test_cancel_token() {
+        let generator = SubtractiveIndividual::new_generator()
+            .target(Arc::new(sine_wave(440.0, 1.0, SAMPLE_RATE as f32, 1.0, 0.0)))
+            .oscillator();
+        let token = Arc::new(AtomicBool::new(false));
+        let mut simulation: GASimulation<SubtractiveIndividual> = GASimulationBuilder::new()
+            .initial_population(10)
+            .max_generations(u32::MAX)
+            .csv_export("tests/cancelled.csv")
+            .signal_export("tests/cancelled.wav")
+            .cancel_token(Arc::clone(&token))
+            .generator(generator)
+            .build().unwrap();
+        for path in ["exports/csv/tests/cancelled.csv", "exports/signal/tests/cancelled.wav"] {
+            let _ = fs::remove_file(path);
+        }
+
+        let canceller = std::thread::spawn(move || {
+            std::thread::sleep(Duration::from_millis(200));
+            token.store(true, Ordering::SeqCst);
+        });
+        let report = simulation.run_with_report().unwrap();
+        canceller.join().unwrap();
+
+        // the generations run so far are exported along with the fittest individual
+        assert_eq!(report.termination_reason, TerminationReason::Cancelled);
+        assert!(report.generations > 0);
+        let csv = fs::read_to_string("exports/csv/tests/cancelled.csv").unwrap();
+        assert_eq!(csv.lines().count(), report.history.len() + 1);
+        assert!(Path::new("exports/signal/tests/cancelled.wav").exists());
+    }
+
+    #[test]
+    fn test_elitism() {
+        let generator = SubtractiveIndividual::new_generator()
+            .target(Arc::new(sine_wave(440.0, 1.0, SAMPLE_RATE as f32, 1.0, 0.0)))
+            .oscillator();
+        // a high mutation rate makes the offspring unlikely to keep up with their parents
+        let mut simulation: GASimulation<SubtractiveIndividual> = GASimulationBuilder::new()
+            .initial_population(10)
+            .max_generations(10)
+            .mutation_rate(0.9)
+            .elitism(1)
+            .csv_export("tests/elitism.csv")
+            .generator(generator)
+            .build().unwrap();
+        simulation.run().unwrap();
+
+        let mut rdr = csv::Reader::from_path("exports/csv/tests/elitism.csv").unwrap();
+        let column = rdr.headers().unwrap().iter().position(|h| h == "max_fitness").unwrap();
+        let max_fitness = rdr.records()
+            .map(|r| r.unwrap()[column].parse::<f32>().unwrap())
+            .collect_vec();
+        assert_eq!(max_fitness.len(), 11);
+        assert!(max_fitness.windows(2).all(|w| w[0] <= w[1]), "{max_fitness:?}");
+    }
+
+    #[test]
+    fn test_elitism_exceeding_selection() {
+        let generator = SubtractiveIndividual::new_generator()
+            .target(Arc::new(Signal::default()))
+            .oscillator();
+        let simulation: Result<GASimulation<SubtractiveIndividual>, _> = GASimulationBuilder::new()
+            .initial_population(10)
+            .elitism(6)
+            .generator(generator)
             .build();
+        assert_eq!(simulation.err().unwrap(), GeneticSimulationError::InvalidElitism { elitism: 6, selected: 5 });
+    }
+
+    #[test]
+    fn test_invalid_configurations() {
+        let generator = SubtractiveIndividual::new_generator()
+            .target(Arc::new(Signal::default()))
+            .oscillator();
+        let build = |builder: GASimulationBuilder<SubtractiveIndividual>| builder.build().err().unwrap();
+        let valid = || GASimulationBuilder::new().generator(generator.clone());
+
+        assert_eq!(build(GASimulationBuilder::new()), GeneticSimulationError::MissingGenerator);
+        assert_eq!(build(GASimulationBuilder { target: None, ..valid() }), GeneticSimulationError::MissingTarget);
+        for size in [0, 1] {
+            assert_eq!(build(valid().initial_population(size)), GeneticSimulationError::InvalidPopulationSize(size));
+        }
+        assert_eq!(build(valid().mutation_rate(2.0)), GeneticSimulationError::InvalidMutationRate);
+        assert_eq!(build(valid().mutation_schedule(MutationSchedule::Exponential { start: 0.1, end: 0.0 })),
+                   GeneticSimulationError::InvalidMutationRate);
+        assert_eq!(build(valid().max_generations(0)), GeneticSimulationError::InvalidMaxGenerations);
+        for fraction in [0.0, 1.5] {
+            assert_eq!(build(valid().survivor_fraction(fraction)), GeneticSimulationError::InvalidSurvivorFraction(fraction));
+        }
+        assert_eq!(build(valid().initial_population(10).survivor_fraction(0.25).elitism(3)),
+                   GeneticSimulationError::InvalidElitism { elitism: 3, selected: 2 });
+        assert_eq!(build(valid().population_evolution(PopulationEvolution::Decreasing { final_size: 10 }).elitism(6)),
+                   GeneticSimulationError::InvalidElitism { elitism: 6, selected: 5 });
+        assert_eq!(build(valid().offspring_per_pair(0)), GeneticSimulationError::InvalidOffspringPerPair);
+        for sigma in [0.0, -1.0] {
+            assert_eq!(build(valid().fitness_sharing(sigma)), GeneticSimulationError::InvalidSharingDistance(sigma));
+        }
+        assert_eq!(build(valid().steady_state(0)), GeneticSimulationError::InvalidOffspringPerStep);
+        for step_size in [0.0, 1.5] {
+            assert_eq!(build(valid().local_search(5, step_size, 2)), GeneticSimulationError::InvalidLocalSearchStepSize(step_size));
+        }
+        let crossover = CrossoverOperator::SBX { eta: -1.0 };
+        assert_eq!(build(valid().crossover(crossover)), GeneticSimulationError::InvalidCrossover(crossover));
+        let mutation = MutationOperator::Gaussian { sigma_fraction: 0.0 };
+        assert_eq!(build(valid().mutation(mutation)), GeneticSimulationError::InvalidMutation(mutation));
+        assert_eq!(build(valid().hall_of_fame(0, 0.1)), GeneticSimulationError::InvalidHallOfFameSize);
+        assert_eq!(build(valid().stagnation_limit(0, 0.01)), GeneticSimulationError::InvalidStagnationLimit);
+        assert_eq!(build(valid().checkpoint_every(0, "tests/invalid_checkpoint.bin")),
+                   GeneticSimulationError::InvalidCheckpointInterval);
+        assert_eq!(build(valid().log_every(0)), GeneticSimulationError::InvalidLogInterval);
+        for (min_hz, max_hz) in [(2_000.0, 50.0), (-10.0, 2_000.0), (50.0, SAMPLE_RATE as f32)] {
+            let band = GASimulationBuilder::new().generator(generator.clone().analysis_band(min_hz, max_hz));
+            assert_eq!(build(band), GeneticSimulationError::InvalidAnalysisBand { min_hz, max_hz });
+        }
+        for length in [1000, 2 * MAX_FFT_LENGTH] {
+            let analysis = GASimulationBuilder::new().generator(generator.clone().analysis_length(length));
+            assert_eq!(build(analysis), GeneticSimulationError::InvalidAnalysisLength(length));
+        }
+        for pressure in [0.5, 3.0] {
+            let strategy = SelectionStrategy::LinearRank { pressure };
+            assert_eq!(build(valid().selection(Box::new(strategy))), GeneticSimulationError::InvalidSelection(strategy));
+        }
+        assert!(valid().build().is_ok());
+    }
+
+    #[test]
+    fn test_custom_selection() {
+        /// Keeps the least fit individuals and only mates the fittest survivor with itself.
+        #[derive(Debug)]
+        struct Contrarian;
+
+        impl<T: Individual> Selection<T> for Contrarian {
+            fn select_parents(&self, _population: &[T], n: usize, _rng: &mut dyn RngCore) -> Vec<usize> {
+                vec![0; n]
+            }
+
+            fn select_survivors(&self, population: &[T], n: usize, _rng: &mut dyn RngCore) -> Vec<usize> {
+                (population.len() - n..population.len()).collect()
+            }
+        }
+
+        let generator = SubtractiveIndividual::new_generator()
+            .target(Arc::new(sine_wave(440.0, 1.0, SAMPLE_RATE as f32, 1.0, 0.0)))
+            .oscillator();
+        let mut simulation: GASimulation<SubtractiveIndividual> = GASimulationBuilder::new()
+            .initial_population(10)
+            .n_random_additions(0)
+            .mutation_rate(0.0)
+            .selection(Box::new(Contrarian))
+            .generator(generator)
+            .build().unwrap();
+        let worst = simulation.population[5..].to_vec();
+        simulation.step().unwrap();
+
+        // without mutations, the six offspring are copies of the fittest of the worst half
+        let close_to = |i: &SubtractiveIndividual, other: &SubtractiveIndividual| {
+            (i.get_fundamental().unwrap() - other.get_fundamental().unwrap()).abs() < 1e-3
+        };
+        assert_eq!(simulation.population.len(), 11);
+        assert!(simulation.population.iter().all(|i| worst.iter().any(|w| close_to(i, w))));
+        assert_eq!(simulation.population.iter().filter(|i| close_to(i, &worst[0])).count(), 7);
+    }
+
+    #[test]
+    fn test_best_ever() {
+        /// Keeps the least fit individuals, so that the best fitness of the population regresses.
+        #[derive(Debug)]
+        struct Worst;
+
+        impl<T: Individual> Selection<T> for Worst {
+            fn select_parents(&self, population: &[T], n: usize, rng: &mut dyn RngCore) -> Vec<usize> {
+                (0..n).map(|_| rng.gen_range(0..population.len())).collect()
+            }
+
+            fn select_survivors(&self, population: &[T], n: usize, _rng: &mut dyn RngCore) -> Vec<usize> {
+                (population.len() - n..population.len()).collect()
+            }
+        }
+
+        let generator = SubtractiveIndividual::new_generator()
+            .target(Arc::new(sine_wave(440.0, 1.0, SAMPLE_RATE as f32, 1.0, 0.0)))
+            .oscillator();
+        let mut simulation: GASimulation<SubtractiveIndividual> = GASimulationBuilder::new()
+            .initial_population(10)
+            .max_generations(10)
+            .mutation_rate(1.0)
+            .selection(Box::new(Worst))
+            .seed(6)
+            .generator(generator)
+            .build()
+            .unwrap();
+        let report = simulation.run_with_report().unwrap();
+
+        let best = report.history.iter().map(|row| row.max_fitness).fold(0.0, f32::max);
+        assert!(report.history.iter().any(|row| row.max_fitness < best));
+        assert!(report.history.windows(2).all(|w| w[0].best_ever_fitness <= w[1].best_ever_fitness));
+        assert_eq!(report.history.last().unwrap().best_ever_fitness, best);
+        assert_eq!(report.fittest.fitness(), best);
+    }
+
+    #[test]
+    fn test_hall_of_fame() {
+        let generator = SubtractiveIndividual::new_generator()
+            .target(Arc::new(sine_wave(440.0, 1.0, SAMPLE_RATE as f32, 1.0, 0.0)))
+            .oscillator();
+        let mut simulation: GASimulation<SubtractiveIndividual> = GASimulationBuilder::new()
+            .initial_population(10)
+            .max_generations(5)
+            .hall_of_fame(4, 0.05)
+            .seed(8)
+            .generator(generator)
+            .build()
+            .unwrap();
+        let report = simulation.run_with_report().unwrap();
+
+        let hall_of_fame = report.hall_of_fame.expect("The hall of fame should have been kept");
+        let members = hall_of_fame.individuals();
+        assert_eq!(members.len(), 4);
+        assert_eq!(members[0].fitness(), report.fittest.fitness());
+        assert!(members.windows(2).all(|w| w[0].fitness() >= w[1].fitness()));
+        assert!(members.iter().tuple_combinations().all(|(a, b)| a.distance(b) >= 0.05));
+    }
+
+    #[test]
+    fn test_hall_of_fame_export_failure() {
+        let generator = SubtractiveIndividual::new_generator()
+            .target(Arc::new(sine_wave(440.0, 1.0, SAMPLE_RATE as f32, 1.0, 0.0)))
+            .oscillator();
+
+        // a file in place of the export directory is reported once the run finishes
+        std::fs::create_dir_all("exports/signal/tests").unwrap();
+        std::fs::write("exports/signal/tests/not_a_directory", "").unwrap();
+        let mut simulation: GASimulation<SubtractiveIndividual> = GASimulationBuilder::new()
+            .initial_population(10)
+            .max_generations(2)
+            .hall_of_fame(2, 0.05)
+            .hall_of_fame_export("tests/not_a_directory/")
+            .seed(8)
+            .generator(generator)
+            .build()
+            .unwrap();
+
+        assert!(matches!(simulation.run(), Err(GeneticSimulationError::ExportFailed { .. })));
+    }
+
+    #[test]
+    fn test_fitness_sharing() {
+        let generator = SubtractiveIndividual::new_generator()
+            .target(Arc::new(sine_wave(440.0, 1.0, SAMPLE_RATE as f32, 1.0, 0.0)))
+            .oscillator();
+        let mut simulation: GASimulation<SubtractiveIndividual> = GASimulationBuilder::new()
+            .initial_population(10)
+            .fitness_sharing(0.2)
+            .seed(2)
+            .generator(generator)
+            .build().unwrap();
+
+        let (a, b) = (&simulation.population[0], &simulation.population[1]);
+        assert_eq!(a.distance(a), 0.0);
+        assert!(a.distance(b) > 0.0 && a.distance(b) <= 1.0);
+
+        // the population is still reported sorted by the actual fitness
+        simulation.step().unwrap();
+        assert!(simulation.population.windows(2).all(|w| w[0].fitness() >= w[1].fitness()));
+    }
+
+    #[test]
+    fn test_deduplicate() {
+        let generator = SubtractiveIndividual::new_generator()
+            .target(Arc::new(sine_wave(440.0, 1.0, SAMPLE_RATE as f32, 1.0, 0.0)))
+            .oscillator();
+        let mut simulation: GASimulation<SubtractiveIndividual> = GASimulationBuilder::new()
+            .initial_population(10)
+            .n_random_additions(0)
+            .mutation_rate(0.0)
+            .deduplicate(true)
+            .seed(4)
+            .generator(generator)
+            .build().unwrap();
+        let clone = simulation.population[0].clone();
+        simulation.population = vec![clone.clone(); 10];
+        simulation.step().unwrap();
+
+        // without mutations the 11 individuals of the next generation are all clones, and all but
+        // one of them are replaced by random individuals
+        assert_eq!(simulation.population.len(), 11);
+        assert_eq!(simulation.population.iter().filter(|i| i.distance(&clone) < DUPLICATE_DISTANCE).count(), 1);
+        for (i, a) in simulation.population.iter().enumerate() {
+            assert!(simulation.population[i + 1..].iter().all(|b| a.distance(b) >= DUPLICATE_DISTANCE));
+        }
+    }
+
+    #[test]
+    fn test_immigrant_policy() {
+        let generator = SubtractiveIndividual::new_generator()
+            .target(Arc::new(sine_wave(440.0, 1.0, SAMPLE_RATE as f32, 1.0, 0.0)))
+            .oscillator();
+        // a population of copies of the fittest of many random individuals, which random additions
+        // are unlikely to outcompete
+        let step = |immigrant_policy: ImmigrantPolicy| {
+            let mut simulation: GASimulation<SubtractiveIndividual> = GASimulationBuilder::new()
+                .initial_population(200)
+                .n_random_additions(3)
+                .immigrant_policy(immigrant_policy)
+                .mutation_rate(0.0)
+                .seed(3)
+                .generator(generator.clone())
+                .build().unwrap();
+            let clone = simulation.population[0].clone();
+            simulation.initial_population = 10;
+            simulation.population = vec![clone.clone(); 10];
+            simulation.step().unwrap();
+            simulation.population.iter().filter(|i| i.distance(&clone) >= DUPLICATE_DISTANCE).count()
+        };
+
+        // appended immigrants are discarded by selection, so without mutations only clones remain
+        assert_eq!(step(ImmigrantPolicy::AppendBeforeSelection), 0);
+
+        // while the immigrants replacing the worst survivors remain along with their offspring
+        assert!(step(ImmigrantPolicy::ReplaceWorstAfterSelection) >= 3);
+    }
+
+    #[test]
+    fn test_steady_state() {
+        let generator = SubtractiveIndividual::new_generator()
+            .target(Arc::new(sine_wave(440.0, 1.0, SAMPLE_RATE as f32, 1.0, 0.0)))
+            .oscillator();
+        let mut simulation: GASimulation<SubtractiveIndividual> = GASimulationBuilder::new()
+            .initial_population(10)
+            .steady_state(3)
+            .seed(8)
+            .generator(generator)
+            .build().unwrap();
+
+        let mut worst = simulation.population.last().unwrap().fitness();
+        for _ in 0..10 {
+            simulation.step().unwrap();
+            assert_eq!(simulation.population.len(), 10);
+            assert!(simulation.population.windows(2).all(|w| w[0].fitness() >= w[1].fitness()));
+
+            let step_worst = simulation.population.last().unwrap().fitness();
+            assert!(step_worst >= worst);
+            worst = step_worst;
+        }
+        assert_eq!(simulation.generation, 10);
+    }
+
+    #[test]
+    fn test_step() {
+        let generator = SubtractiveIndividual::new_generator()
+            .target(Arc::new(sine_wave(440.0, 1.0, SAMPLE_RATE as f32, 1.0, 0.0)))
+            .oscillator();
+        let mut simulation: GASimulation<SubtractiveIndividual> = GASimulationBuilder::new()
+            .initial_population(10)
+            .seed(9)
+            .generator(generator)
+            .build()
+            .unwrap();
+
+        for generation in 1..=3 {
+            let summary = simulation.step().unwrap();
+            assert_eq!(summary.generation, generation);
+            assert_eq!(summary.best_fitness, summary.fittest.fitness());
+            // the odd one out of the 5 selected individuals also mates
+            assert_eq!(summary.offspring, 6);
+        }
+        assert_eq!(simulation.generation, 3);
+    }
+
+    #[test]
+    fn test_generation_callback() {
+        let generator = SubtractiveIndividual::new_generator()
+            .target(Arc::new(sine_wave(440.0, 1.0, SAMPLE_RATE as f32, 1.0, 0.0)))
+            .oscillator();
+        let summaries = Arc::new(Mutex::new(vec![]));
+        let recorded = Arc::clone(&summaries);
+        let mut simulation: GASimulation<SubtractiveIndividual> = GASimulationBuilder::new()
+            .initial_population(10)
+            .max_generations(10)
+            .seed(4)
+            .generator(generator)
+            .on_generation(move |summary: &GenerationSummary<SubtractiveIndividual>| {
+                assert_eq!(summary.best_fitness, summary.fittest.fitness());
+                recorded.lock().unwrap().push(summary.generation);
+                if summary.generation == 3 { ControlFlow::Break(()) } else { ControlFlow::Continue(()) }
+            })
+            .build().unwrap();
+
+        simulation.run().unwrap();
+        assert_eq!(*summaries.lock().unwrap(), vec![1, 2, 3]);
+        assert_eq!(simulation.generation, 3);
+        assert_eq!(simulation.termination_reason, Some(TerminationReason::Callback));
+    }
+
+    #[test]
+    fn test_complementary_offspring() {
+        let generator = SubtractiveIndividual::new_generator()
+            .target(Arc::new(sine_wave(440.0, 1.0, SAMPLE_RATE as f32, 1.0, 0.0)))
+            .oscillator();
+        let mut rng = StdRng::seed_from_u64(3);
+        let (a, b) = (generator.generate(&mut rng), generator.generate(&mut rng));
+
+        // without mutations, the weights of both children add up to one for every gene
+        let offspring = a.crossover(&b, 0.0, CrossoverOperator::Arithmetic, MutationOperator::Replace, &mut rng);
+        assert_eq!(offspring.len(), 2);
+        let parents_sum = a.get_fundamental().unwrap() + b.get_fundamental().unwrap();
+        let offspring_sum = offspring.iter().map(|i| i.get_fundamental().unwrap()).sum::<f32>();
+        assert!((parents_sum - offspring_sum).abs() < 1e-2);
+    }
+
+    #[test]
+    fn test_time_domain_euclidean_aligned_fitness() {
+        let target = sine_wave(440.0, 1.0, SAMPLE_RATE as f32, 1.0, 0.0)
+            .add_amp(&sine_wave(1_234.5, 1.0, SAMPLE_RATE as f32, 0.5, 0.0));
+        let individual = SubtractiveIndividual::new_generator()
+            .target(Arc::new(target.clone()))
+            .fitness_type(FitnessType::time_domain_euclidean_aligned(10.0))
+            .oscillator()
+            .generate(&mut thread_rng());
+
+        // copy of the target shifted by 5 ms
+        let shift = (0.005 * SAMPLE_RATE as f32) as usize;
+        let shifted = Signal::from_samples(&target.samples()[shift..]);
+
+        let aligned = individual.evaluate_fitness(&shifted, &individual.get_fitness_type());
+        let plain = individual.evaluate_fitness(&shifted, &FitnessType::time_domain_euclidean());
+        assert!(aligned > 0.999, "aligned: {aligned}");
+        assert!(plain < 0.9, "plain: {plain}");
+    }
+
+    #[test]
+    fn test_noise_penalty() {
+        let target = sine_wave(440.0, 1.0, SAMPLE_RATE as f32, 1.0, 0.0);
+        let generator = SubtractiveIndividual::new_generator()
+            .target(Arc::new(target.clone()))
+            .oscillator();
+        let penalised = SubtractiveIndividual::new_generator()
+            .noise_penalty(0.5)
+            .target(Arc::new(target.clone()))
+            .oscillator();
+
+        let rng = &mut StdRng::seed_from_u64(1);
+        assert_eq!(generator.generate(rng).get_noise_penalty(), None);
+        let individual = penalised.generate(rng);
+        let (weight, target_harmonicity) = individual.get_noise_penalty().unwrap();
+        assert_eq!(weight, 0.5);
+        assert!((target_harmonicity - target.harmonicity(None)).abs() < 1e-6);
+
+        let signal = individual.to_signal();
+        let unpenalised = individual.evaluate_fitness(&signal, &individual.get_fitness_type());
+        let deviation = (signal.harmonicity(individual.get_fundamental()) - target_harmonicity).abs();
+        assert!((individual.fitness() - (unpenalised - 0.5 * deviation).max(0.0)).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_fitness_type_spectrum_config() {
+        let base = SpectrumConfig { window: WindowFunction::Hann, band: Some((20.0, 20_000.0)), ..Default::default() };
+        assert_eq!(FitnessType::freq_domain_mse().spectrum_config(base.clone()), base);
+        assert_eq!(FitnessType::SpectralSNR.spectrum_config(base.clone()), base);
+
+        let limited = FitnessType::FreqDomainMSE { limit: Some((20.0, 8_000.0)), window: None };
+        assert_eq!(limited.spectrum_config(base.clone()), SpectrumConfig { band: Some((20.0, 8_000.0)), ..base.clone() });
+        let windowed = FitnessType::FreqDomainMSE { limit: None, window: Some(WindowFunction::Rect) };
+        assert_eq!(windowed.spectrum_config(base.clone()), SpectrumConfig { window: WindowFunction::Rect, ..base });
+
+        // the overrides only apply to the metric carrying them
+        let target = sine_wave(440.0, 1.0, SAMPLE_RATE as f32, 1.0, 0.0);
+        let individual = SubtractiveIndividual::new_generator()
+            .target(Arc::new(target.clone()))
+            .fitness_type(limited.clone())
+            .oscillator()
+            .generate(&mut thread_rng());
+        let signal = individual.to_signal();
+        let expected = individual.freq_domain_mse_fitness_with(&signal, &limited.spectrum_config(individual.get_spectrum_config()));
+        assert_eq!(individual.fitness(), expected);
+    }
+
+    #[test]
+    fn test_time_domain_mae_and_nrmse_fitness() {
+        let target = sine_wave(440.0, 1.0, SAMPLE_RATE as f32, 1.0, 0.0);
+        let individual = SubtractiveIndividual::new_generator()
+            .target(Arc::new(target.clone()))
+            .fitness_type(FitnessType::TimeDomainMAE)
+            .oscillator()
+            .generate(&mut thread_rng());
+
+        for fitness_type in [FitnessType::TimeDomainMAE, FitnessType::TimeDomainNRMSE] {
+            let perfect = individual.evaluate_fitness(&target, &fitness_type);
+            let worse = individual.evaluate_fitness(&target.scale_amp(0.5), &fitness_type);
+            let silent = individual.evaluate_fitness(&Signal::default(), &fitness_type);
+            assert_eq!(perfect, 1.0);
+            assert!(perfect > worse && worse > silent, "{fitness_type:?}: {perfect}, {worse}, {silent}");
+        }
+        assert_eq!(individual.evaluate_fitness(&target.scale_amp(0.5), &FitnessType::TimeDomainNRMSE), 0.5);
+    }
+
+    #[test]
+    fn test_envelope_mse_fitness() {
+        // a sine decaying exponentially with a time constant of 1 s
+        let decaying = |phase| Signal::from_samples(&sine_wave(440.0, LENGTH, SAMPLE_RATE as f32, 1.0, phase)
+            .samples()
+            .iter()
+            .enumerate()
+            .map(|(i, s)| s * (-(i as f32) / SAMPLE_RATE as f32).exp())
+            .collect::<Vec<f32>>());
+        let target = decaying(0.0);
+        let rms = (target.samples().iter().map(|s| s.powi(2)).sum::<f32>() / target.n_samples() as f32).sqrt();
+        let individual = SubtractiveIndividual::new_generator()
+            .target(Arc::new(target.clone()))
+            .oscillator()
+            .generate(&mut thread_rng());
+
+        // both candidates are sines of the target's frequency with the same energy, but only one of
+        // them decays like it
+        let (decaying, constant) = (decaying(PI / 2.0), sine_wave(440.0, LENGTH, SAMPLE_RATE as f32, rms * 2f32.sqrt(), 0.0));
+        let envelope = FitnessType::EnvelopeMSE { window_ms: 20.0 };
+        assert_eq!(individual.evaluate_fitness(&target, &envelope), 1.0);
+        assert!(individual.evaluate_fitness(&decaying, &envelope) > 0.9, "{}", individual.evaluate_fitness(&decaying, &envelope));
+        assert!(individual.evaluate_fitness(&constant, &envelope) < 0.5, "{}", individual.evaluate_fitness(&constant, &envelope));
+
+        // which also holds when combined with a spectral metric
+        let weighted = FitnessType::Weighted(vec![(FitnessType::freq_domain_mse(), 0.5), (envelope, 0.5)]);
+        assert!(individual.evaluate_fitness(&decaying, &weighted) > individual.evaluate_fitness(&constant, &weighted));
+    }
+
+    #[test]
+    fn test_seed_frequency_from_target() {
+        let build = |generator: SubtractiveIndividualGenerator| -> GASimulation<SubtractiveIndividual> {
+            GASimulationBuilder::new()
+                .initial_population(30)
+                .generator(generator)
+                .seed(2)
+                .build()
+                .unwrap()
+        };
+
+        let random = build(SubtractiveIndividual::new_generator()
+            .target_file("audio_samples/440hz_sine.wav")
+            .oscillator());
+        let seeded = build(SubtractiveIndividual::new_generator()
+            .seed_frequency_from_target(50.0)
+            .target_file("audio_samples/440hz_sine.wav")
+            .oscillator());
+
+        // all the seeded fundamentals lie within 50 cents of the target's
+        assert!(seeded.population.iter()
+            .all(|i| (i.get_fundamental().unwrap() - 440.0).abs() < 440.0 * 0.03));
+
+        let mean_fitness = |sim: &GASimulation<SubtractiveIndividual>| {
+            mean(&sim.population.iter().map(|i| i.fitness()).collect_vec())
+        };
+        assert!(mean_fitness(&seeded) > 1.5 * mean_fitness(&random),
+                "seeded: {}, random: {}", mean_fitness(&seeded), mean_fitness(&random));
+    }
+
+    #[test]
+    fn test_constant_population() {
+        let target = Signal::default();
+        let generator = SubtractiveIndividual::new_generator()
+            .target(Arc::new(target.clone()))
+            .oscillator();
+
+        let mut simulation: GASimulation<SubtractiveIndividual> = GASimulationBuilder::new()
+            .initial_population(100)
+            .n_random_additions(4)
+            .population_evolution(PopulationEvolution::Constant)
+            .target(Signal::default())
+            .generator(generator)
+            .build().unwrap();
 
         assert_eq!(simulation.population.len(), 100);
-        simulation.next().unwrap();
+        simulation.step().unwrap();
         assert_eq!(simulation.population.len(), 100);
-        simulation.next().unwrap();
+        simulation.step().unwrap();
         assert_eq!(simulation.population.len(), 100);
-        simulation.next().unwrap();
+        simulation.step().unwrap();
         assert_eq!(simulation.population.len(), 100);
     }
+
+    /// Individual with a single gene whose fitness calculations are counted by its generator.
+    #[derive(Clone, Debug, Serialize, Deserialize)]
+    struct CountingIndividual {
+        gene: f32,
+        fitness: Option<f32>,
+        #[serde(skip)]
+        target: Arc<Signal>,
+        #[serde(skip)]
+        evaluations: Arc<AtomicUsize>,
+    }
+
+    #[derive(Clone, Serialize, Deserialize)]
+    struct CountingGenerator {
+        #[serde(skip)]
+        target: Arc<Signal>,
+        #[serde(skip)]
+        evaluations: Arc<AtomicUsize>,
+    }
+
+    impl CountingGenerator {
+        fn evaluations(&self) -> usize {
+            self.evaluations.load(atomic::Ordering::SeqCst)
+        }
+    }
+
+    impl CountingIndividual {
+        fn child(&self, gene: f32) -> Self {
+            Self { gene, fitness: None, ..self.clone() }.include_fitness()
+        }
+    }
+
+    impl PartialEq for CountingIndividual {
+        fn eq(&self, other: &Self) -> bool {
+            self.fitness() == other.fitness()
+        }
+    }
+
+    impl Eq for CountingIndividual {}
+
+    impl PartialOrd for CountingIndividual {
+        fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+            Some(self.cmp(other))
+        }
+    }
+
+    impl Ord for CountingIndividual {
+        fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+            self.fitness().total_cmp(&other.fitness())
+        }
+    }
+
+    impl IndividualGenerator<CountingIndividual> for CountingGenerator {
+        fn new() -> Self {
+            Self { target: Arc::new(Signal::default()), evaluations: Arc::default() }
+        }
+
+        fn generate_unevaluated<R: Rng + ?Sized>(&self, rng: &mut R) -> CountingIndividual {
+            CountingIndividual {
+                gene: rng.gen(),
+                fitness: None,
+                target: Arc::clone(&self.target),
+                evaluations: Arc::clone(&self.evaluations),
+            }
+        }
+
+        fn target(self, target: Arc<Signal>) -> Self {
+            Self { target, ..self }
+        }
+
+        fn fitness_type(self, _fitness_type: FitnessType) -> Self { self }
+        fn zcr_prefilter(self, _max_ratio: f32) -> Self { self }
+        fn noise_penalty(self, _weight: f32) -> Self { self }
+        fn analysis_window(self, _window: WindowFunction) -> Self { self }
+        fn analysis_band(self, _min_hz: f32, _max_hz: f32) -> Self { self }
+        fn perceptual_weighting(self, _weighting: Weighting) -> Self { self }
+        fn analysis_length(self, _n_samples: usize) -> Self { self }
+        fn analysis_averaging(self, _averaged: bool) -> Self { self }
+        fn seed_frequency_from_target(self, _spread_cents: f32) -> Self { self }
+        fn log_frequency(self, _enabled: bool) -> Self { self }
+
+        fn get_target(&self) -> Arc<Signal> {
+            Arc::clone(&self.target)
+        }
+
+        fn get_spectrum_config(&self) -> SpectrumConfig {
+            SpectrumConfig::default()
+        }
+    }
+
+    impl Individual for CountingIndividual {
+        type Generator = CountingGenerator;
+
+        fn new_generator() -> Self::Generator {
+            CountingGenerator::new()
+        }
+
+        fn get_target(&self) -> Arc<Signal> {
+            Arc::clone(&self.target)
+        }
+
+        fn with_target(self, target: Arc<Signal>) -> Self {
+            Self { target, ..self }
+        }
+
+        fn get_fitness(&self) -> Option<f32> {
+            self.fitness
+        }
+
+        fn get_fitness_type(&self) -> FitnessType {
+            FitnessType::default()
+        }
+
+        fn get_zcr_bound(&self) -> Option<f32> {
+            None
+        }
+
+        fn get_noise_penalty(&self) -> Option<(f32, f32)> {
+            None
+        }
+
+        fn get_spectrum_config(&self) -> SpectrumConfig {
+            SpectrumConfig::default()
+        }
+
+        fn calculate_fitness(&self) -> f32 {
+            self.evaluations.fetch_add(1, atomic::Ordering::SeqCst);
+            1.0 - (self.gene - 0.5).abs()
+        }
+
+        fn include_fitness(mut self) -> Self {
+            self.fitness = Some(self.calculate_fitness());
+            self
+        }
+
+        fn crossover<R: Rng + ?Sized>(&self, other: &Self, r: f32, operator: CrossoverOperator, mutation: MutationOperator, rng: &mut R) -> Vec<Self> {
+            let (a, b) = operator.combine(&[self.gene], &[other.gene], r, mutation, |_, rng: &mut R| rng.gen(), |_| (0.0, 1.0), rng);
+            vec![self.child(a[0]), self.child(b[0])]
+        }
+
+        fn to_signal(&self) -> Signal {
+            Signal::default()
+        }
+
+        fn evolve<R: Rng + ?Sized>(&self, step_size: f32, rng: &mut R) -> Self {
+            self.child((self.gene + rng.gen_range(-step_size..=step_size)).clamp(0.0, 1.0))
+        }
+
+        fn dbg(&self) -> String {
+            format!("FITNESS: {:?}, gene: {}", self.fitness, self.gene)
+        }
+
+        fn get_fundamental(&self) -> Option<f32> {
+            None
+        }
+
+        fn distance(&self, other: &Self) -> f32 {
+            (self.gene - other.gene).abs()
+        }
+    }
+
+    #[test]
+    fn test_fitness_calculated_once() {
+        let generator = CountingIndividual::new_generator();
+        let mut simulation: GASimulation<CountingIndividual> = GASimulationBuilder::new()
+            .initial_population(20)
+            .n_random_additions(3)
+            .seed(2)
+            .generator(generator.clone())
+            .build()
+            .unwrap();
+        assert_eq!(generator.evaluations(), 20);
+
+        // only the random additions and the offspring are evaluated, never during the sorts
+        for _ in 0..5 {
+            let before = generator.evaluations();
+            simulation.step().unwrap();
+            assert_eq!(generator.evaluations() - before, 3 + simulation.offspring as usize);
+        }
+    }
+
+    #[test]
+    fn test_evaluate_population() {
+        let generator = CountingIndividual::new_generator();
+        let mut simulation: GASimulation<CountingIndividual> = GASimulationBuilder::new()
+            .initial_population(10)
+            .seed(5)
+            .generator(generator.clone())
+            .build()
+            .unwrap();
+
+        let mut rng = StdRng::seed_from_u64(5);
+        simulation.population.extend((0..1_000).map(|_| generator.generate_unevaluated(&mut rng)));
+        assert_eq!(generator.evaluations(), 10);
+
+        // only the additions are evaluated, and every individual ends up with its fitness
+        simulation.evaluate_population();
+        assert_eq!(generator.evaluations(), 1_010);
+        assert!(simulation.population.iter().all(|i| i.get_fitness().is_some()));
+    }
+
+    #[test]
+    fn test_gaussian_mutation() {
+        let generator = SubtractiveIndividual::new_generator()
+            .target(Arc::new(sine_wave(440.0, 1.0, SAMPLE_RATE as f32, 1.0, 0.0)))
+            .oscillator();
+        let late_variance = |mutation: MutationOperator| {
+            let mut simulation: GASimulation<SubtractiveIndividual> = GASimulationBuilder::new()
+                .initial_population(20)
+                .n_random_additions(0)
+                .max_generations(30)
+                .mutation_rate(0.2)
+                .mutation(mutation)
+                .seed(4)
+                .generator(generator.clone())
+                .build().unwrap();
+            simulation.run().unwrap();
+
+            let fundamentals = simulation.population.iter().filter_map(|i| i.get_fundamental()).collect_vec();
+            std(&fundamentals)
+        };
+
+        // replaced fundamentals keep landing anywhere in the audible range, while perturbed ones
+        // stay around those of their parents
+        let replace = late_variance(MutationOperator::Replace);
+        let gaussian = late_variance(MutationOperator::Gaussian { sigma_fraction: 0.01 });
+        assert!(gaussian < replace / 4.0, "{gaussian} {replace}");
+    }
+
+    #[test]
+    fn test_local_search() {
+        let generator = SubtractiveIndividual::new_generator()
+            .target(Arc::new(sine_wave(440.0, 1.0, SAMPLE_RATE as f32, 1.0, 0.0)))
+            .oscillator();
+        let fittest_after = |builder: GASimulationBuilder<SubtractiveIndividual>, seed: u64| {
+            let mut simulation = builder
+                .initial_population(10)
+                .max_generations(10)
+                .seed(seed)
+                .generator(generator.clone())
+                .build().unwrap();
+            simulation.run().unwrap();
+            (simulation.population[0].fitness(), simulation.local_search_evaluations)
+        };
+
+        // refining the two fittest individuals costs 20 extra evaluations per generation
+        let (plain, evaluations) = fittest_after(GASimulationBuilder::new(), 2);
+        assert_eq!(evaluations, 0);
+        let (memetic, evaluations) = fittest_after(GASimulationBuilder::new().local_search(10, 0.1, 2), 2);
+        assert_eq!(evaluations, 20);
+        assert!(memetic > plain, "{memetic} {plain}");
+    }
+
+    #[test]
+    fn test_silent_target() {
+        let fitness_types = [
+            FitnessType::freq_domain_mse(),
+            FitnessType::time_domain_euclidean(),
+            FitnessType::TimeDomainMAE,
+            FitnessType::TimeDomainNRMSE,
+            FitnessType::SpectralSNR,
+            FitnessType::ItakuraSaito,
+            FitnessType::SpectralCosine,
+            FitnessType::EnvelopeMSE { window_ms: 20.0 },
+            FitnessType::Weighted(vec![(FitnessType::TimeDomainNRMSE, 0.5), (FitnessType::SpectralSNR, 0.5)]),
+        ];
+
+        for fitness_type in fitness_types {
+            let generator = SubtractiveIndividual::new_generator()
+                .target(Arc::new(Signal::from_samples(&[0.0; SAMPLE_RATE as usize])))
+                .fitness_type(fitness_type.clone())
+                .oscillator();
+            let mut simulation: GASimulation<SubtractiveIndividual> = GASimulationBuilder::new()
+                .initial_population(10)
+                .max_generations(3)
+                .seed(1)
+                .generator(generator)
+                .build().unwrap();
+
+            let fittest = simulation.run().unwrap();
+            assert!(fittest.fitness().is_finite(), "{fitness_type:?}");
+        }
+    }
+
+    #[test]
+    fn test_invalid_fitness() {
+        let mut simulation: GASimulation<CountingIndividual> = GASimulationBuilder::new()
+            .initial_population(10)
+            .seed(1)
+            .generator(CountingIndividual::new_generator())
+            .build().unwrap();
+        for individual in &mut simulation.population {
+            individual.fitness = Some(f32::NAN);
+        }
+
+        // the survivors keep their fitness, which is reported instead of panicking while sorting
+        assert!(matches!(simulation.step(), Err(GeneticSimulationError::InvalidFitness { .. })));
+    }
 }
@@ -0,0 +1,138 @@
+use std::sync::Arc;
+use serde::{Serialize, Deserialize};
+use crate::signal_processing::Signal;
+use crate::simulation::algorithms::genetic::Individual;
+
+/// The fittest distinct individuals found during a run, sorted by decreasing fitness. An
+/// individual closer than the minimum distance to a fitter member is considered a near-duplicate
+/// of it and rejected, so that the members are alternatives that sound different from each other.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(bound = "")]
+pub struct HallOfFame<T: Individual> {
+    /// Maximum number of individuals kept.
+    pub capacity: usize,
+    /// Genotype distance below which two individuals are considered near-duplicates.
+    pub min_distance: f32,
+    individuals: Vec<T>,
+}
+
+impl<T: Individual> HallOfFame<T> {
+    /// Creates an empty hall of fame keeping up to `capacity` individuals at least `min_distance`
+    /// apart from each other.
+    pub fn new(capacity: usize, min_distance: f32) -> Self {
+        Self { capacity, min_distance, individuals: Vec::with_capacity(capacity) }
+    }
+
+    /// The members, sorted by decreasing fitness.
+    pub fn individuals(&self) -> &[T] {
+        &self.individuals
+    }
+
+    /// Number of members.
+    pub fn len(&self) -> usize {
+        self.individuals.len()
+    }
+
+    /// Whether there are no members yet.
+    pub fn is_empty(&self) -> bool {
+        self.individuals.is_empty()
+    }
+
+    /// Inserts a copy of the candidate if it is fitter than the least fit member of a full hall of
+    /// fame and than every near-duplicate of it, which it then replaces. Returns whether it was
+    /// inserted.
+    pub fn insert(&mut self, candidate: &T) -> bool {
+        let fitness = candidate.fitness();
+        let full = self.individuals.len() >= self.capacity;
+        if full && self.individuals.last().is_none_or(|worst| fitness <= worst.fitness()) {
+            return false;
+        }
+
+        let near = |member: &T| member.distance(candidate) < self.min_distance;
+        if self.individuals.iter().any(|member| near(member) && member.fitness() >= fitness) {
+            return false;
+        }
+
+        self.individuals.retain(|member| !near(member));
+        let position = self.individuals.partition_point(|member| member.fitness() >= fitness);
+        self.individuals.insert(position, candidate.clone());
+        self.individuals.truncate(self.capacity);
+        true
+    }
+
+    /// Considers the individuals of a population sorted by decreasing fitness, stopping at the
+    /// first one that could not enter a full hall of fame.
+    pub fn update(&mut self, population: &[T]) {
+        for candidate in population {
+            let full = self.individuals.len() >= self.capacity;
+            if full && self.individuals.last().is_none_or(|worst| candidate.fitness() <= worst.fitness()) {
+                break;
+            }
+            self.insert(candidate);
+        }
+    }
+
+    /// Pairs each member with the given target signal again, e.g. after resuming a checkpoint.
+    pub(crate) fn with_target(self, target: Arc<Signal>) -> Self {
+        Self {
+            individuals: self.individuals.into_iter().map(|i| i.with_target(Arc::clone(&target))).collect(),
+            ..self
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::signal_processing::components::oscillator::sine_wave;
+    use crate::signal_processing::SAMPLE_RATE;
+    use crate::simulation::algorithms::genetic::IndividualGenerator;
+    use crate::simulation::synthesis_methods::subtractive::SubtractiveIndividual;
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    fn population(n: usize) -> Vec<SubtractiveIndividual> {
+        let generator = SubtractiveIndividual::new_generator()
+            .target(Arc::new(sine_wave(440.0, 1.0, SAMPLE_RATE as f32, 1.0, 0.0)))
+            .oscillator();
+        let mut rng = StdRng::seed_from_u64(3);
+        let mut population = (0..n).map(|_| generator.generate(&mut rng)).collect::<Vec<_>>();
+        population.sort_by(|a, b| b.cmp(a));
+        population
+    }
+
+    #[test]
+    fn test_capacity() {
+        let population = population(20);
+        let mut hall_of_fame = HallOfFame::new(5, 1e-6);
+        hall_of_fame.update(&population);
+
+        assert_eq!(hall_of_fame.len(), 5);
+        assert_eq!(hall_of_fame.individuals(), &population[..5]);
+
+        // the least fit individual can't enter a full hall of fame, but the fittest one can
+        assert!(!hall_of_fame.insert(population.last().unwrap()));
+        let mut fitter = HallOfFame::new(5, 1e-6);
+        fitter.update(&population[1..]);
+        assert!(fitter.insert(&population[0]));
+        assert_eq!(fitter.individuals(), &population[..5]);
+    }
+
+    #[test]
+    fn test_duplicate_rejection() {
+        let population = population(10);
+        let mut hall_of_fame = HallOfFame::new(5, 1e-6);
+
+        // copies of the same individual only take one place
+        assert!(hall_of_fame.insert(&population[1]));
+        assert!(!hall_of_fame.insert(&population[1]));
+        assert_eq!(hall_of_fame.len(), 1);
+
+        // a fitter near-duplicate replaces the individuals it is close to
+        let mut far_apart = HallOfFame::new(5, f32::INFINITY);
+        far_apart.update(&population[1..]);
+        assert_eq!(far_apart.individuals(), &population[1..2]);
+        assert!(far_apart.insert(&population[0]));
+        assert_eq!(far_apart.individuals(), &population[..1]);
+    }
+}
@@ -1,22 +1,62 @@
 use std::rc::Rc;
 use std::sync::Arc;
-use rand::prelude::ThreadRng;
-use rand::Rng;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::fs;
+use std::fs::File;
+use std::io::{BufReader, BufWriter};
+use std::collections::VecDeque;
+use std::fmt::Debug;
+use std::ops::ControlFlow;
+use std::path::Path;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng, thread_rng};
+use rand_chacha::ChaCha8Rng;
 use crate::error::HillClimbingSimulationError;
 use crate::simulation::algorithms::genetic::{Individual, IndividualGenerator};
 use crate::signal_processing::Signal;
+use crate::signal_processing::signal_analysis::SpectrumConfig;
 use crate::{FitnessType};
 use crate::analytics::{IterationRow, Recorder};
+use crate::simulation::algorithms::TerminationReason;
+use crate::simulation::algorithms::batch::numbered;
+use rayon::prelude::*;
+use anyhow::Result;
+use log::{debug, info, trace};
+use serde::{Serialize, Deserialize};
 
+/// Represents a run of the hill climbing algorithm. The whole state of the simulation can be
+/// serialised, so that it can be checkpointed and resumed.
+#[derive(Serialize, Deserialize)]
+#[serde(bound = "")]
 pub struct HillClimbingSimulation<T: Individual> {
-    /// Fittest individual discovered.
+    /// Individual at the top of the current climb.
     pub current_individual: T,
+    /// Fittest individual discovered across all the climbs.
+    pub best_individual: T,
+    /// Generator of the individuals each climb starts from.
+    pub generator: T::Generator,
+    /// Number of times the climb starts again from a new random individual once it terminates.
+    pub restarts: u32,
+    /// Index of the current climb, 0 being the first one.
+    pub restart: u32,
+    /// Maximum number of iterations of each climb, if specified.
+    pub iterations_per_restart: Option<u32>,
+    /// Number of neighbours evaluated on each step of the climb, the fittest of which is compared
+    /// with the current individual.
+    pub neighbours_per_iter: u32,
     /// Number of individuals generated so far (including rejected ones). 
     pub iteration: u32,
-    /// Signal used as target upon which the fitness function is defined.
-    pub target: Signal,
+    /// Signal used as target upon which the fitness function is defined, shared with the
+    /// individuals.
+    pub target: Arc<Signal>,
     /// Step size at the start of the program.
     pub init_step_size: f32,
+    /// Step size of the next iteration.
+    pub step_size: f32,
+    /// How the step size changes after each iteration.
+    pub step_schedule: StepSchedule,
     /// Maximum number of iterations the simulation will run for.
     pub max_iterations: u32,
     /// The minimum step size tolerated. If the step size is lower than this value, the program
@@ -24,23 +64,154 @@ pub struct HillClimbingSimulation<T: Individual> {
     pub min_step_size: f32,
     /// Maximum number of unsuccessful interations the simulation will tolerate.
     pub max_unsuccessful_iters: u32,
+    /// Number of consecutive unsuccessful iterations of the current climb.
+    pub unsuccessful_iters: u32,
+    /// Whether the last iteration moved to its candidate, which late acceptance may do even if
+    /// it is not fitter. False before the first iteration of each climb.
+    pub accepted: bool,
+    /// Fitness of the current individual over the last iterations, oldest first, against which
+    /// candidates are also compared in late acceptance hill climbing. Empty if it is not used.
+    pub fitness_history: VecDeque<f32>,
+    /// Iteration at which the current climb started.
+    pub climb_start: u32,
+    /// Fitness at which the simulation stops, as the fittest individual is good enough, if
+    /// specified.
+    pub fitness_target: Option<f32>,
+    /// Number of iterations after which the fitness of the current individual is compared with
+    /// the candidates, if late acceptance is used.
+    pub late_acceptance: Option<usize>,
     /// Fundamental frequency of the current individual.
     pub fundamental: Option<f32>,
     /// Whether the simulation should be exported to a CSV file and what file name.
     pub csv_export: Option<String>,
     /// Whether the fittest individual shoudl be exported ot a WAV file and what file name.
-    pub signal_export: Option<String>
+    pub signal_export: Option<String>,
+    /// Wall-clock time after which the simulation stops at the end of the current iteration, if
+    /// specified.
+    pub time_limit: Option<Duration>,
+    /// Why the simulation stopped, once it has run.
+    pub termination_reason: Option<TerminationReason>,
+    /// Wall-clock time spent running the simulation, including before it was resumed.
+    pub elapsed: Duration,
+    /// Every how many iterations the whole simulation is saved and to what file, if specified.
+    pub checkpoint: Option<(u32, String)>,
+    /// Called with a summary of every iteration while the simulation runs, if specified. It is
+    /// not stored in checkpoints, so it has to be set again on resumed simulations.
+    #[serde(skip)]
+    pub on_iteration: Option<IterationCallback>,
+    /// Flag checked before every iteration, which stops the simulation gracefully once it is set,
+    /// if specified. It is not stored in checkpoints.
+    #[serde(skip)]
+    pub cancel_token: Option<Arc<AtomicBool>>,
+    /// Seed of the random number generator, which determines the whole run of the simulation.
+    pub seed: u64,
+    /// Random number generator from which every random decision of the simulation is drawn. Its
+    /// state is stored in checkpoints, so that resumed runs stay reproducible.
+    rng: ChaCha8Rng,
+}
+
+/// Outcome of running a hill climbing simulation.
+#[derive(Clone, Debug)]
+pub struct HillClimbReport<T: Individual> {
+    /// The fittest individual discovered.
+    pub fittest: T,
+    /// The state of every iteration run, starting with the initial individual, whether or not
+    /// they are exported to a CSV file.
+    pub history: Vec<IterationRow>,
+    /// Number of iterations run, which may be fewer than the maximum if it stopped early.
+    pub iterations: u32,
+    /// Wall-clock time spent running the simulation, including before it was resumed.
+    pub elapsed: Duration,
+    /// Why the simulation stopped.
+    pub termination_reason: TerminationReason,
+}
+
+/// Snapshot of an iteration passed to the callback of `HillClimberBuilder::on_iteration`.
+#[derive(Clone, Debug)]
+pub struct IterationSummary {
+    /// Number of candidates evaluated so far, including the ones of the iteration that has just
+    /// been calculated.
+    pub iteration: u32,
+    /// Fitness of the current individual.
+    pub fitness: f32,
+    /// Step size of the next iteration.
+    pub step_size: f32,
+    /// Whether the climb moved to the fittest neighbour of the iteration, which is fitter than the
+    /// previous individual unless late acceptance is used.
+    pub accepted: bool,
+    /// Fundamental frequency of the current individual.
+    pub fundamental: Option<f32>,
+}
+
+/// Callback run after every iteration of a hill climbing simulation, which stops it early by
+/// returning `ControlFlow::Break`. Clones of a builder share the same callback.
+pub struct IterationCallback(Arc<Mutex<IterationFn>>);
+
+type IterationFn = dyn FnMut(&IterationSummary) -> ControlFlow<()> + Send;
+
+impl IterationCallback {
+    /// Calls the callback with the summary of an iteration.
+    pub fn call(&self, summary: &IterationSummary) -> ControlFlow<()> {
+        let mut callback = self.0.lock().expect("The iteration callback should not have panicked");
+        callback(summary)
+    }
+}
+
+impl Clone for IterationCallback {
+    fn clone(&self) -> Self {
+        Self(Arc::clone(&self.0))
+    }
+}
+
+impl Debug for IterationCallback {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("IterationCallback")
+    }
 }
 
+#[derive(Clone)]
 pub struct HillClimberBuilder<T: Individual> {
     pub generator: Option<T::Generator>,
     pub target: Option<Arc<Signal>>,
     pub init_step_size: f32,
+    pub step_schedule: StepSchedule,
     pub max_iterations: u32,
     pub min_step_size: f32,
     pub max_unsuccessful_iters: u32,
+    pub fitness_target: Option<f32>,
+    pub late_acceptance: Option<usize>,
+    pub restarts: u32,
+    pub iterations_per_restart: Option<u32>,
+    pub neighbours_per_iter: u32,
     pub csv_export: Option<String>,
     pub signal_export: Option<String>,
+    pub time_limit: Option<Duration>,
+    pub checkpoint: Option<(u32, String)>,
+    pub on_iteration: Option<IterationCallback>,
+    pub cancel_token: Option<Arc<AtomicBool>>,
+    pub seed: Option<u64>,
+}
+
+/// Outcome of running several hill climbing simulations from different starting points.
+#[derive(Clone, Debug)]
+pub struct MultiStartReport<T: Individual> {
+    /// The fittest individual discovered by any of the climbers.
+    pub fittest: T,
+    /// A summary of each climber, in the order of their numbered export directories.
+    pub starts: Vec<StartSummary>,
+    /// Seed from which the seeds of all the climbers are drawn.
+    pub seed: u64,
+}
+
+/// Summary of one of the climbers of a multi-start run.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct StartSummary {
+    /// Fitness of the fittest individual the climber discovered.
+    pub fitness: f32,
+    /// Number of iterations the climber ran.
+    pub iterations: u32,
+    /// Why the climber stopped.
+    pub termination_reason: TerminationReason,
 }
 
 // impl<T: Individual> Simulation for HillClimbingSimulation<T> {
@@ -55,11 +226,22 @@ impl<T: Individual> Default for HillClimberBuilder<T> {
             generator: None,
             target: None,
             init_step_size: 1.0,
+            step_schedule: StepSchedule::default(),
             max_iterations: 3000,
             min_step_size: 0.0001,
             max_unsuccessful_iters: 5000,
+            fitness_target: None,
+            late_acceptance: None,
+            restarts: 0,
+            iterations_per_restart: None,
+            neighbours_per_iter: 1,
             csv_export: None,
             signal_export: None,
+            time_limit: None,
+            checkpoint: None,
+            on_iteration: None,
+            cancel_token: None,
+            seed: None,
         }
     }
 }
@@ -69,30 +251,61 @@ impl<T: Individual> HillClimberBuilder<T> {
         Self::default()
     }
 
-    pub fn build(self) -> HillClimbingSimulation<T> {
-        let generator = self.generator.expect("Generator expected");
-        let current_individual = generator.generate();
-        let target_rc = self.target
-            .expect("Expected a reference counter to the target signal.");
-        let target = Signal::clone(&*target_rc);
+    /// Builds the hill climbing simulation, checking that its parameters are consistent with each
+    /// other.
+    pub fn build(self) -> Result<HillClimbingSimulation<T>, HillClimbingSimulationError> {
+        self.validate()?;
+        let mut generator = self.generator.ok_or(HillClimbingSimulationError::GeneratorMissing)?;
+        let target = self.target.ok_or(HillClimbingSimulationError::TargetMissing)?;
+        if !Arc::ptr_eq(&target, &generator.get_target()) {
+            generator = generator.target(Arc::clone(&target));
+        }
+        let seed = self.seed.unwrap_or_else(|| thread_rng().gen());
+        let mut rng = ChaCha8Rng::seed_from_u64(seed);
+        let current_individual = generator.generate(&mut rng);
+        let fitness_history = self.late_acceptance
+            .map_or(VecDeque::new(), |len| VecDeque::from(vec![current_individual.fitness(); len]));
 
-        HillClimbingSimulation {
+        Ok(HillClimbingSimulation {
+            best_individual: current_individual.clone(),
             current_individual,
+            generator,
+            restarts: self.restarts,
+            restart: 0,
+            iterations_per_restart: self.iterations_per_restart,
+            neighbours_per_iter: self.neighbours_per_iter,
             target,
             iteration: 0,
             init_step_size: self.init_step_size,
+            step_size: self.init_step_size,
+            step_schedule: self.step_schedule,
             max_iterations: self.max_iterations,
             min_step_size: self.min_step_size,
             max_unsuccessful_iters: self.max_unsuccessful_iters,
+            unsuccessful_iters: 0,
+            accepted: false,
+            fitness_history,
+            climb_start: 0,
+            fitness_target: self.fitness_target,
+            late_acceptance: self.late_acceptance,
             fundamental: None,
             csv_export: self.csv_export,
             signal_export: self.signal_export,
-        }
+            time_limit: self.time_limit,
+            termination_reason: None,
+            elapsed: Duration::ZERO,
+            checkpoint: self.checkpoint,
+            on_iteration: self.on_iteration,
+            cancel_token: self.cancel_token,
+            seed,
+            rng,
+        })
     }
 
-    /// Takes an individual generator than specifies the component layout.
+    /// Takes an individual generator than specifies the component layout, whose target is used
+    /// unless one has been specified with `target`.
     pub fn generator(mut self, generator: T::Generator) -> Self {
-        self.target = Some(generator.get_target());
+        self.target = self.target.or_else(|| Some(generator.get_target()));
         self.generator = Some(generator);
         self
     }
@@ -109,18 +322,67 @@ impl<T: Individual> HillClimberBuilder<T> {
         self
     }
 
-    /// Specifies the target signal.
+    /// Specifies the target signal, which replaces the target of the generator.
     pub fn target(mut self, target: Signal) -> Self {
         self.target = Some(target.into());
         self
     }
 
+    /// Checks the parameters that would otherwise make the simulation fail once it runs.
+    pub(crate) fn validate(&self) -> Result<(), HillClimbingSimulationError> {
+        if !(self.init_step_size > 0.0 && self.init_step_size.is_finite()) {
+            return Err(HillClimbingSimulationError::InvalidStepSize(self.init_step_size));
+        }
+        if self.min_step_size >= self.init_step_size {
+            return Err(HillClimbingSimulationError::InvalidMinStepSize {
+                min_step_size: self.min_step_size,
+                init_step_size: self.init_step_size,
+            });
+        }
+        if self.max_iterations == 0 {
+            return Err(HillClimbingSimulationError::InvalidMaxIterations);
+        }
+        if !self.step_schedule.is_valid() {
+            return Err(HillClimbingSimulationError::InvalidStepSchedule(self.step_schedule));
+        }
+        if self.late_acceptance == Some(0) {
+            return Err(HillClimbingSimulationError::InvalidLateAcceptance);
+        }
+        if self.iterations_per_restart == Some(0) {
+            return Err(HillClimbingSimulationError::InvalidIterationsPerRestart);
+        }
+        if self.neighbours_per_iter == 0 {
+            return Err(HillClimbingSimulationError::InvalidNeighboursPerIter);
+        }
+        if self.checkpoint.as_ref().is_some_and(|(n, _)| *n == 0) {
+            return Err(HillClimbingSimulationError::InvalidCheckpointInterval);
+        }
+
+        if let Some(config) = self.generator.as_ref().map(|generator| generator.get_spectrum_config()) {
+            if let Some((min_hz, max_hz)) = config.band.filter(|&band| !SpectrumConfig::is_valid_band(band)) {
+                return Err(HillClimbingSimulationError::InvalidAnalysisBand { min_hz, max_hz });
+            }
+            if !SpectrumConfig::is_valid_length(config.length) {
+                return Err(HillClimbingSimulationError::InvalidAnalysisLength(config.length));
+            }
+        }
+
+        Ok(())
+    }
+
     /// Specifies the initial step size of the hill-climbing algorithm.
     pub fn init_step_size(mut self, init_step_size: f32) -> Self {
         self.init_step_size = init_step_size;
         self
     }
 
+    /// Specifies how the step size changes after each iteration. Defaults to an adaptive schedule
+    /// that keeps the step after a success and multiplies it by 0.95 after a failure.
+    pub fn step_schedule(mut self, step_schedule: StepSchedule) -> Self {
+        self.step_schedule = step_schedule;
+        self
+    }
+
     /// Specifies the maximum number of generations the simulation will run for.
     pub fn max_iterations(mut self, max_iterations: u32) -> Self {
         self.max_iterations = max_iterations;
@@ -139,73 +401,962 @@ impl<T: Individual> HillClimberBuilder<T> {
         self.max_unsuccessful_iters = max_unsuccessful_iters;
         self
     }
+
+    /// Stops the simulation as soon as an individual with at least the given fitness is found.
+    pub fn fitness_target(mut self, fitness: f32) -> Self {
+        self.fitness_target = Some(fitness);
+        self
+    }
+
+    /// Uses late acceptance hill climbing, which also moves to a candidate that is fitter than the
+    /// current individual was `history_len` iterations ago, even if it is not fitter than the
+    /// current one, so that the climb can cross plateaus and narrow valleys. The termination
+    /// criteria still count the iterations that do not improve on the current individual.
+    pub fn late_acceptance(mut self, history_len: usize) -> Self {
+        self.late_acceptance = Some(history_len);
+        self
+    }
+
+    /// Starts climbing again from a new random individual up to `n` times, whenever a climb
+    /// terminates because its step size or its unsuccessful iterations reached their limits, or
+    /// it ran for the iterations given by `iterations_per_restart`. The fittest individual across
+    /// all the climbs is returned. Disabled by default.
+    pub fn restarts(mut self, n: u32) -> Self {
+        self.restarts = n;
+        self
+    }
+
+    /// Limits the number of iterations of each climb, after which it restarts if any restarts are
+    /// left, or the simulation terminates otherwise.
+    pub fn iterations_per_restart(mut self, iterations: u32) -> Self {
+        self.iterations_per_restart = Some(iterations);
+        self
+    }
+
+    /// Evaluates `k` neighbours of the current individual in parallel on each step of the climb,
+    /// moving to the fittest of them if it is fitter than the current individual. Every neighbour
+    /// counts as an iteration, so that the iteration budgets stay comparable, whereas a whole step
+    /// counts as a single unsuccessful iteration. Defaults to a single neighbour.
+    pub fn neighbours_per_iter(mut self, k: u32) -> Self {
+        self.neighbours_per_iter = k;
+        self
+    }
+
+    /// Stops the simulation once it has been running for the given time, after finishing the
+    /// current iteration and the exports.
+    pub fn time_limit(mut self, time_limit: Duration) -> Self {
+        self.time_limit = Some(time_limit);
+        self
+    }
+
+    /// Saves the whole simulation to `path` in `exports/checkpoints` every `n_iterations` while it
+    /// runs, from which it can be continued with `HillClimbingSimulation::resume` if it is
+    /// interrupted.
+    pub fn checkpoint_every(mut self, n_iterations: u32, path: &str) -> Self {
+        self.checkpoint = Some((n_iterations, path.to_string()));
+        self
+    }
+
+    /// Calls the given function with a summary of every iteration while the simulation runs, after
+    /// it has been recorded for the CSV export. Returning `ControlFlow::Break` stops the simulation
+    /// at that iteration, which is then exported as usual.
+    pub fn on_iteration(
+        mut self,
+        callback: impl FnMut(&IterationSummary) -> ControlFlow<()> + Send + 'static,
+    ) -> Self {
+        self.on_iteration = Some(IterationCallback(Arc::new(Mutex::new(callback))));
+        self
+    }
+
+    /// Stops the simulation before the next iteration once the flag is set, e.g. from another
+    /// thread or by `cancel_on_ctrl_c`, exporting the iterations run so far and the current
+    /// individual as usual.
+    pub fn cancel_token(mut self, token: Arc<AtomicBool>) -> Self {
+        self.cancel_token = Some(token);
+        self
+    }
+
+    /// Seeds the random number generator of the simulation, so that runs with the same seed,
+    /// generator and parameters are reproducible. A random seed is used if not specified.
+    pub fn seed(mut self, seed: u64) -> Self {
+        self.seed = Some(seed);
+        self
+    }
 }
 
 
 impl<T: Individual> HillClimbingSimulation<T> {
+    /// Replaces the current individual with a new random one and resets the step size, so that the
+    /// next iterations climb from somewhere else.
+    fn restart_climb(&mut self) {
+        self.restart += 1;
+        self.current_individual = self.generator.generate(&mut self.rng);
+        self.fundamental = self.current_individual.get_fundamental();
+        self.step_size = self.init_step_size;
+        self.unsuccessful_iters = 0;
+        self.accepted = false;
+        self.climb_start = self.iteration;
+        let fitness = self.current_individual.fitness();
+        self.fitness_history.iter_mut().for_each(|f| *f = fitness);
+        if self.current_individual > self.best_individual {
+            self.best_individual = self.current_individual.clone();
+        }
+        info!("Restart {} of {}", self.restart, self.restarts);
+    }
+
+    /// Runs `n` independent hill climbing simulations with the parameters of the builder in
+    /// parallel, returning the fittest individual across all of them along with a summary of each.
+    /// Each climber draws its own seed from the seed of the builder, so that the whole run is
+    /// reproducible, and its exports are written to a subdirectory named after its number, e.g.
+    /// `climbs/3/iterations.csv` for a CSV export to `climbs/iterations.csv`.
+    pub fn run_multi(builder: HillClimberBuilder<T>, n: u32) -> Result<MultiStartReport<T>, HillClimbingSimulationError>
+    where
+        T::Generator: Clone + Send,
+    {
+        if n == 0 {
+            return Err(HillClimbingSimulationError::InvalidClimberCount);
+        }
+        let seed = builder.seed.unwrap_or_else(|| thread_rng().gen());
+        let mut rng = StdRng::seed_from_u64(seed);
+        let builders: Vec<HillClimberBuilder<T>> = (0..n).map(|start| HillClimberBuilder {
+            csv_export: builder.csv_export.as_deref().map(|path| numbered(path, start)),
+            signal_export: builder.signal_export.as_deref().map(|path| numbered(path, start)),
+            checkpoint: builder.checkpoint.as_ref().map(|(interval, path)| (*interval, numbered(path, start))),
+            seed: Some(rng.gen()),
+            ..builder.clone()
+        }).collect();
+
+        let reports: Vec<HillClimbReport<T>> = builders.into_par_iter()
+            .map(|builder| builder.build()?.run_with_report())
+            .collect::<Result<_, _>>()?;
+
+        let starts = reports.iter().map(|report| StartSummary {
+            fitness: report.fittest.fitness(),
+            iterations: report.iterations,
+            termination_reason: report.termination_reason,
+        }).collect();
+        let fittest = reports.into_iter()
+            .map(|report| report.fittest)
+            .max()
+            .expect("There should be at least one climber");
+
+        Ok(MultiStartReport { fittest, starts, seed })
+    }
+
+    /// Whether the fittest individual discovered has reached the fitness target, if any.
+    fn reached_fitness_target(&self) -> bool {
+        self.fitness_target.is_some_and(|target| self.best_individual.fitness() >= target)
+    }
+
+    /// Evolves `k` neighbours of the current individual, returning the fittest of them. A single
+    /// neighbour draws from the simulation's generator, while several of them are evaluated in
+    /// parallel, each drawing from its own generator seeded in order from the simulation's one.
+    fn fittest_neighbour(&mut self, k: u32) -> T {
+        if k == 1 {
+            return self.current_individual.evolve(self.step_size, &mut self.rng);
+        }
+
+        let seeds: Vec<u64> = (0..k).map(|_| self.rng.gen()).collect();
+        seeds.into_par_iter()
+            .map(|seed| self.current_individual.evolve(self.step_size, &mut StdRng::seed_from_u64(seed)))
+            .collect::<Vec<T>>()
+            .into_iter()
+            .max()
+            .expect("There should be at least one neighbour")
+    }
+
+    /// Runs a hill climbing simulation, returning the fittest individual discovered.
     pub fn run(&mut self) -> Result<T, HillClimbingSimulationError> {
+        self.run_with_report().map(|report| report.fittest)
+    }
+
+    /// Runs a hill climbing simulation, returning the fittest individual discovered along with the
+    /// history of the run.
+    pub fn run_with_report(&mut self) -> Result<HillClimbReport<T>, HillClimbingSimulationError> {
         let mut recorder: Recorder<IterationRow> = Recorder::new();
-        let mut step_size = self.init_step_size;
-        let mut unsuccessful_iters = 0;
+        let first_iteration = self.iteration;
+        let mut history: Vec<IterationRow> = vec![self.into()];
 
-        while self.iteration < self.max_iterations {
+        // a resumed simulation appends its rows to the ones exported up to its checkpoint
+        let mut csv_started = self.iteration > 0;
+        if self.csv_export.is_some() && !csv_started {
+            recorder.add_record(history[0].clone());
+        }
 
-            if step_size < self.min_step_size {
-                println!("Step size too small ({} < {}). Terminating", step_size, self.min_step_size);
-                break;
-            }
+        let start = Instant::now();
+        let previously_elapsed = self.elapsed;
+        self.termination_reason = Some(TerminationReason::MaxIterations);
 
-            if unsuccessful_iters >= self.max_unsuccessful_iters {
-                println!("{} unsuccessful iterations reached. Terminating", unsuccessful_iters);
+        if self.reached_fitness_target() {
+            info!("Fitness target reached by the initial individual. Terminating");
+            self.termination_reason = Some(TerminationReason::FitnessTarget);
+        }
+
+        while self.iteration < self.max_iterations
+            && self.termination_reason != Some(TerminationReason::FitnessTarget) {
+
+            if self.cancel_token.as_ref().is_some_and(|token| token.load(Ordering::SeqCst)) {
+                info!("Cancelled after {} iterations. Terminating", self.iteration);
+                self.termination_reason = Some(TerminationReason::Cancelled);
                 break;
             }
-            
-            // update the record with current state
-            if self.csv_export.is_some() {
-                recorder.add_record(self.into());
+
+            let termination = if self.step_size < self.min_step_size {
+                info!("Step size too small ({} < {})", self.step_size, self.min_step_size);
+                Some(TerminationReason::MinStepSize)
+            } else if self.unsuccessful_iters >= self.max_unsuccessful_iters {
+                info!("{} unsuccessful iterations reached", self.unsuccessful_iters);
+                Some(TerminationReason::UnsuccessfulIterations)
+            } else if self.iterations_per_restart.is_some_and(|n| self.iteration - self.climb_start >= n) {
+                info!("{} iterations of the climb reached", self.iteration - self.climb_start);
+                Some(TerminationReason::MaxIterations)
+            } else {
+                None
+            };
+
+            if let Some(reason) = termination {
+                if self.restart >= self.restarts {
+                    info!("No restarts left. Terminating");
+                    self.termination_reason = Some(reason);
+                    break;
+                }
+
+                // start a new climb from a fresh individual
+                self.restart_climb();
+                let row: IterationRow = self.into();
+                if self.csv_export.is_some() {
+                    recorder.add_record(row.clone());
+                }
+                history.push(row);
+                continue;
             }
 
-            println!("Iteration: {}: {}", self.iteration, self.current_individual.dbg());
+            trace!("Iteration: {}: {}", self.iteration, self.current_individual.dbg());
 
-            let candidate = self.current_individual.evolve(step_size);
+            let k = self.neighbours_per_iter.min(self.max_iterations - self.iteration);
+            let candidate = self.fittest_neighbour(k);
 
-            if candidate.fitness() > self.current_individual.fitness() {
-                // reduce the step size
-                step_size /= 0.95;
-                println!("Step size now {step_size}");
+            // late acceptance also moves to candidates fitter than the individual of a while ago
+            let success = candidate.fitness() > self.current_individual.fitness();
+            let accepted = success || self.fitness_history.front().is_some_and(|&f| candidate.fitness() > f);
+            self.accepted = accepted;
+            self.step_size = self.step_schedule.next(self.step_size, success);
+            trace!("Step size now {}", self.step_size);
 
+            if success {
                 // reset unsuccessful iters
-                unsuccessful_iters = 0;
-                
+                self.unsuccessful_iters = 0;
+            } else {
+                self.unsuccessful_iters += 1;
+            }
+
+            if accepted {
                 // update the current individual
                 self.current_individual = candidate;
                 self.fundamental = self.current_individual.get_fundamental();
-                println!("Current candidate's fitness is {} and params {:?}",
-                         self.current_individual.fitness(),
-                         self.current_individual.dbg()
+                if self.current_individual > self.best_individual {
+                    self.best_individual = self.current_individual.clone();
+                }
+                debug!("Current candidate's fitness is {} and params {:?}",
+                       self.current_individual.fitness(),
+                       self.current_individual.dbg()
                 );
-            } else {
-                unsuccessful_iters += 1;
             }
-            self.iteration += 1;
+            if self.fitness_history.pop_front().is_some() {
+                self.fitness_history.push_back(self.current_individual.fitness());
+            }
+            self.iteration += k;
+            self.elapsed = previously_elapsed + start.elapsed();
+
+            let row: IterationRow = self.into();
+            if self.csv_export.is_some() {
+                recorder.add_record(row.clone());
+            }
+            history.push(row);
+
+            // the rows recorded so far are exported along with the checkpoint, so that both match
+            if let Some((interval, path)) = &self.checkpoint {
+                if (self.iteration - k) / interval < self.iteration / interval {
+                    if let Some(file_name) = &self.csv_export {
+                        Self::export_csv(&mut recorder, file_name, csv_started);
+                        csv_started = true;
+                    }
+                    self.save_checkpoint(path).map_err(|e| HillClimbingSimulationError::CheckpointNotSaved {
+                        details: e.to_string(),
+                    })?;
+                }
+            }
+
+            if let Some(callback) = &self.on_iteration {
+                let summary = IterationSummary {
+                    iteration: self.iteration,
+                    fitness: self.current_individual.fitness(),
+                    step_size: self.step_size,
+                    accepted,
+                    fundamental: self.fundamental,
+                };
+                if callback.call(&summary).is_break() {
+                    info!("Stopped by the iteration callback. Terminating");
+                    self.termination_reason = Some(TerminationReason::Callback);
+                    break;
+                }
+            }
+
+            if accepted && self.reached_fitness_target() {
+                info!("Fitness target reached after {} iterations. Terminating", self.iteration);
+                self.termination_reason = Some(TerminationReason::FitnessTarget);
+                break;
+            }
+
+            if self.time_limit.is_some_and(|limit| self.elapsed >= limit) {
+                info!("Time limit reached after {:?}. Terminating", self.elapsed);
+                self.termination_reason = Some(TerminationReason::TimeLimit);
+                break;
+            }
         }
 
-        println!("{:?}", self.current_individual.dbg());
+        info!("{:?}", self.best_individual.dbg());
         
+        // the reason is written after the rows, where a resumed simulation appends its own ones
         if let Some(file_name) = &self.csv_export {
-            recorder.to_csv(file_name).expect("Exporting to CSV should have been successful.");
+            Self::export_csv(&mut recorder, file_name, csv_started);
+            let reason = format!("{:?}", self.termination_reason.unwrap_or(TerminationReason::MaxIterations));
+            Recorder::<IterationRow>::write_metadata(file_name, "termination_reason", &reason)
+                .expect("Writing the CSV metadata should have been successful");
         }
         
         if let Some(file_name) = &self.signal_export {
-            self.current_individual.to_signal().to_wav(file_name)
+            self.best_individual.to_signal().to_wav(file_name)
                 .expect("Exporting to a WAV file should have been successful.")
         }
 
-        Ok(self.current_individual.clone())
+        Ok(HillClimbReport {
+            fittest: self.best_individual.clone(),
+            history,
+            iterations: self.iteration - first_iteration,
+            elapsed: self.elapsed,
+            termination_reason: self.termination_reason.unwrap_or(TerminationReason::MaxIterations),
+        })
+    }
+
+    /// Saves the whole simulation to a file in `exports/checkpoints`, from which it can be continued
+    /// with `HillClimbingSimulation::resume`.
+    pub fn save_checkpoint(&self, path: &str) -> Result<()> {
+        let path = Path::new("exports/checkpoints").join(path);
+        fs::create_dir_all(path.parent().expect("File should have parent."))?;
+        bincode::serialize_into(BufWriter::new(File::create(path)?), self)?;
+        Ok(())
+    }
+
+    /// Restores a simulation saved with `save_checkpoint` to a file in `exports/checkpoints`.
+    /// Running it continues from the iteration it was saved at, appending to its CSV export.
+    pub fn resume(path: &str) -> Result<Self> {
+        let file = File::open(Path::new("exports/checkpoints").join(path))?;
+        let mut simulation: Self = bincode::deserialize_from(BufReader::new(file))?;
+
+        // the target is only stored once, so the generator and the individuals share it again
+        let target = Arc::clone(&simulation.target);
+        simulation.generator = simulation.generator.target(Arc::clone(&target));
+        simulation.current_individual = simulation.current_individual.with_target(Arc::clone(&target));
+        simulation.best_individual = simulation.best_individual.with_target(target);
+
+        Ok(simulation)
+    }
+
+    /// Exports the recorded rows to the CSV file, after the rows exported before if `append` is
+    /// set, and clears the recorder.
+    fn export_csv(recorder: &mut Recorder<IterationRow>, file_name: &str, append: bool) {
+        if append {
+            recorder.append_to_csv(file_name).expect("Appending to CSV should have been successful");
+        } else {
+            recorder.to_csv(file_name).expect("Exporting to CSV should have been successful");
+        }
+        recorder.clear();
+    }
+}
+
+/// Specifies how the step size of a hill climbing simulation changes after each iteration.
+#[derive(Copy, Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum StepSchedule {
+    /// The initial step size is used in every iteration.
+    Constant,
+    /// The step size is multiplied by `factor_per_iter` after every iteration, regardless of its
+    /// outcome. The factor must be between 0 and 1.
+    GeometricDecay { factor_per_iter: f32 },
+    /// The step size is multiplied by `grow` after an iteration that finds a fitter neighbour and
+    /// by `shrink` after one that does not, so that a run of failures narrows the search until the
+    /// minimum step size is reached. `grow` must be at least 1 and `shrink` between 0 and 1.
+    Adaptive { grow: f32, shrink: f32 },
+}
+
+impl Default for StepSchedule {
+    fn default() -> Self {
+        Self::Adaptive { grow: 1.0, shrink: 0.95 }
+    }
+}
+
+impl StepSchedule {
+    /// Whether the factors of the schedule are within their valid ranges.
+    pub fn is_valid(&self) -> bool {
+        let decay = |factor: &f32| *factor > 0.0 && *factor <= 1.0;
+        match self {
+            StepSchedule::Constant => true,
+            StepSchedule::GeometricDecay { factor_per_iter } => decay(factor_per_iter),
+            StepSchedule::Adaptive { grow, shrink } => *grow >= 1.0 && decay(shrink),
+        }
+    }
+
+    /// The step size following an iteration run with the given one, which found a fitter
+    /// neighbour if `success`.
+    pub fn next(&self, step_size: f32, success: bool) -> f32 {
+        match *self {
+            StepSchedule::Constant => step_size,
+            StepSchedule::GeometricDecay { factor_per_iter } => step_size * factor_per_iter,
+            StepSchedule::Adaptive { grow, shrink } => step_size * if success { grow } else { shrink },
+        }
     }
 }
 
-pub fn evolve_value(val: f32, min_v: f32, max_v: f32, step_size: f32, rng: &mut ThreadRng) -> f32 {
+/// Draws a new value uniformly around `val`, within a distance proportional to the step size and
+/// the width of [`min_v`, `max_v`]. Values outside the range are clamped into it first, and are
+/// returned as they are if there is no room to move, e.g. with a step size of 0.
+pub fn evolve_value<R: Rng + ?Sized>(val: f32, min_v: f32, max_v: f32, step_size: f32, rng: &mut R) -> f32 {
+    let val = val.clamp(min_v, max_v);
     let dist = (max_v - min_v) * step_size / 2.0;
-    rng.gen_range(f32::max(min_v, val-dist)..f32::min(max_v, val+dist))
+    let (low, high) = (f32::max(min_v, val-dist), f32::min(max_v, val+dist));
+    if low < high {
+        rng.gen_range(low..high)
+    } else {
+        val
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use itertools::Itertools;
+    use crate::simulation::synthesis_methods::additive::AdditiveIndividual;
+    use crate::simulation::synthesis_methods::subtractive::SubtractiveIndividual;
+    use crate::signal_processing::components::oscillator::sine_wave;
+    use crate::signal_processing::SAMPLE_RATE;
+    use crate::signal_processing::signal_analysis::MAX_FFT_LENGTH;
+
+    #[test]
+    fn test_time_limit() {
+        let generator = SubtractiveIndividual::new_generator()
+            .target(Arc::new(sine_wave(440.0, 1.0, SAMPLE_RATE as f32, 1.0, 0.0)))
+            .oscillator();
+        let mut simulation: HillClimbingSimulation<SubtractiveIndividual> = HillClimberBuilder::new()
+            .generator(generator)
+            .time_limit(Duration::from_millis(1))
+            .build().unwrap();
+        simulation.run().unwrap();
+
+        assert_eq!(simulation.termination_reason, Some(TerminationReason::TimeLimit));
+        assert_eq!(simulation.iteration, 1);
+        assert!(simulation.elapsed >= Duration::from_millis(1));
+    }
+
+    #[test]
+    fn test_cancel_token() {
+        let generator = SubtractiveIndividual::new_generator()
+            .target(Arc::new(sine_wave(440.0, 1.0, SAMPLE_RATE as f32, 1.0, 0.0)))
+            .oscillator();
+        let token = Arc::new(AtomicBool::new(false));
+        let mut simulation: HillClimbingSimulation<SubtractiveIndividual> = HillClimberBuilder::new()
+            .generator(generator)
+            .max_iterations(u32::MAX)
+            .max_unsuccessful_iters(u32::MAX)
+            .min_step_size(0.0)
+            .csv_export("tests/hill_climb_cancelled.csv")
+            .signal_export("tests/hill_climb_cancelled.wav")
+            .cancel_token(Arc::clone(&token))
+            .build().unwrap();
+        for path in ["exports/csv/tests/hill_climb_cancelled.csv", "exports/signal/tests/hill_climb_cancelled.wav"] {
+            let _ = std::fs::remove_file(path);
+        }
+
+        let canceller = std::thread::spawn(move || {
+            std::thread::sleep(Duration::from_millis(200));
+            token.store(true, Ordering::SeqCst);
+        });
+        let report = simulation.run_with_report().unwrap();
+        canceller.join().unwrap();
+
+        assert_eq!(report.termination_reason, TerminationReason::Cancelled);
+        assert!(report.iterations > 0);
+        assert!(std::path::Path::new("exports/csv/tests/hill_climb_cancelled.csv").exists());
+        assert!(std::path::Path::new("exports/signal/tests/hill_climb_cancelled.wav").exists());
+    }
+
+    #[test]
+    fn test_missing_generator_or_target() {
+        let simulation = HillClimberBuilder::<SubtractiveIndividual>::new().build();
+        assert!(matches!(simulation, Err(HillClimbingSimulationError::GeneratorMissing)));
+
+        let generator = SubtractiveIndividual::new_generator()
+            .target(Arc::new(sine_wave(440.0, 1.0, SAMPLE_RATE as f32, 1.0, 0.0)))
+            .oscillator();
+        let builder = HillClimberBuilder::<SubtractiveIndividual>::new().generator(generator);
+        let simulation = HillClimberBuilder { target: None, ..builder }.build();
+        assert!(matches!(simulation, Err(HillClimbingSimulationError::TargetMissing)));
+    }
+
+    #[test]
+    fn test_invalid_configurations() {
+        let generator = SubtractiveIndividual::new_generator()
+            .target(Arc::new(sine_wave(440.0, 1.0, SAMPLE_RATE as f32, 1.0, 0.0)))
+            .oscillator();
+        let build = |builder: HillClimberBuilder<SubtractiveIndividual>| builder.build().err().unwrap();
+        let valid = || HillClimberBuilder::new().generator(generator.clone());
+
+        for step_size in [0.0, -0.5, f32::NAN, f32::INFINITY] {
+            let error = build(valid().init_step_size(step_size));
+            assert!(matches!(error, HillClimbingSimulationError::InvalidStepSize(size) if size.total_cmp(&step_size).is_eq()));
+        }
+        for min_step_size in [0.5, 0.6] {
+            assert_eq!(build(valid().init_step_size(0.5).min_step_size(min_step_size)),
+                       HillClimbingSimulationError::InvalidMinStepSize { min_step_size, init_step_size: 0.5 });
+        }
+        assert_eq!(build(valid().max_iterations(0)), HillClimbingSimulationError::InvalidMaxIterations);
+        for schedule in [StepSchedule::GeometricDecay { factor_per_iter: 1.5 }, StepSchedule::Adaptive { grow: 0.5, shrink: 0.5 }] {
+            assert_eq!(build(valid().step_schedule(schedule)), HillClimbingSimulationError::InvalidStepSchedule(schedule));
+        }
+        assert_eq!(build(valid().late_acceptance(0)), HillClimbingSimulationError::InvalidLateAcceptance);
+        assert_eq!(build(valid().iterations_per_restart(0)), HillClimbingSimulationError::InvalidIterationsPerRestart);
+        assert_eq!(build(valid().neighbours_per_iter(0)), HillClimbingSimulationError::InvalidNeighboursPerIter);
+        assert_eq!(build(valid().checkpoint_every(0, "tests/invalid_checkpoint.bin")),
+                   HillClimbingSimulationError::InvalidCheckpointInterval);
+        assert!(matches!(HillClimbingSimulation::run_multi(valid(), 0), Err(HillClimbingSimulationError::InvalidClimberCount)));
+        for (min_hz, max_hz) in [(2_000.0, 50.0), (-10.0, 2_000.0), (50.0, SAMPLE_RATE as f32)] {
+            let band = HillClimberBuilder::new().generator(generator.clone().analysis_band(min_hz, max_hz));
+            assert_eq!(build(band), HillClimbingSimulationError::InvalidAnalysisBand { min_hz, max_hz });
+        }
+        for length in [1000, 2 * MAX_FFT_LENGTH] {
+            let analysis = HillClimberBuilder::new().generator(generator.clone().analysis_length(length));
+            assert_eq!(build(analysis), HillClimbingSimulationError::InvalidAnalysisLength(length));
+        }
+        assert!(valid().init_step_size(0.5).min_step_size(0.4).build().is_ok());
+    }
+
+    #[test]
+    fn test_hill_climb_report() {
+        let generator = SubtractiveIndividual::new_generator()
+            .target(Arc::new(sine_wave(440.0, 1.0, SAMPLE_RATE as f32, 1.0, 0.0)))
+            .oscillator();
+        let mut simulation: HillClimbingSimulation<SubtractiveIndividual> = HillClimberBuilder::new()
+            .generator(generator)
+            .max_iterations(20)
+            .seed(3)
+            .build().unwrap();
+        let report = simulation.run_with_report().unwrap();
+
+        assert_eq!(report.iterations, simulation.iteration);
+        assert_eq!(report.history.len(), report.iterations as usize + 1);
+        assert_eq!(report.history.last().unwrap().fitness, report.fittest.fitness());
+        assert_eq!(report.termination_reason, simulation.termination_reason.unwrap());
+    }
+
+    #[test]
+    fn test_step_size_adaptation() {
+        let generator = SubtractiveIndividual::new_generator()
+            .target(Arc::new(sine_wave(440.0, 1.0, SAMPLE_RATE as f32, 1.0, 0.0)))
+            .oscillator();
+        let mut simulation: HillClimbingSimulation<SubtractiveIndividual> = HillClimberBuilder::new()
+            .generator(generator)
+            .max_iterations(1000)
+            .min_step_size(0.1)
+            .step_schedule(StepSchedule::Adaptive { grow: 1.0, shrink: 0.8 })
+            .seed(3)
+            .build().unwrap();
+        let report = simulation.run_with_report().unwrap();
+
+        // every failure narrows the step and every success keeps it, until it is too small
+        for rows in report.history.windows(2) {
+            let expected = if rows[1].fitness > rows[0].fitness { 1.0 } else { 0.8 };
+            assert!((rows[1].step_size - expected * rows[0].step_size).abs() < 1e-6);
+        }
+        assert_eq!(report.termination_reason, TerminationReason::MinStepSize);
+        assert!(simulation.step_size < 0.1);
+        assert!(report.iterations < 1000);
+    }
+
+    #[test]
+    fn test_step_schedules() {
+        let step_after = |schedule: StepSchedule, outcomes: &[bool]| {
+            outcomes.iter().fold(0.5, |step_size, &success| schedule.next(step_size, success))
+        };
+        let outcomes = [true, false, false, true, false, false, false, true, false, false];
+
+        assert_eq!(step_after(StepSchedule::Constant, &outcomes), 0.5);
+        let decayed = step_after(StepSchedule::GeometricDecay { factor_per_iter: 0.9 }, &outcomes);
+        assert!((decayed - 0.5 * 0.9f32.powi(10)).abs() < 1e-6);
+        let adapted = step_after(StepSchedule::Adaptive { grow: 1.5, shrink: 0.5 }, &outcomes);
+        assert!((adapted - 0.5 * 1.5f32.powi(3) * 0.5f32.powi(7)).abs() < 1e-6);
+
+        assert!(!StepSchedule::GeometricDecay { factor_per_iter: 1.5 }.is_valid());
+        assert!(!StepSchedule::Adaptive { grow: 0.5, shrink: 0.5 }.is_valid());
+    }
+
+    #[test]
+    fn test_geometric_decay() {
+        let generator = SubtractiveIndividual::new_generator()
+            .target(Arc::new(sine_wave(440.0, 1.0, SAMPLE_RATE as f32, 1.0, 0.0)))
+            .oscillator();
+        let mut simulation: HillClimbingSimulation<SubtractiveIndividual> = HillClimberBuilder::new()
+            .generator(generator)
+            .max_iterations(20)
+            .step_schedule(StepSchedule::GeometricDecay { factor_per_iter: 0.9 })
+            .seed(3)
+            .build().unwrap();
+        let report = simulation.run_with_report().unwrap();
+
+        // the step recorded after each iteration only depends on the number of iterations run
+        for (iteration, row) in report.history.iter().enumerate() {
+            assert!((row.step_size - 0.9f32.powi(iteration as i32)).abs() < 1e-5);
+        }
+    }
+
+    #[test]
+    fn test_restarts() {
+        let generator = SubtractiveIndividual::new_generator()
+            .target(Arc::new(sine_wave(440.0, 1.0, SAMPLE_RATE as f32, 1.0, 0.0)))
+            .oscillator();
+        let mut simulation: HillClimbingSimulation<SubtractiveIndividual> = HillClimberBuilder::new()
+            .generator(generator)
+            .max_iterations(1000)
+            .restarts(3)
+            .iterations_per_restart(5)
+            .seed(3)
+            .build().unwrap();
+        let report = simulation.run_with_report().unwrap();
+
+        // each of the four climbs starts from a different individual and runs for five iterations
+        let starts = report.history.iter().unique_by(|row| row.restart).collect_vec();
+        assert_eq!(starts.iter().map(|row| row.restart).collect_vec(), vec![0, 1, 2, 3]);
+        assert_eq!(starts.iter().map(|row| row.fundamental.to_bits()).unique().count(), 4);
+        assert_eq!(report.iterations, 20);
+
+        // the fittest individual of all the climbs is returned, not the last one
+        let best = report.history.iter().map(|row| row.fitness).fold(0.0, f32::max);
+        assert_eq!(report.fittest.fitness(), best);
+        assert_eq!(simulation.best_individual.fitness(), best);
+    }
+
+    #[test]
+    fn test_run_multi() {
+        let generator = SubtractiveIndividual::new_generator()
+            .target(Arc::new(sine_wave(440.0, 1.0, SAMPLE_RATE as f32, 1.0, 0.0)))
+            .oscillator();
+        let builder = HillClimberBuilder::new()
+            .generator(generator)
+            .max_iterations(10)
+            .csv_export("tests/multi_start/iterations.csv")
+            .seed(5);
+        let report = HillClimbingSimulation::<SubtractiveIndividual>::run_multi(builder.clone(), 3).unwrap();
+        let again = HillClimbingSimulation::<SubtractiveIndividual>::run_multi(builder, 3).unwrap();
+
+        // the climbers differ from each other, but the whole run is reproducible from its seed
+        assert_eq!(report.starts.len(), 3);
+        assert!(report.starts.iter().all(|start| start.iterations == 10));
+        assert!(report.starts.windows(2).any(|w| w[0].fitness != w[1].fitness));
+        assert_eq!(report.starts, again.starts);
+
+        let best = report.starts.iter().map(|start| start.fitness).fold(0.0, f32::max);
+        assert_eq!(report.fittest.fitness(), best);
+        for start in 0..3 {
+            let path = format!("exports/csv/tests/multi_start/{start}/iterations.csv");
+            assert!(std::path::Path::new(&path).exists(), "{path} should have been exported");
+        }
+    }
+
+    #[test]
+    fn test_neighbours_per_iter() {
+        let generator = SubtractiveIndividual::new_generator()
+            .target(Arc::new(sine_wave(440.0, 1.0, SAMPLE_RATE as f32, 1.0, 0.0)))
+            .oscillator();
+        let builder = HillClimberBuilder::new()
+            .generator(generator)
+            .max_iterations(22)
+            .seed(3);
+        let run = |builder: HillClimberBuilder<SubtractiveIndividual>| {
+            let mut simulation: HillClimbingSimulation<SubtractiveIndividual> = builder.build().unwrap();
+            simulation.run_with_report().unwrap()
+        };
+
+        // every step evaluates four neighbours, except for the last one, which stays in the budget
+        let batched = run(builder.clone().neighbours_per_iter(4));
+        let iterations = batched.history.iter().map(|row| row.iteration).collect_vec();
+        assert_eq!(iterations, vec![0, 4, 8, 12, 16, 20, 22]);
+        assert_eq!(batched.iterations, 22);
+
+        // a single neighbour climbs exactly as the default configuration
+        let single = run(builder.clone().neighbours_per_iter(1));
+        let default = run(builder);
+        assert_eq!(single.history.iter().map(|row| row.fitness).collect_vec(),
+                   default.history.iter().map(|row| row.fitness).collect_vec());
+    }
+
+    #[test]
+    fn test_iteration_rows() {
+        let generator = SubtractiveIndividual::new_generator()
+            .target(Arc::new(sine_wave(440.0, 1.0, SAMPLE_RATE as f32, 1.0, 0.0)))
+            .oscillator();
+        let mut simulation: HillClimbingSimulation<SubtractiveIndividual> = HillClimberBuilder::new()
+            .generator(generator)
+            .max_iterations(50)
+            .csv_export("tests/hill_climb_rows.csv")
+            .seed(3)
+            .build().unwrap();
+        let report = simulation.run_with_report().unwrap();
+
+        // a rejected iteration keeps the individual and counts as one more unsuccessful iteration
+        let first = &report.history[0];
+        assert!(!first.accepted && first.unsuccessful_iters == 0);
+        for rows in report.history.windows(2) {
+            assert_eq!(rows[1].accepted, rows[1].fitness > rows[0].fitness);
+            if rows[1].accepted {
+                assert_eq!(rows[1].unsuccessful_iters, 0);
+            } else {
+                assert_eq!(rows[1].fitness, rows[0].fitness);
+                assert_eq!(rows[1].unsuccessful_iters, rows[0].unsuccessful_iters + 1);
+            }
+        }
+        assert!(report.history.iter().any(|row| row.accepted));
+        assert!(report.history.iter().skip(1).any(|row| !row.accepted));
+
+        // the new columns are exported after the original ones
+        let csv = std::fs::read_to_string("exports/csv/tests/hill_climb_rows.csv").unwrap();
+        assert_eq!(csv.lines().next().unwrap(), "iteration,fitness,fundamental,seed,step_size,restart,unsuccessful_iters,accepted");
+        assert_eq!(csv.lines().filter(|line| !line.starts_with('#')).count(), report.history.len() + 1);
+    }
+
+    #[test]
+    fn test_fitness_target() {
+        let generator = SubtractiveIndividual::new_generator()
+            .target(Arc::new(sine_wave(440.0, 1.0, SAMPLE_RATE as f32, 1.0, 0.0)))
+            .oscillator();
+        let builder = HillClimberBuilder::new()
+            .generator(generator)
+            .min_step_size(0.0)
+            .seed(4);
+
+        // target the signal of the individual the climb starts from
+        let starting: HillClimbingSimulation<SubtractiveIndividual> = builder.clone().build().unwrap();
+        let target = starting.current_individual.to_signal();
+        let builder = builder.target(target);
+        let own_fitness = builder.clone().build().unwrap().current_individual.fitness();
+        let mut simulation = builder.clone().fitness_target(0.9 * own_fitness).build().unwrap();
+        let report = simulation.run_with_report().unwrap();
+
+        assert_eq!(report.termination_reason, TerminationReason::FitnessTarget);
+        assert!(report.iterations <= 1);
+        assert!(report.fittest.fitness() >= 0.9 * own_fitness);
+
+        // an unreachable target leaves the other termination criteria in place
+        let mut simulation = builder.fitness_target(f32::INFINITY).max_iterations(20).build().unwrap();
+        let report = simulation.run_with_report().unwrap();
+        assert_eq!(report.termination_reason, TerminationReason::MaxIterations);
+        assert_eq!(report.iterations, 20);
+    }
+
+    #[test]
+    fn test_seeded_runs() {
+        let run = |seed| {
+            let generator = SubtractiveIndividual::new_generator()
+                .target(Arc::new(sine_wave(440.0, 1.0, SAMPLE_RATE as f32, 1.0, 0.0)))
+                .oscillator();
+            let mut simulation: HillClimbingSimulation<SubtractiveIndividual> = HillClimberBuilder::new()
+                .generator(generator)
+                .max_iterations(30)
+                .seed(seed)
+                .build().unwrap();
+            simulation.run_with_report().unwrap().history
+        };
+
+        assert_eq!(run(6), run(6));
+        assert_ne!(run(6), run(7));
+    }
+
+    #[test]
+    fn test_checkpoint_resume() {
+        let generator = SubtractiveIndividual::new_generator()
+            .target(Arc::new(sine_wave(440.0, 1.0, SAMPLE_RATE as f32, 1.0, 0.0)))
+            .oscillator();
+        let builder = |name: &str| HillClimberBuilder::new()
+            .generator(generator.clone())
+            .seed(8)
+            .csv_export(&format!("tests/{name}.csv"));
+
+        let mut uninterrupted: HillClimbingSimulation<SubtractiveIndividual> = builder("climb_uninterrupted")
+            .max_iterations(20)
+            .build().unwrap();
+        uninterrupted.run().unwrap();
+
+        let mut simulation: HillClimbingSimulation<SubtractiveIndividual> = builder("climb_resumed")
+            .max_iterations(10)
+            .checkpoint_every(10, "tests/climb_checkpoint.bin")
+            .build().unwrap();
+        simulation.run().unwrap();
+
+        // the resumed state draws the same next candidate as the simulation it was saved from
+        let mut resumed: HillClimbingSimulation<SubtractiveIndividual> = HillClimbingSimulation::resume("tests/climb_checkpoint.bin").unwrap();
+        assert_eq!(resumed.iteration, 10);
+        assert_eq!(resumed.current_individual, simulation.current_individual);
+        assert_eq!(resumed.current_individual.get_target(), simulation.current_individual.get_target());
+        assert_eq!(resumed.fittest_neighbour(1), simulation.fittest_neighbour(1));
+
+        let mut resumed: HillClimbingSimulation<SubtractiveIndividual> = HillClimbingSimulation::resume("tests/climb_checkpoint.bin").unwrap();
+        resumed.max_iterations = 20;
+        resumed.run().unwrap();
+        assert_eq!(resumed.iteration, 20);
+        assert_eq!(resumed.best_individual, uninterrupted.best_individual);
+
+        // the rows of both halves follow each other as if the run had not been interrupted
+        let read = |name: &str| std::fs::read_to_string(format!("exports/csv/tests/{name}.csv")).unwrap();
+        assert_eq!(read("climb_resumed"), read("climb_uninterrupted"));
+    }
+
+    #[test]
+    fn test_checkpoint_failure() {
+        let generator = SubtractiveIndividual::new_generator()
+            .target(Arc::new(sine_wave(440.0, 1.0, SAMPLE_RATE as f32, 1.0, 0.0)))
+            .oscillator();
+
+        // a file in place of the checkpoint's directory makes saving it fail
+        std::fs::create_dir_all("exports/checkpoints/tests").unwrap();
+        std::fs::write("exports/checkpoints/tests/climb_not_a_directory", "").unwrap();
+        let mut simulation: HillClimbingSimulation<SubtractiveIndividual> = HillClimberBuilder::new()
+            .generator(generator)
+            .max_iterations(5)
+            .seed(3)
+            .checkpoint_every(1, "tests/climb_not_a_directory/checkpoint.bin")
+            .build().unwrap();
+
+        assert!(matches!(simulation.run(), Err(HillClimbingSimulationError::CheckpointNotSaved { .. })));
+        assert_eq!(simulation.iteration, 1);
+    }
+
+    #[test]
+    fn test_iteration_callback() {
+        let generator = SubtractiveIndividual::new_generator()
+            .target(Arc::new(sine_wave(440.0, 1.0, SAMPLE_RATE as f32, 1.0, 0.0)))
+            .oscillator();
+        let summaries = Arc::new(Mutex::new(vec![]));
+        let recorded = Arc::clone(&summaries);
+        let mut simulation: HillClimbingSimulation<SubtractiveIndividual> = HillClimberBuilder::new()
+            .generator(generator)
+            .max_iterations(20)
+            .seed(5)
+            .csv_export("tests/climb_callback.csv")
+            .on_iteration(move |summary: &IterationSummary| {
+                recorded.lock().unwrap().push(summary.clone());
+                if summary.iteration == 8 { ControlFlow::Break(()) } else { ControlFlow::Continue(()) }
+            })
+            .build().unwrap();
+        let _ = std::fs::remove_file("exports/csv/tests/climb_callback.csv");
+
+        let report = simulation.run_with_report().unwrap();
+        let summaries = summaries.lock().unwrap();
+        assert_eq!(summaries.iter().map(|summary| summary.iteration).collect_vec(), (1..=8).collect_vec());
+        assert_eq!(simulation.iteration, 8);
+        assert_eq!(report.termination_reason, TerminationReason::Callback);
+
+        // every summary matches its row of the history, which was exported before stopping
+        for (summary, rows) in summaries.iter().zip(report.history.windows(2)) {
+            assert_eq!(summary.fitness, rows[1].fitness);
+            assert_eq!(summary.step_size, rows[1].step_size);
+            assert_eq!(summary.accepted, rows[1].fitness > rows[0].fitness);
+        }
+        assert!(std::path::Path::new("exports/csv/tests/climb_callback.csv").exists());
+    }
+
+    #[test]
+    fn test_late_acceptance() {
+        // two close partials make a rugged landscape with plateaus around each of them
+        let target = sine_wave(440.0, 1.0, SAMPLE_RATE as f32, 1.0, 0.0)
+            .add_amp(&sine_wave(466.0, 1.0, SAMPLE_RATE as f32, 1.0, 0.0))
+            .scale_amp(0.5);
+        let generator = AdditiveIndividual::new_generator()
+            .target(Arc::new(target))
+            .harmonics();
+        let builder = HillClimberBuilder::new()
+            .generator(generator)
+            .max_iterations(600)
+            .step_schedule(StepSchedule::Constant)
+            .init_step_size(0.2)
+            .min_step_size(0.0)
+            .max_unsuccessful_iters(u32::MAX)
+            .seed(2);
+        let run = |builder: HillClimberBuilder<AdditiveIndividual>| {
+            let mut simulation: HillClimbingSimulation<AdditiveIndividual> = builder.build().unwrap();
+            simulation.run_with_report().unwrap()
+        };
+
+        // plain climbing stays on a plateau for most of the run
+        let plain = run(builder.clone());
+        assert_eq!(plain.history[400].fitness, plain.fittest.fitness());
+
+        // late acceptance accepts worse candidates, so the current fitness decreases at times
+        let late = run(builder.late_acceptance(10));
+        assert_eq!(late.termination_reason, plain.termination_reason);
+        assert!(late.history.windows(2).any(|rows| rows[1].fitness < rows[0].fitness));
+        assert!(late.fittest.fitness() > plain.fittest.fitness());
+    }
+
+    #[test]
+    fn test_evolve_value_edges() {
+        let mut rng = StdRng::seed_from_u64(3);
+
+        // values at the bounds move into the range
+        for _ in 0..100 {
+            let value = evolve_value(0.0, 0.0, 1.0, 0.1, &mut rng);
+            assert!((0.0..=0.05).contains(&value));
+            let value = evolve_value(1.0, 0.0, 1.0, 0.1, &mut rng);
+            assert!((0.95..=1.0).contains(&value));
+        }
+
+        // values outside the range are clamped into it before moving
+        for _ in 0..100 {
+            let value = evolve_value(5.0, 0.0, 1.0, 0.1, &mut rng);
+            assert!((0.95..=1.0).contains(&value));
+            let value = evolve_value(-5.0, 0.0, 1.0, 0.1, &mut rng);
+            assert!((0.0..=0.05).contains(&value));
+        }
+
+        // without room to move, the (clamped) value is kept
+        assert_eq!(evolve_value(0.3, 0.0, 1.0, 0.0, &mut rng), 0.3);
+        assert_eq!(evolve_value(1.0, 0.0, 1.0, 0.0, &mut rng), 1.0);
+        assert_eq!(evolve_value(5.0, 0.0, 1.0, 0.0, &mut rng), 1.0);
+        assert_eq!(evolve_value(1000.0, 0.0, 2000.0, 1e-12, &mut rng), 1000.0);
+        assert_eq!(evolve_value(0.5, 0.5, 0.5, 1.0, &mut rng), 0.5);
+    }
+
+    #[test]
+    fn test_termination_reasons() {
+        let generator = SubtractiveIndividual::new_generator()
+            .target(Arc::new(sine_wave(440.0, 1.0, SAMPLE_RATE as f32, 1.0, 0.0)))
+            .oscillator();
+        let builder = HillClimberBuilder::new()
+            .generator(generator)
+            .max_iterations(1000)
+            .seed(3)
+            .csv_export("tests/climb_termination.csv");
+        let run = |builder: HillClimberBuilder<SubtractiveIndividual>| {
+            let mut simulation: HillClimbingSimulation<SubtractiveIndividual> = builder.build().unwrap();
+            let reason = simulation.run_with_report().unwrap().termination_reason;
+            let csv = std::fs::read_to_string("exports/csv/tests/climb_termination.csv").unwrap();
+            assert_eq!(csv.lines().last().unwrap(), format!("# termination_reason: {reason:?}"));
+            reason
+        };
+
+        assert_eq!(run(builder.clone().max_iterations(10)), TerminationReason::MaxIterations);
+        assert_eq!(run(builder.clone().min_step_size(0.0).iterations_per_restart(10)), TerminationReason::MaxIterations);
+        assert_eq!(run(builder.clone().fitness_target(0.0)), TerminationReason::FitnessTarget);
+        assert_eq!(run(builder.clone().cancel_token(Arc::new(AtomicBool::new(true)))), TerminationReason::Cancelled);
+        assert_eq!(run(builder.clone().step_schedule(StepSchedule::Adaptive { grow: 1.0, shrink: 0.5 }).min_step_size(0.01)),
+                   TerminationReason::MinStepSize);
+        assert_eq!(run(builder.clone().step_schedule(StepSchedule::Constant).min_step_size(0.0).max_unsuccessful_iters(3)),
+                   TerminationReason::UnsuccessfulIterations);
+    }
 }
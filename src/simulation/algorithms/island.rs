@@ -0,0 +1,251 @@
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng, thread_rng};
+use log::info;
+use rayon::prelude::*;
+use std::sync::atomic::Ordering;
+use crate::analytics::{GenerationRow, Recorder};
+use crate::error::GeneticSimulationError;
+use crate::simulation::algorithms::genetic::{sort_by_fitness, GASimulation, GASimulationBuilder, Individual};
+use crate::simulation::algorithms::TerminationReason;
+
+/// Runs several genetic algorithm simulations, the islands, evolving independently from each
+/// other except for the fittest individuals of each island migrating to the next one every few
+/// generations. Keeping the populations apart lets each of them converge towards a different
+/// optimum, while the migrations spread the best genes between them.
+pub struct IslandGASimulation<T: Individual> {
+    /// The simulation evolving the population of each island.
+    pub islands: Vec<GASimulation<T>>,
+    /// Number of generations between migrations.
+    pub migration_interval: u32,
+    /// Number of the fittest individuals of each island that migrate to the next one, replacing
+    /// its least fit individuals.
+    pub migration_count: u32,
+    /// Current generation number, shared by all islands.
+    pub generation: u32,
+    /// The number of generations the simulation will run for.
+    pub max_generations: u32,
+    /// Whether the simulation should be exported to a CSV file and what file name. The rows of all
+    /// islands are exported to the same file.
+    pub csv_export: Option<String>,
+    /// Whether the fittest individual across all islands should be exported to a WAV file and what
+    /// file name.
+    pub signal_export: Option<String>,
+    /// Why the simulation stopped, once it has run.
+    pub termination_reason: Option<TerminationReason>,
+    /// Seed from which the seeds of all the islands are drawn.
+    pub seed: u64,
+}
+
+impl<T: Individual> GASimulationBuilder<T>
+where
+    T::Generator: Clone + Send,
+{
+    /// Builds an island model simulation with `n` islands, each of them a simulation with the
+    /// parameters of the builder, where the fittest `migration_count` individuals of each island
+    /// migrate to the next one every `migration_interval` generations. The islands are seeded from
+    /// the seed of the builder, and only the exports and the maximum number of generations apply
+    /// to the whole simulation.
+    pub fn build_islands(
+        self,
+        n: u32,
+        migration_interval: u32,
+        migration_count: u32,
+    ) -> Result<IslandGASimulation<T>, GeneticSimulationError> {
+        if n == 0 {
+            return Err(GeneticSimulationError::InvalidIslandCount);
+        }
+        if migration_interval == 0 {
+            return Err(GeneticSimulationError::InvalidMigrationInterval);
+        }
+        if migration_count > self.initial_population / 2 {
+            return Err(GeneticSimulationError::InvalidMigrationCount {
+                migration_count,
+                selected: self.initial_population / 2,
+            });
+        }
+
+        let seed = self.seed.unwrap_or_else(|| thread_rng().gen());
+        let mut rng = StdRng::seed_from_u64(seed);
+        let islands = (0..n).map(|island| {
+            let mut simulation = GASimulationBuilder {
+                csv_export: None,
+                signal_export: None,
+                spectrogram_export: None,
+                hall_of_fame_export: None,
+                seed: Some(rng.gen()),
+                ..self.clone()
+            }.build()?;
+            simulation.island = island;
+            Ok(simulation)
+        }).collect::<Result<_, _>>()?;
+
+        Ok(IslandGASimulation {
+            islands,
+            migration_interval,
+            migration_count,
+            generation: 0,
+            max_generations: self.max_generations,
+            csv_export: self.csv_export,
+            signal_export: self.signal_export,
+            termination_reason: None,
+            seed,
+        })
+    }
+}
+
+impl<T: Individual> IslandGASimulation<T>
+where
+    T::Generator: Send,
+{
+    /// Calculates the next generation of every island in parallel, followed by the migrations if
+    /// they are due.
+    pub(crate) fn next(&mut self) -> Result<(), GeneticSimulationError> {
+        self.islands.par_iter_mut().try_for_each(|island| island.next())?;
+        self.generation += 1;
+
+        if self.generation.is_multiple_of(self.migration_interval) {
+            self.migrate();
+        }
+
+        Ok(())
+    }
+
+    /// Copies the fittest individuals of each island into the next one in a ring, where they
+    /// replace the least fit individuals, so that the size of every population stays the same.
+    pub(crate) fn migrate(&mut self) {
+        let count = self.migration_count as usize;
+        let migrants: Vec<Vec<T>> = self.islands.iter()
+            .map(|island| island.population.iter().take(count).cloned().collect())
+            .collect();
+
+        let n = self.islands.len();
+        for (i, migrants) in migrants.into_iter().enumerate() {
+            let population = &mut self.islands[(i + 1) % n].population;
+            let kept = population.len().saturating_sub(migrants.len());
+            population.truncate(kept);
+            population.extend(migrants);
+            sort_by_fitness(population);
+        }
+    }
+
+    /// Adds a row for the current generation of every island to the recorder.
+    fn record(&mut self, recorder: &mut Recorder<GenerationRow>) {
+        for island in self.islands.iter_mut() {
+            recorder.add_record(island.into());
+        }
+    }
+
+    /// The fittest individual found on any of the islands during the whole run.
+    pub fn fittest(&self) -> T {
+        self.islands.iter()
+            .filter_map(|island| island.best_ever.as_ref().or(island.population.first()))
+            .max()
+            .expect("There should be a fittest individual in the populations.")
+            .to_owned()
+    }
+
+    /// Runs the island model simulation, returning the fittest individual across all islands.
+    pub fn run(&mut self) -> Result<T, GeneticSimulationError> {
+        let mut recorder: Recorder<GenerationRow> = Recorder::new();
+
+        if self.csv_export.is_some() {
+            self.record(&mut recorder);
+        }
+
+        self.termination_reason = Some(TerminationReason::MaxGenerations);
+        while self.generation < self.max_generations {
+            // every island shares the cancel token of the builder
+            let cancel_token = self.islands.first().and_then(|island| island.cancel_token.as_ref());
+            if cancel_token.is_some_and(|token| token.load(Ordering::SeqCst)) {
+                info!("Cancelled after {} generations. Terminating", self.generation);
+                self.termination_reason = Some(TerminationReason::Cancelled);
+                break;
+            }
+
+            self.next()?;
+
+            if self.csv_export.is_some() {
+                self.record(&mut recorder);
+            }
+        }
+
+        if let Some(file_name) = &self.csv_export {
+            recorder.to_csv(file_name).expect("Exporting to CSV should have been successful");
+        }
+
+        let fittest = self.fittest();
+        info!("{:?}", fittest.dbg());
+
+        if let Some(file_name) = &self.signal_export {
+            fittest.to_signal().to_wav(file_name)
+                .expect("Exporting to a WAV file should have been successful.")
+        }
+
+        Ok(fittest)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+    use super::*;
+    use crate::signal_processing::components::oscillator::sine_wave;
+    use crate::signal_processing::SAMPLE_RATE;
+    use crate::simulation::algorithms::genetic::IndividualGenerator;
+    use crate::simulation::synthesis_methods::subtractive::SubtractiveIndividual;
+
+    fn islands(migration_interval: u32) -> IslandGASimulation<SubtractiveIndividual> {
+        let generator = SubtractiveIndividual::new_generator()
+            .target(Arc::new(sine_wave(440.0, 1.0, SAMPLE_RATE as f32, 1.0, 0.0)))
+            .oscillator();
+
+        GASimulationBuilder::new()
+            .initial_population(12)
+            .max_generations(4)
+            .seed(11)
+            .generator(generator)
+            .build_islands(3, migration_interval, 2)
+            .unwrap()
+    }
+
+    #[test]
+    fn test_invalid_islands() {
+        let generator = SubtractiveIndividual::new_generator()
+            .target(Arc::new(sine_wave(440.0, 1.0, SAMPLE_RATE as f32, 1.0, 0.0)))
+            .oscillator();
+        let builder = GASimulationBuilder::<SubtractiveIndividual>::new()
+            .initial_population(12)
+            .generator(generator);
+        let build = |n, migration_interval, migration_count| {
+            builder.clone().build_islands(n, migration_interval, migration_count).err().unwrap()
+        };
+
+        assert_eq!(build(0, 1, 2), GeneticSimulationError::InvalidIslandCount);
+        assert_eq!(build(3, 0, 2), GeneticSimulationError::InvalidMigrationInterval);
+        assert_eq!(build(3, 1, 7), GeneticSimulationError::InvalidMigrationCount { migration_count: 7, selected: 6 });
+    }
+
+    #[test]
+    fn test_migration() {
+        let mut simulation = islands(1);
+        let migrants = simulation.islands.iter().map(|i| i.population[..2].to_vec()).collect::<Vec<_>>();
+        simulation.migrate();
+
+        for (i, migrants) in migrants.iter().enumerate() {
+            let destination = &simulation.islands[(i + 1) % 3].population;
+            assert!(migrants.iter().all(|m| destination.contains(m)));
+            assert_eq!(destination.len(), 12);
+        }
+    }
+
+    #[test]
+    fn test_island_sizes_are_stable() {
+        let mut simulation = islands(2);
+        let fittest = simulation.run().unwrap();
+
+        assert_eq!(simulation.generation, 4);
+        assert!(simulation.islands.iter().all(|i| i.population.len() == 12));
+        assert!(simulation.islands.iter().all(|i| i.population[0].fitness() <= fittest.fitness()));
+        assert_eq!(simulation.islands.iter().map(|i| i.island).collect::<Vec<_>>(), vec![0, 1, 2]);
+    }
+}
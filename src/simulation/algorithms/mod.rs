@@ -1,3 +1,9 @@
+use serde::{Serialize, Deserialize};
+#[cfg(all(feature = "ctrlc", unix))]
+use std::sync::{Arc, OnceLock};
+#[cfg(all(feature = "ctrlc", unix))]
+use std::sync::atomic::{AtomicBool, Ordering};
+
 /// Genetic algorithms are a global optimisation technique inspired by biological mechanisms like
 /// evolution.
 pub mod genetic;
@@ -5,4 +11,75 @@ pub mod genetic;
 /// A hillclimber is one of the simplest stochastic optimisation techniques that works by exploring
 /// the best nearest neighbour. It is not as effective at finding a global optima as a genetic
 /// algorithm, but can be useful as a performance reference.
-pub mod hillclimbing;
\ No newline at end of file
+pub mod hillclimbing;
+
+/// Island model of the genetic algorithm, evolving several populations that exchange their fittest
+/// individuals.
+pub mod island;
+
+/// Genetic algorithm maximising two fitness metrics at once, whose result is the Pareto front of
+/// the trade-offs between them.
+pub mod multi_objective;
+
+/// Repeated independent runs of the genetic algorithm and their aggregated results.
+pub mod batch;
+
+/// The fittest distinct individuals found during a run of the genetic algorithm.
+pub mod hall_of_fame;
+
+/// Operators recombining the genes of two parents in a genetic algorithm.
+pub mod crossover;
+
+/// Operators changing the genes of the offspring that mutate in a genetic algorithm.
+pub mod mutation;
+
+/// Strategies for choosing which individuals reproduce in a genetic algorithm.
+pub mod selection;
+
+/// Reason why a simulation stopped running.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum TerminationReason {
+    /// The maximum number of generations was reached.
+    MaxGenerations,
+    /// The maximum number of iterations of the hill climber, or of its last climb, was reached.
+    MaxIterations,
+    /// The best fitness did not improve enough over the configured number of generations or
+    /// iterations.
+    Stagnation,
+    /// The step size of the hill climber fell below its minimum.
+    MinStepSize,
+    /// The hill climber reached its maximum number of consecutive unsuccessful iterations.
+    UnsuccessfulIterations,
+    /// The time limit of the simulation was exceeded.
+    TimeLimit,
+    /// The callback run after every generation requested to stop.
+    Callback,
+    /// The genotype diversity of the population fell below the convergence threshold.
+    Converged,
+    /// The cancel token of the simulation was set.
+    Cancelled,
+    /// The fittest individual reached the fitness target of the simulation.
+    FitnessTarget,
+}
+
+#[cfg(all(feature = "ctrlc", unix))]
+static INTERRUPTED: OnceLock<Arc<AtomicBool>> = OnceLock::new();
+
+#[cfg(all(feature = "ctrlc", unix))]
+extern "C" fn on_interrupt(_signal: libc::c_int) {
+    if let Some(flag) = INTERRUPTED.get() {
+        flag.store(true, Ordering::SeqCst);
+    }
+}
+
+/// Installs a handler of Ctrl-C that sets the returned flag instead of killing the process, so
+/// that the simulations given it as their cancel token stop after the current generation and
+/// write their exports. Every call returns the same flag.
+#[cfg(all(feature = "ctrlc", unix))]
+pub fn cancel_on_ctrl_c() -> Arc<AtomicBool> {
+    let flag = Arc::clone(INTERRUPTED.get_or_init(|| Arc::new(AtomicBool::new(false))));
+    let handler: extern "C" fn(libc::c_int) = on_interrupt;
+    // SAFETY: the handler only stores to an atomic flag, which is async-signal-safe
+    unsafe { libc::signal(libc::SIGINT, handler as libc::sighandler_t) };
+    flag
+}
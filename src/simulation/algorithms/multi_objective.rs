@@ -0,0 +1,330 @@
+use std::sync::Arc;
+use itertools::Itertools;
+use log::{debug, info};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng, thread_rng};
+use rand_chacha::ChaCha8Rng;
+use rayon::prelude::*;
+use crate::FitnessType;
+use crate::analytics::{ParetoRow, Recorder};
+use crate::error::GeneticSimulationError;
+use crate::simulation::algorithms::crossover::CrossoverOperator;
+use crate::simulation::algorithms::genetic::{GASimulationBuilder, Individual, IndividualGenerator, MutationSchedule};
+use crate::simulation::algorithms::mutation::MutationOperator;
+use crate::signal_processing::Signal;
+use crate::utils::std;
+
+/// Genetic algorithm optimising two fitness metrics at once, e.g. the match of the frequency
+/// spectrum and of the waveform, instead of a single fitness value. The population is ranked by
+/// non-dominated sorting with crowding distance, as in NSGA-II, so that the whole trade-off
+/// between both metrics is explored rather than a fixed weighting of them.
+pub struct MultiObjectiveGASimulation<T: Individual> {
+    /// Current generation number.
+    pub generation: u32,
+    /// The number of generations the simulation will run for.
+    pub max_generations: u32,
+    /// The two fitness metrics maximised by the simulation.
+    pub objectives: (FitnessType, FitnessType),
+    /// The population of the current generation, sorted by Pareto front.
+    pub population: Vec<T>,
+    /// The value of both objectives for each individual of the population.
+    pub scores: Vec<(f32, f32)>,
+    /// The index of the Pareto front of each individual of the population, 0 being the
+    /// non-dominated one.
+    pub ranks: Vec<usize>,
+    /// The crowding distance of each individual of the population within its front.
+    pub crowding: Vec<f32>,
+    /// The probability of seeing a mutation in a specific gene in the current generation, as given
+    /// by the schedule.
+    pub mutation_rate: f32,
+    /// How the mutation rate changes over the generations.
+    pub mutation_schedule: MutationSchedule,
+    /// How the genes of each pair of parents are recombined into their offspring.
+    pub crossover: CrossoverOperator,
+    /// How the genes of the offspring change when they mutate.
+    pub mutation: MutationOperator,
+    /// The signal both objectives are evaluated against.
+    pub target: Arc<Signal>,
+    /// Whether the simulation should be exported to a CSV file and what file name.
+    pub csv_export: Option<String>,
+    /// Seed of the random number generator of the simulation.
+    pub seed: u64,
+    rng: ChaCha8Rng,
+}
+
+/// A member of the Pareto front, along with its value of each objective.
+#[derive(Clone, Debug)]
+pub struct ParetoSolution<T: Individual> {
+    pub individual: T,
+    pub scores: (f32, f32),
+}
+
+impl<T: Individual> GASimulationBuilder<T> {
+    /// Builds a simulation maximising both `objectives` at once, with the population size, number
+    /// of generations, mutation, crossover, CSV export and seed of the builder. The rest of the
+    /// parameters only apply to the scalar simulation.
+    pub fn build_multi_objective(
+        self,
+        objectives: (FitnessType, FitnessType),
+    ) -> Result<MultiObjectiveGASimulation<T>, GeneticSimulationError> {
+        self.validate()?;
+        let mut generator = self.generator.ok_or(GeneticSimulationError::MissingGenerator)?;
+        let target = self.target.ok_or(GeneticSimulationError::MissingTarget)?;
+        if !Arc::ptr_eq(&target, &generator.get_target()) {
+            generator = generator.target(Arc::clone(&target));
+        }
+
+        let seed = self.seed.unwrap_or_else(|| thread_rng().gen());
+        let mut rng = ChaCha8Rng::seed_from_u64(seed);
+        let seeds = (0..self.initial_population).map(|_| rng.gen()).collect_vec();
+        let population: Vec<T> = seeds.into_par_iter()
+            .map(|seed| generator.generate_valid(self.invalid_retries, &mut StdRng::seed_from_u64(seed)))
+            .collect();
+
+        let mut simulation = MultiObjectiveGASimulation {
+            generation: 0,
+            max_generations: self.max_generations,
+            scores: evaluate_objectives(&population, &objectives),
+            objectives,
+            population,
+            ranks: vec![],
+            crowding: vec![],
+            mutation_rate: self.mutation_schedule.initial_rate(),
+            mutation_schedule: self.mutation_schedule,
+            crossover: self.crossover,
+            mutation: self.mutation,
+            target,
+            csv_export: self.csv_export,
+            seed,
+            rng,
+        };
+        simulation.select(simulation.population.len());
+
+        Ok(simulation)
+    }
+}
+
+/// Evaluates both objectives on each individual in parallel.
+fn evaluate_objectives<T: Individual>(population: &[T], objectives: &(FitnessType, FitnessType)) -> Vec<(f32, f32)> {
+    population.par_iter()
+        .map(|individual| (individual.evaluate(&objectives.0), individual.evaluate(&objectives.1)))
+        .collect()
+}
+
+/// Whether the scores `a` are at least as good as `b` in both objectives and better in one of them.
+pub fn dominates(a: (f32, f32), b: (f32, f32)) -> bool {
+    a.0 >= b.0 && a.1 >= b.1 && (a.0 > b.0 || a.1 > b.1)
+}
+
+/// Splits the indices of the given scores into Pareto fronts, where every individual of a front is
+/// only dominated by individuals of the previous ones. The first front is the non-dominated one.
+pub fn non_dominated_sort(scores: &[(f32, f32)]) -> Vec<Vec<usize>> {
+    let n = scores.len();
+    let mut dominated: Vec<Vec<usize>> = vec![vec![]; n];
+    let mut domination_count = vec![0; n];
+
+    for i in 0..n {
+        for j in (i + 1)..n {
+            if dominates(scores[i], scores[j]) {
+                dominated[i].push(j);
+                domination_count[j] += 1;
+            } else if dominates(scores[j], scores[i]) {
+                dominated[j].push(i);
+                domination_count[i] += 1;
+            }
+        }
+    }
+
+    let mut fronts = vec![];
+    let mut front = (0..n).filter(|&i| domination_count[i] == 0).collect_vec();
+    while !front.is_empty() {
+        let mut next = vec![];
+        for &i in &front {
+            for &j in &dominated[i] {
+                domination_count[j] -= 1;
+                if domination_count[j] == 0 {
+                    next.push(j);
+                }
+            }
+        }
+        next.sort_unstable();
+        fronts.push(front);
+        front = next;
+    }
+
+    fronts
+}
+
+/// Calculates the crowding distance of each individual of a front, given by its indices into the
+/// scores, as the sum over both objectives of the normalised distance between its neighbours. The
+/// extremes of each objective have an infinite distance so that they are always preferred.
+pub fn crowding_distance(scores: &[(f32, f32)], front: &[usize]) -> Vec<f32> {
+    let mut distance = vec![0.0; front.len()];
+    let value = |position: usize, objective: usize| {
+        let scores = scores[front[position]];
+        if objective == 0 { scores.0 } else { scores.1 }
+    };
+
+    for objective in 0..2 {
+        let order = (0..front.len())
+            .sorted_by(|&a, &b| value(a, objective).total_cmp(&value(b, objective)))
+            .collect_vec();
+        let (Some(&first), Some(&last)) = (order.first(), order.last()) else {
+            continue;
+        };
+        let range = value(last, objective) - value(first, objective);
+        distance[first] = f32::INFINITY;
+        distance[last] = f32::INFINITY;
+        if range <= 0.0 {
+            continue;
+        }
+
+        for window in order.windows(3) {
+            distance[window[1]] += (value(window[2], objective) - value(window[0], objective)) / range;
+        }
+    }
+
+    distance
+}
+
+impl<T: Individual> MultiObjectiveGASimulation<T> {
+    /// Keeps the `n` best individuals of the population, filling it front by front and breaking
+    /// the ties of the last front that fits by decreasing crowding distance. The ranks and
+    /// crowding distances of the survivors are updated for the next tournament.
+    fn select(&mut self, n: usize) {
+        let fronts = non_dominated_sort(&self.scores);
+        let mut selected = Vec::with_capacity(n);
+        let (mut ranks, mut crowding) = (Vec::with_capacity(n), Vec::with_capacity(n));
+
+        for (rank, front) in fronts.iter().enumerate() {
+            if selected.len() >= n {
+                break;
+            }
+            let distance = crowding_distance(&self.scores, front);
+            let order = (0..front.len()).sorted_by(|&a, &b| distance[b].total_cmp(&distance[a]));
+            for position in order.take(n - selected.len()) {
+                selected.push(front[position]);
+                ranks.push(rank);
+                crowding.push(distance[position]);
+            }
+        }
+
+        let mut population = std::mem::take(&mut self.population).into_iter().map(Some).collect_vec();
+        self.population = selected.iter().map(|&i| population[i].take().expect("Each individual should be selected once")).collect();
+        self.scores = selected.iter().map(|&i| self.scores[i]).collect();
+        self.ranks = ranks;
+        self.crowding = crowding;
+    }
+
+    /// Draws the index of a parent by binary tournament, preferring the lower rank and then the
+    /// larger crowding distance.
+    fn tournament(&mut self) -> usize {
+        let (a, b) = (self.rng.gen_range(0..self.population.len()), self.rng.gen_range(0..self.population.len()));
+        let better = |a: usize, b: usize| {
+            self.ranks[a] < self.ranks[b] || (self.ranks[a] == self.ranks[b] && self.crowding[a] > self.crowding[b])
+        };
+        if better(b, a) { b } else { a }
+    }
+
+    /// Calculates the next generation: the offspring of parents drawn by tournament join the
+    /// population, which is then cut back to its size by selecting the best fronts.
+    pub(crate) fn next(&mut self) {
+        let diversity = std(&self.scores.iter().map(|s| s.0).collect_vec());
+        self.mutation_rate = self.mutation_schedule
+            .rate(self.generation, self.max_generations, self.mutation_rate, diversity);
+
+        // each crossover draws from its own generator, seeded in order from the simulation's one
+        let n = self.population.len();
+        let crossovers = (0..n.div_ceil(2))
+            .map(|_| (self.tournament(), self.tournament(), self.rng.gen()))
+            .collect_vec();
+        let offspring: Vec<T> = crossovers.into_par_iter().flat_map_iter(|(a, b, seed): (usize, usize, u64)| {
+            self.population[a].crossover(&self.population[b], self.mutation_rate, self.crossover, self.mutation,
+                                         &mut StdRng::seed_from_u64(seed))
+        }).collect();
+        let offspring = offspring.into_iter().take(n).collect_vec();
+
+        self.scores.extend(evaluate_objectives(&offspring, &self.objectives));
+        self.population.extend(offspring);
+        self.select(n);
+
+        debug!("Gen: {}, front of {} individuals", self.generation, self.front_size());
+        self.generation += 1;
+    }
+
+    /// Number of individuals of the population in the non-dominated front.
+    pub fn front_size(&self) -> usize {
+        self.ranks.iter().take_while(|&&rank| rank == 0).count()
+    }
+
+    /// The non-dominated front of the current population along with their scores, sorted by
+    /// decreasing value of the first objective.
+    pub fn pareto_front(&self) -> Vec<ParetoSolution<T>> {
+        (0..self.front_size())
+            .map(|i| ParetoSolution { individual: self.population[i].clone(), scores: self.scores[i] })
+            .sorted_by(|a, b| b.scores.0.total_cmp(&a.scores.0))
+            .collect()
+    }
+
+    /// Runs the simulation for the remaining generations, returning the final Pareto front.
+    pub fn run(&mut self) -> Vec<ParetoSolution<T>> {
+        let mut recorder: Recorder<ParetoRow> = Recorder::new();
+        recorder.add_record(ParetoRow::from(&*self));
+
+        while self.generation < self.max_generations {
+            self.next();
+            recorder.add_record(ParetoRow::from(&*self));
+        }
+
+        if let Some(file_name) = &self.csv_export {
+            recorder.to_csv(file_name).expect("Exporting to a CSV file should have been successful.");
+        }
+
+        let front = self.pareto_front();
+        info!("Pareto front of {} individuals after {} generations", front.len(), self.generation);
+        front
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::signal_processing::components::oscillator::sine_wave;
+    use crate::signal_processing::SAMPLE_RATE;
+    use crate::simulation::synthesis_methods::subtractive::SubtractiveIndividual;
+
+    #[test]
+    fn test_non_dominated_sort() {
+        let scores = [(1.0, 0.0), (0.5, 0.5), (0.0, 1.0), (0.4, 0.4), (0.5, 0.5), (0.1, 0.1)];
+        assert_eq!(non_dominated_sort(&scores), vec![vec![0, 1, 2, 4], vec![3], vec![5]]);
+
+        // the extremes of the front are always kept, and the middle one is as far from both
+        let distance = crowding_distance(&scores, &[0, 1, 2]);
+        assert_eq!(&distance[..], &[f32::INFINITY, 2.0, f32::INFINITY]);
+    }
+
+    #[test]
+    fn test_pareto_front() {
+        let generator = SubtractiveIndividual::new_generator()
+            .target(Arc::new(sine_wave(440.0, 1.0, SAMPLE_RATE as f32, 1.0, 0.0)))
+            .oscillator();
+        let mut simulation: MultiObjectiveGASimulation<SubtractiveIndividual> = GASimulationBuilder::new()
+            .initial_population(20)
+            .max_generations(5)
+            .seed(1)
+            .csv_export("test_pareto.csv")
+            .generator(generator)
+            .build_multi_objective((FitnessType::freq_domain_mse(), FitnessType::time_domain_euclidean()))
+            .unwrap();
+        let front = simulation.run();
+
+        assert!(!front.is_empty());
+        assert_eq!(simulation.population.len(), 20);
+        for a in &front {
+            assert!(front.iter().all(|b| !dominates(b.scores, a.scores)));
+            assert!(simulation.scores.iter().all(|&s| !dominates(s, a.scores)));
+            assert_eq!(a.individual.evaluate(&FitnessType::freq_domain_mse()), a.scores.0);
+        }
+        assert!(front.windows(2).all(|w| w[0].scores.0 >= w[1].scores.0));
+    }
+}
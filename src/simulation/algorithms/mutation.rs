@@ -0,0 +1,73 @@
+use rand::Rng;
+use serde::{Serialize, Deserialize};
+use crate::utils::gaussian;
+
+/// Specifies how a gene changes when it mutates during a crossover.
+#[derive(Copy, Clone, Debug, PartialEq, Default, Serialize, Deserialize)]
+pub enum MutationOperator {
+    /// The gene is replaced by a random value from anywhere within its range, which keeps
+    /// exploring the whole search space but can throw the fittest individuals far from their
+    /// optimum late in a run.
+    #[default]
+    Replace,
+    /// The gene is perturbed by a normally distributed value with a standard deviation of
+    /// `sigma_fraction` times its range, and clamped to its bounds afterwards, so that mutated
+    /// children stay close to their parents. The fraction must be positive.
+    Gaussian { sigma_fraction: f32 },
+}
+
+impl MutationOperator {
+    /// Whether the parameters of the operator are within their valid ranges.
+    pub fn is_valid(&self) -> bool {
+        match self {
+            MutationOperator::Replace => true,
+            MutationOperator::Gaussian { sigma_fraction } => *sigma_fraction > 0.0,
+        }
+    }
+
+    /// Mutates a gene with the given bounds, drawing a value from its whole range with
+    /// `random_gene` when the gene is replaced.
+    pub fn mutate<R: Rng + ?Sized>(
+        &self,
+        gene: f32,
+        bounds: (f32, f32),
+        random_gene: impl FnOnce(&mut R) -> f32,
+        rng: &mut R,
+    ) -> f32 {
+        match self {
+            MutationOperator::Replace => random_gene(rng),
+            MutationOperator::Gaussian { sigma_fraction } => {
+                let (min, max) = bounds;
+                (gene + gaussian(rng) * sigma_fraction * (max - min)).clamp(min, max)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use itertools::Itertools;
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+    use crate::utils::{mean, std};
+
+    #[test]
+    fn test_gaussian_mutation() {
+        let mut rng = StdRng::seed_from_u64(1);
+        let mutation = MutationOperator::Gaussian { sigma_fraction: 0.01 };
+        let mutated = (0..10_000)
+            .map(|_| mutation.mutate(440.0, (20.0, 10_000.0), |_| unreachable!(), &mut rng))
+            .collect_vec();
+
+        // a standard deviation of a hundredth of the range around the original gene
+        let deviation = std(&mutated).sqrt();
+        assert!((mean(&mutated) - 440.0).abs() < 5.0, "{}", mean(&mutated));
+        assert!((90.0..110.0).contains(&deviation), "{deviation}");
+
+        // genes at the bounds stay within them
+        let clamped = (0..1000).map(|_| mutation.mutate(1.0, (0.0, 1.0), |_| unreachable!(), &mut rng));
+        assert!(clamped.into_iter().all(|gene| (0.0..=1.0).contains(&gene)));
+        assert!(!MutationOperator::Gaussian { sigma_fraction: 0.0 }.is_valid());
+    }
+}
@@ -0,0 +1,253 @@
+use std::fmt::Debug;
+use itertools::Itertools;
+use rand::distributions::{Distribution, WeightedIndex};
+use rand::seq::SliceRandom;
+use rand::{Rng, RngCore};
+use serde::{Serialize, Deserialize};
+use crate::simulation::algorithms::genetic::Individual;
+
+/// Decides which individuals of a genetic algorithm survive each generation and which of them
+/// reproduce. Populations are always sorted by decreasing fitness, which is shared between
+/// neighbouring individuals when fitness sharing is enabled.
+///
+/// The strategy is shared between clones of a simulation, which may run on different threads, so
+/// it must be `Send + Sync`. Its methods are only called from the thread running the generation,
+/// never concurrently with the crossovers, but any randomness should be drawn from the given `rng`
+/// for seeded runs to be reproducible.
+pub trait Selection<T: Individual>: Debug + Send + Sync {
+    /// Returns the indices of `n` parents from the population, where consecutive indices are
+    /// paired for crossover.
+    fn select_parents(&self, population: &[T], n: usize, rng: &mut dyn RngCore) -> Vec<usize>;
+
+    /// Returns the indices of the `n` individuals surviving into the next generation. Defaults to
+    /// the `n` fittest ones.
+    fn select_survivors(&self, population: &[T], n: usize, _rng: &mut dyn RngCore) -> Vec<usize> {
+        (0..n.min(population.len())).collect()
+    }
+
+    /// The built-in strategy this selection is, if any, which is the only kind of selection stored
+    /// in checkpoints.
+    fn strategy(&self) -> Option<SelectionStrategy> {
+        None
+    }
+}
+
+/// Specifies how the parents of each generation's offspring are drawn from the individuals that
+/// survive selection.
+#[derive(Copy, Clone, Debug, PartialEq, Default, Serialize, Deserialize)]
+pub enum SelectionStrategy {
+    /// Every survivor takes part in the same number of crossovers with random partners, so the
+    /// selection pressure comes only from discarding the less fit half of the population. When the
+    /// survivors are odd, the one left over in each round is paired with a random partner too.
+    #[default]
+    Uniform,
+    /// Parents are drawn with a probability decreasing linearly with their rank, from
+    /// `pressure / n` for the fittest individual to `(2 - pressure) / n` for the least fit one.
+    /// The pressure must be between 1 (uniform) and 2, and the probabilities do not depend on the
+    /// actual fitness values.
+    LinearRank { pressure: f32 },
+}
+
+impl SelectionStrategy {
+    /// Whether the parameters of the strategy are within their valid ranges.
+    pub fn is_valid(&self) -> bool {
+        match self {
+            SelectionStrategy::Uniform => true,
+            SelectionStrategy::LinearRank { pressure } => (1.0..=2.0).contains(pressure),
+        }
+    }
+
+    /// Draws the indices of `n` parents from a population with the given fitness values, which must
+    /// be sorted in decreasing order. Consecutive indices are meant to be paired for crossover.
+    pub fn select_by_fitness<R: Rng + ?Sized>(&self, fitness: &[f32], n: usize, rng: &mut R) -> Vec<usize> {
+        assert!(self.is_valid(), "The parameters of the selection strategy should be valid");
+        if fitness.is_empty() {
+            return vec![];
+        }
+
+        match self {
+            SelectionStrategy::Uniform => {
+                // shuffle the population once per round, wrapping the odd one out with a random
+                // partner so that every individual takes part in each round
+                let len = fitness.len();
+                let mut indices = (0..len).collect_vec();
+                let mut parents = Vec::with_capacity(n);
+
+                while parents.len() < n && len > 1 {
+                    indices.shuffle(rng);
+                    let mut round = indices.clone();
+                    if len % 2 == 1 {
+                        round.push(round[rng.gen_range(0..len - 1)]);
+                    }
+                    parents.extend(round.into_iter().take(n - parents.len()));
+                }
+
+                parents
+            }
+            SelectionStrategy::LinearRank { pressure } => {
+                let len = fitness.len();
+                let weights = (0..len).map(|rank| {
+                    let position = if len > 1 { rank as f32 / (len - 1) as f32 } else { 0.0 };
+                    pressure - 2.0 * (pressure - 1.0) * position
+                });
+                let distribution = WeightedIndex::new(weights)
+                    .expect("The rank weights should be valid for a pressure between 1 and 2.");
+
+                (0..n).map(|_| distribution.sample(rng)).collect()
+            }
+        }
+    }
+}
+
+/// Specifies how the parents drawn by the selection are paired up for crossover.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum PairingStrategy {
+    /// Keeps the pairs formed by the selection, which are random for the built-in strategies, so
+    /// that the fittest parent is as likely to mate with the least fit one as with another elite.
+    #[default]
+    Random,
+    /// Sorts the parents by fitness and pairs each of them with its neighbour, so that similarly
+    /// fit individuals mate with each other.
+    Assortative,
+    /// Pairs every other parent with a partner drawn from the rest of them with a probability
+    /// proportional to its fitness.
+    FitnessProportional,
+}
+
+impl PairingStrategy {
+    /// Rearranges the indices of the parents drawn by the selection, where consecutive indices are
+    /// paired for crossover, given the fitness of the whole population they index.
+    pub fn pair<R: Rng + ?Sized>(&self, mut parents: Vec<usize>, fitness: &[f32], rng: &mut R) -> Vec<usize> {
+        match self {
+            PairingStrategy::Random => parents,
+            PairingStrategy::Assortative => {
+                parents.sort_by(|&i, &j| fitness[j].total_cmp(&fitness[i]));
+                parents
+            }
+            PairingStrategy::FitnessProportional => {
+                let pool = parents.clone();
+                for pair in parents.chunks_exact_mut(2).enumerate() {
+                    let (k, pair) = pair;
+                    // the first parent of the pair can't be drawn as its own partner
+                    let weights = pool.iter().enumerate()
+                        .map(|(position, &i)| if position == 2 * k { 0.0 } else { fitness[i].max(0.0) });
+                    if let Ok(distribution) = WeightedIndex::new(weights) {
+                        pair[1] = pool[distribution.sample(rng)];
+                    }
+                }
+                parents
+            }
+        }
+    }
+}
+
+/// Divides the fitness of each individual by its niche count, the number of individuals within a
+/// distance of `sigma` including itself, so that crowded regions of the search space are less
+/// likely to take over the whole population. `distance` returns the distance between the
+/// individuals at two indices.
+pub fn shared_fitness(fitness: &[f32], distance: impl Fn(usize, usize) -> f32, sigma: f32) -> Vec<f32> {
+    (0..fitness.len()).map(|i| {
+        let niche_count = (0..fitness.len()).filter(|&j| i == j || distance(i, j) < sigma).count();
+        fitness[i] / niche_count as f32
+    }).collect()
+}
+
+impl<T: Individual> Selection<T> for SelectionStrategy {
+    fn select_parents(&self, population: &[T], n: usize, rng: &mut dyn RngCore) -> Vec<usize> {
+        let fitness = population.iter().map(|i| i.fitness()).collect_vec();
+        self.select_by_fitness(&fitness, n, rng)
+    }
+
+    fn strategy(&self) -> Option<SelectionStrategy> {
+        Some(*self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    #[test]
+    fn test_uniform_selection() {
+        let fitness = [0.9, 0.8, 0.7, 0.6, 0.5];
+        let parents = SelectionStrategy::Uniform.select_by_fitness(&fitness, 12, &mut StdRng::seed_from_u64(1));
+
+        // each round pairs all five individuals once, the odd one out with a random partner
+        assert_eq!(parents.len(), 12);
+        for round in parents.chunks(6) {
+            assert_eq!(round[..5].iter().sorted().copied().collect_vec(), vec![0, 1, 2, 3, 4]);
+            assert_ne!(round[4], round[5]);
+        }
+    }
+
+    #[test]
+    fn test_shared_fitness() {
+        // two tight clusters of individuals, the fittest of which is also the most crowded
+        let positions: [f32; 10] = [0.0, 0.01, 0.02, 0.03, 0.04, 0.05, 1.0, 1.01, 1.02, 1.03];
+        let fitness = [0.95, 0.94, 0.93, 0.92, 0.91, 0.90, 0.85, 0.84, 0.83, 0.82];
+        let survivors = |fitness: &[f32]| {
+            let ranking = (0..fitness.len()).sorted_by(|&i, &j| fitness[j].total_cmp(&fitness[i]));
+            ranking.take(5).map(|i| positions[i] > 0.5).collect_vec()
+        };
+
+        assert!(survivors(&fitness).iter().all(|&in_second| !in_second));
+
+        let shared = shared_fitness(&fitness, |i, j| (positions[i] - positions[j]).abs(), 0.1);
+        let survivors = survivors(&shared);
+        assert!(survivors.contains(&true) && survivors.contains(&false));
+    }
+
+    #[test]
+    fn test_assortative_pairing() {
+        let fitness = (0..20).map(|i| 1.0 - 0.05 * i as f32).collect_vec();
+        let mut rng = StdRng::seed_from_u64(1);
+        let mean_difference = |pairing: PairingStrategy, rng: &mut StdRng| {
+            let differences = (0..100).flat_map(|_| {
+                let parents = SelectionStrategy::Uniform.select_by_fitness(&fitness, 20, rng);
+                pairing.pair(parents, &fitness, rng).chunks_exact(2)
+                    .map(|p| (fitness[p[0]] - fitness[p[1]]).abs())
+                    .collect_vec()
+            }).collect_vec();
+            differences.iter().sum::<f32>() / differences.len() as f32
+        };
+
+        // random pairs of 20 evenly spread values differ by about a third of their range on average,
+        // while neighbours differ by a single step
+        let random = mean_difference(PairingStrategy::Random, &mut rng);
+        let assortative = mean_difference(PairingStrategy::Assortative, &mut rng);
+        assert!((0.25..0.45).contains(&random), "{random}");
+        assert!(assortative < random / 4.0, "{assortative} {random}");
+    }
+
+    #[test]
+    fn test_fitness_proportional_pairing() {
+        // the only fit partner is drawn for every pair but its own
+        let fitness = [0.0, 0.0, 0.0, 1.0, 0.0, 0.0];
+        let paired = PairingStrategy::FitnessProportional.pair(vec![0, 1, 2, 3, 4, 5], &fitness, &mut StdRng::seed_from_u64(1));
+
+        assert_eq!(paired[0], 0);
+        assert_eq!(paired[1], 3);
+        assert_eq!(&paired[4..], &[4, 3]);
+        assert_ne!(paired[3], 2);
+    }
+
+    #[test]
+    fn test_linear_rank_selection() {
+        let strategy = SelectionStrategy::LinearRank { pressure: 1.8 };
+        let wide = (0..20).map(|i| 1.0 - 0.04 * i as f32).collect_vec();
+        let narrow = (0..20).map(|i| 1.0 - 0.0004 * i as f32).collect_vec();
+
+        let wide_parents = strategy.select_by_fitness(&wide, 10_000, &mut StdRng::seed_from_u64(1));
+        let narrow_parents = strategy.select_by_fitness(&narrow, 10_000, &mut StdRng::seed_from_u64(1));
+        assert_eq!(wide_parents, narrow_parents);
+
+        // the fittest individual is drawn about 1.8 / 0.2 times as often as the least fit one
+        let counts = wide_parents.iter().counts();
+        let ratio = counts[&0] as f32 / counts[&19] as f32;
+        assert!((6.0..12.0).contains(&ratio), "{ratio}");
+
+        assert!(!SelectionStrategy::LinearRank { pressure: 2.5 }.is_valid());
+    }
+}
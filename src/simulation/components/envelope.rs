@@ -1,48 +1,156 @@
-use crate::utils::random_weighted_average;
-use rand::{thread_rng, Rng};
+use crate::simulation::algorithms::crossover::CrossoverOperator;
+use crate::simulation::algorithms::hillclimbing::evolve_value;
+use crate::simulation::algorithms::mutation::MutationOperator;
+use crate::utils::normalised_distance;
+use rand::Rng;
+use serde::{Serialize, Deserialize};
 
-#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+const MAX_ATTACK: f32 = 2.0;
+const MAX_DECAY: f32 = 3.0;
+const MAX_RELEASE: f32 = 5.0;
+
+/// Shape of the segments of an envelope between two levels.
+#[derive(Copy, Clone, PartialEq, Eq, Debug, Default, Serialize, Deserialize)]
+pub enum CurveShape {
+    /// The level changes at a constant rate.
+    #[default]
+    Linear,
+    /// The level changes quickly at first and slows down as it approaches the next one, like the
+    /// charge and discharge of a capacitor in analogue envelopes.
+    Exponential,
+}
+
+/// Represents the ADSR amplitude envelope in subtractive synthesis.
+#[derive(Copy, Clone, PartialEq, Debug, Serialize, Deserialize)]
 pub struct EnvelopeComponent {
-    attack: u32,  // ms
-    decay: u32,   // ms
-    sustain: u8,  // level 0 - 255
-    release: u32, // ms
+    /// Time to rise from silence to the full level, in seconds between 0 and 2.
+    pub attack: f32,
+    /// Time to fall from the full level to the sustain level, in seconds between 0 and 3.
+    pub decay: f32,
+    /// Level held after the decay until the release, between 0 and 1.
+    pub sustain_level: f32,
+    /// Time to fall from the sustain level to silence, in seconds between 0 and 5.
+    pub release: f32,
+    /// Shape of the attack, decay and release.
+    pub curve: CurveShape,
 }
 
 impl EnvelopeComponent {
-    pub(crate) fn create() -> Self {
-        let mut rng = thread_rng();
+    pub(crate) fn create<R: Rng + ?Sized>(rng: &mut R) -> Self {
+        let genes = (0..5).map(|i| Self::random_gene(i, rng)).collect::<Vec<f32>>();
+        Self::from_genes(&genes)
+    }
+
+    /// Produces two complementary offspring from the envelopes of two parents.
+    pub(crate) fn combine<R: Rng + ?Sized>(&self, other: &Self, r: f32, operator: CrossoverOperator, mutation: MutationOperator, rng: &mut R) -> Option<(Self, Self)> {
+        let (genes_a, genes_b) = operator.combine(&self.genes(), &other.genes(), r, mutation, Self::random_gene, Self::bounds, rng);
+
+        Some((Self::from_genes(&genes_a), Self::from_genes(&genes_b)))
+    }
+
+    /// The attack, decay, sustain level, release and curve of the envelope, which is the order used
+    /// for crossover. The curve is the middle of the half of [0, 1] that maps to it in `from_genes`,
+    /// so that large steps can change it while small ones keep it.
+    pub(crate) fn genes(&self) -> Vec<f32> {
+        let curve = match self.curve {
+            CurveShape::Linear => 0.25,
+            CurveShape::Exponential => 0.75,
+        };
+        vec![self.attack, self.decay, self.sustain_level, self.release, curve]
+    }
+
+    /// Distance between the parameters of two envelopes, between 0 and 1.
+    pub(crate) fn distance(&self, other: &Self) -> f32 {
+        normalised_distance(&self.genes(), &other.genes(), Self::bounds)
+    }
+
+    /// The minimum and maximum values of the gene at the given index of `genes`.
+    fn bounds(i: usize) -> (f32, f32) {
+        match i {
+            0 => (0.0, MAX_ATTACK),
+            1 => (0.0, MAX_DECAY),
+            3 => (0.0, MAX_RELEASE),
+            _ => (0.0, 1.0),
+        }
+    }
+
+    /// Draws a random value for the gene at the given index of `genes`.
+    fn random_gene<R: Rng + ?Sized>(i: usize, rng: &mut R) -> f32 {
+        let (min_v, max_v) = Self::bounds(i);
+        rng.gen_range(min_v..max_v)
+    }
 
+    /// Builds an envelope from the parameters in the order returned by `genes`. The curve is linear
+    /// if its gene is below 0.5, and exponential otherwise.
+    pub(crate) fn from_genes(genes: &[f32]) -> Self {
         Self {
-            attack: rng.gen_range(0..2000),
-            decay: rng.gen_range(0..3000),
-            sustain: rng.gen_range(0..255) as u8,
-            release: rng.gen_range(0..5000),
+            attack: genes[0],
+            decay: genes[1],
+            sustain_level: genes[2],
+            release: genes[3],
+            curve: if genes[4] < 0.5 { CurveShape::Linear } else { CurveShape::Exponential },
         }
     }
 
-    pub(crate) fn combine(&self, other: &Self, r: f32) -> Option<Self> {
-        let mut rng = thread_rng();
-
-        Some(
-            Self {
-                attack: random_weighted_average(self.attack as f32, other.attack as f32, r,
-                    rng.gen_range(0..2000) as f32,
-                ) as u32,
-                decay: random_weighted_average(self.decay as f32, other.decay as f32, r,
-                    rng.gen_range(0..3000) as f32,
-                ) as u32,
-                sustain: random_weighted_average(self.sustain as f32, other.sustain as f32, r,
-                    rng.gen_range(0..255) as f32,
-                ) as u8,
-                release: random_weighted_average(self.release as f32, other.release as f32, r,
-                    rng.gen_range(0..5000) as f32,
-                ) as u32,
-            }
-        )
+    pub(crate) fn evolve<R: Rng + ?Sized>(&self, step_size: f32, rng: &mut R) -> Self {
+        let genes = self.genes().into_iter()
+            .enumerate()
+            .map(|(i, gene)| {
+                let (min_v, max_v) = Self::bounds(i);
+                evolve_value(gene, min_v, max_v, step_size, rng)
+            })
+            .collect::<Vec<f32>>();
+
+        Self::from_genes(&genes)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::SeedableRng;
+    use rand::rngs::StdRng;
+
+    #[test]
+    fn test_combine_fields() {
+        let mut rng = StdRng::seed_from_u64(7);
+
+        // every field is inherited from the same one of the parents
+        for _ in 0..100 {
+            let envelope = EnvelopeComponent::create(&mut rng);
+            let (a, b) = envelope.combine(&envelope, 0.0, CrossoverOperator::Uniform, MutationOperator::Replace, &mut rng).unwrap();
+            assert_eq!((a, b), (envelope, envelope));
+        }
     }
 
-    pub(crate) fn evolve(&self, step_size: f32) -> Self {
-        todo!()
+    #[test]
+    fn test_serde_round_trip() {
+        let envelope = EnvelopeComponent::create(&mut StdRng::seed_from_u64(8));
+        let bytes = bincode::serialize(&envelope).unwrap();
+        assert_eq!(bincode::deserialize::<EnvelopeComponent>(&bytes).unwrap(), envelope);
+    }
+
+    #[test]
+    fn test_evolve_bounds() {
+        let mut rng = StdRng::seed_from_u64(8);
+        let mut envelope = EnvelopeComponent::create(&mut rng);
+        let mut moved = [false; 5];
+
+        // every parameter stays within its bounds however large the steps
+        for _ in 0..1000 {
+            let evolved = envelope.evolve(1.0, &mut rng);
+            for (i, (a, b)) in envelope.genes().into_iter().zip(evolved.genes()).enumerate() {
+                let (min_v, max_v) = EnvelopeComponent::bounds(i);
+                assert!((min_v..=max_v).contains(&b), "gene {i} moved to {b}");
+                moved[i] |= a != b;
+            }
+            envelope = evolved;
+        }
+        assert_eq!(moved, [true; 5]);
+
+        // small steps keep the curve
+        for _ in 0..100 {
+            assert_eq!(envelope.evolve(1e-3, &mut rng).curve, envelope.curve);
+        }
     }
 }
@@ -1,16 +1,32 @@
 use std::char::MAX;
-use rand::rngs::ThreadRng;
-use rand::{Rng, thread_rng};
+use rand::Rng;
 use crate::simulation::algorithms::hillclimbing::evolve_value;
-use crate::utils::random_weighted_average;
+use crate::simulation::algorithms::crossover::CrossoverOperator;
+use crate::simulation::algorithms::mutation::MutationOperator;
+use crate::signal_processing::SAMPLE_RATE;
+use crate::simulation::components::GeneScale;
+use crate::utils::normalised_distance;
+use serde::{Serialize, Deserialize};
 
 const MIN_FREQ: f32 = 0.0;
-const MAX_FREQ: f32 = 20_000.0;
+/// Lowest frequency an envelope can sweep a filter down to. A cutoff of 0 Hz turns the sinc taps
+/// into NaNs and puts the poles of a biquad on the unit circle.
+const MIN_MODULATED_FREQ: f32 = 20.0;
+const MAX_FREQ: f32 = max_freq(SAMPLE_RATE as f32);
+/// Highest frequency of a filter as a fraction of the sample rate, a little below the Nyquist
+/// frequency so that the main lobe of its sinc fits in the spectrum.
+const MAX_FREQ_RATIO: f32 = 0.45;
 const MIN_BAND: f32 = 0.01;
 const MAX_BAND: f32 = 4.0;
-
-#[derive(Copy, Clone, Debug, PartialEq)]
-pub(crate) enum FilterComponent {
+const MIN_Q: f32 = 0.3;
+const MAX_Q: f32 = 20.0;
+const MAX_MOD_AMOUNT: f32 = 10_000.0;
+const MAX_MOD_ATTACK: f32 = 2.0;
+const MAX_MOD_DECAY: f32 = 3.0;
+
+/// Represents the filter applied to the oscillators in subtractive synthesis.
+#[derive(Copy, Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum FilterComponent {
     LowPass {
         cutoff_freq: f32,
         band: f32,
@@ -29,35 +45,69 @@ pub(crate) enum FilterComponent {
         high_freq: f32,
         band: f32
     },
+    /// Recursive second-order filter, which unlike the others can resonate around its cutoff.
+    Biquad {
+        kind: BiquadKind,
+        cutoff: f32,
+        /// Quality factor, between 0.3 and 20. The higher it is, the narrower and louder the
+        /// resonance around the cutoff.
+        q: f32,
+    },
+}
+
+/// Response of a biquad filter around its cutoff.
+#[derive(Copy, Clone, PartialEq, Eq, Debug, Serialize, Deserialize)]
+pub enum BiquadKind {
+    LowPass,
+    HighPass,
+    BandPass,
+    Notch,
+}
+
+/// Modulates the frequencies of the filter over time: they rise by `amount` over the attack, and
+/// fall back to their values over the decay.
+#[derive(Copy, Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct FilterEnvelope {
+    /// Offset added to the frequencies of the filter at the peak of the envelope, in Hz between
+    /// -10 000 and 10 000. A negative amount sweeps them down instead.
+    pub amount: f32,
+    /// Time to rise from no offset to the full amount, in seconds between 0 and 2.
+    pub attack: f32,
+    /// Time to fall from the full amount back to no offset, in seconds between 0 and 3.
+    pub decay: f32,
 }
 
-#[derive(Copy, Clone)]
+/// Highest frequency of a filter for a signal with the given sample rate.
+pub(crate) const fn max_freq(sample_rate: f32) -> f32 {
+    MAX_FREQ_RATIO * sample_rate
+}
+
+#[derive(Copy, Clone, Serialize, Deserialize)]
 pub enum FilterType {
     LowPass,
     HighPass,
     BandPass,
-    BandReject
+    BandReject,
+    Biquad(BiquadKind),
 }
 
 impl FilterComponent {
-    pub(crate) fn create(filter_type: FilterType) -> Self {
-        let mut rng = thread_rng();
-
+    pub(crate) fn create<R: Rng + ?Sized>(filter_type: FilterType, rng: &mut R) -> Self {
         match filter_type {
             FilterType::LowPass => {
                 Self::LowPass {
-                    cutoff_freq: Self::random_freq(&mut rng),
-                    band: Self::random_band(&mut rng),
+                    cutoff_freq: Self::random_freq(rng),
+                    band: Self::random_band(rng),
                 }
             }
             FilterType::HighPass => {
                 Self::HighPass {
-                    cutoff_freq: Self::random_freq(&mut rng),
-                    band: Self::random_band(&mut rng),
+                    cutoff_freq: Self::random_freq(rng),
+                    band: Self::random_band(rng),
                 }
             }
             FilterType::BandPass => {
-                let (freq_1, freq_2) = (Self::random_freq(&mut rng), Self::random_freq(&mut rng));
+                let (freq_1, freq_2) = (Self::random_freq(rng), Self::random_freq(rng));
 
                 let (low_freq, high_freq) = if freq_1 < freq_2 {
                     (freq_1, freq_2)
@@ -65,7 +115,7 @@ impl FilterComponent {
                     (freq_2, freq_1)
                 };
 
-                let band = Self::random_band(&mut rng);
+                let band = Self::random_band(rng);
 
                 Self::BandPass {
                     low_freq,
@@ -74,7 +124,7 @@ impl FilterComponent {
                 }
             }
             FilterType::BandReject => {
-                let (freq_1, freq_2) = (Self::random_freq(&mut rng), Self::random_freq(&mut rng));
+                let (freq_1, freq_2) = (Self::random_freq(rng), Self::random_freq(rng));
 
                 let (low_freq, high_freq) = if freq_1 < freq_2 {
                     (freq_1, freq_2)
@@ -82,7 +132,7 @@ impl FilterComponent {
                     (freq_2, freq_1)
                 };
 
-                let band = Self::random_band(&mut rng);
+                let band = Self::random_band(rng);
 
                 Self::BandReject {
                     low_freq,
@@ -90,177 +140,136 @@ impl FilterComponent {
                     band,
                 }
             }
+            FilterType::Biquad(kind) => {
+                Self::Biquad {
+                    kind,
+                    cutoff: Self::random_freq(rng),
+                    q: Self::random_q(rng),
+                }
+            }
         }
     }
 
-    pub(crate) fn combine(&self, other: &Self, mutation_rate: f32) -> Option<Self> {
-        let mut rng = thread_rng();
-
-        match (self, other) {
-            (
-                Self::LowPass {
-                    cutoff_freq: self_cutoff_freq, band: self_band
-                },
-                Self::LowPass {
-                    cutoff_freq: other_cutoff_freq, band: other_band
-                }
-            ) => {
-                Some(
-                    Self::LowPass {
-                        cutoff_freq: random_weighted_average(
-                            *self_cutoff_freq,
-                            *other_cutoff_freq,
-                            mutation_rate,
-                            Self::random_freq(&mut rng)
-                        ),
-                        band: random_weighted_average(
-                            *self_band,
-                            *other_band,
-                            mutation_rate,
-                            Self::random_band(&mut rng)
-                        )
-                    }
-                )
-            },
+    /// Produces two complementary offspring from the filters of two parents, as long as both are of
+    /// the same type.
+    pub(crate) fn combine<R: Rng + ?Sized>(&self, other: &Self, mutation_rate: f32, operator: CrossoverOperator, mutation: MutationOperator, rng: &mut R) -> Option<(Self, Self)> {
+        if !self.same_type(other) {
+            return None;
+        }
 
-            (
-                Self::HighPass {
-                    cutoff_freq: self_cutoff_freq, band: self_band
-                },
-                Self::HighPass {
-                    cutoff_freq: other_cutoff_freq, band: other_band
-                }
-            ) => {
-                Some(
-                    Self::HighPass {
-                        cutoff_freq: random_weighted_average(
-                            *self_cutoff_freq,
-                            *other_cutoff_freq,
-                            mutation_rate,
-                            Self::random_freq(&mut rng)
-                        ),
-                        band: random_weighted_average(
-                            *self_band,
-                            *other_band,
-                            mutation_rate,
-                            Self::random_band(&mut rng)
-                        )
-                    }
-                )
+        // the band or the q is always the last gene, after the frequencies. The q is combined in
+        // log space, as it is evolved
+        let genes = self.genes();
+        let gene_scale = |i: usize| match (self, i == genes.len() - 1) {
+            (Self::Biquad { .. }, true) => GeneScale::Logarithmic,
+            _ => GeneScale::Linear,
+        };
+        let scaled = |genes: Vec<f32>| genes.into_iter().enumerate().map(|(i, g)| gene_scale(i).scale(g)).collect::<Vec<_>>();
+        let unscaled = |genes: Vec<f32>| genes.into_iter().enumerate().map(|(i, g)| gene_scale(i).unscale(g)).collect::<Vec<_>>();
+        let random_gene = |i, rng: &mut R| gene_scale(i).scale(match (self, i == genes.len() - 1) {
+            (Self::Biquad { .. }, true) => Self::random_q(rng),
+            (_, true) => Self::random_band(rng),
+            _ => Self::random_freq(rng),
+        });
+        let (genes_a, genes_b) = operator.combine(
+            &scaled(genes.clone()),
+            &scaled(other.genes()),
+            mutation_rate,
+            mutation,
+            random_gene,
+            |i| {
+                let (min_v, max_v) = self.bounds(i);
+                (gene_scale(i).scale(min_v), gene_scale(i).scale(max_v))
             },
+            rng
+        );
 
-            (
-                Self::BandPass {
-                    low_freq: self_low_freq, high_freq: self_high_freq, band: self_band
-                },
-                Self::BandPass {
-                    low_freq: other_low_freq, high_freq: other_high_freq, band: other_band
-                }
-            ) => {
-                // We don't know which of the generated frequencies is going to be higher, so we will
-                // re-assign the low and high frequency bounds once both are generated.
-                let freq_1 = random_weighted_average(
-                    *self_low_freq,
-                    *other_low_freq,
-                    mutation_rate,
-                    Self::random_freq(&mut rng)
-                );
-
-                let freq_2 = random_weighted_average(
-                    *self_high_freq,
-                    *other_high_freq,
-                    mutation_rate,
-                    Self::random_freq(&mut rng)
-                );
+        Some((self.with_genes(&unscaled(genes_a)), self.with_genes(&unscaled(genes_b))))
+    }
 
-                let (low_freq, high_freq) = if freq_1 < freq_2 {
-                    (freq_1, freq_2)
-                } else {
-                    (freq_2, freq_1)
-                };
+    /// The frequencies of the filter followed by its band, or by its q for a biquad, which is the
+    /// order used for crossover.
+    pub(crate) fn genes(&self) -> Vec<f32> {
+        match *self {
+            Self::LowPass { cutoff_freq, band } | Self::HighPass { cutoff_freq, band } => vec![cutoff_freq, band],
+            Self::BandPass { low_freq, high_freq, band } | Self::BandReject { low_freq, high_freq, band } => {
+                vec![low_freq, high_freq, band]
+            }
+            Self::Biquad { cutoff, q, .. } => vec![cutoff, q],
+        }
+    }
 
-                let band = random_weighted_average(
-                    *self_band,
-                    *other_band,
-                    mutation_rate,
-                    Self::random_band(&mut rng)
-                );
-
-                Some(
-                    Self::BandPass {
-                        low_freq,
-                        high_freq,
-                        band,
-                    }
-                )
-            },
+    /// The minimum and maximum values of the gene at the given index of `genes`.
+    fn bounds(&self, i: usize) -> (f32, f32) {
+        match (self, i == self.genes().len() - 1) {
+            (Self::Biquad { .. }, true) => (MIN_Q, MAX_Q),
+            (_, true) => (MIN_BAND, MAX_BAND),
+            _ => (MIN_FREQ, MAX_FREQ),
+        }
+    }
 
-            (
-                Self::BandReject {
-                    low_freq: self_low_freq, high_freq: self_high_freq, band: self_band
-                },
-                Self::BandReject {
-                    low_freq: other_low_freq, high_freq: other_high_freq, band: other_band
-                }
-            ) => {
-                let freq_1 = random_weighted_average(
-                    *self_low_freq,
-                    *other_low_freq,
-                    mutation_rate,
-                    Self::random_freq(&mut rng)
-                );
-
-                let freq_2 = random_weighted_average(
-                    *self_high_freq,
-                    *other_high_freq,
-                    mutation_rate,
-                    Self::random_freq(&mut rng)
-                );
+    /// Distance between the parameters of two filters, between 0 and 1. Filters of different types
+    /// are considered as far apart as possible.
+    pub(crate) fn distance(&self, other: &Self) -> f32 {
+        if !self.same_type(other) {
+            return 1.0;
+        }
 
-                let (low_freq, high_freq) = if freq_1 < freq_2 {
-                    (freq_1, freq_2)
-                } else {
-                    (freq_2, freq_1)
-                };
+        normalised_distance(&self.genes(), &other.genes(), |i| self.bounds(i))
+    }
 
-                let band = random_weighted_average(
-                    *self_band,
-                    *other_band,
-                    mutation_rate,
-                    Self::random_band(&mut rng)
-                );
-
-                Some(
-                    Self::BandReject {
-                        low_freq,
-                        high_freq,
-                        band,
-                    }
-                )
-            },
-            _ => None
+    /// Whether two filters are of the same type, including the kind of a biquad.
+    fn same_type(&self, other: &Self) -> bool {
+        match (self, other) {
+            (Self::Biquad { kind: a, .. }, Self::Biquad { kind: b, .. }) => a == b,
+            _ => std::mem::discriminant(self) == std::mem::discriminant(other),
         }
     }
 
-    pub(crate) fn evolve(&self, step_size: f32) -> Self {
-        let mut rng = thread_rng();
+    /// Builds a filter of the same type from the parameters in the order returned by `genes`. We
+    /// don't know which of the frequencies of a band is going to be higher after a crossover, so the
+    /// low and high bounds are re-assigned. The parameters are clamped to their bounds.
+    pub(crate) fn with_genes(&self, genes: &[f32]) -> Self {
+        let genes: Vec<f32> = genes.iter()
+            .enumerate()
+            .map(|(i, &gene)| {
+                let (min_v, max_v) = self.bounds(i);
+                gene.clamp(min_v, max_v)
+            })
+            .collect();
+
+        match self {
+            Self::LowPass { .. } => Self::LowPass { cutoff_freq: genes[0], band: genes[1] },
+            Self::HighPass { .. } => Self::HighPass { cutoff_freq: genes[0], band: genes[1] },
+            Self::BandPass { .. } => {
+                let (low_freq, high_freq) = Self::ordered(genes[0], genes[1]);
+                Self::BandPass { low_freq, high_freq, band: genes[2] }
+            }
+            Self::BandReject { .. } => {
+                let (low_freq, high_freq) = Self::ordered(genes[0], genes[1]);
+                Self::BandReject { low_freq, high_freq, band: genes[2] }
+            }
+            Self::Biquad { kind, .. } => Self::Biquad { kind: *kind, cutoff: genes[0], q: genes[1] },
+        }
+    }
 
+    pub(crate) fn evolve<R: Rng + ?Sized>(&self, step_size: f32, rng: &mut R) -> Self {
         match self {
             FilterComponent::LowPass { cutoff_freq, band } => {
                 Self::LowPass {
-                    cutoff_freq: evolve_value(*cutoff_freq, MIN_FREQ, MAX_FREQ, step_size, &mut rng),
-                    band: evolve_value(*band, MIN_BAND, MAX_BAND, step_size, &mut rng),
+                    cutoff_freq: evolve_value(*cutoff_freq, MIN_FREQ, MAX_FREQ, step_size, rng),
+                    band: evolve_value(*band, MIN_BAND, MAX_BAND, step_size, rng),
                 }
             }
             FilterComponent::HighPass { cutoff_freq, band} => {
                 Self::HighPass {
-                    cutoff_freq: evolve_value(*cutoff_freq, MIN_FREQ, MAX_FREQ, step_size, &mut rng),
-                    band: evolve_value(*band, MIN_BAND, MAX_BAND, step_size, &mut rng),
+                    cutoff_freq: evolve_value(*cutoff_freq, MIN_FREQ, MAX_FREQ, step_size, rng),
+                    band: evolve_value(*band, MIN_BAND, MAX_BAND, step_size, rng),
                 }
             }
             FilterComponent::BandPass { low_freq, high_freq, band } => {
-                let freq_1 = evolve_value(*low_freq, MIN_FREQ, MAX_FREQ, step_size, &mut rng);
-                let freq_2 = evolve_value(*high_freq, MIN_FREQ, MAX_FREQ, step_size, &mut rng);
+                let freq_1 = evolve_value(*low_freq, MIN_FREQ, MAX_FREQ, step_size, rng);
+                let freq_2 = evolve_value(*high_freq, MIN_FREQ, MAX_FREQ, step_size, rng);
 
                 let (low_freq, high_freq) = if freq_1 < freq_2 {
                     (freq_1, freq_2)
@@ -268,7 +277,7 @@ impl FilterComponent {
                     (freq_2, freq_1)
                 };
 
-                let band = evolve_value(*band, MIN_BAND, MAX_BAND, step_size, &mut rng);
+                let band = evolve_value(*band, MIN_BAND, MAX_BAND, step_size, rng);
 
                 Self::BandPass {
                     low_freq,
@@ -277,8 +286,8 @@ impl FilterComponent {
                 }
             }
             FilterComponent::BandReject { low_freq, high_freq, band } => {
-                let freq_1 = evolve_value(*low_freq, MIN_FREQ, MAX_FREQ, step_size, &mut rng);
-                let freq_2 = evolve_value(*high_freq, MIN_FREQ, MAX_FREQ, step_size, &mut rng);
+                let freq_1 = evolve_value(*low_freq, MIN_FREQ, MAX_FREQ, step_size, rng);
+                let freq_2 = evolve_value(*high_freq, MIN_FREQ, MAX_FREQ, step_size, rng);
 
                 let (low_freq, high_freq) = if freq_1 < freq_2 {
                     (freq_1, freq_2)
@@ -286,7 +295,7 @@ impl FilterComponent {
                     (freq_2, freq_1)
                 };
 
-                let band = evolve_value(*band, MIN_BAND, MAX_BAND, step_size, &mut rng);
+                let band = evolve_value(*band, MIN_BAND, MAX_BAND, step_size, rng);
 
                 Self::BandReject {
                     low_freq,
@@ -294,14 +303,279 @@ impl FilterComponent {
                     band
                 }
             }
+            FilterComponent::Biquad { kind, cutoff, q } => {
+                Self::Biquad {
+                    kind: *kind,
+                    cutoff: evolve_value(*cutoff, MIN_FREQ, MAX_FREQ, step_size, rng),
+                    // the resonance is heard as a ratio, so the q is evolved in log space
+                    q: GeneScale::Logarithmic.evolve_value(*q, MIN_Q, MAX_Q, step_size, rng),
+                }
+            }
         }
     }
 
-    fn random_freq(rng: &mut ThreadRng) -> f32 {
+    fn random_freq<R: Rng + ?Sized>(rng: &mut R) -> f32 {
         rng.gen_range(MIN_FREQ..MAX_FREQ)
     }
 
-    fn random_band(rng: &mut ThreadRng) -> f32 {
+    fn random_band<R: Rng + ?Sized>(rng: &mut R) -> f32 {
         rng.gen_range(MIN_BAND..MAX_BAND)
     }
-}
\ No newline at end of file
+
+    fn random_q<R: Rng + ?Sized>(rng: &mut R) -> f32 {
+        rng.gen_range(MIN_Q..MAX_Q)
+    }
+
+    /// The same filter with its frequencies moved by `offset` Hz, within their bounds and no lower
+    /// than [`MIN_MODULATED_FREQ`].
+    pub(crate) fn shifted(&self, offset: f32) -> Self {
+        let mut genes = self.genes();
+        let n_freqs = genes.len() - 1;
+        for freq in &mut genes[..n_freqs] {
+            *freq = (*freq + offset).max(MIN_MODULATED_FREQ);
+        }
+        self.with_genes(&genes)
+    }
+
+    /// Sorts two frequencies into a (low, high) pair.
+    fn ordered(freq_1: f32, freq_2: f32) -> (f32, f32) {
+        if freq_1 < freq_2 {
+            (freq_1, freq_2)
+        } else {
+            (freq_2, freq_1)
+        }
+    }
+}
+
+impl FilterEnvelope {
+    pub(crate) fn create<R: Rng + ?Sized>(rng: &mut R) -> Self {
+        let genes = (0..3).map(|i| Self::random_gene(i, rng)).collect::<Vec<f32>>();
+        Self::from_genes(&genes)
+    }
+
+    /// Produces two complementary offspring from the filter envelopes of two parents.
+    pub(crate) fn combine<R: Rng + ?Sized>(&self, other: &Self, r: f32, operator: CrossoverOperator, mutation: MutationOperator, rng: &mut R) -> Option<(Self, Self)> {
+        let (genes_a, genes_b) = operator.combine(&self.genes(), &other.genes(), r, mutation, Self::random_gene, Self::bounds, rng);
+
+        Some((Self::from_genes(&genes_a), Self::from_genes(&genes_b)))
+    }
+
+    /// The amount, attack and decay of the envelope, which is the order used for crossover.
+    pub(crate) fn genes(&self) -> Vec<f32> {
+        vec![self.amount, self.attack, self.decay]
+    }
+
+    /// Distance between the parameters of two filter envelopes, between 0 and 1.
+    pub(crate) fn distance(&self, other: &Self) -> f32 {
+        normalised_distance(&self.genes(), &other.genes(), Self::bounds)
+    }
+
+    /// The minimum and maximum values of the gene at the given index of `genes`.
+    fn bounds(i: usize) -> (f32, f32) {
+        match i {
+            0 => (-MAX_MOD_AMOUNT, MAX_MOD_AMOUNT),
+            1 => (0.0, MAX_MOD_ATTACK),
+            _ => (0.0, MAX_MOD_DECAY),
+        }
+    }
+
+    /// Draws a random value for the gene at the given index of `genes`.
+    fn random_gene<R: Rng + ?Sized>(i: usize, rng: &mut R) -> f32 {
+        let (min_v, max_v) = Self::bounds(i);
+        rng.gen_range(min_v..max_v)
+    }
+
+    /// Builds a filter envelope from the parameters in the order returned by `genes`.
+    pub(crate) fn from_genes(genes: &[f32]) -> Self {
+        Self { amount: genes[0], attack: genes[1], decay: genes[2] }
+    }
+
+    pub(crate) fn evolve<R: Rng + ?Sized>(&self, step_size: f32, rng: &mut R) -> Self {
+        let genes = self.genes().into_iter()
+            .enumerate()
+            .map(|(i, gene)| {
+                let (min_v, max_v) = Self::bounds(i);
+                evolve_value(gene, min_v, max_v, step_size, rng)
+            })
+            .collect::<Vec<f32>>();
+
+        Self::from_genes(&genes)
+    }
+
+    /// Level of the envelope `seconds` after the start of the signal, between 0 and 1. It rises
+    /// linearly over the attack and falls linearly over the decay, staying at 0 afterwards.
+    pub(crate) fn level(&self, seconds: f32) -> f32 {
+        if seconds < self.attack {
+            seconds / self.attack
+        } else if seconds < self.attack + self.decay {
+            1.0 - (seconds - self.attack) / self.decay
+        } else {
+            0.0
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::SeedableRng;
+    use rand::rngs::StdRng;
+
+    const FILTER_TYPES: [FilterType; 6] = [
+        FilterType::LowPass,
+        FilterType::HighPass,
+        FilterType::BandPass,
+        FilterType::BandReject,
+        FilterType::Biquad(BiquadKind::LowPass),
+        FilterType::Biquad(BiquadKind::Notch),
+    ];
+
+    #[test]
+    fn test_small_steps() {
+        let mut rng = StdRng::seed_from_u64(7);
+        let fields = |filter: &FilterComponent| match *filter {
+            FilterComponent::LowPass { cutoff_freq, band } | FilterComponent::HighPass { cutoff_freq, band } => {
+                vec![(cutoff_freq, MAX_FREQ - MIN_FREQ), (band, MAX_BAND - MIN_BAND)]
+            }
+            FilterComponent::BandPass { low_freq, high_freq, band } | FilterComponent::BandReject { low_freq, high_freq, band } => {
+                vec![(low_freq, MAX_FREQ - MIN_FREQ), (high_freq, MAX_FREQ - MIN_FREQ), (band, MAX_BAND - MIN_BAND)]
+            }
+            FilterComponent::Biquad { cutoff, q, .. } => vec![(cutoff, MAX_FREQ - MIN_FREQ), (q, MAX_Q - MIN_Q)],
+        };
+
+        for filter_type in FILTER_TYPES {
+            for _ in 0..100 {
+                let filter = FilterComponent::create(filter_type, &mut rng);
+
+                // every field stays close to its value, in proportion to its range
+                let evolved = filter.evolve(1e-6, &mut rng);
+                assert_eq!(std::mem::discriminant(&filter), std::mem::discriminant(&evolved));
+                for ((a, range), (b, _)) in fields(&filter).into_iter().zip(fields(&evolved)) {
+                    assert!((a - b).abs() <= 1e-5 * range, "{filter:?} moved to {evolved:?}");
+                }
+
+                // every field is inherited from the same one of the parents, up to the rounding of
+                // the q in log space
+                let (a, b) = filter.combine(&filter, 0.0, CrossoverOperator::Uniform, MutationOperator::Replace, &mut rng).unwrap();
+                for child in [a, b] {
+                    assert_eq!(std::mem::discriminant(&filter), std::mem::discriminant(&child));
+                    for ((a, range), (b, _)) in fields(&filter).into_iter().zip(fields(&child)) {
+                        assert!((a - b).abs() <= 1e-6 * range, "{filter:?} became {child:?}");
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_serde_round_trip() {
+        let mut rng = StdRng::seed_from_u64(8);
+
+        for filter_type in FILTER_TYPES {
+            let filter = FilterComponent::create(filter_type, &mut rng);
+            let bytes = bincode::serialize(&filter).unwrap();
+            assert_eq!(bincode::deserialize::<FilterComponent>(&bytes).unwrap(), filter);
+        }
+    }
+
+    #[test]
+    fn test_shifted() {
+        let low_pass = FilterComponent::LowPass { cutoff_freq: 1_000.0, band: 0.5 };
+        assert_eq!(low_pass.shifted(500.0), FilterComponent::LowPass { cutoff_freq: 1_500.0, band: 0.5 });
+        assert_eq!(low_pass.shifted(-5_000.0), FilterComponent::LowPass { cutoff_freq: MIN_MODULATED_FREQ, band: 0.5 });
+
+        // both frequencies of a band move, and stay within their bounds
+        let band_pass = FilterComponent::BandPass { low_freq: 1_000.0, high_freq: 15_000.0, band: 0.5 };
+        assert_eq!(band_pass.shifted(8_000.0), FilterComponent::BandPass { low_freq: 9_000.0, high_freq: MAX_FREQ, band: 0.5 });
+    }
+
+    #[test]
+    fn test_filter_envelope() {
+        let envelope = FilterEnvelope { amount: 2_000.0, attack: 0.5, decay: 1.0 };
+        assert_eq!(envelope.level(0.0), 0.0);
+        assert_eq!(envelope.level(0.25), 0.5);
+        assert_eq!(envelope.level(0.5), 1.0);
+        assert_eq!(envelope.level(1.0), 0.5);
+        assert_eq!(envelope.level(2.0), 0.0);
+
+        // without an attack it starts at its peak
+        assert_eq!(FilterEnvelope { attack: 0.0, ..envelope }.level(0.0), 1.0);
+
+        let mut rng = StdRng::seed_from_u64(9);
+        let mut envelope = FilterEnvelope::create(&mut rng);
+        for _ in 0..1000 {
+            envelope = envelope.evolve(1.0, &mut rng);
+            for (i, gene) in envelope.genes().into_iter().enumerate() {
+                let (min_v, max_v) = FilterEnvelope::bounds(i);
+                assert!((min_v..=max_v).contains(&gene), "gene {i} moved to {gene}");
+            }
+        }
+
+        let (a, b) = envelope.combine(&envelope, 0.0, CrossoverOperator::Uniform, MutationOperator::Replace, &mut rng).unwrap();
+        assert_eq!((a, b), (envelope, envelope));
+    }
+
+    #[test]
+    fn test_biquad_genes() {
+        let mut rng = StdRng::seed_from_u64(10);
+        let mut filter = FilterComponent::create(FilterType::Biquad(BiquadKind::BandPass), &mut rng);
+
+        // the q stays within its bounds however large the steps, and the kind never changes
+        for _ in 0..1000 {
+            filter = filter.evolve(1.0, &mut rng);
+            let FilterComponent::Biquad { kind, q, .. } = filter else { panic!("{filter:?} is not a biquad") };
+            assert_eq!(kind, BiquadKind::BandPass);
+            assert!((MIN_Q..=MAX_Q).contains(&q), "q moved to {q}");
+        }
+
+        // biquads of different kinds can't be combined
+        let notch = FilterComponent::Biquad { kind: BiquadKind::Notch, cutoff: 1_000.0, q: 1.0 };
+        assert_eq!(filter.combine(&notch, 0.0, CrossoverOperator::Uniform, MutationOperator::Replace, &mut rng), None);
+        assert_eq!(filter.distance(&notch), 1.0);
+
+        // arithmetic crossover blends the q in log space, preserving the product of the parents'
+        let (a, b) = (
+            FilterComponent::Biquad { kind: BiquadKind::Notch, cutoff: 1_000.0, q: 0.5 },
+            FilterComponent::Biquad { kind: BiquadKind::Notch, cutoff: 2_000.0, q: 8.0 },
+        );
+        for _ in 0..100 {
+            let (c, d) = a.combine(&b, 0.0, CrossoverOperator::Arithmetic, MutationOperator::Replace, &mut rng).unwrap();
+            let (FilterComponent::Biquad { q: q_c, .. }, FilterComponent::Biquad { q: q_d, .. }) = (c, d) else { panic!("{c:?} and {d:?} are not biquads") };
+            assert!((q_c * q_d / 4.0 - 1.0).abs() < 1e-4, "{q_c} and {q_d}");
+        }
+
+        // and the q stays within its bounds however far it is pushed
+        for q in [MIN_Q, MAX_Q] {
+            let filter = FilterComponent::Biquad { kind: BiquadKind::Notch, cutoff: 1_000.0, q };
+            for _ in 0..100 {
+                let (c, d) = filter.combine(&filter, 1.0, CrossoverOperator::SBX { eta: 0.0 }, MutationOperator::Gaussian { sigma_fraction: 1.0 }, &mut rng).unwrap();
+                for child in [c, d] {
+                    let FilterComponent::Biquad { q, .. } = child else { panic!("{child:?} is not a biquad") };
+                    assert!((MIN_Q..=MAX_Q).contains(&q), "q moved to {q}");
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_nyquist_bounds() {
+        // the frequencies stay below the Nyquist frequency at the engine's rate, and also would at
+        // 22.05 kHz
+        assert!(MAX_FREQ < SAMPLE_RATE as f32 / 2.0);
+        assert!(max_freq(22_050.0) < 11_025.0);
+
+        let mut rng = StdRng::seed_from_u64(12);
+        for filter_type in FILTER_TYPES {
+            let mut filter = FilterComponent::create(filter_type, &mut rng);
+            for _ in 0..100 {
+                let other = FilterComponent::create(filter_type, &mut rng).evolve(1.0, &mut rng);
+                filter = filter.combine(&other, 0.5, CrossoverOperator::Uniform, MutationOperator::Replace, &mut rng).unwrap().0;
+                assert!(filter.genes().iter().enumerate().all(|(i, &gene)| gene <= filter.bounds(i).1), "{filter:?}");
+            }
+        }
+
+        // and those of a filter built beyond them, such as a deserialised one, are clamped to them
+        let low_pass = FilterComponent::LowPass { cutoff_freq: 20_000.0, band: 0.5 };
+        assert_eq!(low_pass.with_genes(&low_pass.genes()), FilterComponent::LowPass { cutoff_freq: MAX_FREQ, band: 0.5 });
+    }
+}
@@ -1,60 +1,172 @@
-use rand::rngs::ThreadRng;
-use rand::{Rng, thread_rng};
+use rand::Rng;
 use crate::simulation::algorithms::hillclimbing::evolve_value;
-use crate::utils::random_weighted_average;
+use crate::simulation::algorithms::crossover::CrossoverOperator;
+use crate::simulation::algorithms::mutation::MutationOperator;
+use crate::simulation::components::GeneScale;
+use crate::utils::normalised_distance;
+use serde::{Serialize, Deserialize};
 
 const MIN_FREQ: f32 = 20.0;
 const MAX_FREQ: f32 = 10_000.0;
 
 /// Represents the component containing the harmonics information in additive synthesis.
-#[derive(Clone, Debug, PartialEq, PartialOrd)]
+#[derive(Clone, Debug, PartialEq, PartialOrd, Serialize, Deserialize)]
 pub struct HarmonicsComponent {
     /// Fundamental frequency of the harmonic series.
     pub freq: f32,
     /// Amplitudes of each of the n harmonics.
-    pub amplitudes: Vec<f32>
+    pub amplitudes: Vec<f32>,
+    /// Scale on which the fundamental is combined and evolved.
+    pub freq_scale: GeneScale,
 }
 
 impl HarmonicsComponent {
 
-    pub(crate) fn create() -> Self {
-        let mut rng = thread_rng();
-        let freq = Self::random_freq(&mut rng);
+    pub(crate) fn create<R: Rng + ?Sized>(rng: &mut R) -> Self {
+        let freq = Self::random_freq(rng);
         let n = 9;
         let amplitudes = (0..n).map(|_| rng.gen()).collect();
 
         Self {
             freq,
-            amplitudes
+            amplitudes,
+            freq_scale: GeneScale::Linear,
         }
     }
 
-    pub(crate) fn combine(&self, other: &Self, r: f32) -> Option<Self> where Self: Sized {
-        let mut rng = thread_rng();
+    /// Creates a new harmonics component whose fundamental is drawn from the given range instead of
+    /// the whole audible range. The amplitudes are fully random.
+    pub(crate) fn create_in_range<R: Rng + ?Sized>(freq_range: (f32, f32), rng: &mut R) -> Self {
+        let (low, high) = (freq_range.0.max(MIN_FREQ), freq_range.1.min(MAX_FREQ));
 
-        let freq = random_weighted_average(self.freq, other.freq, r, Self::random_freq(&mut rng));
-        let amplitudes = self.amplitudes.iter().zip(&other.amplitudes).map(|(&s, &o)| {
-            random_weighted_average(s, o, r, rng.gen())
-        }).collect();
+        let freq = if low < high { rng.gen_range(low..high) } else { low };
 
-        Some(
-            Self {
-                freq,
-                amplitudes
-            }
-        )
+        Self {
+            freq,
+            ..Self::create(rng)
+        }
     }
 
-    pub(crate) fn evolve(&self, step_size: f32) -> Self {
-        let mut rng = thread_rng();
+    /// Produces two complementary offspring from the harmonics of two parents. If their number of
+    /// harmonics differs, the offspring only keep the ones both parents have.
+    pub(crate) fn combine<R: Rng + ?Sized>(&self, other: &Self, r: f32, operator: CrossoverOperator, mutation: MutationOperator, rng: &mut R) -> Option<(Self, Self)> where Self: Sized {
+        let freq_scale = self.freq_scale;
+        let random_gene = |i, rng: &mut R| if i == 0 { freq_scale.scale(Self::random_freq(rng)) } else { rng.gen() };
+        let bounds = |i| if i == 0 { (freq_scale.scale(MIN_FREQ), freq_scale.scale(MAX_FREQ)) } else { (0.0, 1.0) };
+        let scaled = |harmonics: &Self| {
+            let mut genes = harmonics.genes();
+            genes[0] = freq_scale.scale(genes[0]);
+            genes
+        };
+        let unscaled = |mut genes: Vec<f32>| {
+            genes[0] = freq_scale.unscale(genes[0]).clamp(MIN_FREQ, MAX_FREQ);
+            Self { freq_scale, ..Self::from_genes(&genes) }
+        };
+        let (genes_a, genes_b) = operator.combine(&scaled(self), &scaled(other), r, mutation, random_gene, bounds, rng);
 
+        Some((unscaled(genes_a), unscaled(genes_b)))
+    }
+
+    /// The fundamental frequency followed by the amplitudes, which is the order used for crossover.
+    pub(crate) fn genes(&self) -> Vec<f32> {
+        std::iter::once(self.freq).chain(self.amplitudes.iter().copied()).collect()
+    }
+
+    /// Distance between the fundamentals and amplitudes of two harmonics components, between 0
+    /// and 1. The fundamentals are compared in log space, so that the same interval counts the same
+    /// regardless of the octave.
+    pub(crate) fn distance(&self, other: &Self) -> f32 {
+        let log_genes = |harmonics: &Self| {
+            let mut genes = harmonics.genes();
+            genes[0] = genes[0].log2();
+            genes
+        };
+        let range = |i| if i == 0 { (MIN_FREQ.log2(), MAX_FREQ.log2()) } else { (0.0, 1.0) };
+        normalised_distance(&log_genes(self), &log_genes(other), range)
+    }
+
+    /// Builds a harmonics component from the parameters in the order returned by `genes`.
+    pub(crate) fn from_genes(genes: &[f32]) -> Self {
+        Self {
+            freq: genes[0],
+            amplitudes: genes[1..].to_vec(),
+            freq_scale: GeneScale::Linear,
+        }
+    }
+
+    pub(crate) fn evolve<R: Rng + ?Sized>(&self, step_size: f32, rng: &mut R) -> Self {
         Self {
-            freq: evolve_value(self.freq, 20.0, 10_000.0, step_size, &mut rng),
-            amplitudes: self.amplitudes.iter().map(|&a| evolve_value(a, 0.0, 1.0, step_size, &mut rng)).collect()
+            freq: self.freq_scale.evolve_value(self.freq, 20.0, 10_000.0, step_size, rng),
+            amplitudes: self.amplitudes.iter().map(|&a| evolve_value(a, 0.0, 1.0, step_size, rng)).collect(),
+            freq_scale: self.freq_scale,
         }
     }
 
-    fn random_freq(rng: &mut ThreadRng) -> f32 {
+    fn random_freq<R: Rng + ?Sized>(rng: &mut R) -> f32 {
         rng.gen_range(MIN_FREQ..MAX_FREQ)
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::SeedableRng;
+    use rand::rngs::StdRng;
+
+    #[test]
+    fn test_small_steps() {
+        let mut rng = StdRng::seed_from_u64(7);
+
+        for _ in 0..100 {
+            let harmonics = HarmonicsComponent::create(&mut rng);
+
+            // every field stays close to its value, in proportion to its range
+            let evolved = harmonics.evolve(1e-6, &mut rng);
+            assert!((harmonics.freq - evolved.freq).abs() <= 1e-5 * (MAX_FREQ - MIN_FREQ));
+            assert_eq!(harmonics.amplitudes.len(), evolved.amplitudes.len());
+            assert!(harmonics.amplitudes.iter().zip(&evolved.amplitudes).all(|(a, b)| (a - b).abs() <= 1e-5));
+
+            // every field is inherited from the same one of the parents
+            let (a, b) = harmonics.combine(&harmonics, 0.0, CrossoverOperator::Uniform, MutationOperator::Replace, &mut rng).unwrap();
+            assert_eq!((&a, &b), (&harmonics, &harmonics));
+        }
+    }
+
+    #[test]
+    fn test_log_frequency() {
+        let mut rng = StdRng::seed_from_u64(5);
+        let with_freq = |freq| HarmonicsComponent {
+            freq,
+            freq_scale: GeneScale::Logarithmic,
+            ..HarmonicsComponent::create(&mut StdRng::seed_from_u64(0))
+        };
+
+        // arithmetic crossover blends the fundamentals in log space, preserving their product, and
+        // the offspring keep the scale
+        let (a, b) = (with_freq(100.0), with_freq(400.0));
+        for _ in 0..100 {
+            let (c, d) = a.combine(&b, 0.0, CrossoverOperator::Arithmetic, MutationOperator::Replace, &mut rng).unwrap();
+            assert!((c.freq * d.freq / 40_000.0 - 1.0).abs() < 1e-4, "{} and {}", c.freq, d.freq);
+            assert_eq!((c.freq_scale, d.freq_scale), (GeneScale::Logarithmic, GeneScale::Logarithmic));
+        }
+
+        // the fundamental stays within its bounds
+        for harmonics in [with_freq(MIN_FREQ), with_freq(MAX_FREQ)] {
+            for _ in 0..100 {
+                let evolved = harmonics.evolve(1.0, &mut rng);
+                assert!((MIN_FREQ..=MAX_FREQ).contains(&evolved.freq));
+                assert_eq!(evolved.freq_scale, GeneScale::Logarithmic);
+            }
+        }
+    }
+
+    #[test]
+    fn test_serde_round_trip() {
+        let harmonics = HarmonicsComponent {
+            freq_scale: GeneScale::Logarithmic,
+            ..HarmonicsComponent::create(&mut StdRng::seed_from_u64(8))
+        };
+        let bytes = bincode::serialize(&harmonics).unwrap();
+        assert_eq!(bincode::deserialize::<HarmonicsComponent>(&bytes).unwrap(), harmonics);
+    }
+}
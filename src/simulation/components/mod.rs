@@ -1,8 +1,70 @@
-pub(crate) mod filters;
-pub(crate) mod envelope;
-pub(crate) mod harmonics;
+pub mod filters;
+pub mod envelope;
+pub mod harmonics;
 pub mod oscillator;
 
+use rand::Rng;
+use serde::{Serialize, Deserialize};
+use crate::simulation::algorithms::hillclimbing::evolve_value;
+
+/// Scale on which a gene is combined and evolved.
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Debug, Default, Serialize, Deserialize)]
+pub enum GeneScale {
+    /// The gene is used as it is.
+    #[default]
+    Linear,
+    /// The gene is used as its base 2 logarithm, so that the same ratio counts the same
+    /// everywhere in its range. Suited to frequencies, where a step is then the same interval
+    /// in every octave.
+    Logarithmic,
+}
+
+impl GeneScale {
+    /// Converts a value to the scale of the gene.
+    pub(crate) fn scale(self, val: f32) -> f32 {
+        match self {
+            GeneScale::Linear => val,
+            GeneScale::Logarithmic => val.log2(),
+        }
+    }
+
+    /// Converts a gene back to the original value.
+    pub(crate) fn unscale(self, gene: f32) -> f32 {
+        match self {
+            GeneScale::Linear => gene,
+            GeneScale::Logarithmic => gene.exp2(),
+        }
+    }
+
+    /// Like [`evolve_value`], but drawing the new value on this scale. The result stays within
+    /// [`min_v`, `max_v`].
+    pub(crate) fn evolve_value<R: Rng + ?Sized>(self, val: f32, min_v: f32, max_v: f32, step_size: f32, rng: &mut R) -> f32 {
+        let gene = evolve_value(self.scale(val), self.scale(min_v), self.scale(max_v), step_size, rng);
+        self.unscale(gene).clamp(min_v, max_v)
+    }
+}
+
+/// Distance between the optional components of two individuals, where a component missing from
+/// only one of them counts as the largest distance.
+pub(crate) fn component_distance<C>(a: Option<&C>, b: Option<&C>, distance: impl Fn(&C, &C) -> f32) -> f32 {
+    match (a, b) {
+        (Some(a), Some(b)) => distance(a, b),
+        (None, None) => 0.0,
+        _ => 1.0,
+    }
+}
+
+/// Distance between the lists of components of two individuals, averaged over the components at
+/// the same positions. Those missing from one of the lists count as the largest distance.
+pub(crate) fn components_distance<C>(a: &[C], b: &[C], distance: impl Fn(&C, &C) -> f32) -> f32 {
+    let n = a.len().max(b.len());
+    if n == 0 {
+        return 0.0;
+    }
+
+    (0..n).map(|i| component_distance(a.get(i), b.get(i), &distance)).sum::<f32>() / n as f32
+}
+
 // pub trait Component {
 //     type Params;
 //     /// Creates a new component.
@@ -1,8 +1,11 @@
-use crate::utils::random_weighted_average;
-use rand::rngs::ThreadRng;
-use rand::{thread_rng, Rng};
+use rand::Rng;
 use std::f32::consts::PI;
+use crate::simulation::algorithms::crossover::CrossoverOperator;
+use crate::simulation::algorithms::mutation::MutationOperator;
 use crate::simulation::algorithms::hillclimbing::evolve_value;
+use crate::simulation::components::GeneScale;
+use crate::utils::normalised_distance;
+use serde::{Serialize, Deserialize};
 
 const MIN_FREQ: f32 = 20.0;
 const MAX_FREQ: f32 = 10_000.0;
@@ -10,8 +13,102 @@ const MIN_AMP: f32 = 0.0;
 const MAX_AMP: f32 = 1.0;
 const MIN_PHASE: f32 = 0.0;
 const MAX_PHASE: f32 = 2.0 * PI;
+const MIN_DUTY: f32 = 0.05;
+const MAX_DUTY: f32 = 0.95;
+const MIN_VOICES: u8 = 1;
+const MAX_VOICES: u8 = 7;
+const MAX_DETUNE: f32 = 50.0;
+const MIN_SLAVE_RATIO: f32 = 1.0;
+const MAX_SLAVE_RATIO: f32 = 8.0;
+const N_GENES: usize = 16;
 
-#[derive(Copy, Clone, PartialEq, Debug)]
+/// How the waveforms of an oscillator are scaled when they are summed, so that candidates can be
+/// kept within ±1 like the normalised recordings they are compared to.
+#[derive(Copy, Clone, PartialEq, Eq, Debug, Default, Serialize, Deserialize)]
+pub enum MixNormalisation {
+    /// The waveforms are summed at their raw amplitudes, which may exceed ±1.
+    #[default]
+    None,
+    /// The mix is divided by the sum of the amplitudes of the waveforms, which then only set their
+    /// relative levels.
+    SumToOne,
+    /// The rendered signal is scaled down so that its peak is at most 1.
+    PeakLimit,
+}
+
+/// How the waveforms with discontinuities, i.e. the square, saw and pulse waves, are rendered.
+#[derive(Copy, Clone, PartialEq, Eq, Debug, Default, Serialize, Deserialize)]
+pub enum WaveformQuality {
+    /// The waveforms jump from one sample to the next, so their harmonics above the Nyquist
+    /// frequency alias into the audible range.
+    #[default]
+    Naive,
+    /// The jumps are smoothed with PolyBLEP, which removes most of the aliasing.
+    BandLimited,
+}
+
+/// A waveform mixed by the oscillator.
+#[derive(Copy, Clone, PartialEq, Eq, Debug, Serialize, Deserialize)]
+pub enum Waveform {
+    Sine,
+    Square,
+    Saw,
+    Triangle,
+    Pulse,
+    Noise,
+}
+
+/// Set of the waveforms an oscillator mixes. The genes of the rest are left out of the genome, so
+/// they are never mutated and the waveforms stay silent.
+#[derive(Copy, Clone, PartialEq, Eq, Debug, Serialize, Deserialize)]
+pub struct Waveforms(u8);
+
+impl Waveforms {
+    /// Every waveform of the oscillator.
+    pub const ALL: Self = Waveforms(0b11_1111);
+
+    /// Only the given waveforms.
+    pub fn of(waveforms: &[Waveform]) -> Self {
+        Waveforms(waveforms.iter().fold(0, |bits, &waveform| bits | Self::bit(waveform)))
+    }
+
+    pub fn contains(self, waveform: Waveform) -> bool {
+        self.0 & Self::bit(waveform) != 0
+    }
+
+    fn bit(waveform: Waveform) -> u8 {
+        1 << waveform as u8
+    }
+}
+
+impl Default for Waveforms {
+    fn default() -> Self {
+        Self::ALL
+    }
+}
+
+/// Settings of an oscillator that are not genes. They are chosen on the generator, and offspring
+/// inherit them from their parents.
+#[derive(Copy, Clone, PartialEq, Eq, Debug, Default, Serialize, Deserialize)]
+pub struct OscillatorSettings {
+    /// The waveforms that are mixed, whose genes are the only ones that evolve. The genes of the
+    /// rest are zero.
+    pub waveforms: Waveforms,
+    /// How the waveforms are scaled when they are summed.
+    pub mix: MixNormalisation,
+    /// How the waveforms with discontinuities are rendered.
+    pub quality: WaveformQuality,
+    /// Whether the number of unison voices and their detune are genes. Otherwise the oscillator
+    /// renders a single voice.
+    pub unison: bool,
+    /// Scale on which the frequency is combined and evolved.
+    pub freq_scale: GeneScale,
+    /// Whether the square and saw waves are hard-synced slaves of `freq`, whose ratio to it is a
+    /// gene. Synced waves are always rendered naively.
+    pub sync: bool,
+}
+
+#[derive(Copy, Clone, PartialEq, Debug, Serialize, Deserialize)]
 pub struct OscillatorComponent {
     pub freq: f32,
     pub sine_amp: f32,
@@ -20,85 +117,575 @@ pub struct OscillatorComponent {
     pub square_phase: f32,
     pub saw_amp: f32,
     pub saw_phase: f32,
+    pub tri_amp: f32,
+    pub tri_phase: f32,
+    pub pulse_amp: f32,
+    pub pulse_phase: f32,
+    /// Fraction of every cycle the pulse wave is high for.
+    pub duty: f32,
+    pub noise_amp: f32,
+    /// Number of copies of every waveform but the noise that are stacked, between 1 and 7.
+    pub unison_voices: u8,
+    /// Spread of the unison voices, whose frequencies are spaced evenly within `±detune_cents` of
+    /// `freq`.
+    pub detune_cents: f32,
+    /// Frequency of the hard-synced square and saw waves relative to `freq`, between 1 and 8. Their
+    /// phase is reset at the start of every cycle of `freq`.
+    pub slave_ratio: f32,
+    /// Seed of the white noise, so that the oscillator always renders the same signal. It is not a
+    /// gene, and offspring inherit it from their parents.
+    pub noise_seed: u64,
+    pub settings: OscillatorSettings,
 }
 
 impl OscillatorComponent {
-    pub(crate) fn create() -> Self {
-        let mut rng = thread_rng();
+    /// Creates a new oscillator with the given settings whose genes are random.
+    pub(crate) fn create<R: Rng + ?Sized>(settings: OscillatorSettings, rng: &mut R) -> Self {
+        let genes = (0..N_GENES)
+            .map(|i| if Self::is_enabled(settings, i) { Self::random_gene(i, rng) } else { Self::default_gene(i) })
+            .collect::<Vec<f32>>();
 
         Self {
-            freq: Self::random_freq(&mut rng),
-            sine_amp: Self::random_sine_amp(&mut rng),
-            sine_phase: Self::random_sine_phase(&mut rng),
-            square_amp: Self::random_square_amp(&mut rng),
-            square_phase: Self::random_square_phase(&mut rng),
-            saw_amp: Self::random_saw_amp(&mut rng),
-            saw_phase: Self::random_saw_phase(&mut rng),
-        }
-    }
-
-    pub(crate) fn combine(&self, other: &Self, mutation_rate: f32) -> Option<Self> {
-        let mut rng = thread_rng();
-
-        Some(
-            Self {
-                freq: random_weighted_average(self.freq, other.freq, mutation_rate, Self::random_freq(&mut rng)),
-                sine_amp: random_weighted_average(self.sine_amp, other.sine_amp, mutation_rate, Self::random_sine_amp(&mut rng)),
-                sine_phase: random_weighted_average(self.sine_phase, other.sine_phase, mutation_rate, Self::random_sine_phase(&mut rng)),
-                square_amp: random_weighted_average(self.square_amp, other.square_amp, mutation_rate, Self::random_square_amp(&mut rng)),
-                square_phase: random_weighted_average(self.square_phase, other.square_phase, mutation_rate, Self::random_square_phase(&mut rng)),
-                saw_amp: random_weighted_average(self.saw_amp, other.saw_amp, mutation_rate, Self::random_saw_amp(&mut rng)),
-                saw_phase: random_weighted_average(self.saw_phase, other.saw_phase, mutation_rate, Self::random_saw_phase(&mut rng)),
-            }
-        )
+            noise_seed: rng.gen(),
+            settings,
+            ..Self::from_genes(&genes)
+        }
+    }
+
+    /// Creates a new oscillator whose frequency is drawn from the given range instead of the whole
+    /// audible range. The rest of the parameters are fully random.
+    pub(crate) fn create_in_range<R: Rng + ?Sized>(settings: OscillatorSettings, freq_range: (f32, f32), rng: &mut R) -> Self {
+        let (low, high) = (freq_range.0.max(MIN_FREQ), freq_range.1.min(MAX_FREQ));
+
+        let freq = if low < high { rng.gen_range(low..high) } else { low };
+
+        Self {
+            freq,
+            ..Self::create(settings, rng)
+        }
+    }
+
+    /// Produces two complementary offspring from the oscillators of two parents.
+    pub(crate) fn combine<R: Rng + ?Sized>(&self, other: &Self, mutation_rate: f32, operator: CrossoverOperator, mutation: MutationOperator, rng: &mut R) -> Option<(Self, Self)> {
+        let indices = self.gene_indices();
+        let gene_scale = |i: usize| if indices[i] == 0 { self.settings.freq_scale } else { GeneScale::Linear };
+        let scaled = |genes: Vec<f32>| genes.into_iter().enumerate().map(|(i, g)| gene_scale(i).scale(g)).collect::<Vec<_>>();
+        let unscaled = |genes: Vec<f32>| genes.into_iter().enumerate()
+            .map(|(i, g)| {
+                let (min_v, max_v) = Self::bounds(indices[i]);
+                gene_scale(i).unscale(g).clamp(min_v, max_v)
+            })
+            .collect::<Vec<_>>();
+        let (genes_a, genes_b) = operator.combine(
+            &scaled(self.genes()),
+            &scaled(other.genes()),
+            mutation_rate,
+            mutation,
+            |i, rng| gene_scale(i).scale(Self::random_gene(indices[i], rng)),
+            |i| {
+                let (min_v, max_v) = Self::bounds(indices[i]);
+                (gene_scale(i).scale(min_v), gene_scale(i).scale(max_v))
+            },
+            rng
+        );
+
+        Some((self.with_genes(&unscaled(genes_a)), other.with_genes(&unscaled(genes_b))))
+    }
+
+    /// The parameters of the oscillator that are genes, in the order used for crossover.
+    pub(crate) fn genes(&self) -> Vec<f32> {
+        let genes = self.all_genes();
+        self.gene_indices().into_iter().map(|i| genes[i]).collect()
+    }
+
+    /// Every parameter of the oscillator, whether it is a gene or not, in the order of `from_genes`.
+    fn all_genes(&self) -> Vec<f32> {
+        vec![
+            self.freq,
+            self.sine_amp,
+            self.sine_phase,
+            self.square_amp,
+            self.square_phase,
+            self.saw_amp,
+            self.saw_phase,
+            self.tri_amp,
+            self.tri_phase,
+            self.pulse_amp,
+            self.pulse_phase,
+            self.duty,
+            self.noise_amp,
+            self.unison_voices as f32,
+            self.detune_cents,
+            self.slave_ratio,
+        ]
+    }
+
+    /// Distance between the parameters of two oscillators, between 0 and 1.
+    pub(crate) fn distance(&self, other: &Self) -> f32 {
+        let indices = self.gene_indices();
+        normalised_distance(&self.genes(), &other.genes(), |i| Self::bounds(indices[i]))
     }
 
-    pub(crate) fn evolve(&self, step_size: f32) -> Self {
-        let mut rng = thread_rng();
+    /// Builds an oscillator from all of its parameters in the order of the fields, with the default
+    /// settings. Its noise is drawn from the seed 0.
+    pub(crate) fn from_genes(genes: &[f32]) -> Self {
+        Self {
+            freq: genes[0],
+            sine_amp: genes[1],
+            sine_phase: genes[2],
+            square_amp: genes[3],
+            square_phase: genes[4],
+            saw_amp: genes[5],
+            saw_phase: genes[6],
+            tri_amp: genes[7],
+            tri_phase: genes[8],
+            pulse_amp: genes[9],
+            pulse_phase: genes[10],
+            duty: genes[11],
+            noise_amp: genes[12],
+            unison_voices: Self::voices(genes[13]),
+            detune_cents: genes[14],
+            slave_ratio: genes[15],
+            noise_seed: 0,
+            settings: OscillatorSettings::default(),
+        }
+    }
+
+    /// A copy of the oscillator whose genes take the values in the order returned by `genes`.
+    fn with_genes(&self, genes: &[f32]) -> Self {
+        let mut all_genes = self.all_genes();
+        for (i, &gene) in self.gene_indices().into_iter().zip(genes) {
+            all_genes[i] = gene;
+        }
+
+        Self {
+            noise_seed: self.noise_seed,
+            settings: self.settings,
+            ..Self::from_genes(&all_genes)
+        }
+    }
+
+    pub(crate) fn evolve<R: Rng + ?Sized>(&self, step_size: f32, rng: &mut R) -> Self {
+        // freq: self.freq + Self::random_freq(rng) * step_size,
+        let settings = self.settings;
+        let freq = settings.freq_scale.evolve_value(self.freq, MIN_FREQ, MAX_FREQ, step_size, rng);
+        let enabled = |waveform| settings.waveforms.contains(waveform);
+        let mut evolve = |enabled, val, min_v, max_v| {
+            if enabled { evolve_value(val, min_v, max_v, step_size, rng) } else { val }
+        };
 
         Self {
-            // freq: self.freq + Self::random_freq(&mut rng) * step_size,
-            freq: evolve_value(self.freq, MIN_FREQ, MAX_FREQ, step_size, &mut rng),
-            sine_amp: evolve_value(self.sine_amp, MIN_AMP, MAX_AMP, step_size, &mut rng),
-            sine_phase: evolve_value(self.sine_phase, MIN_PHASE, MAX_PHASE, step_size, &mut rng),
-            square_amp: evolve_value(self.square_amp, MIN_AMP, MAX_AMP, step_size, &mut rng),
-            square_phase: evolve_value(self.square_phase, MIN_PHASE, MAX_PHASE, step_size, &mut rng),
-            saw_amp: evolve_value(self.saw_amp, MIN_AMP, MAX_AMP, step_size, &mut rng),
-            saw_phase: evolve_value(self.saw_amp, MIN_PHASE, MAX_PHASE, step_size, &mut rng),
+            freq,
+            sine_amp: evolve(enabled(Waveform::Sine), self.sine_amp, MIN_AMP, MAX_AMP),
+            sine_phase: evolve(enabled(Waveform::Sine), self.sine_phase, MIN_PHASE, MAX_PHASE),
+            square_amp: evolve(enabled(Waveform::Square), self.square_amp, MIN_AMP, MAX_AMP),
+            square_phase: evolve(enabled(Waveform::Square), self.square_phase, MIN_PHASE, MAX_PHASE),
+            saw_amp: evolve(enabled(Waveform::Saw), self.saw_amp, MIN_AMP, MAX_AMP),
+            saw_phase: evolve(enabled(Waveform::Saw), self.saw_phase, MIN_PHASE, MAX_PHASE),
+            tri_amp: evolve(enabled(Waveform::Triangle), self.tri_amp, MIN_AMP, MAX_AMP),
+            tri_phase: evolve(enabled(Waveform::Triangle), self.tri_phase, MIN_PHASE, MAX_PHASE),
+            pulse_amp: evolve(enabled(Waveform::Pulse), self.pulse_amp, MIN_AMP, MAX_AMP),
+            pulse_phase: evolve(enabled(Waveform::Pulse), self.pulse_phase, MIN_PHASE, MAX_PHASE),
+            duty: evolve(enabled(Waveform::Pulse), self.duty, MIN_DUTY, MAX_DUTY),
+            noise_amp: evolve(enabled(Waveform::Noise), self.noise_amp, MIN_AMP, MAX_AMP),
+            unison_voices: Self::voices(evolve(settings.unison, self.unison_voices as f32, MIN_VOICES as f32, MAX_VOICES as f32)),
+            detune_cents: evolve(settings.unison, self.detune_cents, 0.0, MAX_DETUNE),
+            slave_ratio: evolve(settings.sync, self.slave_ratio, MIN_SLAVE_RATIO, MAX_SLAVE_RATIO),
+            noise_seed: self.noise_seed,
+            settings,
         }
     }
+
+    /// Frequencies of the unison voices, spaced evenly in cents within `±detune_cents` of `freq`.
+    pub(crate) fn voice_freqs(&self) -> Vec<f32> {
+        if self.unison_voices <= 1 {
+            return vec![self.freq];
+        }
+
+        let last = (self.unison_voices - 1) as f32;
+        (0..self.unison_voices)
+            .map(|voice| {
+                let cents = self.detune_cents * (2.0 * voice as f32 / last - 1.0);
+                self.freq * 2f32.powf(cents / 1200.0)
+            })
+            .collect()
+    }
 }
 
 impl OscillatorComponent {
-    fn random_freq(rng: &mut ThreadRng) -> f32 {
+    /// The waveform whose parameter is the gene at the given index of `from_genes`, or none for the
+    /// frequency, the unison and the sync shared by several of them.
+    fn gene_waveform(i: usize) -> Option<Waveform> {
+        match i {
+            1 | 2 => Some(Waveform::Sine),
+            3 | 4 => Some(Waveform::Square),
+            5 | 6 => Some(Waveform::Saw),
+            7 | 8 => Some(Waveform::Triangle),
+            9..=11 => Some(Waveform::Pulse),
+            12 => Some(Waveform::Noise),
+            _ => None,
+        }
+    }
+
+    /// Whether the parameter at the given index of `from_genes` is a gene under the given settings.
+    fn is_enabled(settings: OscillatorSettings, i: usize) -> bool {
+        match i {
+            13 | 14 => settings.unison,
+            15 => settings.sync,
+            _ => Self::gene_waveform(i).is_none_or(|waveform| settings.waveforms.contains(waveform)),
+        }
+    }
+
+    /// The value of a parameter that is not a gene: a single voice, a slave ratio of 1, and zero for
+    /// the rest.
+    fn default_gene(i: usize) -> f32 {
+        match i {
+            13 => MIN_VOICES as f32,
+            15 => MIN_SLAVE_RATIO,
+            _ => 0.0,
+        }
+    }
+
+    /// Rounds the gene of the unison voices to a whole number of them within its bounds.
+    fn voices(gene: f32) -> u8 {
+        gene.round().clamp(MIN_VOICES as f32, MAX_VOICES as f32) as u8
+    }
+
+    /// The indices in `from_genes` of the genes returned by `genes`.
+    fn gene_indices(&self) -> Vec<usize> {
+        (0..N_GENES).filter(|&i| Self::is_enabled(self.settings, i)).collect()
+    }
+
+    /// The minimum and maximum values of the gene at the given index of `from_genes`.
+    fn bounds(i: usize) -> (f32, f32) {
+        match i {
+            0 => (MIN_FREQ, MAX_FREQ),
+            1 | 3 | 5 | 7 | 9 | 12 => (MIN_AMP, MAX_AMP),
+            11 => (MIN_DUTY, MAX_DUTY),
+            13 => (MIN_VOICES as f32, MAX_VOICES as f32),
+            14 => (0.0, MAX_DETUNE),
+            15 => (MIN_SLAVE_RATIO, MAX_SLAVE_RATIO),
+            _ => (MIN_PHASE, MAX_PHASE),
+        }
+    }
+
+    /// Draws a random value for the gene at the given index of `from_genes`.
+    fn random_gene<R: Rng + ?Sized>(i: usize, rng: &mut R) -> f32 {
+        match i {
+            0 => Self::random_freq(rng),
+            1 => Self::random_sine_amp(rng),
+            2 => Self::random_sine_phase(rng),
+            3 => Self::random_square_amp(rng),
+            4 => Self::random_square_phase(rng),
+            5 => Self::random_saw_amp(rng),
+            6 => Self::random_saw_phase(rng),
+            7 => Self::random_tri_amp(rng),
+            8 => Self::random_tri_phase(rng),
+            9 => Self::random_pulse_amp(rng),
+            10 => Self::random_pulse_phase(rng),
+            11 => Self::random_duty(rng),
+            12 => Self::random_noise_amp(rng),
+            13 => Self::random_voices(rng) as f32,
+            14 => Self::random_detune(rng),
+            _ => Self::random_slave_ratio(rng),
+        }
+    }
+
+    fn random_freq<R: Rng + ?Sized>(rng: &mut R) -> f32 {
         rng.gen_range(MIN_FREQ..MAX_FREQ)
     }
 
-    fn random_sine_amp(rng: &mut ThreadRng) -> f32 {
+    fn random_sine_amp<R: Rng + ?Sized>(rng: &mut R) -> f32 {
         rng.gen()
     }
 
-    fn random_sine_phase(rng: &mut ThreadRng) -> f32 {
+    fn random_sine_phase<R: Rng + ?Sized>(rng: &mut R) -> f32 {
         rng.gen_range(MIN_PHASE..MAX_PHASE)
     }
 
-    fn random_square_amp(rng: &mut ThreadRng) -> f32 {
+    fn random_square_amp<R: Rng + ?Sized>(rng: &mut R) -> f32 {
         rng.gen()
     }
 
-    fn random_square_phase(rng: &mut ThreadRng) -> f32 {
+    fn random_square_phase<R: Rng + ?Sized>(rng: &mut R) -> f32 {
         rng.gen_range(MIN_PHASE..MAX_PHASE)
     }
 
-    fn random_saw_amp(rng: &mut ThreadRng) -> f32 {
+    fn random_saw_amp<R: Rng + ?Sized>(rng: &mut R) -> f32 {
         rng.gen()
     }
 
-    fn random_saw_phase(rng: &mut ThreadRng) -> f32 {
+    fn random_saw_phase<R: Rng + ?Sized>(rng: &mut R) -> f32 {
         rng.gen_range(MIN_PHASE..MAX_PHASE)
     }
 
+    fn random_tri_amp<R: Rng + ?Sized>(rng: &mut R) -> f32 {
+        rng.gen()
+    }
+
+    fn random_tri_phase<R: Rng + ?Sized>(rng: &mut R) -> f32 {
+        rng.gen_range(MIN_PHASE..MAX_PHASE)
+    }
+
+    fn random_pulse_amp<R: Rng + ?Sized>(rng: &mut R) -> f32 {
+        rng.gen()
+    }
+
+    fn random_pulse_phase<R: Rng + ?Sized>(rng: &mut R) -> f32 {
+        rng.gen_range(MIN_PHASE..MAX_PHASE)
+    }
+
+    fn random_duty<R: Rng + ?Sized>(rng: &mut R) -> f32 {
+        rng.gen_range(MIN_DUTY..MAX_DUTY)
+    }
+
+    fn random_noise_amp<R: Rng + ?Sized>(rng: &mut R) -> f32 {
+        rng.gen()
+    }
+
+    fn random_voices<R: Rng + ?Sized>(rng: &mut R) -> u8 {
+        rng.gen_range(MIN_VOICES..=MAX_VOICES)
+    }
+
+    fn random_detune<R: Rng + ?Sized>(rng: &mut R) -> f32 {
+        rng.gen_range(0.0..MAX_DETUNE)
+    }
+
+    fn random_slave_ratio<R: Rng + ?Sized>(rng: &mut R) -> f32 {
+        rng.gen_range(MIN_SLAVE_RATIO..MAX_SLAVE_RATIO)
+    }
+
+    /// Sum of the amplitudes of all the waveforms, which bounds the peak of the mix.
+    pub(crate) fn total_amp(&self) -> f32 {
+        self.sine_amp + self.square_amp + self.saw_amp + self.tri_amp + self.pulse_amp + self.noise_amp
+    }
+
     // pub(crate) fn reg(&self) -> f32 {
     //     1.0 - (self.sine_amp.powi(2) + self.saw_amp.powi(2) + self.square_amp.powi(2))
     // }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::signal_processing::{Signal, LENGTH, SAMPLE_RATE};
+    use crate::signal_processing::components::oscillator::{saw_wave, sine_wave};
+    use rand::SeedableRng;
+    use rand::rngs::StdRng;
+
+    #[test]
+    fn test_duty_bounds() {
+        let mut rng = StdRng::seed_from_u64(3);
+        let in_bounds = |oscillator: &OscillatorComponent| (MIN_DUTY..=MAX_DUTY).contains(&oscillator.duty);
+
+        let mut oscillator = OscillatorComponent::create(OscillatorSettings::default(), &mut rng);
+        for _ in 0..1000 {
+            oscillator = oscillator.evolve(1.0, &mut rng);
+            assert!(in_bounds(&oscillator));
+        }
+
+        // offspring stay in bounds too, even when they extrapolate from their parents
+        let extremes = (
+            OscillatorComponent { duty: MIN_DUTY, ..oscillator },
+            OscillatorComponent { duty: MAX_DUTY, ..oscillator },
+        );
+        for operator in [CrossoverOperator::Arithmetic, CrossoverOperator::SBX { eta: 1.0 }] {
+            for mutation in [MutationOperator::Replace, MutationOperator::Gaussian { sigma_fraction: 0.5 }] {
+                for _ in 0..100 {
+                    let (a, b) = extremes.0.combine(&extremes.1, 0.5, operator, mutation, &mut rng).unwrap();
+                    assert!(in_bounds(&a) && in_bounds(&b));
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_noise_reproducible() {
+        let mut rng = StdRng::seed_from_u64(4);
+        let render = |oscillator: OscillatorComponent| {
+            let mut signal = Signal::default();
+            signal.apply_oscillator(oscillator);
+            signal
+        };
+
+        let oscillator = OscillatorComponent { noise_amp: 0.5, ..OscillatorComponent::create(OscillatorSettings::default(), &mut rng) };
+        assert_eq!(render(oscillator), render(oscillator));
+
+        // the noise of the offspring is drawn from the seed of their parents
+        let other = OscillatorComponent::create(OscillatorSettings::default(), &mut rng);
+        let (a, b) = oscillator.combine(&other, 0.0, CrossoverOperator::Arithmetic, MutationOperator::Replace, &mut rng).unwrap();
+        assert_eq!((a.noise_seed, b.noise_seed), (oscillator.noise_seed, other.noise_seed));
+        assert_eq!(oscillator.evolve(0.1, &mut rng).noise_seed, oscillator.noise_seed);
+
+        // a different seed renders different noise
+        assert_ne!(render(oscillator), render(OscillatorComponent { noise_seed: oscillator.noise_seed + 1, ..oscillator }));
+    }
+
+    #[test]
+    fn test_disabled_waveforms() {
+        let mut rng = StdRng::seed_from_u64(5);
+        let settings = OscillatorSettings { waveforms: Waveforms::of(&[Waveform::Sine, Waveform::Saw]), ..Default::default() };
+        let disabled_genes = |oscillator: &OscillatorComponent| [
+            oscillator.square_amp, oscillator.square_phase, oscillator.tri_amp, oscillator.tri_phase,
+            oscillator.pulse_amp, oscillator.pulse_phase, oscillator.duty, oscillator.noise_amp,
+        ];
+
+        // only the frequency and the amplitudes and phases of the sine and saw waves are genes
+        let (mut a, mut b) = (OscillatorComponent::create(settings, &mut rng), OscillatorComponent::create(settings, &mut rng));
+        assert_eq!(a.genes(), vec![a.freq, a.sine_amp, a.sine_phase, a.saw_amp, a.saw_phase]);
+        let initial = a;
+
+        // the disabled genes never change from zero, even when every gene is replaced
+        for operator in [CrossoverOperator::Uniform, CrossoverOperator::SBX { eta: 1.0 }] {
+            for _ in 0..100 {
+                (a, b) = a.evolve(1.0, &mut rng).combine(&b, 1.0, operator, MutationOperator::Replace, &mut rng).unwrap();
+                assert_eq!(disabled_genes(&a), [0.0; 8]);
+                assert_eq!(disabled_genes(&b), [0.0; 8]);
+            }
+        }
+        assert_ne!(a.genes(), initial.genes());
+
+        // the oscillator only renders the enabled waveforms
+        let mut signal = Signal::default();
+        signal.apply_oscillator(a);
+        let sine = sine_wave(a.freq, LENGTH, SAMPLE_RATE as f32, a.sine_amp, a.sine_phase);
+        let saw = saw_wave(a.freq, LENGTH, SAMPLE_RATE as f32, a.saw_amp, a.saw_phase);
+        assert_eq!(signal, sine.add_amp(&saw));
+    }
+
+    #[test]
+    fn test_unison_bounds() {
+        let mut rng = StdRng::seed_from_u64(6);
+        let in_bounds = |oscillator: &OscillatorComponent| {
+            (MIN_VOICES..=MAX_VOICES).contains(&oscillator.unison_voices) && (0.0..=MAX_DETUNE).contains(&oscillator.detune_cents)
+        };
+
+        // the voices stay whole and the detune bounded, even when the offspring extrapolate
+        let settings = OscillatorSettings { unison: true, ..Default::default() };
+        let (mut a, mut b) = (OscillatorComponent::create(settings, &mut rng), OscillatorComponent::create(settings, &mut rng));
+        let mut voices = vec![];
+        for operator in [CrossoverOperator::Arithmetic, CrossoverOperator::SBX { eta: 1.0 }] {
+            for _ in 0..200 {
+                (a, b) = a.evolve(1.0, &mut rng).combine(&b, 0.5, operator, MutationOperator::Gaussian { sigma_fraction: 0.5 }, &mut rng).unwrap();
+                assert!(in_bounds(&a) && in_bounds(&b));
+                voices.push(a.unison_voices);
+            }
+        }
+        assert!(voices.contains(&MIN_VOICES) && voices.contains(&MAX_VOICES));
+
+        // without unison, the oscillator keeps a single voice
+        let mut oscillator = OscillatorComponent::create(OscillatorSettings::default(), &mut rng);
+        for _ in 0..100 {
+            oscillator = oscillator.evolve(1.0, &mut rng);
+            assert_eq!((oscillator.unison_voices, oscillator.detune_cents), (1, 0.0));
+        }
+        assert_eq!(oscillator.voice_freqs(), vec![oscillator.freq]);
+    }
+
+    #[test]
+    fn test_sync_bounds() {
+        let mut rng = StdRng::seed_from_u64(9);
+        let in_bounds = |oscillator: &OscillatorComponent| (MIN_SLAVE_RATIO..=MAX_SLAVE_RATIO).contains(&oscillator.slave_ratio);
+
+        // the slave ratio stays bounded, even when the offspring extrapolate
+        let settings = OscillatorSettings { sync: true, ..Default::default() };
+        let (mut a, mut b) = (OscillatorComponent::create(settings, &mut rng), OscillatorComponent::create(settings, &mut rng));
+        assert_eq!(a.genes().len(), 14);
+        for operator in [CrossoverOperator::Arithmetic, CrossoverOperator::SBX { eta: 1.0 }] {
+            for _ in 0..200 {
+                (a, b) = a.evolve(1.0, &mut rng).combine(&b, 0.5, operator, MutationOperator::Gaussian { sigma_fraction: 0.5 }, &mut rng).unwrap();
+                assert!(in_bounds(&a) && in_bounds(&b));
+            }
+        }
+
+        // without sync, the slave ratio is not a gene and stays at 1
+        let mut oscillator = OscillatorComponent::create(OscillatorSettings::default(), &mut rng);
+        assert_eq!(oscillator.genes().len(), 13);
+        for _ in 0..100 {
+            oscillator = oscillator.evolve(1.0, &mut rng);
+            assert_eq!(oscillator.slave_ratio, 1.0);
+        }
+    }
+
+    #[test]
+    fn test_small_steps() {
+        let mut rng = StdRng::seed_from_u64(7);
+        let fields = |o: &OscillatorComponent| [
+            o.freq, o.sine_amp, o.sine_phase, o.square_amp, o.square_phase, o.saw_amp, o.saw_phase, o.tri_amp,
+            o.tri_phase, o.pulse_amp, o.pulse_phase, o.duty, o.noise_amp, o.unison_voices as f32, o.detune_cents,
+            o.slave_ratio,
+        ];
+        let settings = OscillatorSettings { unison: true, sync: true, ..Default::default() };
+
+        for _ in 0..100 {
+            let oscillator = OscillatorComponent::create(settings, &mut rng);
+
+            // every field stays close to its value, in proportion to its range
+            let evolved = oscillator.evolve(1e-6, &mut rng);
+            for (i, (a, b)) in fields(&oscillator).into_iter().zip(fields(&evolved)).enumerate() {
+                let (min, max) = OscillatorComponent::bounds(i);
+                assert!((a - b).abs() <= 1e-5 * (max - min), "field {i} moved from {a} to {b}");
+            }
+
+            // every field is inherited from the same one of the parents
+            let (a, b) = oscillator.combine(&oscillator, 0.0, CrossoverOperator::Uniform, MutationOperator::Replace, &mut rng).unwrap();
+            assert_eq!((a, b), (oscillator, oscillator));
+        }
+    }
+
+    #[test]
+    fn test_log_frequency() {
+        let mut rng = StdRng::seed_from_u64(4);
+        let settings = OscillatorSettings { freq_scale: GeneScale::Logarithmic, ..Default::default() };
+        let with_freq = |freq, freq_scale| OscillatorComponent {
+            freq,
+            settings: OscillatorSettings { freq_scale, ..Default::default() },
+            ..OscillatorComponent::create(settings, &mut StdRng::seed_from_u64(0))
+        };
+        let cents = |a: f32, b: f32| 1200.0 * (b / a).log2().abs();
+        let largest_step = |oscillator: OscillatorComponent, rng: &mut StdRng| {
+            (0..200).map(|_| cents(oscillator.freq, oscillator.evolve(0.01, rng).freq)).fold(0.0, f32::max)
+        };
+
+        // the same step size moves the frequency by the same interval at any pitch
+        let (low, high) = (largest_step(with_freq(110.0, GeneScale::Logarithmic), &mut rng), largest_step(with_freq(3520.0, GeneScale::Logarithmic), &mut rng));
+        assert!((low - high).abs() < 0.1 * high, "{low} and {high} cents");
+        // whereas on a linear scale it is several semitones at low frequencies and tiny at high ones
+        let (low, high) = (largest_step(with_freq(110.0, GeneScale::Linear), &mut rng), largest_step(with_freq(3520.0, GeneScale::Linear), &mut rng));
+        assert!(low > 500.0 && high < 30.0, "{low} and {high} cents");
+
+        // arithmetic crossover blends the frequencies in log space, preserving their product
+        let (a, b) = (with_freq(100.0, GeneScale::Logarithmic), with_freq(400.0, GeneScale::Logarithmic));
+        for _ in 0..100 {
+            let (c, d) = a.combine(&b, 0.0, CrossoverOperator::Arithmetic, MutationOperator::Replace, &mut rng).unwrap();
+            assert!((c.freq * d.freq / 40_000.0 - 1.0).abs() < 1e-4, "{} and {}", c.freq, d.freq);
+            assert_eq!(c.settings.freq_scale, GeneScale::Logarithmic);
+        }
+
+        // the frequency stays within its bounds however far it is pushed
+        for freq in [MIN_FREQ, MAX_FREQ] {
+            let oscillator = with_freq(freq, GeneScale::Logarithmic);
+            for _ in 0..100 {
+                assert!((MIN_FREQ..=MAX_FREQ).contains(&oscillator.evolve(1.0, &mut rng).freq));
+                let (c, d) = oscillator.combine(&oscillator, 1.0, CrossoverOperator::SBX { eta: 0.0 }, MutationOperator::Gaussian { sigma_fraction: 1.0 }, &mut rng).unwrap();
+                assert!((MIN_FREQ..=MAX_FREQ).contains(&c.freq) && (MIN_FREQ..=MAX_FREQ).contains(&d.freq));
+            }
+        }
+    }
+
+    #[test]
+    fn test_serde_round_trip() {
+        // the settings are serialised along with the genes and the seed of the noise
+        let settings = OscillatorSettings {
+            waveforms: Waveforms::of(&[Waveform::Saw, Waveform::Noise]),
+            mix: MixNormalisation::PeakLimit,
+            quality: WaveformQuality::BandLimited,
+            unison: true,
+            freq_scale: GeneScale::Logarithmic,
+            sync: true,
+        };
+        let oscillator = OscillatorComponent::create(settings, &mut StdRng::seed_from_u64(8));
+        let bytes = bincode::serialize(&oscillator).unwrap();
+        assert_eq!(bincode::deserialize::<OscillatorComponent>(&bytes).unwrap(), oscillator);
+    }
+}
@@ -1,23 +1,43 @@
 use std::cmp::Ordering;
 use std::f32::consts::PI;
 use std::sync::Arc;
+use rand::Rng;
+use serde::{Serialize, Deserialize};
 use crate::FitnessType;
+use crate::simulation::components::{component_distance, GeneScale};
 use crate::simulation::components::harmonics::HarmonicsComponent;
-use crate::signal_processing::{Signal, LENGTH, SAMPLE_RATE};
+use crate::signal_processing::{Signal, LENGTH, MAX_FUNDAMENTAL, MIN_FUNDAMENTAL, SAMPLE_RATE};
+use crate::signal_processing::signal_analysis::{SpectrumConfig, Weighting, WindowFunction};
+use crate::utils::cents_range;
+use crate::simulation::algorithms::crossover::CrossoverOperator;
+use crate::simulation::algorithms::mutation::MutationOperator;
 use crate::simulation::algorithms::genetic::{Individual, IndividualGenerator};
 
-#[derive(Clone, Debug, PartialEq)]
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 pub struct AdditiveIndividual {
+    /// The target is shared by the whole population, so it is not serialised with each individual.
+    #[serde(skip)]
     target: Arc<Signal>,
     fitness_type: FitnessType,
+    zcr_bound: Option<f32>,
+    noise_penalty: Option<(f32, f32)>,
+    spectrum_config: SpectrumConfig,
     fitness: Option<f32>,
     harmonics: Option<HarmonicsComponent>
 }
 
-#[derive(Clone)]
+#[derive(Clone, Serialize, Deserialize)]
 pub struct AdditiveIndividualGenerator {
+    #[serde(skip)]
     target: Option<Arc<Signal>>,
     fitness_type: FitnessType,
+    zcr_bound: Option<f32>,
+    noise_penalty: Option<(f32, f32)>,
+    spectrum_config: SpectrumConfig,
+    seed_spread: Option<f32>,
+    seed_range: Option<(f32, f32)>,
+    noise_penalty_weight: Option<f32>,
+    freq_scale: GeneScale,
     harmonics: bool
 }
 
@@ -31,12 +51,34 @@ impl PartialOrd<Self> for AdditiveIndividual {
 
 impl Ord for AdditiveIndividual {
     fn cmp(&self, other: &Self) -> Ordering {
-        self.fitness().partial_cmp(&other.fitness()).expect("No fitness value should be NaN")
+        self.fitness().total_cmp(&other.fitness())
     }
 }
 
-impl AdditiveIndividual {
-    fn harmonics_are_valid(&self) -> bool {
+impl Individual for AdditiveIndividual {
+    type Generator = AdditiveIndividualGenerator;
+
+    fn new_generator() -> Self::Generator {
+        Self::Generator::new()
+    }
+
+    fn get_target(&self) -> Arc<Signal> {
+        Arc::clone(&self.target)
+    }
+
+    fn with_target(self, target: Arc<Signal>) -> Self {
+        Self { target, ..self }
+    }
+
+    fn get_fitness(&self) -> Option<f32> {
+        self.fitness
+    }
+
+    fn get_fitness_type(&self) -> FitnessType {
+        self.fitness_type.clone()
+    }
+
+    fn is_valid(&self) -> bool {
         match self.harmonics.as_ref() {
             Some(harmonics) => {
                 let fund = harmonics.freq;
@@ -48,35 +90,21 @@ impl AdditiveIndividual {
             _ => true // This doesn't apply if there's no harmonics component.
         }
     }
-}
-
-impl Individual for AdditiveIndividual {
-    type Generator = AdditiveIndividualGenerator;
 
-    fn new_generator() -> Self::Generator {
-        Self::Generator::new()
+    fn get_zcr_bound(&self) -> Option<f32> {
+        self.zcr_bound
     }
 
-    fn get_target(&self) -> Arc<Signal> {
-        Arc::clone(&self.target)
-    }
-
-    fn fitness(&self) -> f32 {
-        self.fitness.unwrap_or_else(|| {
-            if self.harmonics_are_valid() {
-                self.calculate_fitness()
-            } else {
-                0.0 // Invalidate the individual if the harmonics are not valid
-            }
-        })
+    fn get_noise_penalty(&self) -> Option<(f32, f32)> {
+        self.noise_penalty
     }
 
-    fn get_fitness_type(&self) -> FitnessType {
-        self.fitness_type
+    fn get_spectrum_config(&self) -> SpectrumConfig {
+        self.spectrum_config.clone()
     }
 
     fn include_fitness(mut self) -> Self {
-        if self.harmonics_are_valid() {
+        if self.is_valid() {
             self.fitness = Some(self.calculate_fitness())
         } else {
             self.fitness = Some(0.0);
@@ -85,20 +113,23 @@ impl Individual for AdditiveIndividual {
         self
     }
 
-    fn crossover(&self, other: &Self, r: f32) -> Option<Self> {
-        let harmonics = match (&self.harmonics, &other.harmonics) {
-            (Some(s), Some(o)) => s.combine(o, r),
+    fn crossover<R: Rng + ?Sized>(&self, other: &Self, r: f32, operator: CrossoverOperator, mutation: MutationOperator, rng: &mut R) -> Vec<Self> {
+        let (harmonics_a, harmonics_b) = match (&self.harmonics, &other.harmonics) {
+            (Some(s), Some(o)) => s.combine(o, r, operator, mutation, rng),
             _ => None
-        };
+        }.unzip();
+
+        let offspring = |harmonics| Self {
+            target: self.get_target(),
+            fitness: None,
+            fitness_type: self.fitness_type.clone(),
+            zcr_bound: self.zcr_bound,
+            noise_penalty: self.noise_penalty,
+            spectrum_config: self.spectrum_config.clone(),
+            harmonics
+        }.include_fitness();
 
-        Some(
-            Self {
-                target: self.get_target(),
-                fitness: None,
-                fitness_type: self.fitness_type,
-                harmonics
-            }.include_fitness()
-        )
+        vec![offspring(harmonics_a), offspring(harmonics_b)]
     }
 
     fn to_signal(&self) -> Signal {
@@ -111,12 +142,15 @@ impl Individual for AdditiveIndividual {
         signal
     }
 
-    fn evolve(&self, step_size: f32) -> Self {
+    fn evolve<R: Rng + ?Sized>(&self, step_size: f32, rng: &mut R) -> Self {
         Self {
             target: Arc::clone(&self.target),
             fitness: None,
-            fitness_type: self.fitness_type,
-            harmonics: self.harmonics.as_ref().map(|har| har.evolve(step_size)),
+            fitness_type: self.fitness_type.clone(),
+            zcr_bound: self.zcr_bound,
+            noise_penalty: self.noise_penalty,
+            spectrum_config: self.spectrum_config.clone(),
+            harmonics: self.harmonics.as_ref().map(|har| har.evolve(step_size, rng)),
         }.include_fitness()
     }
 
@@ -127,6 +161,17 @@ impl Individual for AdditiveIndividual {
     fn get_fundamental(&self) -> Option<f32> {
         Some(self.harmonics.as_ref()?.freq)
     }
+
+    fn distance(&self, other: &Self) -> f32 {
+        component_distance(self.harmonics.as_ref(), other.harmonics.as_ref(), HarmonicsComponent::distance)
+    }
+}
+
+impl AdditiveIndividual {
+    /// The harmonics of the individual, if it has them.
+    pub fn harmonics(&self) -> Option<&HarmonicsComponent> {
+        self.harmonics.as_ref()
+    }
 }
 
 impl IndividualGenerator<AdditiveIndividual> for AdditiveIndividualGenerator {
@@ -134,26 +179,42 @@ impl IndividualGenerator<AdditiveIndividual> for AdditiveIndividualGenerator {
         AdditiveIndividualGenerator {
             target: None,
             fitness_type: FitnessType::default(),
+            zcr_bound: None,
+            noise_penalty: None,
+            spectrum_config: SpectrumConfig::default(),
+            seed_spread: None,
+            seed_range: None,
+            noise_penalty_weight: None,
+            freq_scale: GeneScale::Linear,
             harmonics: false
         }
     }
 
-    fn generate(&self) -> AdditiveIndividual {
-        let harmonics = self.harmonics.then(HarmonicsComponent::create);
+    fn generate_unevaluated<R: Rng + ?Sized>(&self, rng: &mut R) -> AdditiveIndividual {
+        let harmonics = self.harmonics.then(|| HarmonicsComponent {
+            freq_scale: self.freq_scale,
+            ..match self.seed_range {
+                Some(range) => HarmonicsComponent::create_in_range(range, rng),
+                None => HarmonicsComponent::create(rng),
+            }
+        });
 
-        let individual = AdditiveIndividual {
+        AdditiveIndividual {
             target: Arc::clone(self.target.as_ref()
                 .expect("Expected target in AdditiveIndividualGenerator")),
-            fitness_type: self.fitness_type,
+            fitness_type: self.fitness_type.clone(),
+            zcr_bound: self.zcr_bound,
+            noise_penalty: self.noise_penalty,
+            spectrum_config: self.spectrum_config.clone(),
             fitness: None,
             harmonics,
-        };
-
-        individual.include_fitness()
+        }
     }
 
     fn target(mut self, target: Arc<Signal>) -> Self {
         self.target = Some(target);
+        self.update_seed_range();
+        self.update_noise_penalty();
         self
     }
 
@@ -162,16 +223,157 @@ impl IndividualGenerator<AdditiveIndividual> for AdditiveIndividualGenerator {
         self
     }
 
+    fn zcr_prefilter(mut self, max_ratio: f32) -> Self {
+        self.zcr_bound = Some(max_ratio);
+        self
+    }
+
+    fn analysis_window(mut self, window: WindowFunction) -> Self {
+        self.spectrum_config.window = window;
+        self
+    }
+
+    fn analysis_band(mut self, min_hz: f32, max_hz: f32) -> Self {
+        self.spectrum_config.band = Some((min_hz, max_hz));
+        self
+    }
+
+    fn perceptual_weighting(mut self, weighting: Weighting) -> Self {
+        self.spectrum_config.weighting = weighting;
+        self
+    }
+
+    fn analysis_length(mut self, n_samples: usize) -> Self {
+        self.spectrum_config.length = n_samples;
+        self
+    }
+
+    fn analysis_averaging(mut self, averaged: bool) -> Self {
+        self.spectrum_config.averaged = averaged;
+        self
+    }
+
+    fn noise_penalty(mut self, weight: f32) -> Self {
+        self.noise_penalty_weight = Some(weight);
+        self.update_noise_penalty();
+        self
+    }
+
+    fn seed_frequency_from_target(mut self, spread_cents: f32) -> Self {
+        self.seed_spread = Some(spread_cents);
+        self.update_seed_range();
+        self
+    }
+
+    fn log_frequency(mut self, enabled: bool) -> Self {
+        self.freq_scale = if enabled { GeneScale::Logarithmic } else { GeneScale::Linear };
+        self
+    }
+
     fn get_target(&self) -> Arc<Signal> {
         Arc::clone(self.target.as_ref().expect("The generator should have a target set."))
     }
+
+    fn get_spectrum_config(&self) -> SpectrumConfig {
+        self.spectrum_config.clone()
+    }
 }
 
 impl AdditiveIndividualGenerator {
 
+    /// Recomputes the frequency window used to seed new individuals once both the target and the
+    /// spread are known.
+    fn update_seed_range(&mut self) {
+        self.seed_range = match (&self.target, self.seed_spread) {
+            (Some(target), Some(spread)) => target
+                .detect_fundamental(MIN_FUNDAMENTAL, MAX_FUNDAMENTAL)
+                .map(|f| cents_range(f, spread)),
+            _ => None,
+        };
+    }
+
+    /// Recomputes the harmonicity of the target used by the noise penalty once both the target and
+    /// the weight are known.
+    fn update_noise_penalty(&mut self) {
+        self.noise_penalty = match (&self.target, self.noise_penalty_weight) {
+            (Some(target), Some(weight)) => Some((weight, target.harmonicity(None))),
+            _ => None,
+        };
+    }
+
     /// Whether the individual should include a harmonics component.
     pub fn harmonics(mut self) -> Self {
         self.harmonics = true;
         self
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::signal_processing::components::oscillator::sine_wave;
+    use crate::simulation::algorithms::genetic::{GASimulation, GASimulationBuilder};
+
+    fn individual(harmonics: Option<(f32, Vec<f32>)>) -> AdditiveIndividual {
+        AdditiveIndividual {
+            target: Arc::new(Signal::default()),
+            fitness_type: FitnessType::default(),
+            zcr_bound: None,
+            noise_penalty: None,
+            spectrum_config: SpectrumConfig::default(),
+            fitness: None,
+            harmonics: harmonics.map(|(freq, amplitudes)| HarmonicsComponent { freq, amplitudes, freq_scale: GeneScale::Linear }),
+        }
+    }
+
+    #[test]
+    fn test_distance() {
+        let a = individual(Some((100.0, vec![0.5, 0.5])));
+        assert_eq!(a.distance(&a), 0.0);
+        assert_eq!(a.distance(&individual(None)), 1.0);
+        assert_eq!(individual(None).distance(&individual(None)), 0.0);
+
+        // two octaves apart, out of the log2(500) octaves between the lowest and highest
+        // fundamentals, averaged with the two equal amplitudes
+        let b = individual(Some((400.0, vec![0.5, 0.5])));
+        let expected = (2.0 / 500f32.log2()) / 3f32.sqrt();
+        assert!((a.distance(&b) - expected).abs() < 1e-6);
+        assert_eq!(a.distance(&b), b.distance(&a));
+
+        // the same interval is as far apart in any octave
+        let (c, d) = (individual(Some((1_000.0, vec![0.5, 0.5]))), individual(Some((4_000.0, vec![0.5, 0.5]))));
+        assert!((c.distance(&d) - expected).abs() < 1e-6);
+
+        let e = individual(Some((100.0, vec![0.0, 1.0])));
+        assert!((a.distance(&e) - (0.5f32 / 3.0).sqrt()).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_retry_invalid() {
+        let generator = AdditiveIndividual::new_generator()
+            .target(Arc::new(sine_wave(5_000.0, LENGTH, SAMPLE_RATE as f32, 1.0, 0.0)))
+            .harmonics();
+        let first_generation = |retries: u32| {
+            let mut simulation: GASimulation<AdditiveIndividual> = GASimulationBuilder::new()
+                .initial_population(50)
+                .retry_invalid(retries)
+                .seed(1)
+                .generator(generator.clone())
+                .build()
+                .unwrap();
+            simulation.step().unwrap();
+
+            let dead = simulation.population.iter().filter(|i| i.fitness() == 0.0).count();
+            (dead as f32 / simulation.population.len() as f32, simulation.invalid)
+        };
+
+        // most fundamentals are too high for all nine harmonics to fit below the Nyquist frequency
+        let (dead, invalid) = first_generation(0);
+        assert!(dead > 0.2, "{dead}");
+        assert!(invalid > 0);
+
+        let (dead, invalid) = first_generation(30);
+        assert!(dead < 0.02, "{dead}");
+        assert_eq!(invalid, 0);
+    }
+}
@@ -1,32 +1,54 @@
-use crate::signal_processing::Signal;
+use crate::signal_processing::{Signal, MAX_FUNDAMENTAL, MIN_FUNDAMENTAL};
 use std::cmp::Ordering;
 use std::sync::{Arc, Mutex};
+use rand::Rng;
+use serde::{Serialize, Deserialize};
 use crate::{FitnessType};
+use crate::simulation::algorithms::crossover::CrossoverOperator;
+use crate::simulation::algorithms::mutation::MutationOperator;
 use crate::simulation::algorithms::genetic::{GASimulation, Individual, IndividualGenerator};
+use crate::simulation::components::{component_distance, components_distance, GeneScale};
 use crate::simulation::components::envelope::EnvelopeComponent;
-use crate::simulation::components::filters::{FilterComponent, FilterType};
-use crate::simulation::components::oscillator::OscillatorComponent;
+use crate::simulation::components::filters::{FilterComponent, FilterEnvelope, FilterType};
+use crate::simulation::components::oscillator::{MixNormalisation, OscillatorComponent, OscillatorSettings, Waveform, WaveformQuality, Waveforms};
+use crate::signal_processing::signal_analysis::{SpectrumConfig, Weighting, WindowFunction};
+use crate::utils::cents_range;
 
 /// Contains the components and other information related to an individual representing subtractive
 /// synthesis.
-#[derive(Clone, Debug, PartialEq)]
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 pub struct SubtractiveIndividual {
+    /// The target is shared by the whole population, so it is not serialised with each individual.
+    #[serde(skip)]
     target: Arc<Signal>,
     fitness_type: FitnessType,
+    zcr_bound: Option<f32>,
+    noise_penalty: Option<(f32, f32)>,
+    spectrum_config: SpectrumConfig,
     fitness: Option<f32>,
-    oscillator: Option<OscillatorComponent>,
+    oscillators: Vec<OscillatorComponent>,
     envelope: Option<EnvelopeComponent>,
-    filter: Option<FilterComponent>
+    filter: Option<FilterComponent>,
+    filter_envelope: Option<FilterEnvelope>,
 }
 
 /// Specifies the components of a SubtractiveIndividual and other information.
-#[derive(Clone)]
+#[derive(Clone, Serialize, Deserialize)]
 pub struct SubtractiveIndividualGenerator {
+    #[serde(skip)]
     target: Option<Arc<Signal>>,
     fitness_type: FitnessType,
-    oscillator: bool,
+    zcr_bound: Option<f32>,
+    noise_penalty: Option<(f32, f32)>,
+    spectrum_config: SpectrumConfig,
+    seed_spread: Option<f32>,
+    seed_range: Option<(f32, f32)>,
+    noise_penalty_weight: Option<f32>,
+    oscillators: usize,
+    oscillator_settings: OscillatorSettings,
     envelope: bool,
     filter: Option<FilterType>,
+    filter_envelope: bool,
 }
 
 impl Individual for SubtractiveIndividual {
@@ -40,12 +62,28 @@ impl Individual for SubtractiveIndividual {
         Arc::clone(&self.target)
     }
 
-    fn fitness(&self) -> f32 {
-        self.fitness.unwrap_or_else(|| self.calculate_fitness())
+    fn with_target(self, target: Arc<Signal>) -> Self {
+        Self { target, ..self }
+    }
+
+    fn get_fitness(&self) -> Option<f32> {
+        self.fitness
     }
 
     fn get_fitness_type(&self) -> FitnessType {
-        self.fitness_type
+        self.fitness_type.clone()
+    }
+
+    fn get_zcr_bound(&self) -> Option<f32> {
+        self.zcr_bound
+    }
+
+    fn get_noise_penalty(&self) -> Option<(f32, f32)> {
+        self.noise_penalty
+    }
+
+    fn get_spectrum_config(&self) -> SpectrumConfig {
+        self.spectrum_config.clone()
     }
 
     fn include_fitness(mut self) -> Self {
@@ -53,72 +91,108 @@ impl Individual for SubtractiveIndividual {
         self
     }
 
-    fn crossover(&self, other: &Self, r: f32) -> Option<Self> {
-        let oscillator = match (&self.oscillator, &other.oscillator) {
-            (Some(s), Some(o)) => s.combine(o, r),
-            _ => None,
-        };
+    fn crossover<R: Rng + ?Sized>(&self, other: &Self, r: f32, operator: CrossoverOperator, mutation: MutationOperator, rng: &mut R) -> Vec<Self> {
+        // the oscillators are paired by position, and those of the parent with more of them that have
+        // no counterpart are inherited unchanged by its offspring
+        let (mut oscillators_a, mut oscillators_b): (Vec<_>, Vec<_>) = self.oscillators.iter()
+            .zip(&other.oscillators)
+            .filter_map(|(s, o)| s.combine(o, r, operator, mutation, rng))
+            .unzip();
+        let paired = self.oscillators.len().min(other.oscillators.len());
+        oscillators_a.extend_from_slice(&self.oscillators[paired..]);
+        oscillators_b.extend_from_slice(&other.oscillators[paired..]);
         
-        let envelope = match (&self.envelope, &other.envelope) {
-            (Some(s), Some(o)) => s.combine(o, r),
+        let (envelope_a, envelope_b) = match (&self.envelope, &other.envelope) {
+            (Some(s), Some(o)) => s.combine(o, r, operator, mutation, rng),
             _ => None,
-        };
+        }.unzip();
         
-        let filter = match (&self.filter, &other.filter) {
-            (Some(s), Some(o)) => s.combine(o, r),
+        let (filter_a, filter_b) = match (&self.filter, &other.filter) {
+            (Some(s), Some(o)) => s.combine(o, r, operator, mutation, rng),
             _ => None,
-        };
+        }.unzip();
+
+        let (filter_envelope_a, filter_envelope_b) = match (&self.filter_envelope, &other.filter_envelope) {
+            (Some(s), Some(o)) => s.combine(o, r, operator, mutation, rng),
+            _ => None,
+        }.unzip();
         
-        let offspring = Self {
-            fitness_type: self.fitness_type,
+        let offspring = |oscillators, envelope, filter, filter_envelope| Self {
+            fitness_type: self.fitness_type.clone(),
+            zcr_bound: self.zcr_bound,
+            noise_penalty: self.noise_penalty,
+            spectrum_config: self.spectrum_config.clone(),
             fitness: None,
             target: self.get_target(),
-            oscillator,
+            oscillators,
             envelope,
             filter,
-        };
+            filter_envelope,
+        }.include_fitness();
 
-        Some(offspring.include_fitness())
+        vec![
+            offspring(oscillators_a, envelope_a, filter_a, filter_envelope_a),
+            offspring(oscillators_b, envelope_b, filter_b, filter_envelope_b),
+        ]
     }
 
     /// Converts a genetic individual to a `Signal` by applying the specified components.
     fn to_signal(&self) -> Signal {
         let mut signal = Signal::default();
 
-        if let Some(oscillator) = self.oscillator {
-            signal.apply_oscillator(oscillator);
+        let rendered = self.oscillators.iter().map(|&oscillator| {
+            let mut rendered = Signal::default();
+            rendered.apply_oscillator(oscillator);
+            rendered
+        });
+        if let Some(mix) = rendered.reduce(|mix, rendered| mix.add_amp(&rendered)) {
+            signal = mix;
         }
 
         if let Some(envelope) = self.envelope {
             signal.apply_envelope(envelope);
         }
 
-        if let Some(filter) = self.filter {
-            signal.apply_filter(filter);
+        match (self.filter, self.filter_envelope) {
+            (Some(filter), Some(envelope)) => signal.apply_filter_modulated(filter, envelope),
+            (Some(filter), None) => signal.apply_filter(filter),
+            _ => {}
         }
 
         signal
     }
 
-    fn evolve(&self, step_size: f32) -> Self {
+    fn evolve<R: Rng + ?Sized>(&self, step_size: f32, rng: &mut R) -> Self {
         Self {
             target: Arc::clone(&self.target),
-            fitness_type: self.fitness_type,
+            fitness_type: self.fitness_type.clone(),
+            zcr_bound: self.zcr_bound,
+            noise_penalty: self.noise_penalty,
+            spectrum_config: self.spectrum_config.clone(),
             fitness: None,
-            oscillator: self.oscillator.map(|osc| osc.evolve(step_size)),
-            envelope: self.envelope.map(|env| env.evolve(step_size)),
-            filter: self.filter.map(|fil| fil.evolve(step_size))
+            oscillators: self.oscillators.iter().map(|osc| osc.evolve(step_size, rng)).collect(),
+            envelope: self.envelope.map(|env| env.evolve(step_size, rng)),
+            filter: self.filter.map(|fil| fil.evolve(step_size, rng)),
+            filter_envelope: self.filter_envelope.map(|env| env.evolve(step_size, rng)),
         }.include_fitness()
     }
 
     fn dbg(&self) -> String {
-        format!("FITNESS: {:?}, Oscillator: {:?}, Envelope: {:?}, Filter: {:?}",
-                self.fitness.unwrap_or(0.0), self.oscillator, self.envelope, self.filter
+        format!("FITNESS: {:?}, Oscillators: {:?}, Envelope: {:?}, Filter: {:?}, Filter envelope: {:?}",
+                self.fitness.unwrap_or(0.0), self.oscillators, self.envelope, self.filter, self.filter_envelope
         )
     }
     
+    /// The frequency of the lowest oscillator.
     fn get_fundamental(&self) -> Option<f32> {
-        Some(self.oscillator?.freq)
+        self.oscillators.iter().map(|oscillator| oscillator.freq).min_by(f32::total_cmp)
+    }
+
+    fn distance(&self, other: &Self) -> f32 {
+        components_distance(&self.oscillators, &other.oscillators, OscillatorComponent::distance)
+            + component_distance(self.envelope.as_ref(), other.envelope.as_ref(), EnvelopeComponent::distance)
+            + component_distance(self.filter.as_ref(), other.filter.as_ref(), FilterComponent::distance)
+            + component_distance(self.filter_envelope.as_ref(), other.filter_envelope.as_ref(), FilterEnvelope::distance)
     }
 }
 
@@ -132,8 +206,35 @@ impl Eq for SubtractiveIndividual {}
 
 impl Ord for SubtractiveIndividual {
     fn cmp(&self, other: &Self) -> Ordering {
-        // Needs to use partial_cmp since f32 does not implement the Ord trait.
-        self.fitness().partial_cmp(&other.fitness()).expect("No fitness value should be NaN.")
+        // f32 does not implement the Ord trait, but its total order does not panic on NaN
+        self.fitness().total_cmp(&other.fitness())
+    }
+}
+
+impl SubtractiveIndividual {
+    /// The first oscillator of the individual, if it has any.
+    pub fn oscillator(&self) -> Option<&OscillatorComponent> {
+        self.oscillators.first()
+    }
+
+    /// Every oscillator of the individual, in the order they were generated.
+    pub fn oscillators(&self) -> &[OscillatorComponent] {
+        &self.oscillators
+    }
+
+    /// The envelope of the individual, if it has one.
+    pub fn envelope(&self) -> Option<&EnvelopeComponent> {
+        self.envelope.as_ref()
+    }
+
+    /// The filter of the individual, if it has one.
+    pub fn filter(&self) -> Option<&FilterComponent> {
+        self.filter.as_ref()
+    }
+
+    /// The envelope followed by the frequencies of the filter, if it has one.
+    pub fn filter_envelope(&self) -> Option<&FilterEnvelope> {
+        self.filter_envelope.as_ref()
     }
 }
 
@@ -142,32 +243,52 @@ impl IndividualGenerator<SubtractiveIndividual> for SubtractiveIndividualGenerat
         SubtractiveIndividualGenerator {
             target: None,
             fitness_type: FitnessType::default(),
-            oscillator: false,
+            zcr_bound: None,
+            noise_penalty: None,
+            spectrum_config: SpectrumConfig::default(),
+            seed_spread: None,
+            seed_range: None,
+            noise_penalty_weight: None,
+            oscillators: 0,
+            oscillator_settings: OscillatorSettings::default(),
             envelope: false,
             filter: None,
+            filter_envelope: false,
         }
     }
 
-    fn generate(&self) -> SubtractiveIndividual {
-        let oscillator = self.oscillator.then(OscillatorComponent::create);
-        let envelope = self.envelope.then(EnvelopeComponent::create);
-        let filter = self.filter.as_ref().map(|&f| FilterComponent::create(f));
+    fn generate_unevaluated<R: Rng + ?Sized>(&self, rng: &mut R) -> SubtractiveIndividual {
+        // only the first oscillator is seeded near the fundamental of the target, so that the rest
+        // can find its other notes
+        let oscillators = (0..self.oscillators)
+            .map(|i| match self.seed_range {
+                Some(range) if i == 0 => OscillatorComponent::create_in_range(self.oscillator_settings, range, rng),
+                _ => OscillatorComponent::create(self.oscillator_settings, rng),
+            })
+            .collect();
+        let envelope = self.envelope.then(|| EnvelopeComponent::create(rng));
+        let filter = self.filter.as_ref().map(|&f| FilterComponent::create(f, rng));
+        let filter_envelope = (filter.is_some() && self.filter_envelope).then(|| FilterEnvelope::create(rng));
 
-        let individual = SubtractiveIndividual {
+        SubtractiveIndividual {
             target: Arc::clone(self.target.as_ref()
                 .expect("Expected target in SubtractiveIndividualGenerator")),
-            fitness_type: self.fitness_type,
+            fitness_type: self.fitness_type.clone(),
+            zcr_bound: self.zcr_bound,
+            noise_penalty: self.noise_penalty,
+            spectrum_config: self.spectrum_config.clone(),
             fitness: None,
-            oscillator,
+            oscillators,
             envelope,
             filter,
-        };
-
-        individual.include_fitness()
+            filter_envelope,
+        }
     }
 
     fn target(mut self, target: Arc<Signal>) -> Self {
         self.target = Some(target);
+        self.update_seed_range();
+        self.update_noise_penalty();
         self
     }
 
@@ -176,16 +297,132 @@ impl IndividualGenerator<SubtractiveIndividual> for SubtractiveIndividualGenerat
         self
     }
 
+    fn zcr_prefilter(mut self, max_ratio: f32) -> Self {
+        self.zcr_bound = Some(max_ratio);
+        self
+    }
+
+    fn analysis_window(mut self, window: WindowFunction) -> Self {
+        self.spectrum_config.window = window;
+        self
+    }
+
+    fn analysis_band(mut self, min_hz: f32, max_hz: f32) -> Self {
+        self.spectrum_config.band = Some((min_hz, max_hz));
+        self
+    }
+
+    fn perceptual_weighting(mut self, weighting: Weighting) -> Self {
+        self.spectrum_config.weighting = weighting;
+        self
+    }
+
+    fn analysis_length(mut self, n_samples: usize) -> Self {
+        self.spectrum_config.length = n_samples;
+        self
+    }
+
+    fn analysis_averaging(mut self, averaged: bool) -> Self {
+        self.spectrum_config.averaged = averaged;
+        self
+    }
+
+    fn noise_penalty(mut self, weight: f32) -> Self {
+        self.noise_penalty_weight = Some(weight);
+        self.update_noise_penalty();
+        self
+    }
+
+    fn seed_frequency_from_target(mut self, spread_cents: f32) -> Self {
+        self.seed_spread = Some(spread_cents);
+        self.update_seed_range();
+        self
+    }
+
+    fn log_frequency(mut self, enabled: bool) -> Self {
+        self.oscillator_settings.freq_scale = if enabled { GeneScale::Logarithmic } else { GeneScale::Linear };
+        self
+    }
+
     fn get_target(&self) -> Arc<Signal> {
         Arc::clone(self.target.as_ref().expect("The generator should have a target set."))
     }
+
+    fn get_spectrum_config(&self) -> SpectrumConfig {
+        self.spectrum_config.clone()
+    }
 }
 
 impl SubtractiveIndividualGenerator {
 
-    /// Used to specify whether the individual will contain an oscillator component.
-    pub fn oscillator(mut self) -> Self {
-        self.oscillator = true;
+    /// Recomputes the frequency window used to seed new individuals once both the target and the
+    /// spread are known.
+    fn update_seed_range(&mut self) {
+        self.seed_range = match (&self.target, self.seed_spread) {
+            (Some(target), Some(spread)) => target
+                .detect_fundamental(MIN_FUNDAMENTAL, MAX_FUNDAMENTAL)
+                .map(|f| cents_range(f, spread)),
+            _ => None,
+        };
+    }
+
+    /// Recomputes the harmonicity of the target used by the noise penalty once both the target and
+    /// the weight are known.
+    fn update_noise_penalty(&mut self) {
+        self.noise_penalty = match (&self.target, self.noise_penalty_weight) {
+            (Some(target), Some(weight)) => Some((weight, target.harmonicity(None))),
+            _ => None,
+        };
+    }
+
+    /// Used to specify whether the individual will contain an oscillator component, which mixes
+    /// every waveform. It is a shorthand for `.oscillators(1)`.
+    pub fn oscillator(self) -> Self {
+        self.oscillators(1)
+    }
+
+    /// Used to specify that the individual will contain `n` oscillator components, whose signals are
+    /// summed, so that it can reach targets with several notes.
+    pub fn oscillators(mut self, n: usize) -> Self {
+        assert!(n > 0, "The individual should contain at least one oscillator");
+        self.oscillators = n;
+        self
+    }
+
+    /// Used to specify that the individual will contain an oscillator component mixing only the
+    /// given waveforms, e.g. `.oscillator_with(&[Waveform::Sine, Waveform::Saw])`. The genes of the
+    /// rest are left out of the genome, which is smaller and quicker to search.
+    pub fn oscillator_with(mut self, waveforms: &[Waveform]) -> Self {
+        self.oscillators = self.oscillators.max(1);
+        self.oscillator_settings.waveforms = Waveforms::of(waveforms);
+        self
+    }
+
+    /// Used to specify how the waveforms of the oscillator are scaled when they are summed. They are
+    /// not normalised by default.
+    pub fn mix_normalisation(mut self, mix: MixNormalisation) -> Self {
+        self.oscillator_settings.mix = mix;
+        self
+    }
+
+    /// Used to specify how the oscillator renders the waveforms with discontinuities. They are not
+    /// band-limited by default.
+    pub fn waveform_quality(mut self, quality: WaveformQuality) -> Self {
+        self.oscillator_settings.quality = quality;
+        self
+    }
+
+    /// Used to specify that the oscillator stacks several detuned copies of its waveforms, whose
+    /// number and detune are genes. It renders a single voice by default.
+    pub fn unison(mut self) -> Self {
+        self.oscillator_settings.unison = true;
+        self
+    }
+
+    /// Used to specify that the square and saw waves of the oscillator are hard-synced to its
+    /// frequency, with their ratio to it as a gene. They are free-running by default.
+    pub fn hard_sync(mut self) -> Self {
+        self.oscillator_settings.sync = true;
         self
     }
 
@@ -200,4 +437,263 @@ impl SubtractiveIndividualGenerator {
         self.filter = Some(filter_type);
         self
     }
+
+    /// Used to specify that the frequencies of the filter follow an envelope, whose amount, attack
+    /// and decay are genes. It has no effect without a filter, which is static by default.
+    pub fn filter_envelope(mut self) -> Self {
+        self.filter_envelope = true;
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::signal_processing::{LENGTH, SAMPLE_RATE};
+    use crate::signal_processing::components::oscillator::sine_wave;
+    use crate::simulation::components::filters::max_freq;
+    use crate::simulation::algorithms::hillclimbing::{HillClimberBuilder, HillClimbingSimulation};
+    use rand::SeedableRng;
+    use rand::rngs::StdRng;
+
+    fn individual(
+        oscillators: Vec<OscillatorComponent>,
+        envelope: Option<EnvelopeComponent>,
+        filter: Option<FilterComponent>,
+    ) -> SubtractiveIndividual {
+        SubtractiveIndividual {
+            target: Arc::new(Signal::default()),
+            fitness_type: FitnessType::default(),
+            zcr_bound: None,
+            noise_penalty: None,
+            spectrum_config: SpectrumConfig::default(),
+            fitness: None,
+            oscillators,
+            envelope,
+            filter,
+            filter_envelope: None,
+        }
+    }
+
+    #[test]
+    fn test_distance() {
+        let oscillator = OscillatorComponent {
+            freq: 20.0,
+            sine_amp: 0.0,
+            sine_phase: 0.0,
+            square_amp: 0.0,
+            square_phase: 0.0,
+            saw_amp: 0.0,
+            saw_phase: 0.0,
+            tri_amp: 0.0,
+            tri_phase: 0.0,
+            pulse_amp: 0.0,
+            pulse_phase: 0.0,
+            duty: 0.5,
+            noise_amp: 0.0,
+            unison_voices: 1,
+            detune_cents: 0.0,
+            slave_ratio: 1.0,
+            noise_seed: 0,
+            settings: OscillatorSettings::default(),
+        };
+        let a = individual(vec![oscillator], None, None);
+        assert_eq!(a.distance(&a), 0.0);
+
+        // a single parameter of the thirteen spans its whole range
+        let b = individual(vec![OscillatorComponent { sine_amp: 1.0, ..oscillator }], None, None);
+        assert!((a.distance(&b) - (1.0f32 / 13.0).sqrt()).abs() < 1e-6);
+        assert_eq!(a.distance(&b), b.distance(&a));
+
+        // a component missing from one of the individuals is as far apart as possible
+        let envelope = EnvelopeComponent::from_genes(&[0.1, 0.2, 0.5, 0.3, 0.25]);
+        let c = individual(vec![oscillator], Some(envelope), None);
+        assert_eq!(a.distance(&c), 1.0);
+
+        // half of the range of the cutoff frequency, averaged with the equal bands
+        let low_pass = |cutoff_freq| FilterComponent::LowPass { cutoff_freq, band: 1.0 };
+        let d = individual(vec![oscillator], Some(envelope), Some(low_pass(0.0)));
+        let e = individual(vec![oscillator], Some(envelope), Some(low_pass(max_freq(SAMPLE_RATE as f32) / 2.0)));
+        assert!((d.distance(&e) - 0.125f32.sqrt()).abs() < 1e-6);
+
+        // filters of different types are as far apart as possible
+        let f = individual(vec![oscillator], Some(envelope), Some(FilterComponent::HighPass { cutoff_freq: 0.0, band: 1.0 }));
+        assert_eq!(d.distance(&f), 1.0);
+        assert!((b.distance(&f) - ((1.0f32 / 13.0).sqrt() + 2.0)).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_silent_candidate_and_target() {
+        // a silent oscillator against a silent target, where the cost of a zero MSE is log10(0)
+        let silent = OscillatorComponent::from_genes(&[440.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.5, 0.0, 1.0, 0.0, 1.0]);
+        let fitness_types = [
+            FitnessType::default(),
+            FitnessType::time_domain_euclidean(),
+            FitnessType::TimeDomainMAE,
+            FitnessType::TimeDomainNRMSE,
+            FitnessType::SpectralSNR,
+            FitnessType::SpectralCosine,
+            FitnessType::ItakuraSaito,
+        ];
+
+        for fitness_type in fitness_types {
+            let candidate = SubtractiveIndividual {
+                target: Arc::new(Signal::from_samples(&[0.0; SAMPLE_RATE as usize])),
+                fitness_type: fitness_type.clone(),
+                ..individual(vec![silent], None, None)
+            }.include_fitness();
+            assert!(candidate.fitness().is_finite(), "{fitness_type:?}");
+            assert_eq!(candidate.cmp(&candidate), Ordering::Equal);
+        }
+    }
+
+    #[test]
+    fn test_mix_normalisation() {
+        let generator = SubtractiveIndividual::new_generator()
+            .target(Arc::new(Signal::default()))
+            .oscillator();
+        let mut rng = rand::thread_rng();
+
+        // the oscillators of the generated individuals, and of their offspring, keep the mode
+        let normalised = generator.clone().mix_normalisation(MixNormalisation::PeakLimit);
+        let (a, b) = (normalised.generate(&mut rng), normalised.generate(&mut rng));
+        let offspring = a.crossover(&b, 0.5, CrossoverOperator::default(), MutationOperator::default(), &mut rng);
+        for individual in offspring.iter().chain([&a, &b]) {
+            assert_eq!(individual.oscillators[0].settings.mix, MixNormalisation::PeakLimit);
+            assert!(individual.to_signal().peak() <= 1.0);
+        }
+        assert_eq!(a.evolve(0.1, &mut rng).oscillators[0].settings.mix, MixNormalisation::PeakLimit);
+        assert_eq!(generator.generate(&mut rng).oscillators[0].settings.mix, MixNormalisation::None);
+    }
+
+    #[test]
+    fn test_multiple_oscillators() {
+        let sine = |freq| OscillatorComponent::from_genes(&[freq, 0.5, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.5, 0.0, 1.0, 0.0, 1.0]);
+        let two_notes = individual(vec![sine(660.0), sine(440.0)], None, None);
+
+        // the signal is the sum of those of the oscillators, whose lowest one is the fundamental
+        let expected = sine_wave(660.0, LENGTH, SAMPLE_RATE as f32, 0.5, 0.0)
+            .add_amp(&sine_wave(440.0, LENGTH, SAMPLE_RATE as f32, 0.5, 0.0));
+        assert_eq!(two_notes.to_signal(), expected);
+        assert_eq!(two_notes.get_fundamental(), Some(440.0));
+        assert_eq!(individual(vec![], None, None).get_fundamental(), None);
+
+        // every oscillator evolves
+        let mut rng = StdRng::seed_from_u64(1);
+        let evolved = two_notes.evolve(0.1, &mut rng);
+        assert_eq!(evolved.oscillators.len(), 2);
+        assert!(evolved.oscillators.iter().zip(&two_notes.oscillators).all(|(a, b)| a.freq != b.freq));
+
+        // an oscillator missing from one individual counts as the largest distance
+        let one_note = individual(vec![sine(660.0)], None, None);
+        assert_eq!(one_note.distance(&two_notes), 0.5);
+        assert_eq!(two_notes.distance(&one_note), 0.5);
+    }
+
+    #[test]
+    fn test_crossover_mismatched_oscillators() {
+        let mut rng = StdRng::seed_from_u64(2);
+        let generator = SubtractiveIndividual::new_generator()
+            .target(Arc::new(Signal::default()))
+            .oscillators(3);
+        let three = generator.generate(&mut rng);
+        // the parents share their first oscillator, so that the offspring inherit it as it is
+        let one = SubtractiveIndividual { oscillators: three.oscillators[..1].to_vec(), ..generator.generate(&mut rng) };
+        assert_eq!(three.oscillators.len(), 3);
+
+        // the paired oscillators are recombined, and the unpaired ones are inherited unchanged by the
+        // offspring of the parent with more of them
+        for (a, b) in [(&three, &one), (&one, &three)] {
+            let offspring = a.crossover(b, 0.0, CrossoverOperator::Uniform, MutationOperator::default(), &mut rng);
+            assert_eq!(offspring[0].oscillators.len(), a.oscillators.len());
+            assert_eq!(offspring[1].oscillators.len(), b.oscillators.len());
+            assert_eq!(offspring[0].oscillators[0], three.oscillators[0]);
+            let longer = if a.oscillators.len() > b.oscillators.len() { &offspring[0] } else { &offspring[1] };
+            assert_eq!(longer.oscillators[1..], three.oscillators[1..]);
+        }
+    }
+
+    #[test]
+    fn test_log_frequency() {
+        let target = Arc::new(sine_wave(110.0, LENGTH, SAMPLE_RATE as f32, 1.0, 0.0));
+        let cents_off = |log: bool, seed: u64| {
+            let generator = SubtractiveIndividual::new_generator()
+                .target(Arc::clone(&target))
+                .seed_frequency_from_target(1200.0)
+                .log_frequency(log)
+                .oscillator_with(&[Waveform::Sine]);
+            let mut simulation: HillClimbingSimulation<SubtractiveIndividual> = HillClimberBuilder::new()
+                .generator(generator)
+                .max_iterations(100)
+                .seed(seed)
+                .build()
+                .unwrap();
+            let fittest = simulation.run().unwrap();
+            1200.0 * (fittest.get_fundamental().unwrap() / 110.0).log2().abs()
+        };
+
+        // on a linear scale the steps around 110 Hz span several semitones, so that the climber
+        // keeps jumping past the target, whereas in log space they are a fraction of a semitone
+        let linear: Vec<f32> = (0..4).map(|seed| cents_off(false, seed)).collect();
+        let log: Vec<f32> = (0..4).map(|seed| cents_off(true, seed)).collect();
+        let close = |errors: &[f32]| errors.iter().filter(|&&e| e < 20.0).count();
+        assert!(close(&log) > close(&linear), "{log:?} against {linear:?}");
+        assert!(log.iter().sum::<f32>() < linear.iter().sum::<f32>(), "{log:?} against {linear:?}");
+    }
+
+    #[test]
+    fn test_component_accessors() {
+        let mut rng = StdRng::seed_from_u64(3);
+        let generator = SubtractiveIndividual::new_generator()
+            .target(Arc::new(Signal::default()))
+            .oscillators(2)
+            .filter(FilterType::LowPass);
+        let individual = generator.generate(&mut rng);
+
+        assert_eq!(individual.oscillators().len(), 2);
+        assert_eq!(individual.oscillator(), individual.oscillators().first());
+        assert_eq!(individual.envelope(), None);
+        assert!(matches!(individual.filter(), Some(FilterComponent::LowPass { .. })));
+    }
+
+    #[test]
+    fn test_hill_climb_envelope() {
+        let generator = SubtractiveIndividual::new_generator()
+            .target(Arc::new(sine_wave(440.0, LENGTH, SAMPLE_RATE as f32, 1.0, 0.0)))
+            .oscillator()
+            .envelope();
+        let mut simulation: HillClimbingSimulation<SubtractiveIndividual> = HillClimberBuilder::new()
+            .generator(generator)
+            .max_iterations(5)
+            .seed(1)
+            .build()
+            .unwrap();
+
+        // the envelope evolves along with the oscillator
+        let fittest = simulation.run().unwrap();
+        assert!(fittest.envelope().is_some());
+        let evolved = fittest.evolve(0.1, &mut StdRng::seed_from_u64(2));
+        assert_ne!(evolved.envelope(), fittest.envelope());
+    }
+
+    #[test]
+    fn test_filter_envelope() {
+        let mut rng = StdRng::seed_from_u64(4);
+        let generator = SubtractiveIndividual::new_generator()
+            .target(Arc::new(Signal::default()))
+            .oscillator()
+            .filter_envelope();
+
+        // the envelope is only attached to a filter
+        assert_eq!(generator.clone().generate(&mut rng).filter_envelope(), None);
+        let individual = generator.filter(FilterType::LowPass).generate(&mut rng);
+        assert!(individual.filter_envelope().is_some());
+
+        let evolved = individual.evolve(0.1, &mut rng);
+        assert_ne!(evolved.filter_envelope(), individual.filter_envelope());
+        assert!(individual.distance(&evolved) > 0.0);
+        assert!(individual.crossover(&evolved, 0.0, CrossoverOperator::Uniform, MutationOperator::Replace, &mut rng)
+            .iter()
+            .all(|offspring| offspring.filter_envelope().is_some()));
+    }
 }
@@ -1,20 +1,56 @@
 use std::f32::consts::PI;
 use itertools::Itertools;
-use rand::{thread_rng, Rng};
+use rand::Rng;
+use rustfft::FftPlanner;
+use rustfft::num_complex::Complex;
 
-/// Performs a weighted average with randomly generated weights between two values. However, if a mutation is triggered,
-/// the value returned will be completely random, specified by the calling code as the ranges may vary.
-pub fn random_weighted_average(v_self: f32, v_other: f32, r: f32, random_val: f32) -> f32 {
-    let mut prob = thread_rng();
+/// Performs a weighted average with a randomly generated weight between two values, returning the
+/// two complementary averages obtained by swapping the weights. However, if a mutation is
+/// triggered in either of them, the value returned will be the one given by `mutate_val` from the
+/// average, as the ranges and kinds of mutation may vary.
+pub fn random_weighted_average<R: Rng + ?Sized>(
+    v_self: f32,
+    v_other: f32,
+    r: f32,
+    mut mutate_val: impl FnMut(f32, &mut R) -> f32,
+    rng: &mut R,
+) -> (f32, f32) {
+    let beta: f32 = rng.gen();
 
-    let beta: f32 = prob.gen();
-    let mutation: f32 = prob.gen();
+    let mut mutate = |v: f32, rng: &mut R| {
+        let mutation: f32 = rng.gen();
+        if mutation < r { mutate_val(v, rng) } else { v }
+    };
 
-    if mutation < r {
-        random_val
-    } else {
-        beta * v_self + (1.0 - beta) * v_other
-    }
+    (
+        mutate(beta * v_self + (1.0 - beta) * v_other, rng),
+        mutate((1.0 - beta) * v_self + beta * v_other, rng),
+    )
+}
+
+/// Calculates the distance between two sequences of genes, which are truncated to the shortest of
+/// them, as the root mean square of their differences relative to the range of each gene, given by
+/// `range` from its index. The distance is between 0 and 1 for genes within their ranges.
+pub fn normalised_distance(genes_a: &[f32], genes_b: &[f32], range: impl Fn(usize) -> (f32, f32)) -> f32 {
+    let differences = genes_a.iter().zip(genes_b).enumerate().map(|(i, (a, b))| {
+        let (min, max) = range(i);
+        ((a - b) / (max - min)).powi(2)
+    }).collect_vec();
+
+    if differences.is_empty() { 0.0 } else { mean(&differences).sqrt() }
+}
+
+/// Returns the frequency range spanning `cents` below and above the given frequency.
+pub fn cents_range(freq: f32, cents: f32) -> (f32, f32) {
+    let factor = 2f32.powf(cents.abs() / 1200.0);
+    (freq / factor, freq * factor)
+}
+
+/// Draws a value from the standard normal distribution with the Box-Muller transform.
+pub fn gaussian<R: Rng + ?Sized>(rng: &mut R) -> f32 {
+    let u: f32 = 1.0 - rng.gen::<f32>();
+    let v: f32 = rng.gen();
+    (-2.0 * u.ln()).sqrt() * (2.0 * PI * v).cos()
 }
 
 /// Sigmoid function.
@@ -29,8 +65,9 @@ pub fn mean(values: &[f32]) -> f32 {
 
 /// Calculates the standard deviation of a set of elements.
 pub fn std(values: &[f32]) -> f32 {
-    mean(&values.iter().map(|f| f.powi(2)).collect_vec())
-    - mean(values).powi(2)
+    // rounding can take the difference slightly below 0 when all the values are equal
+    (mean(&values.iter().map(|f| f.powi(2)).collect_vec())
+    - mean(values).powi(2)).max(0.0)
 }
 
 /// Performs a convolution between a given filter and an input signal.
@@ -53,6 +90,63 @@ pub fn convolve(filter: &[f32], input: &[f32]) -> Vec<f32> {
     output
 }
 
+/// Number of products of a direct convolution above which `fast_convolve` uses the FFT instead.
+pub const FFT_CONVOLUTION_THRESHOLD: usize = 100_000;
+
+/// Same as `convolve`, but performed with the FFT when the filter and the input are long enough
+/// for it to be faster.
+pub fn fast_convolve(filter: &[f32], input: &[f32]) -> Vec<f32> {
+    if filter.len() * input.len() > FFT_CONVOLUTION_THRESHOLD {
+        fft_convolve(filter, input)
+    } else {
+        convolve(filter, input)
+    }
+}
+
+/// Same as `convolve`, but with overlap-add: the input is split into blocks that are convolved with
+/// the filter by multiplying their FFTs, and the results are added where they overlap. This takes
+/// O(n log m) rather than O(n·m) operations for an input of n samples and a filter of m.
+pub fn fft_convolve(filter: &[f32], input: &[f32]) -> Vec<f32> {
+    let (m, n) = (filter.len(), input.len());
+    let h_len = m / 2;
+    if m == 0 || n == 0 {
+        return convolve(filter, input);
+    }
+
+    let fft_len = (4 * m).next_power_of_two();
+    let block_len = fft_len - m + 1;
+    let mut planner = FftPlanner::new();
+    let (fft, ifft) = (planner.plan_fft_forward(fft_len), planner.plan_fft_inverse(fft_len));
+    let mut scratch = vec![Complex::default(); fft.get_inplace_scratch_len().max(ifft.get_inplace_scratch_len())];
+
+    // `convolve` doesn't flip the filter, so the reversed one is used here
+    let mut filter_fft: Vec<Complex<f32>> = filter.iter().rev().map(|&x| Complex::new(x, 0.0)).collect();
+    filter_fft.resize(fft_len, Complex::default());
+    fft.process_with_scratch(&mut filter_fft, &mut scratch);
+
+    let mut full = vec![0.0; n + m - 1];
+    let mut buffer = Vec::with_capacity(fft_len);
+    for (i, block) in input.chunks(block_len).enumerate() {
+        buffer.clear();
+        buffer.extend(block.iter().map(|&x| Complex::new(x, 0.0)));
+        buffer.resize(fft_len, Complex::default());
+
+        fft.process_with_scratch(&mut buffer, &mut scratch);
+        buffer.iter_mut().zip(&filter_fft).for_each(|(x, h)| *x *= h);
+        ifft.process_with_scratch(&mut buffer, &mut scratch);
+
+        let start = i * block_len;
+        let end = (start + block.len() + m - 1).min(full.len());
+        for (out, x) in full[start..end].iter_mut().zip(&buffer) {
+            *out += x.re / fft_len as f32;
+        }
+    }
+
+    // the full convolution starts m - 1 samples before the first output of `convolve`, which is
+    // centred on the filter
+    let offset = m - 1 - h_len;
+    full[offset..offset + n + h_len - 1].to_vec()
+}
 
 /// Creates a blackman window filter of a given size.
 pub fn blackman_window(size: usize) -> Vec<f32> {
@@ -83,4 +177,44 @@ pub fn spectral_invert(filter: &[f32]) -> Vec<f32> {
 /// Performs addition over the elements of two slices.
 pub fn add(a: &[f32], b: &[f32]) -> Vec<f32> {
     a.iter().zip(b.iter()).map(|(i, j)| i + j).collect()
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::SeedableRng;
+    use rand::rngs::StdRng;
+
+    #[test]
+    fn test_fft_convolve() {
+        let mut rng = StdRng::seed_from_u64(13);
+        let random_samples = |len: usize, rng: &mut StdRng| (0..len).map(|_| rng.gen_range(-1.0..1.0)).collect::<Vec<f32>>();
+
+        // the FFT gives the same output as the direct convolution for any lengths of the filter and
+        // the input, including filters longer than the input
+        for _ in 0..200 {
+            let (m, n) = (rng.gen_range(1..200), rng.gen_range(1..3_000));
+            let (filter, input) = (random_samples(m, &mut rng), random_samples(n, &mut rng));
+            let (direct, fft) = (convolve(&filter, &input), fft_convolve(&filter, &input));
+
+            assert_eq!(direct.len(), fft.len(), "filter of {m} and input of {n}");
+            let error = direct.iter().zip(&fft).map(|(a, b)| (a - b).abs()).fold(0.0, f32::max);
+            assert!(error < 1e-4, "error of {error} with a filter of {m} and input of {n}");
+        }
+
+        assert_eq!(fft_convolve(&[], &[1.0, 2.0]), convolve(&[], &[1.0, 2.0]));
+        assert_eq!(fft_convolve(&[1.0, 2.0], &[]), convolve(&[1.0, 2.0], &[]));
+    }
+
+    #[test]
+    fn test_fast_convolve() {
+        // a 400-tap filter, as designed for a band of 0.01, over 3 s of audio is done with the FFT
+        let filter = blackman_window(400);
+        let input: Vec<f32> = (0..132_300).map(|i| (i as f32 * 0.05).sin()).collect();
+        assert!(filter.len() * input.len() > FFT_CONVOLUTION_THRESHOLD);
+        assert_eq!(fast_convolve(&filter, &input), fft_convolve(&filter, &input));
+
+        // while a short one is done directly
+        assert_eq!(fast_convolve(&filter[..4], &input[..100]), convolve(&filter[..4], &input[..100]));
+    }
+}